@@ -0,0 +1,275 @@
+//! Derive macros for [biscuit](https://docs.rs/biscuit)
+//!
+//! Use these through the `derive` feature of biscuit rather than depending on this crate
+//! directly.
+#![deny(missing_docs, unused_results)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Field, Fields,
+    GenericArgument, Meta, NestedMeta, PathArguments, PathSegment, Type,
+};
+
+/// Derive `biscuit::Claims` and `biscuit::CompactJson` for a struct of custom claims
+///
+/// The struct must have a `biscuit::RegisteredClaims` field, which is usually flattened with
+/// `#[serde(flatten)]`. If the struct has more than one such field, mark the one holding the
+/// registered claims with `#[claims(registered)]`.
+///
+/// The generated implementations refer to `serde`, which must be a dependency of the crate
+/// deriving `Claims`. Derive macros cannot add serde attributes to fields, so use
+/// [`macro@claims`] instead to also serialize `DateTime<Utc>` fields as NumericDates.
+#[proc_macro_derive(Claims, attributes(claims))]
+pub fn derive_claims(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_claims(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// Add the serde attributes that a struct of custom claims needs, and derive `biscuit::Claims`
+/// for it
+///
+/// Use this as `#[biscuit::claims]`, above `#[derive(Serialize, Deserialize)]` so that serde sees
+/// the added attributes:
+///
+/// - The field holding the registered claims, which is found like `#[derive(Claims)]` does, is
+///   flattened with `#[serde(flatten)]`.
+/// - Fields of type `DateTime<Utc>` are serialized as NumericDates with
+///   `biscuit::numeric_date`, and fields of type `Option<DateTime<Utc>>` with
+///   `biscuit::option_numeric_date`.
+///
+/// Fields that already have a `flatten` or `with` serde attribute are left as they are.
+#[proc_macro_attribute]
+pub fn claims(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return Error::new(Span::call_site(), "`claims` takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_claims_attribute(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn expand_claims(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let fields = named_fields(&input.data)?;
+    let registered = registered_field(fields.iter())?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    // Bound on the struct itself rather than its type parameters, so that serde decides which
+    // parameters need to be serializable
+    let predicates = where_clause.map(|where_clause| &where_clause.predicates);
+
+    Ok(quote! {
+        impl #impl_generics ::biscuit::Claims for #name #type_generics
+        where
+            #name #type_generics: ::serde::Serialize,
+            #predicates
+        {
+            fn registered(&self) -> &::biscuit::RegisteredClaims {
+                &self.#registered
+            }
+        }
+
+        impl #impl_generics ::biscuit::CompactJson for #name #type_generics
+        where
+            #name #type_generics: ::serde::Serialize + ::serde::de::DeserializeOwned,
+            #predicates
+        {
+        }
+    })
+}
+
+fn expand_claims_attribute(mut input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let registered = registered_field(named_fields(&input.data)?.iter())?.clone();
+    if let Data::Struct(ref mut data) = input.data {
+        for field in data.fields.iter_mut() {
+            if field.ident.as_ref() == Some(&registered) {
+                if !has_serde_attribute(field, &["flatten"])? {
+                    field.attrs.push(parse_quote!(#[serde(flatten)]));
+                }
+            } else if !has_serde_attribute(field, &["with", "serialize_with", "deserialize_with"])?
+            {
+                if let Some(attr) = numeric_date_attribute(&field.ty) {
+                    field.attrs.push(attr);
+                }
+            }
+        }
+    }
+
+    Ok(quote! {
+        #[derive(::biscuit::Claims)]
+        #input
+    })
+}
+
+/// The serde attribute that serializes a field of type `ty` as a NumericDate, if it holds a
+/// `DateTime<Utc>`
+fn numeric_date_attribute(ty: &Type) -> Option<Attribute> {
+    if is_date_time_utc(ty) {
+        Some(parse_quote!(#[serde(with = "::biscuit::numeric_date")]))
+    } else if optional_type(ty).map(is_date_time_utc) == Some(true) {
+        Some(parse_quote!(
+            #[serde(default, with = "::biscuit::option_numeric_date")]
+        ))
+    } else {
+        None
+    }
+}
+
+fn named_fields(data: &Data) -> Result<&Punctuated<Field, Comma>, Error> {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => Ok(&fields.named),
+            _ => Err(Error::new(
+                Span::call_site(),
+                "Claims can only be derived for structs with named fields",
+            )),
+        },
+        _ => Err(Error::new(
+            Span::call_site(),
+            "Claims can only be derived for structs",
+        )),
+    }
+}
+
+/// Find the field holding the registered claims: the field marked `#[claims(registered)]`, or
+/// else the only field of type `RegisteredClaims`
+fn registered_field<'a, I>(fields: I) -> Result<&'a syn::Ident, Error>
+where
+    I: Iterator<Item = &'a Field> + Clone,
+{
+    let mut marked = vec![];
+    for field in fields.clone() {
+        if is_marked_registered(field)? {
+            marked.push(field);
+        }
+    }
+    let candidates: Vec<&Field> = if marked.is_empty() {
+        fields
+            .filter(|field| is_registered_claims(&field.ty))
+            .collect()
+    } else {
+        marked
+    };
+
+    match candidates.len() {
+        1 => Ok(candidates[0]
+            .ident
+            .as_ref()
+            .expect("named fields have identifiers")),
+        0 => Err(Error::new(
+            Span::call_site(),
+            "Claims requires a field of type `RegisteredClaims`",
+        )),
+        _ => Err(Error::new(
+            Span::call_site(),
+            "Mark the field holding the registered claims with `#[claims(registered)]`",
+        )),
+    }
+}
+
+fn is_marked_registered(field: &Field) -> Result<bool, Error> {
+    let mut registered = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("claims"))
+    {
+        match attr.parse_meta()? {
+            Meta::List(ref list) => {
+                for nested in &list.nested {
+                    match *nested {
+                        NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("registered") => {
+                            registered = true
+                        }
+                        ref other => {
+                            return Err(Error::new_spanned(other, "Unknown `claims` attribute"))
+                        }
+                    }
+                }
+            }
+            ref other => {
+                return Err(Error::new_spanned(
+                    other,
+                    "Expected `#[claims(registered)]`",
+                ))
+            }
+        }
+    }
+    Ok(registered)
+}
+
+/// Whether `field` has a serde attribute named like one of `names`, such as `flatten`
+fn has_serde_attribute(field: &Field, names: &[&str]) -> Result<bool, Error> {
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+    {
+        if let Meta::List(ref list) = attr.parse_meta()? {
+            for nested in &list.nested {
+                let path = match *nested {
+                    NestedMeta::Meta(Meta::Path(ref path)) => path,
+                    NestedMeta::Meta(Meta::NameValue(ref name_value)) => &name_value.path,
+                    _ => continue,
+                };
+                if names.iter().any(|name| path.is_ident(name)) {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// The last segment of the path of `ty`, such as `DateTime<Utc>` for `chrono::DateTime<Utc>`
+fn last_segment(ty: &Type) -> Option<&PathSegment> {
+    match *ty {
+        Type::Path(ref path) => path.path.segments.last(),
+        _ => None,
+    }
+}
+
+/// The only type argument of `segment`, such as `Utc` for `DateTime<Utc>`
+fn type_argument(segment: &PathSegment) -> Option<&Type> {
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref arguments) if arguments.args.len() == 1 => {
+            match arguments.args[0] {
+                GenericArgument::Type(ref ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_registered_claims(ty: &Type) -> bool {
+    last_segment(ty)
+        .map(|segment| segment.ident == "RegisteredClaims")
+        .unwrap_or(false)
+}
+
+fn is_date_time_utc(ty: &Type) -> bool {
+    last_segment(ty)
+        .filter(|segment| segment.ident == "DateTime")
+        .and_then(type_argument)
+        .and_then(last_segment)
+        .map(|segment| segment.ident == "Utc")
+        .unwrap_or(false)
+}
+
+/// The type wrapped by `ty` if it is an `Option`
+fn optional_type(ty: &Type) -> Option<&Type> {
+    last_segment(ty)
+        .filter(|segment| segment.ident == "Option")
+        .and_then(type_argument)
+}
@@ -0,0 +1,305 @@
+//! A command line tool to decode, sign and verify JWTs
+//!
+//! Keys are read from files in the same formats as the `biscuit::jws::Secret` constructors,
+//! either DER encoded or wrapped in PEM armor.
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use biscuit::errors::Error;
+use biscuit::jwa::SignatureAlgorithm;
+use biscuit::jwk::JWKSet;
+use biscuit::jws::{self, Secret};
+use biscuit::{ClaimsSet, Empty, Token, Validation, ValidationOptions};
+use chrono::Duration;
+use data_encoding::BASE64_MIME;
+use serde_json::{Map, Value};
+
+const USAGE: &str = "\
+Usage:
+  jwt decode [TOKEN]
+      Print the header and claims of TOKEN without verifying it
+  jwt encode --alg ALG (--key FILE | --secret SECRET) [OPTIONS]
+      Sign a JWT and print it
+      --claim NAME=VALUE   Set a private claim. VALUE is parsed as JSON if possible.
+      --iss ISSUER, --sub SUBJECT, --aud AUDIENCE (repeatable), --jti ID, --kid KEY_ID
+      --expires-in SECONDS
+  jwt verify [TOKEN] (--alg ALG (--key FILE | --secret SECRET) | --jwks FILE [--alg ALG])
+      Verify the signature of TOKEN, validate it and print its claims
+      --iss ISSUER, --aud AUDIENCE
+
+TOKEN is read from standard input if it is omitted.
+RSA private keys must be PKCS#1, ECDSA private keys PKCS#8, and public keys are used as is.
+";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((command, args)) => match command.as_str() {
+            "decode" => Arguments::parse(args).and_then(|args| decode(&args)),
+            "encode" => Arguments::parse(args).and_then(|args| encode(&args)),
+            "verify" => Arguments::parse(args).and_then(|args| verify(&args)),
+            "help" | "--help" | "-h" => {
+                print!("{}", USAGE);
+                return;
+            }
+            other => Err(CliError::Usage(format!("Unknown command {:?}", other))),
+        },
+        None => Err(CliError::Usage("Missing command".to_string())),
+    };
+
+    match result {
+        Ok(output) => println!("{}", output),
+        Err(CliError::Usage(message)) => {
+            eprint!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("error: {}", message);
+            process::exit(1);
+        }
+    }
+}
+
+enum CliError {
+    /// The command line is invalid
+    Usage(String),
+    /// The command failed
+    Failed(String),
+}
+
+impl From<Error> for CliError {
+    fn from(error: Error) -> Self {
+        CliError::Failed(error.to_string())
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Failed(error.to_string())
+    }
+}
+
+#[derive(Default)]
+struct Arguments {
+    token: Option<String>,
+    algorithm: Option<SignatureAlgorithm>,
+    key: Option<String>,
+    secret: Option<String>,
+    jwks: Option<String>,
+    claims: Vec<(String, Value)>,
+    issuer: Option<String>,
+    subject: Option<String>,
+    audiences: Vec<String>,
+    id: Option<String>,
+    key_id: Option<String>,
+    expires_in: Option<i64>,
+}
+
+impl Arguments {
+    fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut parsed = Arguments::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                if parsed.token.is_some() {
+                    Err(CliError::Usage(format!("Unexpected argument {:?}", arg)))?
+                }
+                parsed.token = Some(arg.clone());
+                continue;
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| CliError::Usage(format!("Missing value for {}", arg)))?
+                .clone();
+            match arg.as_str() {
+                "--alg" => {
+                    let algorithm = value
+                        .parse()
+                        .map_err(|e: Error| CliError::Usage(e.to_string()))?;
+                    parsed.algorithm = Some(algorithm);
+                }
+                "--key" => parsed.key = Some(value),
+                "--secret" => parsed.secret = Some(value),
+                "--jwks" => parsed.jwks = Some(value),
+                "--claim" => {
+                    let separator = value.find('=').ok_or_else(|| {
+                        CliError::Usage(format!("Expected NAME=VALUE for --claim, got {:?}", value))
+                    })?;
+                    let (name, claim) = (&value[..separator], &value[separator + 1..]);
+                    let claim = serde_json::from_str(claim)
+                        .unwrap_or_else(|_| Value::String(claim.to_string()));
+                    parsed.claims.push((name.to_string(), claim));
+                }
+                "--iss" => parsed.issuer = Some(value),
+                "--sub" => parsed.subject = Some(value),
+                "--aud" => parsed.audiences.push(value),
+                "--jti" => parsed.id = Some(value),
+                "--kid" => parsed.key_id = Some(value),
+                "--expires-in" => {
+                    let seconds = value.parse().map_err(|_| {
+                        CliError::Usage(format!(
+                            "Expected seconds for --expires-in, got {:?}",
+                            value
+                        ))
+                    })?;
+                    parsed.expires_in = Some(seconds);
+                }
+                other => Err(CliError::Usage(format!("Unknown option {}", other)))?,
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// The token from the command line, or else from standard input
+    fn token(&self) -> Result<String, CliError> {
+        match self.token {
+            Some(ref token) => Ok(token.clone()),
+            None => {
+                let mut token = String::new();
+                let _ = io::stdin().read_to_string(&mut token)?;
+                Ok(token.trim().to_string())
+            }
+        }
+    }
+
+    fn algorithm(&self) -> Result<SignatureAlgorithm, CliError> {
+        self.algorithm
+            .ok_or_else(|| CliError::Usage("Missing --alg".to_string()))
+    }
+
+    /// The secret to sign with, if `private`, or to verify with
+    fn secret(&self, algorithm: SignatureAlgorithm, private: bool) -> Result<Secret, CliError> {
+        if let Some(ref secret) = self.secret {
+            return Ok(Secret::bytes_from_str(secret));
+        }
+        let key = match self.key {
+            Some(ref path) => read_key(path)?,
+            None => Err(CliError::Usage("Missing --key or --secret".to_string()))?,
+        };
+        Ok(match algorithm {
+            SignatureAlgorithm::HS256 | SignatureAlgorithm::HS384 | SignatureAlgorithm::HS512 => {
+                Secret::Bytes(key)
+            }
+            SignatureAlgorithm::RS256
+            | SignatureAlgorithm::RS384
+            | SignatureAlgorithm::RS512
+            | SignatureAlgorithm::PS256
+            | SignatureAlgorithm::PS384
+            | SignatureAlgorithm::PS512
+                if private =>
+            {
+                Secret::rsa_keypair_from_der(&key)?
+            }
+            SignatureAlgorithm::ES256 | SignatureAlgorithm::ES384 | SignatureAlgorithm::ES512
+                if private =>
+            {
+                Secret::ecdsa_keypair_from_der(algorithm, &key)?
+            }
+            _ => Secret::PublicKey(key),
+        })
+    }
+
+    fn validation_options(&self, algorithm: SignatureAlgorithm) -> ValidationOptions {
+        let validate = |value: Option<&String>| match value {
+            Some(value) => Validation::Validate(value.clone()),
+            None => Validation::Ignored,
+        };
+        ValidationOptions {
+            algorithms: Validation::Validate(vec![algorithm]),
+            // Unsecured tokens are only verified with an explicit `--alg none`
+            allow_unsecured_tokens: algorithm == SignatureAlgorithm::None,
+            issuer: validate(self.issuer.as_ref()),
+            audience: validate(self.audiences.first()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Read a DER encoded key, which may be wrapped in PEM armor
+fn read_key(path: &str) -> Result<Vec<u8>, CliError> {
+    let bytes = fs::read(path)?;
+    if !bytes.starts_with(b"-----BEGIN") {
+        return Ok(bytes);
+    }
+
+    let pem = String::from_utf8_lossy(&bytes);
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    BASE64_MIME
+        .decode(body.as_bytes())
+        .map_err(|e| CliError::Failed(format!("Invalid PEM in {}: {}", path, e)))
+}
+
+fn decode(args: &Arguments) -> Result<String, CliError> {
+    Ok(biscuit::inspect(&args.token()?)?.to_string())
+}
+
+fn encode(args: &Arguments) -> Result<String, CliError> {
+    let algorithm = args.algorithm()?;
+    let mut builder = Token::builder().issued_now();
+    if let Some(ref issuer) = args.issuer {
+        builder = builder.issuer(issuer.as_str());
+    }
+    if let Some(ref subject) = args.subject {
+        builder = builder.subject(subject.as_str());
+    }
+    for audience in &args.audiences {
+        builder = builder.audience(audience.as_str());
+    }
+    if let Some(ref id) = args.id {
+        builder = builder.id(id.as_str());
+    }
+    if let Some(ref key_id) = args.key_id {
+        builder = builder.key_id(key_id.as_str());
+    }
+    if let Some(seconds) = args.expires_in {
+        builder = builder.expires_in(Duration::seconds(seconds));
+    }
+    for (name, value) in &args.claims {
+        builder = builder.claim(name.as_str(), value.clone());
+    }
+    if algorithm == SignatureAlgorithm::None {
+        return Ok(builder.danger_unsecured()?);
+    }
+    Ok(builder.sign(&args.secret(algorithm, true)?, algorithm)?)
+}
+
+fn verify(args: &Arguments) -> Result<String, CliError> {
+    if args.jwks.is_none() && args.algorithm()? == SignatureAlgorithm::None {
+        return verify_unsecured(args);
+    }
+    let token = jws::Compact::<ClaimsSet<Map<String, Value>>, Empty>::new_encoded(&args.token()?);
+    let (token, options) = match args.jwks {
+        Some(ref path) => {
+            let jwks: JWKSet<Empty> = serde_json::from_slice(&fs::read(path)?)
+                .map_err(|e| CliError::Failed(format!("Invalid JWKS in {}: {}", path, e)))?;
+            let token = token.decode_with_jwks(&jwks, args.algorithm)?;
+            let algorithm = token.header()?.registered.algorithm;
+            (token, args.validation_options(algorithm))
+        }
+        None => {
+            let algorithm = args.algorithm()?;
+            let token = token.decode(&args.secret(algorithm, false)?, algorithm)?;
+            (token, args.validation_options(algorithm))
+        }
+    };
+    token.validate(options)?;
+
+    let claims = serde_json::to_string_pretty(token.payload()?).map_err(Error::from)?;
+    Ok(claims)
+}
+
+/// Validate an unsecured TOKEN, which has no signature to verify, and print its claims
+fn verify_unsecured(args: &Arguments) -> Result<String, CliError> {
+    let token = jws::UnsecuredJwt::<Map<String, Value>, Empty>::danger_decode(&args.token()?)?;
+    token.validate(args.validation_options(SignatureAlgorithm::None))?;
+
+    let claims = serde_json::to_string_pretty(token.claims()).map_err(Error::from)?;
+    Ok(claims)
+}
@@ -0,0 +1,640 @@
+//! Claims sets encoded in [CBOR](https://tools.ietf.org/html/rfc8949) instead of JSON
+//!
+//! A JWS whose payload is CBOR is smaller than one whose payload is JSON, which matters for
+//! constrained devices and transports. The token is otherwise an ordinary JWS: it is signed and
+//! verified with the same [`Secret`]s and algorithms, and its claims are validated with the same
+//! [`ValidationOptions`]. Tokens made with [`encode`] have a `cty` header of [`CONTENT_TYPE`],
+//! which [`decode`] requires.
+//!
+//! Claims are converted to CBOR through their JSON representation, so any claims that can be
+//! serialized to JSON can be encoded. Byte strings are decoded as base64url strings, and tags
+//! are ignored.
+//!
+//! This module is only available with the `cbor` feature.
+//!
+//! # Examples
+//! ```
+//! use biscuit::cbor;
+//! use biscuit::jwa::SignatureAlgorithm;
+//! use biscuit::jws::{RegisteredHeader, Secret};
+//! use biscuit::{ClaimsSet, RegisteredClaims, Validation, ValidationOptions};
+//! use serde_json::{json, Value};
+//!
+//! let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+//! let claims = ClaimsSet {
+//!     registered: RegisteredClaims {
+//!         issuer: Some("https://www.acme.com".to_string()),
+//!         ..Default::default()
+//!     },
+//!     private: json!({ "sensor": 12 }),
+//! };
+//! let token = cbor::encode(From::from(RegisteredHeader::default()), &claims, &secret).unwrap();
+//!
+//! let options = ValidationOptions {
+//!     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+//!     ..Default::default()
+//! };
+//! let decoded: ClaimsSet<Value> = cbor::decode(&token, &secret, &options).unwrap();
+//! assert_eq!(decoded, claims);
+//! ```
+use std::convert::TryFrom;
+use std::ops::Deref;
+
+use data_encoding::BASE64URL_NOPAD;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::errors::{DecodeError, Error};
+use crate::jws::{self, Header, Secret};
+use crate::{ClaimsSet, CompactPart, ValidationOptions};
+
+/// The `cty` header of a JWS whose payload is CBOR
+pub const CONTENT_TYPE: &str = "cbor";
+
+/// The deepest nesting of arrays, maps and tags that is decoded, which keeps malicious input
+/// from exhausting the stack
+const MAX_DEPTH: usize = 128;
+
+/// A CBOR data item
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// An unsigned or negative integer, from `-2^64` to `2^64 - 1`
+    Integer(i128),
+    /// A byte string
+    Bytes(Vec<u8>),
+    /// A text string
+    Text(String),
+    /// An array of data items
+    Array(Vec<Value>),
+    /// A map of data items, in the order that they are encoded
+    Map(Vec<(Value, Value)>),
+    /// A data item with a semantic tag
+    Tag(u64, Box<Value>),
+    /// A boolean
+    Bool(bool),
+    /// `null`
+    Null,
+    /// `undefined`
+    Undefined,
+    /// A floating point number
+    Float(f64),
+}
+
+impl Value {
+    /// Encode the data item
+    ///
+    /// Integers and lengths are encoded in their shortest form, and floats as single precision
+    /// if that is lossless. Returns [`DecodeError::InvalidCbor`] if an integer is out of range.
+    pub fn to_vec(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        self.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Decode a single data item, which must span all of `bytes`
+    ///
+    /// Returns [`DecodeError::InvalidCbor`] if `bytes` are not well-formed CBOR, or use
+    /// indefinite lengths.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, position: 0 };
+        let value = reader.value(0)?;
+        if reader.position != bytes.len() {
+            Err(invalid("trailing bytes after the data item"))?
+        }
+        Ok(value)
+    }
+
+    /// Convert a JSON value to a data item
+    pub fn from_json(value: serde_json::Value) -> Self {
+        use serde_json::Value as Json;
+
+        match value {
+            Json::Null => Value::Null,
+            Json::Bool(value) => Value::Bool(value),
+            Json::Number(number) => match (number.as_u64(), number.as_i64()) {
+                (Some(value), _) => Value::Integer(i128::from(value)),
+                (None, Some(value)) => Value::Integer(i128::from(value)),
+                (None, None) => Value::Float(number.as_f64().unwrap_or_default()),
+            },
+            Json::String(value) => Value::Text(value),
+            Json::Array(values) => Value::Array(values.into_iter().map(Value::from_json).collect()),
+            Json::Object(map) => Value::Map(
+                map.into_iter()
+                    .map(|(key, value)| (Value::Text(key), Value::from_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Convert the data item to a JSON value
+    ///
+    /// Byte strings are converted to base64url strings, tags are dropped and `undefined` is
+    /// `null`. Returns [`DecodeError::InvalidCbor`] for maps with keys that are not text, and for
+    /// numbers that JSON cannot represent.
+    pub fn into_json(self) -> Result<serde_json::Value, Error> {
+        use serde_json::{Number, Value as Json};
+
+        Ok(match self {
+            Value::Integer(value) => {
+                if let Ok(value) = u64::try_from(value) {
+                    Json::from(value)
+                } else if let Ok(value) = i64::try_from(value) {
+                    Json::from(value)
+                } else {
+                    Err(invalid("an integer is out of the range of JSON numbers"))?
+                }
+            }
+            Value::Bytes(bytes) => Json::String(BASE64URL_NOPAD.encode(&bytes)),
+            Value::Text(value) => Json::String(value),
+            Value::Array(values) => Json::Array(
+                values
+                    .into_iter()
+                    .map(Value::into_json)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(entries) => {
+                let mut map = serde_json::Map::with_capacity(entries.len());
+                for (key, value) in entries {
+                    match key {
+                        Value::Text(key) => {
+                            let _ = map.insert(key, value.into_json()?);
+                        }
+                        _ => Err(invalid("a map key is not a text string"))?,
+                    }
+                }
+                Json::Object(map)
+            }
+            Value::Tag(_, value) => value.into_json()?,
+            Value::Bool(value) => Json::Bool(value),
+            Value::Null | Value::Undefined => Json::Null,
+            Value::Float(value) => match Number::from_f64(value) {
+                Some(number) => Json::Number(number),
+                None => Err(invalid("a float is not finite"))?,
+            },
+        })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        match *self {
+            Value::Integer(value) => {
+                if let Ok(argument) = u64::try_from(value) {
+                    write_header(bytes, 0, argument);
+                } else if let Ok(argument) = u64::try_from(-1 - value) {
+                    write_header(bytes, 1, argument);
+                } else {
+                    Err(invalid("an integer is out of range"))?
+                }
+            }
+            Value::Bytes(ref value) => {
+                write_header(bytes, 2, value.len() as u64);
+                bytes.extend_from_slice(value);
+            }
+            Value::Text(ref value) => {
+                write_header(bytes, 3, value.len() as u64);
+                bytes.extend_from_slice(value.as_bytes());
+            }
+            Value::Array(ref values) => {
+                write_header(bytes, 4, values.len() as u64);
+                for value in values {
+                    value.write(bytes)?;
+                }
+            }
+            Value::Map(ref entries) => {
+                write_header(bytes, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    key.write(bytes)?;
+                    value.write(bytes)?;
+                }
+            }
+            Value::Tag(tag, ref value) => {
+                write_header(bytes, 6, tag);
+                value.write(bytes)?;
+            }
+            Value::Bool(false) => bytes.push(0xf4),
+            Value::Bool(true) => bytes.push(0xf5),
+            Value::Null => bytes.push(0xf6),
+            Value::Undefined => bytes.push(0xf7),
+            Value::Float(value) => {
+                let single = value as f32;
+                if f64::from(single) == value || value.is_nan() {
+                    bytes.push(0xfa);
+                    bytes.extend_from_slice(&single.to_bits().to_be_bytes());
+                } else {
+                    bytes.push(0xfb);
+                    bytes.extend_from_slice(&value.to_bits().to_be_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write the initial byte of a data item of `major` type, and its argument in the shortest form
+fn write_header(bytes: &mut Vec<u8>, major: u8, argument: u64) {
+    let major = major << 5;
+    if argument < 24 {
+        bytes.push(major | argument as u8);
+    } else if argument <= 0xff {
+        bytes.push(major | 24);
+        bytes.push(argument as u8);
+    } else if argument <= 0xffff {
+        bytes.push(major | 25);
+        bytes.extend_from_slice(&(argument as u16).to_be_bytes());
+    } else if argument <= 0xffff_ffff {
+        bytes.push(major | 26);
+        bytes.extend_from_slice(&(argument as u32).to_be_bytes());
+    } else {
+        bytes.push(major | 27);
+        bytes.extend_from_slice(&argument.to_be_bytes());
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::DecodeError(DecodeError::InvalidCbor(reason.to_string()))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of input"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn argument(&mut self, additional: u8) -> Result<u64, Error> {
+        let mut read = |length: usize| -> Result<u64, Error> {
+            Ok(self
+                .take(length)?
+                .iter()
+                .fold(0, |argument, byte| argument << 8 | u64::from(*byte)))
+        };
+        match additional {
+            0..=23 => Ok(u64::from(additional)),
+            24 => read(1),
+            25 => read(2),
+            26 => read(4),
+            27 => read(8),
+            31 => Err(invalid("indefinite lengths are not supported")),
+            _ => Err(invalid("reserved additional information")),
+        }
+    }
+
+    fn length(&mut self, additional: u8) -> Result<usize, Error> {
+        let length = self.argument(additional)?;
+        // Every item takes at least a byte, so longer lengths cannot be valid
+        if length > (self.bytes.len() - self.position) as u64 {
+            Err(invalid("unexpected end of input"))?
+        }
+        Ok(length as usize)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            Err(invalid("data items are nested too deeply"))?
+        }
+        let initial = self.take(1)?[0];
+        let (major, additional) = (initial >> 5, initial & 0x1f);
+        Ok(match major {
+            0 => Value::Integer(i128::from(self.argument(additional)?)),
+            1 => Value::Integer(-1 - i128::from(self.argument(additional)?)),
+            2 => {
+                let length = self.length(additional)?;
+                Value::Bytes(self.take(length)?.to_vec())
+            }
+            3 => {
+                let length = self.length(additional)?;
+                let text = std::str::from_utf8(self.take(length)?)
+                    .map_err(|_| invalid("a text string is not UTF-8"))?;
+                Value::Text(text.to_string())
+            }
+            4 => {
+                let length = self.length(additional)?;
+                let mut values = Vec::with_capacity(length);
+                for _ in 0..length {
+                    values.push(self.value(depth + 1)?);
+                }
+                Value::Array(values)
+            }
+            5 => {
+                let length = self.length(additional)?;
+                let mut entries = Vec::with_capacity(length);
+                for _ in 0..length {
+                    let key = self.value(depth + 1)?;
+                    entries.push((key, self.value(depth + 1)?));
+                }
+                Value::Map(entries)
+            }
+            6 => {
+                let tag = self.argument(additional)?;
+                Value::Tag(tag, Box::new(self.value(depth + 1)?))
+            }
+            _ => match additional {
+                20 => Value::Bool(false),
+                21 => Value::Bool(true),
+                22 => Value::Null,
+                23 => Value::Undefined,
+                25 => Value::Float(half_to_f64(self.argument(additional)? as u16)),
+                26 => Value::Float(f64::from(f32::from_bits(self.argument(additional)? as u32))),
+                27 => Value::Float(f64::from_bits(self.argument(additional)?)),
+                _ => Err(invalid("unsupported simple value"))?,
+            },
+        })
+    }
+}
+
+/// Convert a half precision float to a double
+fn half_to_f64(half: u16) -> f64 {
+    let half = u32::from(half);
+    let (exponent, mantissa) = ((half >> 10) & 0x1f, half & 0x3ff);
+    let magnitude = match exponent {
+        // Subnormal numbers
+        0 => f64::from(mantissa) * 2f64.powi(-24),
+        // Infinity and NaN
+        31 => f64::from(f32::from_bits(0x7f80_0000 | mantissa << 13)),
+        _ => f64::from(f32::from_bits((exponent + 112) << 23 | mantissa << 13)),
+    };
+    if half & 0x8000 == 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+/// Serialize `value` to CBOR, through its JSON representation
+pub fn to_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    Value::from_json(serde_json::to_value(value)?).to_vec()
+}
+
+/// Deserialize a `T` from CBOR, through its JSON representation
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    Ok(serde_json::from_value(
+        Value::from_slice(bytes)?.into_json()?,
+    )?)
+}
+
+/// An adapter to use any type that can be serialized and deserialized with serde as a
+/// `CompactPart`, by serializing it to CBOR
+///
+/// # Examples
+/// ```
+/// use biscuit::CompactPart;
+/// use biscuit::cbor::Cbor;
+///
+/// let part = Cbor(vec![1, 2, 3]);
+/// let encoded = part.to_base64().unwrap();
+/// assert_eq!(encoded.str(), "gwECAw");
+/// assert_eq!(Cbor::<Vec<u32>>::from_base64(&encoded).unwrap(), part);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Cbor<T>(pub T);
+
+impl<T> CompactPart for Cbor<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        to_vec(&self.0)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Cbor(from_slice(bytes)?))
+    }
+}
+
+impl<T> Deref for Cbor<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Sign `claims` encoded in CBOR with `secret`, and return the token in its compact form
+///
+/// The `cty` header is set to [`CONTENT_TYPE`].
+pub fn encode<T, H>(
+    mut header: Header<H>,
+    claims: &ClaimsSet<T>,
+    secret: &Secret,
+) -> Result<String, Error>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    H: Serialize + DeserializeOwned,
+{
+    header.registered.content_type = Some(CONTENT_TYPE.to_string());
+    Ok(jws::Compact::new_decoded(header, Cbor(claims.clone()))
+        .into_encoded(secret)?
+        .unwrap_encoded()
+        .to_string())
+}
+
+/// Verify the signature of a token made with [`encode`], validate it according to `options`,
+/// and deserialize its claims set, like [`crate::decode`]
+///
+/// Returns [`ValidationError::InvalidContentType`] if the `cty` header of the token is not
+/// [`CONTENT_TYPE`].
+///
+/// [`ValidationError::InvalidContentType`]: crate::errors::ValidationError::InvalidContentType
+pub fn decode<T>(
+    token: &str,
+    secret: &Secret,
+    options: &ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    crate::decode_with_content_type(token, secret, options, CONTENT_TYPE, |Cbor(claims)| claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::errors::ValidationError;
+    use crate::jwa::SignatureAlgorithm;
+    use crate::jws::{PinnedHeader, RegisteredHeader};
+    use crate::{Empty, JsonLimits, RegisteredClaims, Validation};
+
+    #[test]
+    fn rfc8949_examples() {
+        let examples: Vec<(Value, &str)> = vec![
+            (Value::Integer(0), "00"),
+            (Value::Integer(23), "17"),
+            (Value::Integer(24), "1818"),
+            (Value::Integer(1000), "1903e8"),
+            (Value::Integer(1_000_000_000_000), "1b000000e8d4a51000"),
+            (
+                Value::Integer(18_446_744_073_709_551_615),
+                "1bffffffffffffffff",
+            ),
+            (
+                Value::Integer(-18_446_744_073_709_551_616),
+                "3bffffffffffffffff",
+            ),
+            (Value::Integer(-1000), "3903e7"),
+            (Value::Float(1.5), "fa3fc00000"),
+            (Value::Float(1.1), "fb3ff199999999999a"),
+            (Value::Bool(false), "f4"),
+            (Value::Null, "f6"),
+            (Value::Text("\u{6c34}".to_string()), "63e6b0b4"),
+            (Value::Bytes(vec![1, 2, 3, 4]), "4401020304"),
+            (
+                Value::Tag(1, Box::new(Value::Integer(1_363_896_240))),
+                "c11a514b67b0",
+            ),
+            (
+                Value::Map(vec![
+                    (Value::Integer(1), Value::Integer(2)),
+                    (
+                        Value::Text("a".to_string()),
+                        Value::Array(vec![Value::Integer(3)]),
+                    ),
+                ]),
+                "a2010261618103",
+            ),
+        ];
+        for (value, hex) in examples {
+            let bytes = not_err!(data_encoding::HEXLOWER.decode(hex.as_bytes()));
+            assert_eq!(not_err!(value.to_vec()), bytes);
+            assert_eq!(not_err!(Value::from_slice(&bytes)), value);
+        }
+
+        // Half precision floats are decoded
+        assert_eq!(
+            not_err!(Value::from_slice(&[0xf9, 0x3c, 0x00])),
+            Value::Float(1.0)
+        );
+        assert_eq!(
+            not_err!(Value::from_slice(&[0xf9, 0xc4, 0x00])),
+            Value::Float(-4.0)
+        );
+    }
+
+    #[test]
+    fn malformed_cbor_is_rejected() {
+        let malformed: Vec<&[u8]> = vec![
+            // Truncated
+            &[0x19, 0x03],
+            &[0x62, 0x61],
+            // Indefinite length
+            &[0x9f, 0x01, 0xff],
+            // Trailing bytes
+            &[0x01, 0x02],
+            // A length that is longer than the input
+            &[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ];
+        for bytes in malformed {
+            match Value::from_slice(bytes) {
+                Err(Error::DecodeError(DecodeError::InvalidCbor(_))) => {}
+                other => panic!("Unexpected result {:?} for {:?}", other, bytes),
+            }
+        }
+        let nested = vec![0x81; MAX_DEPTH + 2];
+        assert!(Value::from_slice(&nested).is_err());
+
+        // Maps with keys that are not text have no JSON representation
+        let map = Value::Map(vec![(Value::Integer(1), Value::Null)]);
+        assert!(map.into_json().is_err());
+    }
+
+    #[test]
+    fn cbor_claims_round_trip() {
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let claims = ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some("https://www.acme.com".to_string()),
+                expiry: Some((chrono::Utc::now().timestamp() + 60).into()),
+                ..Default::default()
+            },
+            private: json!({ "reading": 21.5, "tags": ["a", "b"] }),
+        };
+        let token = not_err!(encode(
+            From::from(RegisteredHeader::default()),
+            &claims,
+            &secret
+        ));
+        let header = not_err!(
+            jws::Compact::<Cbor<ClaimsSet<serde_json::Value>>, Empty>::new_encoded(&token)
+                .unverified_header()
+        );
+        assert_eq!(
+            header.registered.content_type.as_deref(),
+            Some(CONTENT_TYPE)
+        );
+        // Smaller than the JSON encoding
+        assert!(not_err!(to_vec(&claims)).len() < not_err!(serde_json::to_vec(&claims)).len());
+
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+        let decoded: ClaimsSet<serde_json::Value> = not_err!(decode(&token, &secret, &options));
+        assert_eq!(decoded, claims);
+
+        // A JSON token is not accepted
+        let json_token = not_err!(crate::encode(
+            From::from(RegisteredHeader::default()),
+            claims,
+            &secret
+        ));
+        match decode::<serde_json::Value>(&json_token, &secret, &options) {
+            Err(Error::ValidationError(ValidationError::InvalidContentType(None))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_checks_pinned_header_and_json_limits() {
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let claims = ClaimsSet {
+            registered: Default::default(),
+            private: json!({ "act": { "act": { "sub": "admin" } } }),
+        };
+        let token = not_err!(encode(
+            From::from(RegisteredHeader::default()),
+            &claims,
+            &secret
+        ));
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+        let _ = not_err!(decode::<serde_json::Value>(&token, &secret, &options));
+
+        let pinned_options = ValidationOptions {
+            pinned_header: Some(PinnedHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                key_id: Some("key-1".to_string()),
+            }),
+            ..options.clone()
+        };
+        match decode::<serde_json::Value>(&token, &secret, &pinned_options) {
+            Err(Error::ValidationError(ValidationError::KeyNotFound)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let limited_options = ValidationOptions {
+            json_limits: JsonLimits {
+                max_depth: Some(2),
+                ..Default::default()
+            },
+            ..options
+        };
+        match decode::<serde_json::Value>(&token, &secret, &limited_options) {
+            Err(Error::DecodeError(DecodeError::JsonLimitExceeded { limit, max })) => {
+                assert_eq!((limit, max), ("depth", 2))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Helpers for carrying tokens in [HTTP cookies](https://tools.ietf.org/html/rfc6265)
+//!
+//! [`token_from_cookies`] reads a token from the `Cookie` header of a request, and
+//! [`TokenCookie`] renders the `Set-Cookie` header that sends a token to the client, expiring
+//! with the token.
+//!
+//! # Examples
+//! ```
+//! use biscuit::cookie::{token_from_cookies, TokenCookie};
+//!
+//! // A token with `"exp": 1600000000`
+//! let token = "eyJhbGciOiJub25lIn0.eyJleHAiOjE2MDAwMDAwMDB9.";
+//! let set_cookie = TokenCookie::new("session").set_cookie(token).unwrap();
+//! assert_eq!(
+//!     set_cookie,
+//!     format!(
+//!         "session={}; Expires=Sun, 13 Sep 2020 12:26:40 GMT; Path=/; Secure; HttpOnly; \
+//!          SameSite=Strict",
+//!         token
+//!     )
+//! );
+//!
+//! let cookies = format!("theme=dark; session={}", token);
+//! assert_eq!(token_from_cookies(&cookies, "session"), Some(token));
+//! ```
+use std::fmt;
+
+use crate::errors::Error;
+
+/// The `SameSite` attribute of a cookie, which controls whether it is sent with cross-site
+/// requests
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SameSite {
+    /// Only send the cookie with same-site requests
+    Strict,
+    /// Also send the cookie when navigating to the site from another site
+    Lax,
+    /// Send the cookie with every request. Browsers require the `Secure` attribute for this.
+    None,
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match *self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        };
+        f.write_str(value)
+    }
+}
+
+/// The attributes of a cookie carrying a token, used to render its `Set-Cookie` header
+///
+/// [`TokenCookie::new`] starts with the most restrictive attributes: `Secure`, `HttpOnly`,
+/// `SameSite=Strict` and a `Path` of `/`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenCookie {
+    /// The name of the cookie. It is not escaped, so it must be a valid cookie name.
+    pub name: String,
+    /// The `Domain` attribute, for the cookie to be sent to subdomains as well
+    pub domain: Option<String>,
+    /// The `Path` attribute
+    pub path: Option<String>,
+    /// Whether to set the `Secure` attribute, so that the cookie is only sent over HTTPS
+    pub secure: bool,
+    /// Whether to set the `HttpOnly` attribute, so that the cookie cannot be read by scripts
+    pub http_only: bool,
+    /// The `SameSite` attribute
+    pub same_site: Option<SameSite>,
+}
+
+impl TokenCookie {
+    /// A cookie named `name` with the most restrictive attributes
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        TokenCookie {
+            name: name.into(),
+            domain: None,
+            path: Some("/".to_string()),
+            secure: true,
+            http_only: true,
+            same_site: Some(SameSite::Strict),
+        }
+    }
+
+    /// Render the value of a `Set-Cookie` header carrying `token`, a compact JWS or JWE
+    ///
+    /// The cookie expires at the `exp` claim of the token, which is read without verifying the
+    /// token since it is meant to be one that was just signed. A token without an `exp` claim,
+    /// or whose claims are encrypted, is sent in a session cookie.
+    pub fn set_cookie(&self, token: &str) -> Result<String, Error> {
+        let expiry = crate::inspect(token)?.expiry();
+
+        let mut cookie = format!("{}={}", self.name, token);
+        if let Some(expiry) = expiry {
+            cookie.push_str(&format!(
+                "; Expires={}",
+                expiry.format("%a, %d %b %Y %H:%M:%S GMT")
+            ));
+        }
+        if let Some(ref domain) = self.domain {
+            cookie.push_str(&format!("; Domain={}", domain));
+        }
+        if let Some(ref path) = self.path {
+            cookie.push_str(&format!("; Path={}", path));
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        if self.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        if let Some(same_site) = self.same_site {
+            cookie.push_str(&format!("; SameSite={}", same_site));
+        }
+        Ok(cookie)
+    }
+}
+
+/// Find the value of the cookie named `name` in the value of a `Cookie` header, as described
+/// by [RFC6265#5.4](https://tools.ietf.org/html/rfc6265#section-5.4)
+///
+/// Returns the value of the first cookie with the name, without the optional double quotes.
+pub fn token_from_cookies<'a>(cookies: &'a str, name: &str) -> Option<&'a str> {
+    cookies
+        .split(';')
+        .filter_map(|cookie| {
+            let cookie = cookie.trim();
+            let separator = cookie.find('=')?;
+            if cookie[..separator] == *name {
+                Some(&cookie[separator + 1..])
+            } else {
+                None
+            }
+        })
+        .map(|value| {
+            if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                &value[1..value.len() - 1]
+            } else {
+                value
+            }
+        })
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_cookie_attributes() {
+        // A token without claims
+        let token = "eyJhbGciOiJub25lIn0.e30.";
+        let cookie = TokenCookie {
+            domain: Some("example.com".to_string()),
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: Some(SameSite::Lax),
+            ..TokenCookie::new("id")
+        };
+        assert_eq!(
+            not_err!(cookie.set_cookie(token)),
+            format!("id={}; Domain=example.com; SameSite=Lax", token)
+        );
+
+        assert!(TokenCookie::new("id").set_cookie("not a token").is_err());
+    }
+
+    #[test]
+    fn token_from_cookie_header() {
+        let cookies = "a=1; token=\"abc.def.\"; token=other;b=";
+        assert_eq!(token_from_cookies(cookies, "a"), Some("1"));
+        assert_eq!(token_from_cookies(cookies, "token"), Some("abc.def."));
+        assert_eq!(token_from_cookies(cookies, "b"), Some(""));
+        assert_eq!(token_from_cookies(cookies, "c"), None);
+        assert_eq!(token_from_cookies("", "a"), None);
+    }
+}
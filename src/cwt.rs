@@ -0,0 +1,489 @@
+//! [CBOR Web Tokens](https://tools.ietf.org/html/rfc8392) (CWT)
+//!
+//! A CWT carries the same claims as a JWT, encoded in [CBOR](crate::cbor) with integer keys for
+//! the registered claims, and protected with [COSE](https://tools.ietf.org/html/rfc9052) instead
+//! of JWS. Tokens are signed and verified with the same [`Secret`]s and algorithms as JWSs:
+//! the HMAC algorithms make a `COSE_Mac0` structure, and the others a `COSE_Sign1` structure.
+//! Claims are validated with the same [`ValidationOptions`] as JWTs, so a service can issue and
+//! accept both kinds of tokens from one set of keys and options.
+//!
+//! The `cti` claim is a byte string, which is the UTF-8 encoding of the `jti` claim of the
+//! [`ClaimsSet`]. A `cti` that is not UTF-8 is decoded as a base64url string. Private claims
+//! with integer keys are decoded with the decimal key as their name.
+//!
+//! This module is only available with the `cwt` feature.
+//!
+//! # Examples
+//! ```
+//! use biscuit::cwt;
+//! use biscuit::jwa::SignatureAlgorithm;
+//! use biscuit::jws::Secret;
+//! use biscuit::{ClaimsSet, RegisteredClaims, Validation, ValidationOptions};
+//! use serde_json::{json, Value};
+//!
+//! let secret = Secret::ecdsa_keypair_from_file(
+//!     SignatureAlgorithm::ES256,
+//!     "test/fixtures/ecdsa_private_key.p8",
+//! )
+//! .unwrap();
+//! let claims = ClaimsSet {
+//!     registered: RegisteredClaims {
+//!         issuer: Some("coap://as.example.com".to_string()),
+//!         ..Default::default()
+//!     },
+//!     private: json!({ "sensor": 12 }),
+//! };
+//! let token = cwt::encode(&claims, &secret, SignatureAlgorithm::ES256, Some(b"11")).unwrap();
+//! assert_eq!(cwt::key_id(&token).unwrap(), Some(b"11".to_vec()));
+//!
+//! let public_key = Secret::public_key_from_file("test/fixtures/ecdsa_public_key.der").unwrap();
+//! let options = ValidationOptions {
+//!     algorithms: Validation::Validate(vec![SignatureAlgorithm::ES256]),
+//!     ..Default::default()
+//! };
+//! let decoded: ClaimsSet<Value> = cwt::decode(&token, &public_key, &options).unwrap();
+//! assert_eq!(decoded, claims);
+//! ```
+use std::convert::TryFrom;
+
+use data_encoding::BASE64URL_NOPAD;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::cbor::Value;
+use crate::errors::{DecodeError, Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+use crate::jws::Secret;
+use crate::{ClaimsSet, ValidationOptions};
+
+/// The CBOR tag of a CWT, which may enclose the COSE structure
+pub const CWT_TAG: u64 = 61;
+
+/// The CBOR tag of a `COSE_Mac0` structure
+pub const COSE_MAC0_TAG: u64 = 17;
+
+/// The CBOR tag of a `COSE_Sign1` structure
+pub const COSE_SIGN1_TAG: u64 = 18;
+
+/// The keys of the registered claims, in the order of their integer keys
+const CLAIM_KEYS: [(&str, i128); 7] = [
+    ("iss", 1),
+    ("sub", 2),
+    ("aud", 3),
+    ("exp", 4),
+    ("nbf", 5),
+    ("iat", 6),
+    ("jti", 7),
+];
+
+/// The label of the `alg` header parameter
+const ALG: i128 = 1;
+/// The label of the `crit` header parameter
+const CRIT: i128 = 2;
+/// The label of the `kid` header parameter
+const KID: i128 = 4;
+
+/// The [COSE algorithm](https://www.iana.org/assignments/cose/cose.xhtml#algorithms) identifier
+/// of `algorithm`, if it has one
+pub fn cose_algorithm(algorithm: SignatureAlgorithm) -> Option<i64> {
+    use crate::jwa::SignatureAlgorithm::*;
+
+    match algorithm {
+        None => Option::None,
+        HS256 => Some(5),
+        HS384 => Some(6),
+        HS512 => Some(7),
+        RS256 => Some(-257),
+        RS384 => Some(-258),
+        RS512 => Some(-259),
+        ES256 => Some(-7),
+        ES384 => Some(-35),
+        ES512 => Some(-36),
+        PS256 => Some(-37),
+        PS384 => Some(-38),
+        PS512 => Some(-39),
+    }
+}
+
+/// The algorithm whose [`cose_algorithm`] identifier is `identifier`
+pub fn from_cose_algorithm(identifier: i64) -> Option<SignatureAlgorithm> {
+    use crate::jwa::SignatureAlgorithm::*;
+
+    [
+        HS256, HS384, HS512, RS256, RS384, RS512, ES256, ES384, ES512, PS256, PS384, PS512,
+    ]
+    .iter()
+    .cloned()
+    .find(|algorithm| cose_algorithm(*algorithm) == Some(identifier))
+}
+
+fn is_mac(algorithm: SignatureAlgorithm) -> bool {
+    use crate::jwa::SignatureAlgorithm::*;
+
+    algorithm == HS256 || algorithm == HS384 || algorithm == HS512
+}
+
+/// Sign `claims` with `secret` and `algorithm`, and return the encoded CWT
+///
+/// The COSE structure is tagged, but not enclosed in the [`CWT_TAG`]. `key_id` is set as the
+/// `kid` header parameter, which is unprotected. Returns [`Error::UnsupportedOperation`] for
+/// [`SignatureAlgorithm::None`], since COSE has no unsecured structure.
+pub fn encode<T: Serialize>(
+    claims: &ClaimsSet<T>,
+    secret: &Secret,
+    algorithm: SignatureAlgorithm,
+    key_id: Option<&[u8]>,
+) -> Result<Vec<u8>, Error> {
+    let identifier = cose_algorithm(algorithm).ok_or(Error::UnsupportedOperation)?;
+    let protected = Value::Map(vec![(
+        Value::Integer(ALG),
+        Value::Integer(identifier.into()),
+    )])
+    .to_vec()?;
+    let unprotected = match key_id {
+        Some(key_id) => vec![(Value::Integer(KID), Value::Bytes(key_id.to_vec()))],
+        None => vec![],
+    };
+    let payload = claims_to_cbor(claims)?.to_vec()?;
+
+    let signature = algorithm.sign(&to_be_signed(algorithm, &protected, &payload)?, secret)?;
+    let tag = if is_mac(algorithm) {
+        COSE_MAC0_TAG
+    } else {
+        COSE_SIGN1_TAG
+    };
+    Value::Tag(
+        tag,
+        Box::new(Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(unprotected),
+            Value::Bytes(payload),
+            Value::Bytes(signature),
+        ])),
+    )
+    .to_vec()
+}
+
+/// The `kid` header parameter of the CWT `token`, protected or not, which identifies the key
+/// that it was signed with. The signature is not verified.
+pub fn key_id(token: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let structure = Structure::parse(token)?;
+    for map in [&structure.protected, &structure.unprotected].iter() {
+        if let Some(Value::Bytes(key_id)) = header_parameter(map, KID) {
+            return Ok(Some(key_id.clone()));
+        }
+    }
+    Ok(None)
+}
+
+/// Verify the CWT `token` with `secret`, validate it according to `options`, and deserialize
+/// its claims set, like [`crate::decode`]
+///
+/// The algorithm in the protected header must be one of `options.algorithms`, and must match
+/// the COSE structure. Tokens with a `crit` header parameter are rejected, since no extensions
+/// are understood.
+pub fn decode<T>(
+    token: &[u8],
+    secret: &Secret,
+    options: &ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    crate::record_verification(
+        options.metrics.as_deref(),
+        &*options.temporal_options.clock,
+        || {
+            if let Some(max) = options.size_limits.max_token_length {
+                if token.len() > max {
+                    Err(DecodeError::TokenTooLong {
+                        max,
+                        actual: token.len(),
+                    })?
+                }
+            }
+            let structure = Structure::parse(token)?;
+            if header_parameter(&structure.protected, CRIT).is_some() {
+                Err(ValidationError::UnsupportedCriticalHeaders(vec![
+                    "crit".to_string()
+                ]))?
+            }
+            let algorithm = match header_parameter(&structure.protected, ALG) {
+                Some(Value::Integer(identifier)) => i64::try_from(*identifier)
+                    .ok()
+                    .and_then(from_cose_algorithm)
+                    .ok_or(ValidationError::UnsupportedKeyAlgorithm)?,
+                _ => Err(ValidationError::MissingAlgorithm)?,
+            };
+            crate::check_algorithm(algorithm, options)?;
+            if is_mac(algorithm) != (structure.tag == COSE_MAC0_TAG) {
+                Err(ValidationError::WrongAlgorithmHeader)?
+            }
+
+            let data = to_be_signed(algorithm, &structure.protected_bytes, &structure.payload)?;
+            algorithm.verify(&structure.signature, &data, secret)?;
+
+            let claims: ClaimsSet<T> =
+                serde_json::from_value(claims_from_cbor(Value::from_slice(&structure.payload)?)?)?;
+            claims.validate(options.clone())?;
+            Ok((algorithm, claims))
+        },
+    )
+}
+
+/// The `Sig_structure` or `MAC_structure` of a token, which is what is signed
+fn to_be_signed(
+    algorithm: SignatureAlgorithm,
+    protected: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let context = if is_mac(algorithm) {
+        "MAC0"
+    } else {
+        "Signature1"
+    };
+    Value::Array(vec![
+        Value::Text(context.to_string()),
+        Value::Bytes(protected.to_vec()),
+        // There is no external additional authenticated data
+        Value::Bytes(vec![]),
+        Value::Bytes(payload.to_vec()),
+    ])
+    .to_vec()
+}
+
+fn header_parameter(map: &[(Value, Value)], label: i128) -> Option<&Value> {
+    map.iter()
+        .find(|(key, _)| *key == Value::Integer(label))
+        .map(|(_, value)| value)
+}
+
+/// A decoded `COSE_Mac0` or `COSE_Sign1` structure
+struct Structure {
+    tag: u64,
+    protected_bytes: Vec<u8>,
+    protected: Vec<(Value, Value)>,
+    unprotected: Vec<(Value, Value)>,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl Structure {
+    fn parse(token: &[u8]) -> Result<Self, Error> {
+        let mut value = Value::from_slice(token)?;
+        if let Value::Tag(CWT_TAG, inner) = value {
+            value = *inner;
+        }
+        let (tag, parts) = match value {
+            Value::Tag(tag, inner) if tag == COSE_MAC0_TAG || tag == COSE_SIGN1_TAG => match *inner
+            {
+                Value::Array(parts) => (tag, parts),
+                _ => Err(DecodeError::InvalidToken)?,
+            },
+            _ => Err(DecodeError::InvalidToken)?,
+        };
+        if parts.len() != 4 {
+            Err(DecodeError::PartsLengthError {
+                expected: 4,
+                actual: parts.len(),
+            })?
+        }
+
+        let mut parts = parts.into_iter();
+        let (protected_bytes, unprotected, payload, signature) =
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (
+                    Some(Value::Bytes(protected)),
+                    Some(Value::Map(unprotected)),
+                    Some(Value::Bytes(payload)),
+                    Some(Value::Bytes(signature)),
+                ) => (protected, unprotected, payload, signature),
+                _ => Err(DecodeError::InvalidToken)?,
+            };
+
+        // An empty protected header is encoded as an empty byte string
+        let protected = if protected_bytes.is_empty() {
+            vec![]
+        } else {
+            match Value::from_slice(&protected_bytes)? {
+                Value::Map(map) => map,
+                _ => Err(DecodeError::InvalidToken)?,
+            }
+        };
+        Ok(Structure {
+            tag,
+            protected_bytes,
+            protected,
+            unprotected,
+            payload,
+            signature,
+        })
+    }
+}
+
+/// Encode the claims of `claims` as a CWT claims set, with the integer keys of the registered
+/// claims
+fn claims_to_cbor<T: Serialize>(claims: &ClaimsSet<T>) -> Result<Value, Error> {
+    let map = match serde_json::to_value(claims)? {
+        serde_json::Value::Object(map) => map,
+        _ => Err(Error::NotAnObject)?,
+    };
+    Ok(Value::Map(
+        map.into_iter()
+            .map(
+                |(name, value)| match CLAIM_KEYS.iter().find(|(claim, _)| *claim == name) {
+                    Some(&("jti", key)) => {
+                        let id = match value {
+                            serde_json::Value::String(id) => Value::Bytes(id.into_bytes()),
+                            value => Value::from_json(value),
+                        };
+                        (Value::Integer(key), id)
+                    }
+                    Some(&(_, key)) => (Value::Integer(key), Value::from_json(value)),
+                    None => (Value::Text(name), Value::from_json(value)),
+                },
+            )
+            .collect(),
+    ))
+}
+
+/// Decode a CWT claims set into the JSON representation of a [`ClaimsSet`]
+fn claims_from_cbor(claims: Value) -> Result<serde_json::Value, Error> {
+    let entries = match claims {
+        Value::Map(entries) => entries,
+        _ => Err(Error::NotAnObject)?,
+    };
+    let mut map = serde_json::Map::with_capacity(entries.len());
+    for (key, value) in entries {
+        let name = match key {
+            Value::Integer(key) => match CLAIM_KEYS.iter().find(|(_, claim)| *claim == key) {
+                Some(&(name, _)) => name.to_string(),
+                None => key.to_string(),
+            },
+            Value::Text(name) => name,
+            _ => Err(DecodeError::InvalidCbor(
+                "a claim key is not an integer or a text string".to_string(),
+            ))?,
+        };
+        let value = match value {
+            Value::Bytes(id) if name == "jti" => match String::from_utf8(id) {
+                Ok(id) => serde_json::Value::String(id),
+                Err(id) => serde_json::Value::String(BASE64URL_NOPAD.encode(id.as_bytes())),
+            },
+            value => value.into_json()?,
+        };
+        let _ = map.insert(name, value);
+    }
+    Ok(serde_json::Value::Object(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{RegisteredClaims, SingleOrMultiple, Validation};
+
+    /// The example CWT claims set of RFC 8392, appendix A.1
+    const RFC8392_CLAIMS: &str = "a70175636f61703a2f2f61732e6578616d706c652e636f6d02656572696b77\
+                                  037818636f61703a2f2f6c696768742e6578616d706c652e636f6d041a5612\
+                                  aeb0051a5610d9f0061a5610d9f007420b71";
+
+    fn hex(hex: &str) -> Vec<u8> {
+        not_err!(data_encoding::HEXLOWER.decode(hex.as_bytes()))
+    }
+
+    #[test]
+    fn rfc8392_claims() {
+        let claims: ClaimsSet<serde_json::Value> = not_err!(serde_json::from_value(not_err!(
+            claims_from_cbor(not_err!(Value::from_slice(&hex(RFC8392_CLAIMS))))
+        )));
+        assert_eq!(
+            claims.registered,
+            RegisteredClaims {
+                issuer: Some("coap://as.example.com".to_string()),
+                subject: Some("erikw".to_string()),
+                audience: Some(SingleOrMultiple::Single(
+                    "coap://light.example.com".to_string()
+                )),
+                expiry: Some(1_444_064_944.into()),
+                not_before: Some(1_443_944_944.into()),
+                issued_at: Some(1_443_944_944.into()),
+                // The `cti` is 0x0b71, which happens to be UTF-8
+                id: Some("\u{b}q".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn cwt_round_trip() {
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let claims = ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some("coap://as.example.com".to_string()),
+                audience: Some(SingleOrMultiple::Single(
+                    "coap://light.example.com".to_string(),
+                )),
+                expiry: Some((chrono::Utc::now().timestamp() + 60).into()),
+                id: Some("token-1".to_string()),
+                ..Default::default()
+            },
+            private: json!({ "scope": "read" }),
+        };
+        let token = not_err!(encode(&claims, &secret, SignatureAlgorithm::HS256, None));
+        // A tagged COSE_Mac0
+        assert_eq!(token[0], 0xd1);
+        assert_eq!(not_err!(key_id(&token)), None);
+
+        let options = ValidationOptions {
+            audience: Validation::Validate("coap://light.example.com".to_string()),
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+        let decoded: ClaimsSet<serde_json::Value> = not_err!(decode(&token, &secret, &options));
+        assert_eq!(decoded, claims);
+
+        // Enclosed in the CWT tag
+        let mut tagged = vec![0xd8, 0x3d];
+        tagged.extend_from_slice(&token);
+        let _ = not_err!(decode::<serde_json::Value>(&tagged, &secret, &options));
+
+        let other = Secret::bytes_from_str("another secret that is 32 bytes long");
+        match decode::<serde_json::Value>(&token, &other, &options) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::ES256]),
+            ..Default::default()
+        };
+        match decode::<serde_json::Value>(&token, &secret, &options) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cwt_algorithms() {
+        for algorithm in &[
+            SignatureAlgorithm::HS256,
+            SignatureAlgorithm::ES384,
+            SignatureAlgorithm::PS512,
+        ] {
+            let identifier = cose_algorithm(*algorithm).unwrap();
+            assert_eq!(from_cose_algorithm(identifier), Some(*algorithm));
+        }
+        assert_eq!(cose_algorithm(SignatureAlgorithm::None), None);
+        assert_eq!(from_cose_algorithm(-8), None);
+
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let claims = ClaimsSet {
+            registered: Default::default(),
+            private: json!({}),
+        };
+        match encode(&claims, &secret, SignatureAlgorithm::None, None) {
+            Err(Error::UnsupportedOperation) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
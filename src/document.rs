@@ -0,0 +1,227 @@
+//! Fetching and verifying JWS protected documents, like the entity statements of
+//! [OpenID Federation](https://openid.net/specs/openid-federation-1_0.html) or signed metadata
+//!
+//! This crate does not make HTTP requests. Implement [`Fetcher`] with an HTTP client, which
+//! should verify the TLS certificate of the server, and [`fetch_and_verify`] enforces the other
+//! safeguards: the URL must use `https`, and the document must not be longer than the limit.
+//!
+//! Documents are verified against [`TrustedKeys`], which are either a JWK Set, or the SHA-256
+//! [thumbprints](https://tools.ietf.org/html/rfc7638) of the keys in the `jwks` claim of
+//! self-signed documents.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::{DecodeError, Error, ValidationError};
+use crate::jwk::JWKSet;
+use crate::jws::Compact;
+use crate::{ClaimsSet, Empty, ValidationOptions};
+
+/// The default maximum length of a fetched document, in bytes
+pub const DEFAULT_MAX_LENGTH: usize = 256 * 1024;
+
+/// A client that downloads documents
+pub trait Fetcher {
+    /// Download the document at `url`
+    ///
+    /// Implementations should stop reading the response once it is longer than `max_length`
+    /// bytes; longer documents are rejected anyway.
+    fn fetch(&self, url: &str, max_length: usize) -> Result<Vec<u8>, Error>;
+}
+
+/// The keys that documents must be signed with
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TrustedKeys {
+    /// The key of the JWK Set that the `kid` header of the document refers to
+    Keys(JWKSet<Empty>),
+    /// A key in the `jwks` claim of the document itself, whose base64url encoded SHA-256 JWK
+    /// thumbprint is one of these
+    PinnedThumbprints(Vec<String>),
+}
+
+/// Download the document at `url` with `fetcher`, and verify it like [`verify`]
+///
+/// Returns [`Error::InvalidUri`] if `url` does not use `https`, and
+/// [`DecodeError::TokenTooLong`] if the document is longer than `max_length` bytes.
+pub fn fetch_and_verify<T, F>(
+    fetcher: &F,
+    url: &str,
+    max_length: usize,
+    keys: &TrustedKeys,
+    options: ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+    F: Fetcher + ?Sized,
+{
+    if !url.starts_with("https://") {
+        Err(Error::InvalidUri(url.to_string()))?
+    }
+    let document = fetcher.fetch(url, max_length)?;
+    if document.len() > max_length {
+        Err(DecodeError::TokenTooLong {
+            max: max_length,
+            actual: document.len(),
+        })?
+    }
+    verify(std::str::from_utf8(&document)?.trim(), keys, options)
+}
+
+/// Verify a compact JWS protected `document` with `keys`, validate it with `options`, and
+/// return its payload
+///
+/// With [`TrustedKeys::PinnedThumbprints`], only the keys of the `jwks` claim of the document
+/// whose thumbprint is pinned are used to verify it. Returns [`ValidationError::KeyNotFound`]
+/// if there are none.
+pub fn verify<T>(
+    document: &str,
+    keys: &TrustedKeys,
+    options: ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    match *keys {
+        TrustedKeys::Keys(ref jwks) => crate::providers::verify(document, jwks, options),
+        TrustedKeys::PinnedThumbprints(ref thumbprints) => {
+            let jwks = pinned_keys(document, thumbprints)?;
+            crate::providers::verify(document, &jwks, options)
+        }
+    }
+}
+
+/// The keys of the `jwks` claim of the unverified `document` whose thumbprint is pinned
+fn pinned_keys(document: &str, thumbprints: &[String]) -> Result<JWKSet<Empty>, Error> {
+    let claims: ClaimsSet<serde_json::Map<String, Value>> =
+        Compact::<_, Empty>::new_encoded(document).unverified_payload()?;
+    let jwks: JWKSet<Empty> = match claims.private.get("jwks") {
+        Some(jwks) => serde_json::from_value(jwks.clone())
+            .map_err(|_| ValidationError::MalformedClaim("jwks".to_string()))?,
+        None => Err(ValidationError::KeyNotFound)?,
+    };
+
+    let mut keys = vec![];
+    for key in jwks.keys {
+        let thumbprint = key.algorithm.thumbprint(&crate::digest::SHA256)?;
+        if thumbprints.contains(&thumbprint) {
+            keys.push(key);
+        }
+    }
+    if keys.is_empty() {
+        Err(ValidationError::KeyNotFound)?
+    }
+    Ok(JWKSet { keys })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jwa::SignatureAlgorithm;
+    use crate::jwk::JWK;
+    use crate::jws::{RegisteredHeader, Secret};
+
+    struct StaticFetcher(Vec<u8>);
+
+    impl Fetcher for StaticFetcher {
+        fn fetch(&self, url: &str, _: usize) -> Result<Vec<u8>, Error> {
+            assert_eq!(url, "https://op.example.org/.well-known/openid-federation");
+            Ok(self.0.clone())
+        }
+    }
+
+    fn entity_configuration() -> (String, JWK<Empty>) {
+        let mut key = JWK::<Empty>::new_octet_key(&[7; 32], Empty {});
+        key.common.key_id = Some("key0".to_string());
+        let claims: ClaimsSet<serde_json::Map<String, Value>> =
+            not_err!(serde_json::from_value(json!({
+                "iss": "https://op.example.org",
+                "sub": "https://op.example.org",
+                "jwks": { "keys": [key] },
+            })));
+        let header = RegisteredHeader {
+            algorithm: SignatureAlgorithm::HS256,
+            key_id: Some("key0".to_string()),
+            ..Default::default()
+        };
+        let secret = Secret::Bytes(vec![7; 32]);
+        let document =
+            not_err!(Compact::new_decoded(From::from(header), claims).into_encoded(&secret))
+                .unwrap_encoded()
+                .to_string();
+        (document, key)
+    }
+
+    #[test]
+    fn fetch_and_verify_self_signed_document() {
+        let (document, key) = entity_configuration();
+        let thumbprint = not_err!(key.algorithm.thumbprint(&crate::digest::SHA256));
+        let fetcher = StaticFetcher(format!("{}\n", document).into_bytes());
+        let url = "https://op.example.org/.well-known/openid-federation";
+
+        let pinned = TrustedKeys::PinnedThumbprints(vec![thumbprint]);
+        let claims: ClaimsSet<Value> = not_err!(fetch_and_verify(
+            &fetcher,
+            url,
+            DEFAULT_MAX_LENGTH,
+            &pinned,
+            Default::default()
+        ));
+        assert_eq!(
+            claims.registered.issuer.as_deref(),
+            Some("https://op.example.org")
+        );
+
+        let other = TrustedKeys::PinnedThumbprints(vec!["another thumbprint".to_string()]);
+        match fetch_and_verify::<Value, _>(
+            &fetcher,
+            url,
+            DEFAULT_MAX_LENGTH,
+            &other,
+            Default::default(),
+        ) {
+            Err(Error::ValidationError(ValidationError::KeyNotFound)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let jwks = TrustedKeys::Keys(JWKSet { keys: vec![key] });
+        let _ = not_err!(fetch_and_verify::<Value, _>(
+            &fetcher,
+            url,
+            DEFAULT_MAX_LENGTH,
+            &jwks,
+            Default::default()
+        ));
+    }
+
+    #[test]
+    fn fetch_and_verify_safeguards() {
+        let (document, key) = entity_configuration();
+        let fetcher = StaticFetcher(document.clone().into_bytes());
+        let keys = TrustedKeys::Keys(JWKSet { keys: vec![key] });
+
+        match fetch_and_verify::<Value, _>(
+            &fetcher,
+            "http://op.example.org/.well-known/openid-federation",
+            DEFAULT_MAX_LENGTH,
+            &keys,
+            Default::default(),
+        ) {
+            Err(Error::InvalidUri(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match fetch_and_verify::<Value, _>(
+            &fetcher,
+            "https://op.example.org/.well-known/openid-federation",
+            16,
+            &keys,
+            Default::default(),
+        ) {
+            Err(Error::DecodeError(DecodeError::TokenTooLong { max, actual })) => {
+                assert_eq!((max, actual), (16, document.len()))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
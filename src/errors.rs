@@ -7,13 +7,17 @@ use std::{error, fmt, io, str, string};
 /// All the errors we can encounter while signing/verifying tokens
 /// and a couple of custom one for when the token we are trying
 /// to verify is invalid
+#[non_exhaustive]
 pub enum Error {
-    /// A generic error which is described by the contained string
+    /// A generic error which is described by the contained string.
+    /// The crate itself returns more specific variants.
     GenericError(String),
     /// Error returned from failed token decoding
     DecodeError(DecodeError),
     /// Error returned from failed token validation
     ValidationError(ValidationError),
+    /// Every failure found while validating a token, when all checks are run
+    ValidationReport(ValidationReport),
     /// Error during the serialization or deserialization of tokens
     JsonError(serde_json::error::Error),
     /// Error during base64 encoding or decoding
@@ -45,10 +49,49 @@ pub enum Error {
     UnspecifiedCryptographicError,
     /// An unsupported or invalid operation
     UnsupportedOperation,
+    /// Private claims have the same names as registered claims, which is rejected by
+    /// [`ClaimCollisions::Reject`](crate::ClaimCollisions::Reject)
+    ClaimCollision(Vec<String>),
+    /// The name of an algorithm was not recognised
+    UnknownAlgorithm {
+        /// The name that was parsed
+        name: String,
+        /// The names of the supported algorithms
+        supported: Vec<&'static str>,
+    },
+    /// A registered claim, or another reserved name, was set where it is not allowed.
+    /// The parameter shows the name
+    ReservedClaim(String),
+    /// A value contains a `:` but is not a valid URI, so it is not a valid `StringOrURI`.
+    /// The parameter shows the value
+    InvalidUri(String),
+    /// Claims or parameters were not serialized to a JSON object
+    NotAnObject,
+    /// A claim was expected but is not present.
+    /// The parameter shows the name of the claim
+    ClaimNotFound(String),
+    /// An array element was expected but is not present.
+    /// The parameter shows the index of the element
+    ElementNotFound(usize),
+    /// A key is shorter than the minimum required by [RFC 7518](https://tools.ietf.org/html/rfc7518)
+    /// for the algorithm
+    WeakKey {
+        /// The minimum length of the key, in bits
+        minimum_bits: usize,
+        /// The length of the key, in bits
+        actual_bits: usize,
+    },
+    /// A certificate could not be parsed, or does not hold an RSA public key.
+    /// The parameter shows the ID of the key
+    InvalidCertificate(String),
+    /// A time cannot be represented as a [`crate::Timestamp`].
+    /// The parameter shows the number of seconds since the Unix Epoch
+    TimestampOutOfRange(i64),
 }
 
 #[derive(Debug)]
 /// Errors from decoding tokens
+#[non_exhaustive]
 pub enum DecodeError {
     /// Token is invalid in structure or form
     InvalidToken,
@@ -59,18 +102,63 @@ pub enum DecodeError {
         /// Actual number of parts
         actual: usize,
     },
+    /// The token is longer than allowed by [`crate::SizeLimits`]
+    TokenTooLong {
+        /// Maximum length allowed, in bytes
+        max: usize,
+        /// Actual length
+        actual: usize,
+    },
+    /// A part of the token is longer than allowed by [`crate::SizeLimits`]
+    SegmentTooLong {
+        /// Index of the part
+        index: usize,
+        /// Maximum length allowed, in bytes
+        max: usize,
+        /// Actual length
+        actual: usize,
+    },
+    /// The JSON of the claims is more complex than allowed by [`crate::JsonLimits`]
+    JsonLimitExceeded {
+        /// The limit that was exceeded: `depth`, `claims` or `string length`
+        limit: &'static str,
+        /// The value of the limit
+        max: usize,
+    },
+    /// The payload of the token is not valid CBOR, or cannot be represented as claims.
+    /// The parameter shows the reason
+    InvalidCbor(String),
+    /// The payload of the token is not valid MessagePack, or cannot be represented as claims.
+    /// The parameter shows the reason
+    InvalidMessagePack(String),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 /// Errors from validating tokens
+#[non_exhaustive]
 pub enum ValidationError {
     /// Token has an invalid signature (RFC7523 3.9)
     InvalidSignature,
     /// Token provided was signed or encrypted with an unexpected algorithm
     WrongAlgorithmHeader,
+    /// Token is unsecured, with the `none` algorithm, and
+    /// [`allow_unsecured_tokens`](crate::ValidationOptions::allow_unsecured_tokens) is not set
+    UnsecuredToken,
+    /// The `typ` header of the token is missing or is not the expected media type.
+    /// The parameter shows the `typ` of the token
+    InvalidTokenType(Option<String>),
+    /// The `cty` header of the token is missing or is not the media type of the payload
+    /// encoding that is expected. The parameter shows the `cty` of the token
+    InvalidContentType(Option<String>),
+    /// The `crit` header of the token lists extension headers that are not understood, or is
+    /// empty. The parameter shows the headers that are not understood
+    UnsupportedCriticalHeaders(Vec<String>),
     /// A field required is missing from the token
     /// The parameter shows the name of the missing claim
     MissingRequiredClaims(Vec<String>),
+    /// Claims that are not allowed are present in the token.
+    /// The parameter shows the names of the forbidden claims
+    ForbiddenClaims(Vec<String>),
     /// The token's expiry has passed (exp check failled, RFC7523 3.4)
     /// The parameter show how long the token has expired
     Expired(Duration),
@@ -83,10 +171,55 @@ pub enum ValidationError {
     /// some more sensitive operations.
     /// The parameter show how much older the token is than required
     TooOld(Duration),
+    /// The token is valid for longer than allowed, from its `iat` claim or else from now until
+    /// its `exp` claim.
+    /// The parameter shows how much longer the token is valid for than allowed
+    ValidityTooLong(Duration),
     /// The token does not have or has the wrong issuer (iss check failed, RFC7523 3.1)
     InvalidIssuer(String),
     /// The token does not have or has the wrong audience (aud check failed, RFC7523 3.3
     InvalidAudience(SingleOrMultiple<String>),
+    /// The token has not been granted the scopes required.
+    /// The parameter shows the scopes that are missing
+    InsufficientScope(Vec<String>),
+    /// The token does not have the roles, permissions or groups required.
+    /// The parameters show the claim that was checked and the values that are missing
+    MissingRoles(String, Vec<String>),
+    /// A claim assertion failed.
+    /// The parameter shows the JSON pointer to the claim that was checked
+    FailedClaimAssertion(String),
+    /// A claim has a value that is not allowed by its specification.
+    /// The parameter shows the name of the malformed claim
+    MalformedClaim(String),
+    /// The token's `azp` claim is not the expected authorized party.
+    /// The parameter shows the `azp` of the token
+    InvalidAuthorizedParty(String),
+    /// The token's `client_id` claim is not the expected client.
+    /// The parameter shows the `client_id` of the token
+    InvalidClientId(String),
+    /// The token's `nonce` claim does not match the nonce of the authentication request
+    InvalidNonce,
+    /// The token's `at_hash` or `c_hash` claim does not match the access token or authorization
+    /// code. The parameter shows the name of the claim
+    InvalidTokenHash(String),
+    /// The SPIFFE ID in the token's `sub` claim is not in the expected trust domain.
+    /// The parameter shows the trust domain of the token
+    InvalidTrustDomain(String),
+    /// The authorization server reported that the token is not active, in the response to an
+    /// [introspection request](https://tools.ietf.org/html/rfc7662)
+    InactiveToken,
+    /// The key or certificate presented does not match the token's `cnf` claim
+    ConfirmationMismatch,
+    /// A disclosure of an SD-JWT is malformed, is not referenced by the token, or would replace
+    /// an existing claim. The parameter shows the encoded disclosure
+    InvalidDisclosure(String),
+    /// An SD-JWT presentation does not have the key binding JWT that is required
+    MissingKeyBinding,
+    /// The token's `jti` has been seen before, indicating that the token has been replayed.
+    /// The parameter shows the offending `jti`
+    Replayed(String),
+    /// The token has been revoked, according to the [`crate::Denylist`]
+    Revoked,
     /// The token doesn't contains the Kid claim in the header
     KidMissing,
     /// The by the Kid specified key, wasn't found in the KeySet
@@ -95,6 +228,86 @@ pub enum ValidationError {
     UnsupportedKeyAlgorithm,
     /// An algorithm is needed for verification but was not provided
     MissingAlgorithm,
+    /// A custom `ClaimsValidator` rejected the token.
+    /// The parameter describes why the token was rejected
+    CustomValidation(String),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+/// Every failure found while validating a token
+///
+/// This is returned when validation is asked to run every check instead of stopping at the
+/// first failure, for example with [`crate::ClaimsSet::validate_all`].
+pub struct ValidationReport {
+    errors: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    /// Create a report from a list of validation failures
+    pub fn new(errors: Vec<ValidationError>) -> Self {
+        Self { errors }
+    }
+
+    /// The validation failures, in the order that the checks were run
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    /// Consumes self and returns the validation failures
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    /// Yields an iterator over the validation failures
+    pub fn iter(&self) -> std::slice::Iter<'_, ValidationError> {
+        self.errors.iter()
+    }
+
+    /// Returns the number of validation failures
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns whether there are no validation failures
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+/// Why a signature is invalid, found by [`crate::jwa::SignatureAlgorithm::diagnose`]
+#[non_exhaustive]
+pub enum SignatureFailure {
+    /// The signature is not as long as the signatures of the algorithm and key
+    WrongLength {
+        /// Length of the signatures of the algorithm and key, in bytes
+        expected: usize,
+        /// Actual length of the signature
+        actual: usize,
+    },
+    /// The signature does not match the data and the key.
+    ///
+    /// ring does not tell a mismatch apart from a public key that cannot be parsed, so this is
+    /// also the failure for malformed public keys.
+    Mismatch,
+}
+
+impl<'a> IntoIterator for &'a ValidationReport {
+    type Item = &'a ValidationError;
+    type IntoIter = std::slice::Iter<'a, ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for ValidationReport {
+    type Item = ValidationError;
+    type IntoIter = std::vec::IntoIter<ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
 }
 
 macro_rules! impl_from_error {
@@ -112,6 +325,7 @@ impl_from_error!(serde_json::error::Error, Error::JsonError);
 impl_from_error!(data_encoding::DecodeError, Error::DecodeBase64);
 impl_from_error!(str::Utf8Error, Error::Utf8);
 impl_from_error!(ValidationError, Error::ValidationError);
+impl_from_error!(ValidationReport, Error::ValidationReport);
 impl_from_error!(DecodeError, Error::DecodeError);
 impl_from_error!(io::Error, Error::IOError);
 impl_from_error!(ring::error::KeyRejected, Error::KeyRejected);
@@ -139,6 +353,7 @@ impl fmt::Display for Error {
             Utf8(ref err) => fmt::Display::fmt(err, f),
             DecodeError(ref err) => fmt::Display::fmt(err, f),
             ValidationError(ref err) => fmt::Display::fmt(err, f),
+            ValidationReport(ref err) => fmt::Display::fmt(err, f),
             IOError(ref err) => fmt::Display::fmt(err, f),
             KeyRejected(ref err) => fmt::Display::fmt(err, f),
             WrongKeyType {
@@ -159,6 +374,43 @@ impl fmt::Display for Error {
             ),
             UnspecifiedCryptographicError => write!(f, "An unspecified cryptographic error"),
             UnsupportedOperation => write!(f, "This operation is not supported"),
+            ClaimCollision(ref claims) => write!(
+                f,
+                "Private claims collide with registered claims: {}",
+                claims.join(", ")
+            ),
+            UnknownAlgorithm {
+                ref name,
+                ref supported,
+            } => write!(
+                f,
+                "Unknown algorithm {:?}, expected one of: {}",
+                name,
+                supported.join(", ")
+            ),
+            ReservedClaim(ref name) => write!(f, "{:?} is reserved and cannot be set", name),
+            InvalidUri(ref value) => {
+                write!(f, "{:?} contains a ':' but is not a valid URI", value)
+            }
+            NotAnObject => write!(f, "Value must be serializable to a JSON object"),
+            ClaimNotFound(ref name) => write!(f, "Claim {:?} is not present", name),
+            ElementNotFound(index) => write!(f, "No array element at index {}", index),
+            WeakKey {
+                minimum_bits,
+                actual_bits,
+            } => write!(
+                f,
+                "Key of {} bits is shorter than the minimum of {} bits",
+                actual_bits, minimum_bits
+            ),
+            InvalidCertificate(ref key_id) => {
+                write!(f, "The certificate of key {:?} is invalid", key_id)
+            }
+            TimestampOutOfRange(seconds) => write!(
+                f,
+                "{} seconds since the Unix Epoch is out of the range of a timestamp",
+                seconds
+            ),
         }
     }
 }
@@ -173,6 +425,7 @@ impl error::Error for Error {
             Utf8(ref err) => Some(err),
             DecodeError(ref err) => Some(err),
             ValidationError(ref err) => Some(err),
+            ValidationReport(ref err) => Some(err),
             IOError(ref err) => Some(err),
             _ => None,
         }
@@ -190,6 +443,21 @@ impl fmt::Display for DecodeError {
                 "Expected {} parts in Compact JSON representation but got {}",
                 expected, actual
             ),
+            TokenTooLong { max, actual } => write!(
+                f,
+                "Token is {} bytes long, which is more than the maximum of {}",
+                actual, max
+            ),
+            SegmentTooLong { index, max, actual } => write!(
+                f,
+                "Part {} of the token is {} bytes long, which is more than the maximum of {}",
+                index, actual, max
+            ),
+            JsonLimitExceeded { limit, max } => {
+                write!(f, "Claims exceed the maximum {} of {}", limit, max)
+            }
+            InvalidCbor(ref reason) => write!(f, "Invalid CBOR: {}", reason),
+            InvalidMessagePack(ref reason) => write!(f, "Invalid MessagePack: {}", reason),
         }
     }
 }
@@ -210,6 +478,11 @@ impl fmt::Display for ValidationError {
                 "The following claims are required, but missing: {:?}",
                 fields
             ),
+            ForbiddenClaims(ref fields) => write!(
+                f,
+                "The following claims are not allowed, but present: {:?}",
+                fields
+            ),
             Expired(ago) => write!(f, "Token expired {} seconds ago", ago.num_seconds()),
             NotYetValid(nyv_for) => write!(
                 f,
@@ -221,13 +494,57 @@ impl fmt::Display for ValidationError {
                 "Token has been considered too old for {} seconds",
                 duration.num_seconds()
             ),
+            ValidityTooLong(duration) => write!(
+                f,
+                "Token is valid for {} seconds longer than allowed",
+                duration.num_seconds()
+            ),
             InvalidIssuer(ref iss) => write!(f, "Issuer of token is invalid: {:?}", iss),
             InvalidAudience(ref aud) => write!(f, "Audience of token is invalid: {:?}", aud),
+            InsufficientScope(ref scopes) => {
+                write!(f, "Token is missing the required scopes: {:?}", scopes)
+            }
+            MissingRoles(ref claim, ref values) => write!(
+                f,
+                "Claim {:?} is missing the required values: {:?}",
+                claim, values
+            ),
+            FailedClaimAssertion(ref pointer) => {
+                write!(f, "Assertion on claim {:?} failed", pointer)
+            }
+            MalformedClaim(ref claim) => write!(f, "Claim {:?} is malformed", claim),
+            InvalidAuthorizedParty(ref azp) => {
+                write!(f, "Authorized party of token is invalid: {:?}", azp)
+            }
+            InvalidClientId(ref client_id) => {
+                write!(f, "Client ID of token is invalid: {:?}", client_id)
+            }
+            InvalidNonce => write!(f, "Nonce of token is invalid"),
+            InvalidTokenHash(ref claim) => write!(f, "Claim {:?} does not match", claim),
+            InvalidTrustDomain(ref trust_domain) => {
+                write!(f, "Trust domain of token is invalid: {:?}", trust_domain)
+            }
+            InactiveToken => write!(f, "Token is not active"),
+            ConfirmationMismatch => write!(f, "Key does not match the confirmation claim"),
+            InvalidDisclosure(ref disclosure) => {
+                write!(f, "Disclosure {:?} is invalid", disclosure)
+            }
+            MissingKeyBinding => write!(f, "SD-JWT is missing the key binding JWT"),
+            Replayed(ref jti) => write!(f, "Token with ID {:?} has already been used", jti),
+            Revoked => write!(f, "Token has been revoked"),
             InvalidSignature => write!(f, "Invalid signature"),
+            InvalidTokenType(ref typ) => write!(f, "Token type is invalid: {:?}", typ),
+            InvalidContentType(ref cty) => write!(f, "Content type is invalid: {:?}", cty),
+            UnsupportedCriticalHeaders(ref headers) => write!(
+                f,
+                "Token has critical headers that are not understood: {:?}",
+                headers
+            ),
             WrongAlgorithmHeader => write!(
                 f,
                 "Token provided was signed or encrypted with an unexpected algorithm"
             ),
+            UnsecuredToken => write!(f, "Token is unsecured, which is not allowed"),
             KidMissing => write!(f, "Header is missing kid"),
             KeyNotFound => write!(f, "Key not found in JWKS"),
             UnsupportedKeyAlgorithm => write!(f, "Algorithm of JWK not supported"),
@@ -235,7 +552,25 @@ impl fmt::Display for ValidationError {
                 f,
                 "An algorithm is needed for verification but was not provided"
             ),
+            CustomValidation(ref reason) => write!(f, "Token was rejected: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token failed {} validation check(s)", self.errors.len())?;
+        for (i, error) in self.errors.iter().enumerate() {
+            let separator = if i == 0 { ": " } else { "; " };
+            write!(f, "{}{}", separator, error)?;
         }
+        Ok(())
+    }
+}
+
+impl error::Error for ValidationReport {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
     }
 }
 
@@ -244,3 +579,24 @@ impl error::Error for ValidationError {
         None
     }
 }
+
+impl fmt::Display for SignatureFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::SignatureFailure::*;
+
+        match *self {
+            WrongLength { expected, actual } => write!(
+                f,
+                "Signature is {} bytes long, but {} bytes were expected",
+                actual, expected
+            ),
+            Mismatch => write!(f, "Signature does not match the data and the key"),
+        }
+    }
+}
+
+impl error::Error for SignatureFailure {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::errors::ValidationError;
+
+/// An actor in a delegation chain, used for the `act` (actor) claim of
+/// [RFC8693#4.1](https://tools.ietf.org/html/rfc8693#section-4.1)
+///
+/// The outermost `act` claim identifies the current actor, and any nested `act` claim identifies
+/// the actor that delegated to it, and so on. Additional claims identifying the actor are kept in
+/// `additional`.
+///
+/// # Examples
+/// ```
+/// use biscuit::Actor;
+/// use serde_json::json;
+///
+/// let claims = json!({
+///     "sub": "user@example.com",
+///     "act": {
+///         "sub": "service-16",
+///         "act": { "sub": "admin@example.com" }
+///     }
+/// });
+///
+/// let actor = Actor::from_claims(claims.as_object().unwrap()).unwrap().unwrap();
+/// let chain: Vec<_> = actor.chain().filter_map(|actor| actor.subject.as_deref()).collect();
+/// assert_eq!(chain, vec!["service-16", "admin@example.com"]);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub struct Actor {
+    /// The subject of the actor. Serialized to `sub`.
+    #[serde(rename = "sub", skip_serializing_if = "Option::is_none", default)]
+    pub subject: Option<String>,
+
+    /// The issuer of the subject of the actor. Serialized to `iss`.
+    #[serde(rename = "iss", skip_serializing_if = "Option::is_none", default)]
+    pub issuer: Option<String>,
+
+    /// The actor that this actor is acting on behalf of. Serialized to `act`.
+    #[serde(rename = "act", skip_serializing_if = "Option::is_none", default)]
+    pub actor: Option<Box<Actor>>,
+
+    /// Other claims identifying the actor
+    #[serde(flatten)]
+    pub additional: Map<String, Value>,
+}
+
+impl Actor {
+    /// Create an actor identified by its subject
+    pub fn new<S: Into<String>>(subject: S) -> Self {
+        Actor {
+            subject: Some(subject.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Record that this actor is acting on behalf of `actor`, replacing any previous delegation.
+    ///
+    /// Use this when building a delegation token, after a token exchange, to record the new
+    /// current actor on top of the `act` claim of the subject token.
+    pub fn delegated_by(mut self, actor: Actor) -> Self {
+        self.actor = Some(Box::new(actor));
+        self
+    }
+
+    /// Read the `act` claim from a claims set, returning `None` if it is missing
+    ///
+    /// Returns [`ValidationError::MalformedClaim`] if the claim is not a valid actor.
+    pub fn from_claims(claims: &Map<String, Value>) -> Result<Option<Self>, ValidationError> {
+        match claims.get("act") {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => Actor::deserialize(value)
+                .map(Some)
+                .map_err(|_| ValidationError::MalformedClaim("act".to_string())),
+        }
+    }
+
+    /// Yields an iterator over this actor and every actor in its delegation chain, from the
+    /// current actor to the earliest one
+    pub fn chain(&self) -> ActorChain<'_> {
+        ActorChain { next: Some(self) }
+    }
+}
+
+/// Iterator over a delegation chain, returned by [`Actor::chain`]
+#[derive(Clone, Debug)]
+pub struct ActorChain<'a> {
+    next: Option<&'a Actor>,
+}
+
+impl<'a> Iterator for ActorChain<'a> {
+    type Item = &'a Actor;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next?;
+        self.next = current.actor.as_deref();
+        Some(current)
+    }
+}
+
+/// The `may_act` (authorized actor) claim of [RFC8693#4.4](https://tools.ietf.org/html/rfc8693#section-4.4),
+/// identifying a party that is allowed to act on behalf of the subject of the token
+///
+/// # Examples
+/// ```
+/// use biscuit::{Actor, AuthorizedActor};
+/// use serde_json::json;
+///
+/// let claims = json!({ "may_act": { "sub": "admin@example.com" } });
+/// let may_act = AuthorizedActor::from_claims(claims.as_object().unwrap()).unwrap().unwrap();
+/// assert!(may_act.permits(&Actor::new("admin@example.com")));
+/// assert!(!may_act.permits(&Actor::new("someone@example.com")));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub struct AuthorizedActor {
+    /// The subject of the authorized actor. Serialized to `sub`.
+    #[serde(rename = "sub", skip_serializing_if = "Option::is_none", default)]
+    pub subject: Option<String>,
+
+    /// The issuer of the subject of the authorized actor. Serialized to `iss`.
+    #[serde(rename = "iss", skip_serializing_if = "Option::is_none", default)]
+    pub issuer: Option<String>,
+
+    /// Other claims identifying the authorized actor
+    #[serde(flatten)]
+    pub additional: Map<String, Value>,
+}
+
+impl AuthorizedActor {
+    /// Read the `may_act` claim from a claims set, returning `None` if it is missing
+    ///
+    /// Returns [`ValidationError::MalformedClaim`] if the claim is not a valid authorized actor.
+    pub fn from_claims(claims: &Map<String, Value>) -> Result<Option<Self>, ValidationError> {
+        match claims.get("may_act") {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => AuthorizedActor::deserialize(value)
+                .map(Some)
+                .map_err(|_| ValidationError::MalformedClaim("may_act".to_string())),
+        }
+    }
+
+    /// Returns whether `actor` is the authorized actor: every claim identifying the authorized
+    /// actor must be present in `actor` with the same value
+    pub fn permits(&self, actor: &Actor) -> bool {
+        let matches = |expected: &Option<String>, actual: &Option<String>| {
+            expected.is_none() || expected == actual
+        };
+        matches(&self.subject, &actor.subject)
+            && matches(&self.issuer, &actor.issuer)
+            && self
+                .additional
+                .iter()
+                .all(|(claim, value)| actor.additional.get(claim) == Some(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn actor_chain_round_trip() {
+        let actor =
+            Actor::new("service-77").delegated_by(Actor::new("service-16").delegated_by(Actor {
+                issuer: Some("https://issuer.example.net".to_string()),
+                ..Actor::new("admin@example.net")
+            }));
+
+        let serialized = not_err!(serde_json::to_value(&actor));
+        assert_eq!(
+            serialized,
+            json!({
+                "sub": "service-77",
+                "act": {
+                    "sub": "service-16",
+                    "act": { "sub": "admin@example.net", "iss": "https://issuer.example.net" }
+                }
+            })
+        );
+
+        let claims = json!({ "act": serialized });
+        let deserialized = not_err!(Actor::from_claims(claims.as_object().unwrap()));
+        assert_eq!(deserialized.as_ref(), Some(&actor));
+        assert_eq!(actor.chain().count(), 3);
+    }
+
+    #[test]
+    fn actor_from_claims() {
+        assert_eq!(not_err!(Actor::from_claims(&Map::new())), None);
+
+        let claims = json!({ "act": "service-77" });
+        assert_eq!(
+            Actor::from_claims(claims.as_object().unwrap()),
+            Err(ValidationError::MalformedClaim("act".to_string()))
+        );
+    }
+
+    #[test]
+    fn authorized_actor_permits() {
+        let may_act = AuthorizedActor {
+            subject: Some("admin@example.net".to_string()),
+            issuer: Some("https://issuer.example.net".to_string()),
+            ..Default::default()
+        };
+
+        assert!(may_act.permits(&Actor {
+            issuer: Some("https://issuer.example.net".to_string()),
+            ..Actor::new("admin@example.net")
+        }));
+        assert!(!may_act.permits(&Actor::new("admin@example.net")));
+        assert!(AuthorizedActor::default().permits(&Actor::new("anyone")));
+    }
+}
@@ -0,0 +1,187 @@
+use serde_json::{Map, Value};
+
+use super::validator::ClaimsValidator;
+use crate::errors::ValidationError;
+
+/// An assertion on a claim addressed by a [JSON pointer](https://tools.ietf.org/html/rfc6901),
+/// which can be nested in objects and arrays
+///
+/// This can be registered as a validator in [`crate::ValidationOptions`] to validate deeply
+/// nested claims declaratively, like the roles that some identity providers issue.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{ClaimAssertion, ClaimsSet, RegisteredClaims, ValidationOptions};
+/// use serde_json::json;
+///
+/// let options = ValidationOptions {
+///     validators: vec![
+///         Arc::new(ClaimAssertion::contains("/resource_access/my-api/roles", json!("admin"))),
+///         Arc::new(ClaimAssertion::equals("/tenant/region", json!("eu"))),
+///     ],
+///     ..Default::default()
+/// };
+///
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims::default(),
+///     private: json!({
+///         "resource_access": { "my-api": { "roles": ["admin", "user"] } },
+///         "tenant": { "region": "eu" },
+///     }),
+/// };
+/// assert!(claims.validate(options).is_ok());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimAssertion {
+    pointer: String,
+    assertion: Assertion,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Assertion {
+    Exists,
+    Equals(Value),
+    OneOf(Vec<Value>),
+    Contains(Value),
+}
+
+impl ClaimAssertion {
+    /// Assert that the claim at `pointer` is present and not `null`
+    pub fn exists<S: Into<String>>(pointer: S) -> Self {
+        Self::new(pointer, Assertion::Exists)
+    }
+
+    /// Assert that the claim at `pointer` is `value`
+    pub fn equals<S: Into<String>>(pointer: S, value: Value) -> Self {
+        Self::new(pointer, Assertion::Equals(value))
+    }
+
+    /// Assert that the claim at `pointer` is one of `values`
+    pub fn one_of<S: Into<String>>(pointer: S, values: Vec<Value>) -> Self {
+        Self::new(pointer, Assertion::OneOf(values))
+    }
+
+    /// Assert that the claim at `pointer` is an array containing `value`, or is `value` itself
+    pub fn contains<S: Into<String>>(pointer: S, value: Value) -> Self {
+        Self::new(pointer, Assertion::Contains(value))
+    }
+
+    fn new<S: Into<String>>(pointer: S, assertion: Assertion) -> Self {
+        ClaimAssertion {
+            pointer: pointer.into(),
+            assertion,
+        }
+    }
+
+    /// The JSON pointer to the claim
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+impl ClaimsValidator for ClaimAssertion {
+    /// Returns [`ValidationError::MissingRequiredClaims`] with the pointer if the claim is missing,
+    /// and [`ValidationError::FailedClaimAssertion`] if it does not satisfy the assertion.
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        let value = match lookup(claims, &self.pointer) {
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![self
+                .pointer
+                .clone()]))?,
+            Some(value) => value,
+        };
+
+        let satisfied = match self.assertion {
+            Assertion::Exists => true,
+            Assertion::Equals(ref expected) => value == expected,
+            Assertion::OneOf(ref expected) => expected.contains(value),
+            Assertion::Contains(ref expected) => match *value {
+                Value::Array(ref values) => values.contains(expected),
+                ref value => value == expected,
+            },
+        };
+        if satisfied {
+            Ok(())
+        } else {
+            Err(ValidationError::FailedClaimAssertion(self.pointer.clone()))
+        }
+    }
+}
+
+/// Resolves a JSON pointer against the claims set. Returns `None` if the pointer is invalid or
+/// the claim is missing.
+fn lookup<'a>(claims: &'a Map<String, Value>, pointer: &str) -> Option<&'a Value> {
+    if !pointer.starts_with('/') {
+        return None;
+    }
+    let pointer = &pointer[1..];
+    let (claim, rest) = match pointer.find('/') {
+        Some(index) => pointer.split_at(index),
+        None => (pointer, ""),
+    };
+    let claim = claim.replace("~1", "/").replace("~0", "~");
+    claims.get(&claim)?.pointer(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn claims() -> Map<String, Value> {
+        match json!({
+            "resource_access": { "my-api": { "roles": ["admin", "user"] } },
+            "a/b": { "m~n": 1 },
+            "groups": [{ "name": "staff" }],
+            "level": "gold",
+        }) {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn json_pointer_lookup() {
+        let claims = claims();
+        assert_eq!(
+            lookup(&claims, "/resource_access/my-api/roles/1"),
+            Some(&json!("user"))
+        );
+        assert_eq!(lookup(&claims, "/a~1b/m~0n"), Some(&json!(1)));
+        assert_eq!(lookup(&claims, "/groups/0/name"), Some(&json!("staff")));
+        assert_eq!(lookup(&claims, "/groups/1/name"), None);
+        assert_eq!(lookup(&claims, "level"), None);
+    }
+
+    #[test]
+    fn claim_assertions() {
+        let claims = claims();
+
+        not_err!(ClaimAssertion::exists("/groups/0").validate(&claims));
+        not_err!(ClaimAssertion::equals("/level", json!("gold")).validate(&claims));
+        not_err!(
+            ClaimAssertion::one_of("/level", vec![json!("silver"), json!("gold")])
+                .validate(&claims)
+        );
+        not_err!(
+            ClaimAssertion::contains("/resource_access/my-api/roles", json!("admin"))
+                .validate(&claims)
+        );
+        not_err!(ClaimAssertion::contains("/level", json!("gold")).validate(&claims));
+
+        assert_eq!(
+            ClaimAssertion::contains("/resource_access/my-api/roles", json!("root"))
+                .validate(&claims),
+            Err(ValidationError::FailedClaimAssertion(
+                "/resource_access/my-api/roles".to_string()
+            ))
+        );
+        assert_eq!(
+            ClaimAssertion::equals("/resource_access/other-api", json!({})).validate(&claims),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "/resource_access/other-api".to_string()
+            ]))
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use serde_json::{Map, Value};
+
+use super::clock::Clock;
+use super::id_generator::{IdGenerator, RandomUuid};
+use super::issuance::IssuanceOptions;
+use super::metrics::Metrics;
+use crate::errors::Error;
+use crate::jwa::SignatureAlgorithm;
+use crate::jws::{self, HeaderOrder, RegisteredHeader, Secret};
+use crate::{ClaimsSet, RegisteredClaims, SingleOrMultiple, REGISTERED_CLAIMS};
+
+/// Entry point for building and signing tokens with a [`TokenBuilder`]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub struct Token;
+
+impl Token {
+    /// Start building a token
+    pub fn builder() -> TokenBuilder {
+        TokenBuilder::default()
+    }
+}
+
+/// A fluent builder of signed JWTs, for issuing simple tokens without constructing the header,
+/// claims set and [`jws::Compact`] manually
+///
+/// Relative times are resolved when the token is signed, like [`IssuanceOptions`].
+///
+/// # Examples
+/// ```
+/// use biscuit::Token;
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+/// use chrono::Duration;
+/// use serde_json::json;
+///
+/// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+/// let token = Token::builder()
+///     .issuer("https://www.acme.com")
+///     .audience("https://api.acme.com")
+///     .subject("John Doe")
+///     .claim("tenant", json!("ACME"))
+///     .expires_in(Duration::minutes(5))
+///     .sign(&secret, SignatureAlgorithm::HS256)
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TokenBuilder {
+    header: RegisteredHeader,
+    registered: RegisteredClaims,
+    private: Map<String, Value>,
+    options: IssuanceOptions,
+}
+
+impl TokenBuilder {
+    /// Set the `iss` claim
+    pub fn issuer<S: Into<String>>(mut self, issuer: S) -> Self {
+        self.registered.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Set the `sub` claim
+    pub fn subject<S: Into<String>>(mut self, subject: S) -> Self {
+        self.registered.subject = Some(subject.into());
+        self
+    }
+
+    /// Add an audience to the `aud` claim. The claim is a single string if only one audience
+    /// is added, and an array otherwise.
+    pub fn audience<S: Into<String>>(mut self, audience: S) -> Self {
+        let audience = audience.into();
+        self.registered.audience = Some(match self.registered.audience.take() {
+            None => SingleOrMultiple::Single(audience),
+            Some(audiences) => {
+                let mut audiences = audiences.into_vec();
+                audiences.push(audience);
+                SingleOrMultiple::Multiple(audiences)
+            }
+        });
+        self
+    }
+
+    /// Set the `jti` claim
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.registered.id = Some(id.into());
+        self
+    }
+
+    /// Set the `jti` claim to an identifier from `generator` when the token is signed. Use
+    /// [`RandomUuid`] for a random identifier.
+    pub fn id_from<G: IdGenerator + 'static>(mut self, generator: G) -> Self {
+        self.options.id = Some(Arc::new(generator));
+        self
+    }
+
+    /// Set the `jti` claim to a random UUID when the token is signed
+    pub fn random_id(self) -> Self {
+        self.id_from(RandomUuid)
+    }
+
+    /// Set the `exp` claim to `duration` after the token is signed
+    pub fn expires_in(mut self, duration: Duration) -> Self {
+        self.options.expires_in = Some(duration);
+        self
+    }
+
+    /// Set the `nbf` claim to `duration` after the token is signed
+    pub fn not_before_in(mut self, duration: Duration) -> Self {
+        self.options.not_before_in = Some(duration);
+        self
+    }
+
+    /// Set the `iat` claim to the time the token is signed
+    pub fn issued_now(mut self) -> Self {
+        self.options.issued_at = true;
+        self
+    }
+
+    /// Use `clock` as the source of the time the token is signed
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.options.clock = Arc::new(clock);
+        self
+    }
+
+    /// Record the latency of signing the token in `metrics`
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.options.metrics = Some(metrics);
+        self
+    }
+
+    /// Set the private claim `name` to `value`, replacing any previous value
+    ///
+    /// Registered claims must be set with their own methods.
+    pub fn claim<S: Into<String>, V: Into<Value>>(mut self, name: S, value: V) -> Self {
+        let _ = self.private.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the `kid` header
+    pub fn key_id<S: Into<String>>(mut self, key_id: S) -> Self {
+        self.header.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Set the order of the header fields, which defaults to [`HeaderOrder::Declared`]
+    pub fn header_order(mut self, order: HeaderOrder) -> Self {
+        self.options.header_order = order;
+        self
+    }
+
+    /// Encode the claims as canonical JSON, see [`crate::IssuanceOptions::canonical_claims`]
+    pub fn canonical_claims(mut self) -> Self {
+        self.options.canonical_claims = true;
+        self
+    }
+
+    /// Set the `typ` header, which defaults to `JWT`
+    pub fn media_type<S: Into<String>>(mut self, media_type: S) -> Self {
+        self.header.media_type = Some(media_type.into());
+        self
+    }
+
+    /// Sign the token with `secret` and `algorithm`, returning it in its compact form
+    ///
+    /// Returns [`Error::ReservedClaim`] if a registered claim was set with
+    /// [`TokenBuilder::claim`].
+    pub fn sign(&self, secret: &Secret, algorithm: SignatureAlgorithm) -> Result<String, Error> {
+        self.sign_with(&jws::Signer::new(algorithm, secret)?)
+    }
+
+    /// Sign the token with a prepared [`jws::Signer`] and its algorithm, like
+    /// [`TokenBuilder::sign`], without preparing the secret again for every token
+    pub fn sign_with(&self, signer: &jws::Signer) -> Result<String, Error> {
+        let header = RegisteredHeader {
+            algorithm: signer.algorithm(),
+            ..self.header.clone()
+        };
+        jws::Compact::new_decoded(From::from(header), self.claims()?)
+            .sign_with_options(signer, &self.options)
+    }
+
+    /// Encode the token as an unsecured JWT, with the `none` algorithm and no signature
+    ///
+    /// Unsecured JWTs can be forged by anyone, see [`jws::UnsecuredJwt`]. Returns
+    /// [`Error::ReservedClaim`] like [`TokenBuilder::sign`].
+    pub fn danger_unsecured(&self) -> Result<String, Error> {
+        let mut claims = self.claims()?;
+        claims.registered.apply_issuance_options(&self.options)?;
+        jws::UnsecuredJwt::danger_new(From::from(self.header.clone()), claims).encode()
+    }
+
+    fn claims(&self) -> Result<ClaimsSet<Map<String, Value>>, Error> {
+        if let Some(claim) = REGISTERED_CLAIMS
+            .iter()
+            .find(|claim| self.private.contains_key(**claim))
+        {
+            Err(Error::ReservedClaim(claim.to_string()))?
+        }
+
+        Ok(ClaimsSet {
+            registered: self.registered.clone(),
+            private: self.private.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Empty, FixedClock};
+
+    #[test]
+    fn token_builder_round_trip() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(Token::builder()
+            .issuer("https://www.acme.com")
+            .audience("https://api.acme.com")
+            .audience("https://admin.acme.com")
+            .claim("tenant", "ACME")
+            .claim("level", json!(3))
+            .key_id("key-1")
+            .expires_in(Duration::minutes(5))
+            .issued_now()
+            .random_id()
+            .clock(FixedClock(Utc.timestamp(1000, 0)))
+            .sign(&secret, SignatureAlgorithm::HS256));
+
+        let decoded = not_err!(
+            jws::Compact::<ClaimsSet<Map<String, Value>>, Empty>::new_encoded(&token)
+                .into_decoded(&secret, SignatureAlgorithm::HS256)
+        );
+        let header = not_err!(decoded.header());
+        assert_eq!(header.registered.key_id.as_deref(), Some("key-1"));
+        assert_eq!(header.registered.media_type.as_deref(), Some("JWT"));
+
+        let claims = not_err!(decoded.payload());
+        assert_eq!(
+            claims.registered.audience,
+            Some(SingleOrMultiple::Multiple(vec![
+                "https://api.acme.com".to_string(),
+                "https://admin.acme.com".to_string()
+            ]))
+        );
+        assert_eq!(claims.registered.expiry, Some(1300.into()));
+        assert_eq!(claims.registered.issued_at, Some(1000.into()));
+        assert!(claims.registered.id.is_some());
+        assert_eq!(
+            Value::Object(claims.private.clone()),
+            json!({ "tenant": "ACME", "level": 3 })
+        );
+    }
+
+    #[test]
+    fn token_builder_rejects_registered_private_claims() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        match Token::builder()
+            .claim("exp", 1000)
+            .sign(&secret, SignatureAlgorithm::HS256)
+        {
+            Err(Error::ReservedClaim(ref claim)) if claim == "exp" => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn token_builder_unsecured() {
+        let token = not_err!(Token::builder()
+            .subject("John Doe")
+            .expires_in(Duration::minutes(5))
+            .danger_unsecured());
+        assert!(token.ends_with('.'));
+
+        let decoded =
+            not_err!(jws::UnsecuredJwt::<Map<String, Value>, Empty>::danger_decode(&token));
+        assert_eq!(
+            decoded.claims().registered.subject.as_deref(),
+            Some("John Doe")
+        );
+        assert!(decoded.claims().registered.expiry.is_some());
+
+        match Token::builder().claim("sub", "Jane Doe").danger_unsecured() {
+            Err(Error::ReservedClaim(ref claim)) if claim == "sub" => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::errors::ValidationError;
+use crate::Timestamp;
+
+/// Typed getters for claims decoded into an untyped `Map<String, Value>`, like the private
+/// claims of a `ClaimsSet<Map<String, Value>>`
+///
+/// Every getter returns [`ValidationError::MissingRequiredClaims`] with the name of the claim if
+/// it is missing or `null`, and [`ValidationError::MalformedClaim`] if it does not have the
+/// requested type.
+///
+/// # Examples
+/// ```
+/// use biscuit::TypedClaims;
+/// use serde_json::json;
+///
+/// let claims = json!({
+///     "tenant": "ACME",
+///     "onboarded_at": 1234,
+///     "groups": ["staff", "admins"],
+/// });
+/// let claims = claims.as_object().unwrap();
+///
+/// assert_eq!(claims.get_str("tenant").unwrap(), "ACME");
+/// assert_eq!(claims.get_timestamp("onboarded_at").unwrap().timestamp(), 1234);
+/// assert_eq!(claims.get_string_array("groups").unwrap(), vec!["staff", "admins"]);
+/// assert!(claims.get_str("groups").is_err());
+/// ```
+pub trait TypedClaims {
+    /// Returns the claim `name`, which must be a string
+    fn get_str(&self, name: &str) -> Result<&str, ValidationError>;
+
+    /// Returns the claim `name`, which must be a NumericDate
+    fn get_timestamp(&self, name: &str) -> Result<Timestamp, ValidationError>;
+
+    /// Returns the claim `name`, which must be an array of strings
+    fn get_string_array(&self, name: &str) -> Result<Vec<&str>, ValidationError>;
+}
+
+impl TypedClaims for Map<String, Value> {
+    fn get_str(&self, name: &str) -> Result<&str, ValidationError> {
+        present(self, name)?
+            .as_str()
+            .ok_or_else(|| ValidationError::MalformedClaim(name.to_string()))
+    }
+
+    fn get_timestamp(&self, name: &str) -> Result<Timestamp, ValidationError> {
+        Timestamp::deserialize(present(self, name)?)
+            .map_err(|_| ValidationError::MalformedClaim(name.to_string()))
+    }
+
+    fn get_string_array(&self, name: &str) -> Result<Vec<&str>, ValidationError> {
+        present(self, name)?
+            .as_array()
+            .and_then(|values| values.iter().map(Value::as_str).collect())
+            .ok_or_else(|| ValidationError::MalformedClaim(name.to_string()))
+    }
+}
+
+fn present<'a>(claims: &'a Map<String, Value>, name: &str) -> Result<&'a Value, ValidationError> {
+    match claims.get(name) {
+        None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![
+            name.to_string()
+        ])),
+        Some(value) => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn typed_claims_errors() {
+        let claims = json!({
+            "tenant": "ACME",
+            "nothing": null,
+            "groups": ["staff", 1],
+            "onboarded_at": "yesterday",
+        });
+        let claims = claims.as_object().unwrap();
+
+        assert_eq!(
+            claims.get_str("nothing"),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "nothing".to_string()
+            ]))
+        );
+        assert_eq!(
+            claims.get_string_array("department"),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "department".to_string()
+            ]))
+        );
+        assert_eq!(
+            claims.get_string_array("groups"),
+            Err(ValidationError::MalformedClaim("groups".to_string()))
+        );
+        assert_eq!(
+            claims.get_string_array("tenant"),
+            Err(ValidationError::MalformedClaim("tenant".to_string()))
+        );
+        assert_eq!(
+            claims.get_timestamp("onboarded_at"),
+            Err(ValidationError::MalformedClaim("onboarded_at".to_string()))
+        );
+    }
+}
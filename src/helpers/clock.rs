@@ -0,0 +1,66 @@
+use std::fmt::Debug;
+
+use chrono::{DateTime, Utc};
+
+/// A source of the current time, used during temporal validation and when issuing tokens
+///
+/// By default, the system clock is used with [`SystemClock`]. Supply your own implementation in
+/// [`crate::TemporalOptions`] or [`crate::IssuanceOptions`] to freeze time in tests with [`FixedClock`], or to use a different
+/// time source.
+///
+/// Closures with the signature `Fn() -> DateTime<Utc>` implement this trait.
+pub trait Clock: Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> DateTime<Utc> + Send + Sync,
+{
+    fn now(&self) -> DateTime<Utc> {
+        self()
+    }
+}
+
+/// A [`Clock`] that returns the system time
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A [`Clock`] that is frozen at a fixed time
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use chrono::{TimeZone, Utc};
+/// use biscuit::{FixedClock, TemporalOptions};
+///
+/// let temporal_options = TemporalOptions {
+///     clock: Arc::new(FixedClock(Utc.timestamp(1234, 0))),
+///     ..Default::default()
+/// };
+/// assert_eq!(temporal_options.current_time(), Utc.timestamp(1234, 0));
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Helper to print a `Clock` trait object in `Debug` implementations
+pub(crate) struct DebugClock;
+
+impl Debug for DebugClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Clock")
+    }
+}
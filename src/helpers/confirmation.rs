@@ -0,0 +1,189 @@
+use data_encoding::BASE64URL_NOPAD;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::errors::ValidationError;
+use crate::jwk::JWK;
+use crate::Empty;
+
+/// The `cnf` (confirmation) claim of a proof-of-possession token, defined by
+/// [RFC7800](https://tools.ietf.org/html/rfc7800)
+///
+/// The confirmation claim identifies the key that the presenter of the token must prove
+/// possession of. The key can be given directly with `jwk`, or by its
+/// [RFC7638](https://tools.ietf.org/html/rfc7638) thumbprint with `jkt` as used by DPoP
+/// ([RFC9449](https://tools.ietf.org/html/rfc9449)), or by the thumbprint of an X.509
+/// certificate with `x5t#S256` as used by mutual TLS ([RFC8705](https://tools.ietf.org/html/rfc8705)).
+///
+/// # Examples
+/// ```
+/// use biscuit::Confirmation;
+/// use biscuit::jwk::JWK;
+/// use biscuit::Empty;
+/// use serde_json::json;
+///
+/// let key: JWK<Empty> = serde_json::from_value(json!({
+///     "kty": "oct",
+///     "k": "c2VjcmV0",
+/// })).unwrap();
+///
+/// let claims = json!({ "cnf": { "jkt": Confirmation::jwk_thumbprint(&key).unwrap() } });
+/// let confirmation = Confirmation::from_claims(claims.as_object().unwrap()).unwrap();
+/// assert!(confirmation.verify_key(&key).is_ok());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub struct Confirmation {
+    /// The proof-of-possession key. Serialized to `jwk`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jwk: Option<JWK<Empty>>,
+
+    /// The base64url encoded SHA-256 JWK thumbprint of the proof-of-possession key.
+    /// Serialized to `jkt`.
+    #[serde(rename = "jkt", skip_serializing_if = "Option::is_none", default)]
+    pub jwk_thumbprint: Option<String>,
+
+    /// The base64url encoded SHA-256 thumbprint of the DER encoding of the X.509 certificate of
+    /// the proof-of-possession key. Serialized to `x5t#S256`.
+    #[serde(rename = "x5t#S256", skip_serializing_if = "Option::is_none", default)]
+    pub x509_sha256_thumbprint: Option<String>,
+
+    /// The key ID of the proof-of-possession key. Serialized to `kid`.
+    #[serde(rename = "kid", skip_serializing_if = "Option::is_none", default)]
+    pub key_id: Option<String>,
+
+    /// The URL of a JWK set containing the proof-of-possession key. Serialized to `jku`.
+    #[serde(rename = "jku", skip_serializing_if = "Option::is_none", default)]
+    pub jwk_set_url: Option<String>,
+}
+
+impl Confirmation {
+    /// Read the `cnf` claim from a claims set
+    ///
+    /// Returns [`ValidationError::MissingRequiredClaims`] if the claim is missing, and
+    /// [`ValidationError::MalformedClaim`] if it is not a valid confirmation claim.
+    pub fn from_claims(claims: &Map<String, Value>) -> Result<Self, ValidationError> {
+        match claims.get("cnf") {
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![
+                "cnf".to_string()
+            ])),
+            Some(value) => Confirmation::deserialize(value)
+                .map_err(|_| ValidationError::MalformedClaim("cnf".to_string())),
+        }
+    }
+
+    /// Computes the `jkt` thumbprint of a key
+    pub fn jwk_thumbprint<T>(key: &JWK<T>) -> Result<String, serde_json::Error> {
+        key.algorithm.thumbprint(&crate::digest::SHA256)
+    }
+
+    /// Computes the `x5t#S256` thumbprint of a DER encoded X.509 certificate
+    pub fn x509_sha256_thumbprint(certificate: &[u8]) -> String {
+        BASE64URL_NOPAD.encode(ring::digest::digest(&ring::digest::SHA256, certificate).as_ref())
+    }
+
+    /// Verifies that `key` is the confirmed key, according to the `jwk` and `jkt` members
+    ///
+    /// Returns [`ValidationError::ConfirmationMismatch`] if any of the members present does not
+    /// match the key, or if neither of them is present.
+    pub fn verify_key<T>(&self, key: &JWK<T>) -> Result<(), ValidationError> {
+        if self.jwk.is_none() && self.jwk_thumbprint.is_none() {
+            Err(ValidationError::ConfirmationMismatch)?
+        }
+
+        let thumbprint =
+            Self::jwk_thumbprint(key).map_err(|_| ValidationError::ConfirmationMismatch)?;
+        if let Some(ref jwk) = self.jwk {
+            if Self::jwk_thumbprint(jwk).ok().as_ref() != Some(&thumbprint) {
+                Err(ValidationError::ConfirmationMismatch)?
+            }
+        }
+        if let Some(ref jkt) = self.jwk_thumbprint {
+            if *jkt != thumbprint {
+                Err(ValidationError::ConfirmationMismatch)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies that the DER encoded X.509 `certificate` is the confirmed certificate, according
+    /// to the `x5t#S256` member
+    ///
+    /// Returns [`ValidationError::ConfirmationMismatch`] if the member is missing or does not
+    /// match.
+    pub fn verify_certificate(&self, certificate: &[u8]) -> Result<(), ValidationError> {
+        match self.x509_sha256_thumbprint {
+            Some(ref expected) if *expected == Self::x509_sha256_thumbprint(certificate) => Ok(()),
+            _ => Err(ValidationError::ConfirmationMismatch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn key(secret: &str) -> JWK<Empty> {
+        JWK::new_octet_key(secret.as_bytes(), Default::default())
+    }
+
+    #[test]
+    fn confirmation_serde() {
+        let claims = json!({
+            "cnf": {
+                "jkt": "0ZcOCORZNYy-DWpqq30jZyJGHTN0d2HglBV3uiguA4I",
+                "x5t#S256": "bwcK0esc3ACC3DB2Y5_lESsXE8o9ltc05O89jdN-dg2",
+            }
+        });
+        let confirmation = not_err!(Confirmation::from_claims(claims.as_object().unwrap()));
+        assert_eq!(
+            confirmation.jwk_thumbprint.as_deref(),
+            Some("0ZcOCORZNYy-DWpqq30jZyJGHTN0d2HglBV3uiguA4I")
+        );
+        assert_eq!(not_err!(serde_json::to_value(&confirmation)), claims["cnf"]);
+
+        assert_eq!(
+            Confirmation::from_claims(&Map::new()),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "cnf".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn confirmation_verify_key() {
+        let by_jwk = Confirmation {
+            jwk: Some(key("secret")),
+            ..Default::default()
+        };
+        not_err!(by_jwk.verify_key(&key("secret")));
+        assert!(by_jwk.verify_key(&key("other")).is_err());
+
+        let by_thumbprint = Confirmation {
+            jwk_thumbprint: Some(not_err!(Confirmation::jwk_thumbprint(&key("secret")))),
+            ..Default::default()
+        };
+        not_err!(by_thumbprint.verify_key(&key("secret")));
+        assert!(by_thumbprint.verify_key(&key("other")).is_err());
+
+        assert_eq!(
+            Confirmation::default().verify_key(&key("secret")),
+            Err(ValidationError::ConfirmationMismatch)
+        );
+    }
+
+    #[test]
+    fn confirmation_verify_certificate() {
+        let certificate = b"not really a certificate";
+        let confirmation = Confirmation {
+            x509_sha256_thumbprint: Some(Confirmation::x509_sha256_thumbprint(certificate)),
+            ..Default::default()
+        };
+        not_err!(confirmation.verify_certificate(certificate));
+        assert!(confirmation.verify_certificate(b"another").is_err());
+        assert!(Confirmation::default()
+            .verify_certificate(certificate)
+            .is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+
+use crate::errors::ValidationError;
+
+/// A list of revoked tokens, used to reject tokens that are otherwise valid, for example after
+/// a user logs out or their credentials are compromised.
+///
+/// Register an implementation in [`crate::ValidationOptions`] to have every validated token
+/// checked against it. Unlike a [`crate::ReplayCache`], the list is never written to during
+/// validation, so it can be backed by an external store that is maintained elsewhere.
+///
+/// An in-memory implementation is provided with [`InMemoryDenylist`].
+pub trait Denylist: Send + Sync {
+    /// Check whether the token with the `jti`, `sub` and `iat` claims given has been revoked.
+    /// Each of them is `None` if the token does not have the claim.
+    ///
+    /// Implementations should return [`ValidationError::Revoked`] if the token has been revoked.
+    fn check(
+        &self,
+        id: Option<&str>,
+        subject: Option<&str>,
+        issued_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ValidationError>;
+}
+
+/// An in-memory [`Denylist`] of revoked `jti`s and subjects
+///
+/// Individual tokens are revoked by their `jti` with [`InMemoryDenylist::revoke_id`], and every
+/// token of a subject issued up to some time with [`InMemoryDenylist::revoke_subject`]. Entries
+/// are never dropped.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{InMemoryDenylist, RegisteredClaims, Validation, ValidationOptions};
+/// use chrono::{TimeZone, Utc};
+///
+/// let denylist = Arc::new(InMemoryDenylist::default());
+/// let options = ValidationOptions {
+///     denylist: Validation::Validate(denylist.clone()),
+///     ..Default::default()
+/// };
+///
+/// let claims = RegisteredClaims {
+///     subject: Some("John Doe".to_string()),
+///     issued_at: Some(1000.into()),
+///     ..Default::default()
+/// };
+/// assert!(claims.validate(options.clone()).is_ok());
+///
+/// // John Doe logged out everywhere
+/// denylist.revoke_subject("John Doe", Utc.timestamp(2000, 0));
+/// assert!(claims.validate(options).is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct InMemoryDenylist {
+    inner: RwLock<DenylistState>,
+}
+
+#[derive(Debug, Default)]
+struct DenylistState {
+    ids: HashSet<String>,
+    /// Subject to the time up to which its tokens are revoked
+    subjects: HashMap<String, DateTime<Utc>>,
+}
+
+impl InMemoryDenylist {
+    /// Revoke the token with the `jti` claim `id`
+    pub fn revoke_id(&self, id: &str) {
+        let _ = self.write().ids.insert(id.to_string());
+    }
+
+    /// Revoke every token of `subject` issued at or before `issued_before`. Tokens of the subject
+    /// without an `iat` claim are revoked too, since it is not known when they were issued.
+    pub fn revoke_subject(&self, subject: &str, issued_before: DateTime<Utc>) {
+        let mut state = self.write();
+        let revoked_until = state
+            .subjects
+            .entry(subject.to_string())
+            .or_insert(issued_before);
+        *revoked_until = (*revoked_until).max(issued_before);
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, DenylistState> {
+        // Each update is a single insertion, so a panic cannot leave the state inconsistent
+        self.inner
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, DenylistState> {
+        self.inner
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Denylist for InMemoryDenylist {
+    fn check(
+        &self,
+        id: Option<&str>,
+        subject: Option<&str>,
+        issued_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ValidationError> {
+        let state = self.read();
+        if let Some(id) = id {
+            if state.ids.contains(id) {
+                Err(ValidationError::Revoked)?
+            }
+        }
+
+        let revoked_until = subject.and_then(|subject| state.subjects.get(subject));
+        match (revoked_until, issued_at) {
+            (Some(_), None) => Err(ValidationError::Revoked),
+            (Some(revoked_until), Some(issued_at)) if issued_at <= *revoked_until => {
+                Err(ValidationError::Revoked)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn in_memory_denylist_revokes_ids_and_subjects() {
+        let denylist = InMemoryDenylist::default();
+        let issued_at = Some(Utc.timestamp(1000, 0));
+        not_err!(denylist.check(Some("id"), Some("subject"), issued_at));
+
+        denylist.revoke_id("id");
+        assert_eq!(
+            denylist.check(Some("id"), Some("subject"), issued_at),
+            Err(ValidationError::Revoked)
+        );
+        not_err!(denylist.check(Some("other"), Some("subject"), issued_at));
+
+        denylist.revoke_subject("subject", Utc.timestamp(1000, 0));
+        assert_eq!(
+            denylist.check(None, Some("subject"), issued_at),
+            Err(ValidationError::Revoked)
+        );
+        assert_eq!(
+            denylist.check(None, Some("subject"), None),
+            Err(ValidationError::Revoked)
+        );
+        // Tokens issued after the subject was revoked are accepted
+        not_err!(denylist.check(None, Some("subject"), Some(Utc.timestamp(1001, 0))));
+        not_err!(denylist.check(None, Some("other"), issued_at));
+
+        // Revoking an earlier time does not accept tokens again
+        denylist.revoke_subject("subject", Utc.timestamp(500, 0));
+        assert_eq!(
+            denylist.check(None, Some("subject"), issued_at),
+            Err(ValidationError::Revoked)
+        );
+    }
+}
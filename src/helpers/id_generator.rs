@@ -0,0 +1,72 @@
+use ring::rand::SecureRandom;
+
+use crate::errors::Error;
+
+/// A generator of `jti` (JWT ID) claims for tokens being issued
+///
+/// Register an implementation in [`crate::IssuanceOptions`] to have a `jti` generated for every
+/// issued token that does not already have one. Identifiers must be unique enough that they
+/// are not reused by accident, since they are used to detect replayed tokens.
+///
+/// Random UUIDs are generated with [`RandomUuid`]. Closures with the signature
+/// `Fn() -> Result<String, Error>` also implement this trait.
+pub trait IdGenerator: Send + Sync {
+    /// Generate a new identifier
+    fn generate(&self) -> Result<String, Error>;
+}
+
+impl<F> IdGenerator for F
+where
+    F: Fn() -> Result<String, Error> + Send + Sync,
+{
+    fn generate(&self) -> Result<String, Error> {
+        self()
+    }
+}
+
+/// An [`IdGenerator`] that generates random (version 4) UUIDs from a secure random number
+/// generator
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub struct RandomUuid;
+
+impl IdGenerator for RandomUuid {
+    fn generate(&self) -> Result<String, Error> {
+        let mut bytes = [0u8; 16];
+        crate::jwa::rng().fill(&mut bytes)?;
+
+        // Set the version to 4 and the variant to RFC 4122
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        Ok(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_uuid_is_version_4() {
+        let uuid = not_err!(RandomUuid.generate());
+        assert_eq!(uuid.len(), 36);
+
+        let groups: Vec<&str> = uuid.split('-').collect();
+        assert_eq!(
+            groups.iter().map(|group| group.len()).collect::<Vec<_>>(),
+            vec![8, 4, 4, 4, 12]
+        );
+        assert!(groups[2].starts_with('4'));
+        assert!(groups[3].starts_with(&['8', '9', 'a', 'b'][..]));
+
+        assert_ne!(uuid, not_err!(RandomUuid.generate()));
+    }
+}
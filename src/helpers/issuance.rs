@@ -0,0 +1,95 @@
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::Duration;
+
+use super::clock::{Clock, DebugClock, SystemClock};
+use super::id_generator::IdGenerator;
+use super::metrics::Metrics;
+use crate::jws::HeaderOrder;
+
+#[derive(Clone)]
+/// Options for filling in registered claims when a token is issued
+///
+/// Durations are relative to the time according to `clock` when the options are applied, which
+/// is when the token is encoded with [`crate::jws::Compact::into_encoded_with_options`], or when
+/// calling [`crate::RegisteredClaims::apply_issuance_options`] directly.
+///
+/// Claims that are already set are never overwritten.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use chrono::{Duration, TimeZone, Utc};
+/// use biscuit::{FixedClock, IssuanceOptions, RegisteredClaims};
+///
+/// let options = IssuanceOptions {
+///     expires_in: Some(Duration::minutes(5)),
+///     issued_at: true,
+///     clock: Arc::new(FixedClock(Utc.timestamp(1000, 0))),
+///     ..Default::default()
+/// };
+///
+/// let mut claims = RegisteredClaims::default();
+/// claims.apply_issuance_options(&options).unwrap();
+/// assert_eq!(claims.expiry, Some(1300.into()));
+/// assert_eq!(claims.issued_at, Some(1000.into()));
+/// ```
+pub struct IssuanceOptions {
+    /// Set `exp` to this long after the time of issue
+    pub expires_in: Option<Duration>,
+
+    /// Set `nbf` to this long after the time of issue. Use `Duration::zero()` for a token that
+    /// is valid immediately.
+    pub not_before_in: Option<Duration>,
+
+    /// Set `iat` to the time of issue
+    pub issued_at: bool,
+
+    /// Set `jti` to an identifier from this generator, such as [`crate::RandomUuid`]
+    pub id: Option<Arc<dyn IdGenerator>>,
+
+    /// The source of the time of issue. Defaults to the system clock.
+    pub clock: Arc<dyn Clock>,
+
+    /// The order of the header fields in the encoded token. Defaults to
+    /// [`HeaderOrder::Declared`].
+    pub header_order: HeaderOrder,
+
+    /// Encode the claims as canonical JSON with [`crate::to_canonical_json`], so that tokens
+    /// over equal claims have identical payloads
+    pub canonical_claims: bool,
+
+    /// Record the latency of signing in these [`Metrics`]
+    pub metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl Default for IssuanceOptions {
+    fn default() -> Self {
+        IssuanceOptions {
+            expires_in: None,
+            not_before_in: None,
+            issued_at: false,
+            id: None,
+            clock: Arc::new(SystemClock),
+            header_order: HeaderOrder::Declared,
+            canonical_claims: false,
+            metrics: None,
+        }
+    }
+}
+
+impl fmt::Debug for IssuanceOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IssuanceOptions")
+            .field("expires_in", &self.expires_in)
+            .field("not_before_in", &self.not_before_in)
+            .field("issued_at", &self.issued_at)
+            .field("id", &self.id.as_ref().map(|_| "IdGenerator"))
+            .field("clock", &DebugClock)
+            .field("header_order", &self.header_order)
+            .field("canonical_claims", &self.canonical_claims)
+            .field("metrics", &self.metrics.as_ref().map(|_| "Metrics"))
+            .finish()
+    }
+}
@@ -0,0 +1,134 @@
+use crate::errors::DecodeError;
+
+/// Limits on the JSON of a token's claims, which are checked before the claims are deserialized
+///
+/// The payload is scanned without being parsed, so that hostile tokens with deeply nested values,
+/// very many claims or huge strings are rejected before any memory is allocated for them.
+/// There are no limits by default.
+///
+/// # Examples
+/// ```
+/// use biscuit::JsonLimits;
+///
+/// let limits = JsonLimits {
+///     max_depth: Some(2),
+///     ..Default::default()
+/// };
+/// assert!(limits.check(br#"{"roles":["admin"]}"#).is_ok());
+/// assert!(limits.check(br#"{"act":{"act":{"sub":"admin"}}}"#).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct JsonLimits {
+    /// Maximum nesting depth of objects and arrays. The claims set itself has a depth of 1.
+    pub max_depth: Option<usize>,
+    /// Maximum number of claims in the claims set. Members of nested objects are not counted.
+    pub max_claims: Option<usize>,
+    /// Maximum length of each string, including the names of claims, in bytes as encoded
+    pub max_string_length: Option<usize>,
+}
+
+impl JsonLimits {
+    /// Check the JSON in `json` against the limits
+    ///
+    /// Malformed JSON is not rejected, since it fails when it is deserialized.
+    pub fn check(&self, json: &[u8]) -> Result<(), DecodeError> {
+        let mut depth = 0;
+        let mut claims = 0;
+        let mut string_start = None;
+        let mut escaped = false;
+
+        for (index, byte) in json.iter().enumerate() {
+            if let Some(start) = string_start {
+                if escaped {
+                    escaped = false;
+                } else if *byte == b'\\' {
+                    escaped = true;
+                } else if *byte == b'"' {
+                    self.check_string_length(index - start)?;
+                    string_start = None;
+                }
+                continue;
+            }
+
+            match *byte {
+                b'"' => string_start = Some(index + 1),
+                b'{' | b'[' => {
+                    depth += 1;
+                    check_limit("depth", self.max_depth, depth)?;
+                }
+                b'}' | b']' => depth = depth.saturating_sub(1),
+                b':' if depth == 1 => {
+                    claims += 1;
+                    check_limit("claims", self.max_claims, claims)?;
+                }
+                _ => {}
+            }
+        }
+
+        match string_start {
+            Some(start) => self.check_string_length(json.len() - start),
+            None => Ok(()),
+        }
+    }
+
+    fn check_string_length(&self, length: usize) -> Result<(), DecodeError> {
+        check_limit("string length", self.max_string_length, length)
+    }
+}
+
+fn check_limit(limit: &'static str, max: Option<usize>, actual: usize) -> Result<(), DecodeError> {
+    match max {
+        Some(max) if actual > max => Err(DecodeError::JsonLimitExceeded { limit, max }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exceeded(result: Result<(), DecodeError>) -> (&'static str, usize) {
+        match result {
+            Err(DecodeError::JsonLimitExceeded { limit, max }) => (limit, max),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn json_limits_are_unlimited_by_default() {
+        let json = br#"{"a":{"b":[[["deep"]]]},"c":"long string","d":1}"#;
+        not_err!(JsonLimits::default().check(json));
+    }
+
+    #[test]
+    fn json_limits_count_top_level_claims() {
+        let limits = JsonLimits {
+            max_claims: Some(2),
+            ..Default::default()
+        };
+        not_err!(limits.check(br#"{"a":{"x":1,"y":2,"z":3},"b":"c:d"}"#));
+        assert_eq!(
+            exceeded(limits.check(br#"{"a":1,"b":2,"c":3}"#)),
+            ("claims", 2)
+        );
+    }
+
+    #[test]
+    fn json_limits_check_depth_and_strings() {
+        let limits = JsonLimits {
+            max_depth: Some(2),
+            max_string_length: Some(5),
+            ..Default::default()
+        };
+        not_err!(limits.check(br#"{"roles":["a\"[["]}"#));
+        assert_eq!(exceeded(limits.check(br#"{"a":[[1]]}"#)), ("depth", 2));
+        assert_eq!(
+            exceeded(limits.check(br#"{"a":"123456"}"#)),
+            ("string length", 5)
+        );
+        assert_eq!(
+            exceeded(limits.check(br#"{"a":"123456"#)),
+            ("string length", 5)
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use chrono::Duration;
+
+use super::clock::Clock;
+use crate::errors::{Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+
+/// A sink for metrics about signing and verifying tokens, to be wired into a metrics library
+/// like `prometheus` or `metrics`
+///
+/// Register an implementation in [`crate::ValidationOptions`] to have the outcome and latency
+/// of [`crate::decode`] and [`crate::providers::verify`] recorded, and in
+/// [`crate::IssuanceOptions`] or with [`crate::TokenBuilder::metrics`] for the latency of
+/// signing. Latencies are measured with the `clock` of the options, so they are zero with a
+/// [`crate::FixedClock`].
+///
+/// Every method does nothing by default, so implementations only implement the metrics that
+/// they collect. Neither tokens nor keys are ever passed to the methods.
+///
+/// # Examples
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use chrono::Duration;
+/// use biscuit::{decode, Metrics, Validation, ValidationOptions};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+///
+/// #[derive(Default)]
+/// struct Rejections(AtomicUsize);
+///
+/// impl Metrics for Rejections {
+///     fn token_rejected(&self, reason: &'static str, _elapsed: Duration) {
+///         assert_eq!(reason, "malformed");
+///         let _ = self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let rejections = Arc::new(Rejections::default());
+/// let options = ValidationOptions {
+///     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+///     metrics: Some(rejections.clone()),
+///     ..Default::default()
+/// };
+/// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+/// assert!(decode::<biscuit::Empty>("not a token", &secret, &options).is_err());
+/// assert_eq!(rejections.0.load(Ordering::Relaxed), 1);
+/// ```
+pub trait Metrics: Send + Sync {
+    /// A token signed with `algorithm` was verified and validated in `elapsed`
+    fn token_verified(&self, _algorithm: SignatureAlgorithm, _elapsed: Duration) {}
+
+    /// A token was rejected in `elapsed`, for the [`failure_reason`] `reason`
+    fn token_rejected(&self, _reason: &'static str, _elapsed: Duration) {}
+
+    /// A token was signed with `algorithm` in `elapsed`
+    fn token_signed(&self, _algorithm: SignatureAlgorithm, _elapsed: Duration) {}
+
+    /// A JWK Set was downloaded in `elapsed`, with `keys` keys, or the download failed
+    ///
+    /// This crate does not download JWK Sets. Call this from the code that does, so that every
+    /// metric goes to the same sink.
+    fn jwks_refreshed(&self, _keys: Result<usize, &Error>, _elapsed: Duration) {}
+}
+
+/// A short label for the cause of `error`, with few enough distinct values to be used as a
+/// metric label
+///
+/// The labels are `malformed`, `invalid_signature`, `invalid_key`, `algorithm`, `key_not_found`,
+/// `expired`, `not_yet_valid`, `too_old`, `invalid_issuer`, `invalid_audience`,
+/// `missing_claims`, `replayed`, `revoked`, `invalid_claims` and `other`.
+pub fn failure_reason(error: &Error) -> &'static str {
+    match *error {
+        Error::DecodeError(_)
+        | Error::JsonError(_)
+        | Error::DecodeBase64(_)
+        | Error::Utf8(_)
+        | Error::NotAnObject => "malformed",
+        Error::UnspecifiedCryptographicError => "invalid_signature",
+        Error::KeyRejected(_) | Error::WrongKeyType { .. } | Error::WeakKey { .. } => "invalid_key",
+        Error::ValidationReport(_) => "invalid_claims",
+        Error::ValidationError(ref error) => match *error {
+            ValidationError::InvalidSignature => "invalid_signature",
+            ValidationError::WrongAlgorithmHeader
+            | ValidationError::UnsecuredToken
+            | ValidationError::MissingAlgorithm
+            | ValidationError::UnsupportedKeyAlgorithm => "algorithm",
+            ValidationError::KidMissing | ValidationError::KeyNotFound => "key_not_found",
+            ValidationError::Expired(_) => "expired",
+            ValidationError::NotYetValid(_) => "not_yet_valid",
+            ValidationError::TooOld(_) => "too_old",
+            ValidationError::InvalidIssuer(_) => "invalid_issuer",
+            ValidationError::InvalidAudience(_) => "invalid_audience",
+            ValidationError::MissingRequiredClaims(_) => "missing_claims",
+            ValidationError::Replayed(_) => "replayed",
+            ValidationError::Revoked => "revoked",
+            _ => "invalid_claims",
+        },
+        _ => "other",
+    }
+}
+
+/// Run `verify`, and record its outcome and latency in `metrics`
+pub(crate) fn record_verification<T, F>(
+    metrics: Option<&dyn Metrics>,
+    clock: &dyn Clock,
+    verify: F,
+) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<(SignatureAlgorithm, T), Error>,
+{
+    let metrics = match metrics {
+        Some(metrics) => metrics,
+        None => return verify().map(|(_, verified)| verified),
+    };
+    let start = clock.now();
+    let result = verify();
+    let elapsed = clock.now() - start;
+    match result {
+        Ok((algorithm, verified)) => {
+            metrics.token_verified(algorithm, elapsed);
+            Ok(verified)
+        }
+        Err(error) => {
+            metrics.token_rejected(failure_reason(&error), elapsed);
+            Err(error)
+        }
+    }
+}
+
+/// Run `sign`, and record its latency in `metrics` if it succeeds
+pub(crate) fn record_signing<T, F>(
+    metrics: Option<&dyn Metrics>,
+    clock: &dyn Clock,
+    algorithm: SignatureAlgorithm,
+    sign: F,
+) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    let metrics = match metrics {
+        Some(metrics) => metrics,
+        None => return sign(),
+    };
+    let start = clock.now();
+    let signed = sign()?;
+    metrics.token_signed(algorithm, clock.now() - start);
+    Ok(signed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::jws::Secret;
+    use crate::{Empty, Token, Validation, ValidationOptions};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Metrics for Recorder {
+        fn token_verified(&self, algorithm: SignatureAlgorithm, _: Duration) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("verified {:?}", algorithm));
+        }
+
+        fn token_rejected(&self, reason: &'static str, _: Duration) {
+            self.0.lock().unwrap().push(format!("rejected {}", reason));
+        }
+
+        fn token_signed(&self, algorithm: SignatureAlgorithm, _: Duration) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("signed {:?}", algorithm));
+        }
+    }
+
+    #[test]
+    fn metrics_are_recorded() {
+        let recorder = Arc::new(Recorder::default());
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let token = not_err!(Token::builder()
+            .expires_in(Duration::minutes(5))
+            .metrics(recorder.clone())
+            .sign(&secret, SignatureAlgorithm::HS256));
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            metrics: Some(recorder.clone()),
+            ..Default::default()
+        };
+        let _ = not_err!(crate::decode::<Empty>(&token, &secret, &options));
+
+        let other = Secret::bytes_from_str("another secret that is 32 bytes long");
+        assert!(crate::decode::<Empty>(&token, &other, &options).is_err());
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                "signed HS256",
+                "verified HS256",
+                "rejected invalid_signature"
+            ]
+        );
+    }
+}
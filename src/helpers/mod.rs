@@ -1,7 +1,43 @@
+mod actor;
+mod assertion;
+mod builder;
+mod claims_map;
+mod clock;
+mod confirmation;
+mod denylist;
+mod id_generator;
+mod issuance;
+mod json_limits;
+mod metrics;
 mod presence;
+mod replay;
+mod roles;
+mod scope;
+mod string_or_uri;
 mod temporal_options;
 mod validation;
+mod validator;
+mod verification_cache;
 
+pub use self::actor::*;
+pub use self::assertion::*;
+pub use self::builder::*;
+pub use self::claims_map::*;
+pub(crate) use self::clock::DebugClock;
+pub use self::clock::{Clock, FixedClock, SystemClock};
+pub use self::confirmation::*;
+pub use self::denylist::*;
+pub use self::id_generator::*;
+pub use self::issuance::*;
+pub use self::json_limits::*;
+pub use self::metrics::{failure_reason, Metrics};
+pub(crate) use self::metrics::{record_signing, record_verification};
 pub use self::presence::*;
+pub use self::replay::*;
+pub use self::roles::*;
+pub use self::scope::*;
+pub use self::string_or_uri::*;
 pub use self::temporal_options::*;
 pub use self::validation::*;
+pub use self::validator::*;
+pub use self::verification_cache::*;
@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::errors::ValidationError;
+
+/// A store of previously seen `jti` (JWT ID) claims, used to reject tokens that are replayed.
+///
+/// Register an implementation in [`crate::ValidationOptions`] to have every validated token with
+/// a `jti` claim checked against, and then recorded in, the cache.
+///
+/// An in-memory implementation is provided with [`InMemoryReplayCache`], and one that is shared
+/// between instances of a service with [`RedisReplayCache`].
+pub trait ReplayCache: Send + Sync {
+    /// Check whether `jti` has been seen before. If it has not, record it so that subsequent
+    /// checks will fail.
+    ///
+    /// `expires_at` is the time after which the token would no longer be accepted anyway, derived
+    /// from its `exp` claim and the validation leeway. The entry need not be kept past this time.
+    /// It is `None` if the token has no `exp` claim.
+    ///
+    /// Implementations should return [`ValidationError::Replayed`] if `jti` has been seen.
+    fn check_and_store(
+        &self,
+        jti: &str,
+        expires_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<(), ValidationError>;
+}
+
+/// A bounded, in-memory [`ReplayCache`] that evicts the least recently used entries when full.
+///
+/// Entries are dropped once the token they belong to has expired. Note that tokens without an
+/// `exp` claim are kept until they are evicted, after which a replay will no longer be detected.
+/// Consider requiring `exp` with [`crate::ClaimPresenceOptions`] when using this cache.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{InMemoryReplayCache, RegisteredClaims, Validation, ValidationOptions};
+///
+/// let options = ValidationOptions {
+///     id: Validation::Validate(Arc::new(InMemoryReplayCache::new(1024))),
+///     ..Default::default()
+/// };
+///
+/// let claims = RegisteredClaims {
+///     id: Some("unique-id".to_string()),
+///     ..Default::default()
+/// };
+///
+/// assert!(claims.validate(options.clone()).is_ok());
+/// assert!(claims.validate(options).is_err());
+/// ```
+#[derive(Debug)]
+pub struct InMemoryReplayCache {
+    capacity: usize,
+    inner: Mutex<LruState<()>>,
+}
+
+/// Entries keyed by strings, such as `jti`s, that expire and are evicted when least recently used
+#[derive(Debug)]
+pub(super) struct LruState<V> {
+    /// Monotonically increasing counter used to track recency
+    tick: u64,
+    /// Key to (expiry, last used tick, value)
+    entries: HashMap<String, (Option<DateTime<Utc>>, u64, V)>,
+    /// Last used tick to key, ordered from least to most recently used
+    recency: BTreeMap<u64, String>,
+}
+
+impl<V> Default for LruState<V> {
+    fn default() -> Self {
+        LruState {
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> LruState<V> {
+    pub(super) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(super) fn touch(&mut self, jti: &str, expires_at: Option<DateTime<Utc>>, value: V) {
+        self.tick += 1;
+        if let Some((_, tick, _)) = self
+            .entries
+            .insert(jti.to_string(), (expires_at, self.tick, value))
+        {
+            let _ = self.recency.remove(&tick);
+        }
+        let _ = self.recency.insert(self.tick, jti.to_string());
+    }
+
+    /// The value of an entry that has not expired, marking it as the most recently used
+    pub(super) fn get(&mut self, jti: &str, now: DateTime<Utc>) -> Option<&V> {
+        let tick = match self.entries.get(jti) {
+            Some((Some(expiry), _, _)) if *expiry < now => {
+                self.remove(jti);
+                return None;
+            }
+            Some((_, tick, _)) => *tick,
+            None => return None,
+        };
+        self.tick += 1;
+        let _ = self.recency.remove(&tick);
+        let _ = self.recency.insert(self.tick, jti.to_string());
+        let entry = self.entries.get_mut(jti)?;
+        entry.1 = self.tick;
+        Some(&entry.2)
+    }
+
+    pub(super) fn remove(&mut self, jti: &str) {
+        if let Some((_, tick, _)) = self.entries.remove(jti) {
+            let _ = self.recency.remove(&tick);
+        }
+    }
+
+    /// Make room for a new entry, first by dropping expired entries, and then by evicting the
+    /// least recently used ones
+    pub(super) fn make_room(&mut self, capacity: usize, now: DateTime<Utc>) {
+        if self.entries.len() >= capacity {
+            self.purge_expired(now);
+        }
+        while self.entries.len() >= capacity {
+            self.evict_least_recently_used();
+        }
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let oldest = self.recency.keys().next().cloned();
+        if let Some(tick) = oldest {
+            if let Some(jti) = self.recency.remove(&tick) {
+                let _ = self.entries.remove(&jti);
+            }
+        }
+    }
+
+    fn purge_expired(&mut self, now: DateTime<Utc>) {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, (expires_at, _, _))| {
+                expires_at.map(|expiry| expiry < now).unwrap_or(false)
+            })
+            .map(|(jti, _)| jti.clone())
+            .collect();
+        for jti in expired {
+            self.remove(&jti);
+        }
+    }
+}
+
+impl InMemoryReplayCache {
+    /// Create a cache that remembers at most `capacity` `jti`s.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Replay cache capacity must be non-zero");
+        Self {
+            capacity,
+            inner: Mutex::new(Default::default()),
+        }
+    }
+
+    /// The maximum number of `jti`s remembered
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of `jti`s currently remembered, including any that have expired but have not
+    /// been purged yet.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether no `jti`s are currently remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruState<()>> {
+        // A panic while holding the lock cannot leave the state inconsistent in a way
+        // that matters for replay detection, so recover from poisoning.
+        self.inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl ReplayCache for InMemoryReplayCache {
+    fn check_and_store(
+        &self,
+        jti: &str,
+        expires_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<(), ValidationError> {
+        let mut state = self.lock();
+
+        match state.entries.get(jti) {
+            Some((Some(expiry), _, _)) if *expiry < now => state.remove(jti),
+            Some(_) => {
+                state.touch(jti, expires_at, ());
+                Err(ValidationError::Replayed(jti.to_string()))?
+            }
+            None => {}
+        }
+
+        state.make_room(self.capacity, now);
+        state.touch(jti, expires_at, ());
+        Ok(())
+    }
+}
+
+/// A connection to [Redis](https://redis.io), or a store with the same semantics, used by
+/// [`RedisReplayCache`]
+///
+/// This crate does not depend on a Redis client. Implement this with the client of your choice,
+/// for example with `redis::cmd("SET").arg(key).arg(value).arg("NX").arg("PX").arg(ttl)`, which
+/// returns `OK` if the key was set and nil otherwise.
+pub trait RedisConnection: Send + Sync {
+    /// Run `SET key value NX PX ttl_milliseconds`, returning whether the key was set because it
+    /// did not exist, or a description of the error if the command failed
+    fn set_if_absent(&self, key: &str, value: &str, ttl_milliseconds: u64) -> Result<bool, String>;
+}
+
+/// A [`ReplayCache`] backed by Redis, so that every instance of a service rejects a token that
+/// was presented to any of them
+///
+/// Each `jti` is stored under a key made of the key prefix and the `jti`, with a time to live
+/// derived from the `exp` claim of the token and the validation leeway, so that Redis drops it
+/// once the token would be rejected anyway. Tokens without an `exp` claim are kept for the
+/// default time to live, after which a replay will no longer be detected.
+///
+/// Errors of the connection reject the token with [`ValidationError::CustomValidation`], since
+/// replays cannot be detected without the store.
+///
+/// # Examples
+/// ```
+/// use std::collections::HashSet;
+/// use std::sync::{Arc, Mutex};
+/// use biscuit::{RedisConnection, RedisReplayCache, RegisteredClaims, Validation, ValidationOptions};
+///
+/// /// A stand-in for a Redis client, which never expires keys
+/// #[derive(Default)]
+/// struct FakeRedis(Mutex<HashSet<String>>);
+///
+/// impl RedisConnection for FakeRedis {
+///     fn set_if_absent(&self, key: &str, _: &str, _: u64) -> Result<bool, String> {
+///         Ok(self.0.lock().unwrap().insert(key.to_string()))
+///     }
+/// }
+///
+/// let options = ValidationOptions {
+///     id: Validation::Validate(Arc::new(RedisReplayCache::new(FakeRedis::default()))),
+///     ..Default::default()
+/// };
+/// let claims = RegisteredClaims {
+///     id: Some("unique-id".to_string()),
+///     ..Default::default()
+/// };
+/// assert!(claims.validate(options.clone()).is_ok());
+/// assert!(claims.validate(options).is_err());
+/// ```
+#[derive(Debug)]
+pub struct RedisReplayCache<C> {
+    connection: C,
+    key_prefix: String,
+    default_ttl: Duration,
+}
+
+impl<C: RedisConnection> RedisReplayCache<C> {
+    /// The prefix of the keys that `jti`s are stored under by default
+    pub const DEFAULT_KEY_PREFIX: &'static str = "biscuit:jti:";
+
+    /// Create a cache that stores `jti`s with `connection`, under [`Self::DEFAULT_KEY_PREFIX`],
+    /// and keeps the `jti`s of tokens without an `exp` claim for a day
+    pub fn new(connection: C) -> Self {
+        RedisReplayCache {
+            connection,
+            key_prefix: Self::DEFAULT_KEY_PREFIX.to_string(),
+            default_ttl: Duration::days(1),
+        }
+    }
+
+    /// Store `jti`s under keys starting with `key_prefix`, for example to separate the `jti`s of
+    /// different issuers or services that share a Redis instance
+    pub fn with_key_prefix<S: Into<String>>(mut self, key_prefix: S) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// Keep the `jti`s of tokens without an `exp` claim for `default_ttl`
+    pub fn with_default_ttl(mut self, default_ttl: Duration) -> Self {
+        self.default_ttl = default_ttl;
+        self
+    }
+}
+
+impl<C: RedisConnection> ReplayCache for RedisReplayCache<C> {
+    fn check_and_store(
+        &self,
+        jti: &str,
+        expires_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<(), ValidationError> {
+        let ttl = match expires_at {
+            Some(expires_at) => expires_at - now,
+            None => self.default_ttl,
+        };
+        // Redis rejects a time to live that is not positive
+        let ttl = ttl.num_milliseconds().max(1) as u64;
+        let key = format!("{}{}", self.key_prefix, jti);
+        match self.connection.set_if_absent(&key, "1", ttl) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ValidationError::Replayed(jti.to_string())),
+            Err(error) => Err(ValidationError::CustomValidation(format!(
+                "The replay cache is unavailable: {}",
+                error
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    /// Records the TTL of every key that is set, and never expires them
+    #[derive(Default)]
+    struct FakeRedis(Mutex<HashMap<String, u64>>);
+
+    impl RedisConnection for FakeRedis {
+        fn set_if_absent(&self, key: &str, _: &str, ttl: u64) -> Result<bool, String> {
+            let mut keys = self.0.lock().unwrap();
+            if keys.contains_key(key) {
+                return Ok(false);
+            }
+            let _ = keys.insert(key.to_string(), ttl);
+            Ok(true)
+        }
+    }
+
+    struct UnavailableRedis;
+
+    impl RedisConnection for UnavailableRedis {
+        fn set_if_absent(&self, _: &str, _: &str, _: u64) -> Result<bool, String> {
+            Err("connection refused".to_string())
+        }
+    }
+
+    #[test]
+    fn in_memory_replay_cache_rejects_seen_jti() {
+        let cache = InMemoryReplayCache::new(2);
+        let now = Utc.timestamp(100, 0);
+
+        not_err!(cache.check_and_store("a", None, now));
+        assert_eq!(
+            cache.check_and_store("a", None, now),
+            Err(ValidationError::Replayed("a".to_string()))
+        );
+        not_err!(cache.check_and_store("b", None, now));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn in_memory_replay_cache_forgets_expired_jti() {
+        let cache = InMemoryReplayCache::new(2);
+        let now = Utc.timestamp(100, 0);
+
+        not_err!(cache.check_and_store("a", Some(now + Duration::seconds(10)), now));
+        assert!(cache
+            .check_and_store("a", Some(now + Duration::seconds(10)), now)
+            .is_err());
+
+        let later = now + Duration::seconds(11);
+        not_err!(cache.check_and_store("a", Some(later + Duration::seconds(10)), later));
+    }
+
+    #[test]
+    fn in_memory_replay_cache_evicts_least_recently_used() {
+        let cache = InMemoryReplayCache::new(2);
+        let now = Utc.timestamp(100, 0);
+
+        not_err!(cache.check_and_store("a", None, now));
+        not_err!(cache.check_and_store("b", None, now));
+        // Touch "a" so that "b" becomes the least recently used
+        assert!(cache.check_and_store("a", None, now).is_err());
+        not_err!(cache.check_and_store("c", None, now));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.check_and_store("a", None, now).is_err());
+        assert!(cache.check_and_store("c", None, now).is_err());
+        not_err!(cache.check_and_store("b", None, now));
+    }
+
+    #[test]
+    fn in_memory_replay_cache_purges_expired_before_evicting() {
+        let cache = InMemoryReplayCache::new(2);
+        let now = Utc.timestamp(100, 0);
+
+        not_err!(cache.check_and_store("a", None, now));
+        not_err!(cache.check_and_store("b", Some(now + Duration::seconds(1)), now));
+
+        let later = now + Duration::seconds(5);
+        not_err!(cache.check_and_store("c", None, later));
+        // "a" survives because the expired "b" was purged instead
+        assert!(cache.check_and_store("a", None, later).is_err());
+    }
+
+    #[test]
+    fn redis_replay_cache_sets_keys_with_ttl() {
+        let cache = RedisReplayCache::new(FakeRedis::default()).with_key_prefix("api:");
+        let now = Utc.timestamp(100, 0);
+
+        not_err!(cache.check_and_store("a", Some(now + Duration::seconds(30)), now));
+        assert_eq!(
+            cache.check_and_store("a", Some(now + Duration::seconds(30)), now),
+            Err(ValidationError::Replayed("a".to_string()))
+        );
+        not_err!(cache.check_and_store("b", None, now));
+        not_err!(cache.check_and_store("c", Some(now - Duration::seconds(1)), now));
+
+        let keys = cache.connection.0.lock().unwrap();
+        assert_eq!(keys["api:a"], 30_000);
+        assert_eq!(keys["api:b"], 86_400_000);
+        assert_eq!(keys["api:c"], 1);
+    }
+
+    #[test]
+    fn redis_replay_cache_fails_closed() {
+        let cache = RedisReplayCache::new(UnavailableRedis);
+        match cache.check_and_store("a", None, Utc.timestamp(100, 0)) {
+            Err(ValidationError::CustomValidation(message)) => {
+                assert!(message.contains("connection refused"))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use super::validator::ClaimsValidator;
+use crate::errors::ValidationError;
+
+/// The location of a claim within a claims set, given as the names of the nested objects leading
+/// to it
+///
+/// Helpers are provided for the locations commonly used for authorization claims.
+///
+/// # Examples
+/// ```
+/// use biscuit::ClaimPath;
+/// use serde_json::json;
+///
+/// let claims = json!({ "realm_access": { "roles": ["admin", "user"] } });
+/// let claims = claims.as_object().unwrap();
+///
+/// let path = ClaimPath::keycloak_realm_roles();
+/// assert_eq!(path, ClaimPath::from_dotted("realm_access.roles"));
+/// assert_eq!(path.values(claims).unwrap(), vec!["admin", "user"]);
+/// assert!(path.contains(claims, "admin").unwrap());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ClaimPath(Vec<String>);
+
+impl ClaimPath {
+    /// Create a path from the names of the nested objects leading to the claim
+    pub fn new<I, S>(segments: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ClaimPath(segments.into_iter().map(Into::into).collect())
+    }
+
+    /// Create a path from a string of names separated by `.`, like `realm_access.roles`
+    ///
+    /// Use [`ClaimPath::new`] if the names themselves contain a `.`.
+    pub fn from_dotted(path: &str) -> Self {
+        Self::new(path.split('.'))
+    }
+
+    /// The top level `roles` claim
+    pub fn roles() -> Self {
+        Self::new(vec!["roles"])
+    }
+
+    /// The top level `permissions` claim
+    pub fn permissions() -> Self {
+        Self::new(vec!["permissions"])
+    }
+
+    /// The top level `groups` claim
+    pub fn groups() -> Self {
+        Self::new(vec!["groups"])
+    }
+
+    /// Realm roles issued by Keycloak, in `realm_access.roles`
+    pub fn keycloak_realm_roles() -> Self {
+        Self::new(vec!["realm_access", "roles"])
+    }
+
+    /// Client roles issued by Keycloak, in `resource_access.<client_id>.roles`
+    pub fn keycloak_client_roles(client_id: &str) -> Self {
+        Self::new(vec!["resource_access", client_id, "roles"])
+    }
+
+    /// Returns the claim at this path, if it is present
+    pub fn lookup<'a>(&self, claims: &'a Map<String, Value>) -> Option<&'a Value> {
+        let (last, parents) = self.0.split_last()?;
+        let mut object = claims;
+        for segment in parents {
+            object = object.get(segment)?.as_object()?;
+        }
+        object.get(last)
+    }
+
+    /// Returns the values of the claim at this path, which must be a string or an array of
+    /// strings. A missing claim has no values.
+    ///
+    /// Returns [`ValidationError::MalformedClaim`] if the claim has any other type.
+    pub fn values<'a>(
+        &self,
+        claims: &'a Map<String, Value>,
+    ) -> Result<Vec<&'a str>, ValidationError> {
+        let malformed = || ValidationError::MalformedClaim(self.to_string());
+        match self.lookup(claims) {
+            None | Some(Value::Null) => Ok(vec![]),
+            Some(Value::String(value)) => Ok(vec![value]),
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|value| value.as_str().ok_or_else(malformed))
+                .collect(),
+            Some(_) => Err(malformed()),
+        }
+    }
+
+    /// Returns whether the claim at this path contains `value`
+    pub fn contains(
+        &self,
+        claims: &Map<String, Value>,
+        value: &str,
+    ) -> Result<bool, ValidationError> {
+        Ok(self.values(claims)?.contains(&value))
+    }
+}
+
+impl fmt::Display for ClaimPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join("."))
+    }
+}
+
+/// Checks that a token has the roles, permissions or groups needed to access a resource
+///
+/// The values are read from the claim at a [`ClaimPath`]. This can be registered as a validator
+/// in [`crate::ValidationOptions`].
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{ClaimPath, ClaimsSet, RegisteredClaims, RoleMatcher, ValidationOptions};
+/// use serde_json::json;
+///
+/// let options = ValidationOptions {
+///     validators: vec![Arc::new(
+///         RoleMatcher::new(ClaimPath::keycloak_realm_roles()).requires_any(vec!["admin", "editor"]),
+///     )],
+///     ..Default::default()
+/// };
+///
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims::default(),
+///     private: json!({ "realm_access": { "roles": ["viewer"] } }),
+/// };
+/// assert!(claims.validate(options).is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoleMatcher {
+    path: ClaimPath,
+    all: Vec<String>,
+    any: Vec<String>,
+}
+
+impl RoleMatcher {
+    /// Create a matcher for the values of the claim at `path`, which accepts any values
+    pub fn new(path: ClaimPath) -> Self {
+        RoleMatcher {
+            path,
+            all: vec![],
+            any: vec![],
+        }
+    }
+
+    /// Require every one of `values` to be present
+    pub fn requires_all<I, S>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.all.extend(values.into_iter().map(Into::into));
+        self
+    }
+
+    /// Require at least one of `values` to be present
+    pub fn requires_any<I, S>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.any.extend(values.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl ClaimsValidator for RoleMatcher {
+    /// Returns [`ValidationError::MissingRoles`] with the values that are missing. If none of
+    /// the values required by [`RoleMatcher::requires_any`] are present, all of them are
+    /// reported as missing.
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        let values = self.path.values(claims)?;
+        let mut missing: Vec<String> = self
+            .all
+            .iter()
+            .filter(|value| !values.contains(&value.as_str()))
+            .cloned()
+            .collect();
+        if !self.any.is_empty()
+            && !self
+                .any
+                .iter()
+                .any(|value| values.contains(&value.as_str()))
+        {
+            missing.extend(self.any.iter().cloned());
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::MissingRoles(
+                self.path.to_string(),
+                missing,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn claims(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn claim_path_values() {
+        let claims = claims(json!({
+            "roles": "admin",
+            "groups": ["a", "b"],
+            "permissions": 1,
+            "resource_access": { "my.client": { "roles": ["owner"] } },
+        }));
+
+        assert_eq!(not_err!(ClaimPath::roles().values(&claims)), vec!["admin"]);
+        assert_eq!(
+            not_err!(ClaimPath::groups().values(&claims)),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            not_err!(ClaimPath::keycloak_client_roles("my.client").values(&claims)),
+            vec!["owner"]
+        );
+        assert!(not_err!(ClaimPath::keycloak_realm_roles().values(&claims)).is_empty());
+        assert!(not_err!(ClaimPath::from_dotted("roles.nested").values(&claims)).is_empty());
+        assert_eq!(
+            ClaimPath::permissions().values(&claims),
+            Err(ValidationError::MalformedClaim("permissions".to_string()))
+        );
+    }
+
+    #[test]
+    fn role_matcher_requirements() {
+        let claims = claims(json!({ "permissions": ["read", "write"] }));
+        let matcher = || RoleMatcher::new(ClaimPath::permissions());
+
+        not_err!(matcher().validate(&claims));
+        not_err!(matcher()
+            .requires_all(vec!["read", "write"])
+            .requires_any(vec!["write", "admin"])
+            .validate(&claims));
+
+        assert_eq!(
+            matcher()
+                .requires_all(vec!["read", "delete"])
+                .requires_any(vec!["admin"])
+                .validate(&claims),
+            Err(ValidationError::MissingRoles(
+                "permissions".to_string(),
+                vec!["delete".to_string(), "admin".to_string()]
+            ))
+        );
+    }
+}
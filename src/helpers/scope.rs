@@ -0,0 +1,303 @@
+use std::fmt;
+
+use serde::de;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use super::validator::ClaimsValidator;
+use crate::errors::ValidationError;
+
+/// The scopes granted to a token, from the `scope` claim of
+/// [RFC 8693](https://tools.ietf.org/html/rfc8693#section-4.2) or the `scp` claim used by some
+/// identity providers
+///
+/// `scope` is a space-delimited string, while `scp` is usually an array of strings. This type
+/// deserializes from either form, and serializes to a space-delimited string.
+///
+/// # Examples
+/// ```
+/// use biscuit::Scopes;
+///
+/// let scopes: Scopes = serde_json::from_str(r#""read write""#).unwrap();
+/// assert!(scopes.contains("write"));
+///
+/// let scopes: Scopes = serde_json::from_str(r#"["read", "write"]"#).unwrap();
+/// assert_eq!(serde_json::to_string(&scopes).unwrap(), r#""read write""#);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct Scopes(Vec<String>);
+
+impl Scopes {
+    /// Create a set of scopes, ignoring duplicates
+    pub fn new<I, S>(scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut result = Scopes::default();
+        result.extend(scopes);
+        result
+    }
+
+    /// Read the scopes from the `scope` and `scp` claims of a claims set, combining them if both
+    /// are present
+    ///
+    /// Returns an error if either claim is not a string or an array of strings.
+    pub fn from_claims(claims: &Map<String, Value>) -> Result<Self, ValidationError> {
+        let mut scopes = Scopes::default();
+        for claim in &["scope", "scp"] {
+            match claims.get(*claim) {
+                None | Some(Value::Null) => {}
+                Some(value) => {
+                    let parsed = Scopes::deserialize(value)
+                        .map_err(|_| ValidationError::MalformedClaim(claim.to_string()))?;
+                    scopes.extend(parsed.0);
+                }
+            }
+        }
+        Ok(scopes)
+    }
+
+    /// Returns whether `scope` has been granted
+    pub fn contains(&self, scope: &str) -> bool {
+        self.0.iter().any(|granted| granted == scope)
+    }
+
+    /// Yields an iterator over the scopes, in the order they were granted
+    pub fn iter(&self) -> std::slice::Iter<'_, String> {
+        self.0.iter()
+    }
+
+    /// Returns the number of scopes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether no scopes have been granted
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn extend<I, S>(&mut self, scopes: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for scope in scopes {
+            let scope = scope.into();
+            if !scope.is_empty() && !self.contains(&scope) {
+                self.0.push(scope);
+            }
+        }
+    }
+}
+
+impl Serialize for Scopes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0.join(" "))
+    }
+}
+
+impl<'de> Deserialize<'de> for Scopes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ScopesVisitor;
+
+        impl<'de> de::Visitor<'de> for ScopesVisitor {
+            type Value = Scopes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a space-delimited string or an array of strings")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Scopes::new(value.split_whitespace()))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut scopes = Scopes::default();
+                while let Some(scope) = seq.next_element::<String>()? {
+                    scopes.extend(Some(scope));
+                }
+                Ok(scopes)
+            }
+        }
+
+        deserializer.deserialize_any(ScopesVisitor)
+    }
+}
+
+/// Checks that a token has been granted the scopes needed to access a resource
+///
+/// This can be used on its own with [`ScopeMatcher::check`], or registered as a validator in
+/// [`crate::ValidationOptions`] to check the `scope` and `scp` claims.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{ClaimsSet, RegisteredClaims, ScopeMatcher, Scopes, ValidationOptions};
+/// use serde_json::json;
+///
+/// let matcher = ScopeMatcher::new()
+///     .requires_all(vec!["read"])
+///     .requires_any(vec!["admin", "write"]);
+/// assert!(matcher.check(&Scopes::new(vec!["read", "write"])).is_ok());
+/// assert!(matcher.check(&Scopes::new(vec!["read"])).is_err());
+///
+/// let options = ValidationOptions {
+///     validators: vec![Arc::new(matcher)],
+///     ..Default::default()
+/// };
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims::default(),
+///     private: json!({ "scope": "read admin" }),
+/// };
+/// assert!(claims.validate(options).is_ok());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct ScopeMatcher {
+    all: Vec<String>,
+    any: Vec<String>,
+}
+
+impl ScopeMatcher {
+    /// Create a matcher that accepts any scopes
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Require every one of `scopes` to be granted
+    pub fn requires_all<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.all.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Require at least one of `scopes` to be granted
+    pub fn requires_any<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.any.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns whether the granted scopes satisfy the requirements
+    pub fn matches(&self, scopes: &Scopes) -> bool {
+        self.check(scopes).is_ok()
+    }
+
+    /// Checks that the granted scopes satisfy the requirements, returning
+    /// [`ValidationError::InsufficientScope`] with the scopes that are missing otherwise
+    ///
+    /// If none of the scopes required by [`ScopeMatcher::requires_any`] are granted, all of them
+    /// are reported as missing.
+    pub fn check(&self, scopes: &Scopes) -> Result<(), ValidationError> {
+        let mut missing: Vec<String> = self
+            .all
+            .iter()
+            .filter(|scope| !scopes.contains(scope))
+            .cloned()
+            .collect();
+        if !self.any.is_empty() && !self.any.iter().any(|scope| scopes.contains(scope)) {
+            missing.extend(self.any.iter().cloned());
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::InsufficientScope(missing))
+        }
+    }
+}
+
+impl ClaimsValidator for ScopeMatcher {
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        self.check(&Scopes::from_claims(claims)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn claims(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn scopes_from_claims() {
+        let scopes = not_err!(Scopes::from_claims(&claims(json!({
+            "scope": "read  write",
+            "scp": ["write", "admin"],
+        }))));
+        assert_eq!(
+            scopes.iter().map(String::as_str).collect::<Vec<_>>(),
+            vec!["read", "write", "admin"]
+        );
+
+        let scopes = not_err!(Scopes::from_claims(&claims(json!({}))));
+        assert!(scopes.is_empty());
+
+        assert_eq!(
+            Scopes::from_claims(&claims(json!({ "scp": [1, 2] }))),
+            Err(ValidationError::MalformedClaim("scp".to_string()))
+        );
+    }
+
+    #[test]
+    fn scope_matcher_requirements() {
+        let scopes = Scopes::new(vec!["read", "write"]);
+
+        not_err!(ScopeMatcher::new().check(&scopes));
+        not_err!(ScopeMatcher::new()
+            .requires_all(vec!["read", "write"])
+            .check(&scopes));
+        not_err!(ScopeMatcher::new()
+            .requires_any(vec!["admin", "write"])
+            .check(&scopes));
+
+        assert_eq!(
+            ScopeMatcher::new()
+                .requires_all(vec!["read", "delete"])
+                .requires_any(vec!["admin", "root"])
+                .check(&scopes),
+            Err(ValidationError::InsufficientScope(vec![
+                "delete".to_string(),
+                "admin".to_string(),
+                "root".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn scope_matcher_as_validator() {
+        let matcher = ScopeMatcher::new().requires_all(vec!["read"]);
+
+        not_err!(ClaimsValidator::validate(
+            &matcher,
+            &claims(json!({ "scp": "read" }))
+        ));
+        assert!(ClaimsValidator::validate(&matcher, &claims(json!({}))).is_err());
+    }
+}
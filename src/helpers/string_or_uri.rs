@@ -0,0 +1,273 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use serde::de;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::Error;
+
+/// A [StringOrURI](https://tools.ietf.org/html/rfc7519#section-2) value, as used by the `iss`,
+/// `sub` and `aud` claims
+///
+/// RFC 7519 requires any value containing a `:` to be a URI, so this type can only be constructed
+/// from arbitrary strings without a `:`, or from syntactically valid URIs.
+///
+/// Arbitrary strings are compared exactly. URIs are compared after the case normalization of
+/// [RFC 3986 §6.2.2.1](https://tools.ietf.org/html/rfc3986#section-6.2.2.1): the scheme, the
+/// host and the hexadecimal digits of percent-encodings are case-insensitive.
+///
+/// # Examples
+/// ```
+/// use std::str::FromStr;
+/// use biscuit::StringOrUri;
+///
+/// let issuer = StringOrUri::from_str("https://Example.COM/tenant").unwrap();
+/// assert!(issuer.is_uri());
+/// assert_eq!(issuer, StringOrUri::from_str("HTTPS://example.com/tenant").unwrap());
+/// assert_ne!(issuer, StringOrUri::from_str("https://example.com/TENANT").unwrap());
+///
+/// assert!(StringOrUri::from_str("not a uri: really").is_err());
+/// assert!(!StringOrUri::from_str("plain issuer").unwrap().is_uri());
+/// ```
+#[derive(Clone, Debug)]
+pub struct StringOrUri(String);
+
+impl StringOrUri {
+    /// Create a `StringOrUri`, returning an error if `value` contains a `:` but is not a valid URI
+    pub fn new<S: Into<String>>(value: S) -> Result<Self, Error> {
+        let value = value.into();
+        if value.contains(':') && !is_valid_uri(&value) {
+            return Err(Error::InvalidUri(value));
+        }
+        Ok(StringOrUri(value))
+    }
+
+    /// Returns whether the value is a URI, rather than an arbitrary string
+    pub fn is_uri(&self) -> bool {
+        self.0.contains(':')
+    }
+
+    /// The value as it was provided
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes self and returns the value as it was provided
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// The form of the value used for comparisons
+    fn comparison_key(&self) -> Cow<'_, str> {
+        let colon = match self.0.find(':') {
+            Some(colon) => colon,
+            None => return Cow::Borrowed(&self.0),
+        };
+        let (scheme, rest) = self.0.split_at(colon);
+        let mut key = scheme.to_ascii_lowercase();
+
+        // URIs are validated on construction to be ASCII, so this cannot split a character
+        let (separator, hierarchy) = rest.split_at(rest.len().min(3));
+        let rest = if separator == "://" {
+            key.push_str(separator);
+            let authority_end = hierarchy
+                .find(&['/', '?', '#'][..])
+                .unwrap_or(hierarchy.len());
+            let (authority, path) = hierarchy.split_at(authority_end);
+            // Only the host is case-insensitive, not any user information before it
+            let host_start = authority.rfind('@').map(|at| at + 1).unwrap_or(0);
+            let (user_info, host) = authority.split_at(host_start);
+            push_normalized_percent_encoding(&mut key, user_info);
+            key.push_str(&host.to_ascii_lowercase());
+            path
+        } else {
+            rest
+        };
+        push_normalized_percent_encoding(&mut key, rest);
+        Cow::Owned(key)
+    }
+}
+
+/// Checks the generic URI syntax from RFC 3986: a scheme followed by `:` and then only characters
+/// that are allowed in URIs, with well-formed percent-encodings
+fn is_valid_uri(value: &str) -> bool {
+    let (scheme, rest) = match value.find(':') {
+        Some(colon) => (&value[..colon], &value[colon + 1..]),
+        None => return false,
+    };
+
+    let mut scheme_chars = scheme.chars();
+    let scheme_valid = scheme_chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic())
+        .unwrap_or(false)
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+    if !scheme_valid {
+        return false;
+    }
+
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex_digits = bytes.get(i + 1..i + 3);
+                if !hex_digits
+                    .map(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+                    .unwrap_or(false)
+                {
+                    return false;
+                }
+                i += 3;
+            }
+            b if b.is_ascii_alphanumeric() || b"-._~:/?#[]@!$&'()*+,;=".contains(&b) => i += 1,
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn push_normalized_percent_encoding(key: &mut String, value: &str) {
+    let mut remaining = value;
+    while let Some(percent) = remaining.find('%') {
+        key.push_str(&remaining[..=percent]);
+        // URIs are validated on construction, so every `%` is followed by two hex digits
+        key.push_str(&remaining[percent + 1..percent + 3].to_ascii_uppercase());
+        remaining = &remaining[percent + 3..];
+    }
+    key.push_str(remaining);
+}
+
+impl PartialEq for StringOrUri {
+    fn eq(&self, other: &Self) -> bool {
+        self.comparison_key() == other.comparison_key()
+    }
+}
+
+impl Eq for StringOrUri {}
+
+impl Hash for StringOrUri {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.comparison_key().hash(state)
+    }
+}
+
+impl FromStr for StringOrUri {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl AsRef<str> for StringOrUri {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<StringOrUri> for String {
+    fn from(value: StringOrUri) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for StringOrUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for StringOrUri {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for StringOrUri {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Self::new(value).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn string_or_uri_validates_uris() {
+        for valid in &[
+            "issuer",
+            "an issuer with spaces",
+            "",
+            "urn:example:animal:ferret:nose",
+            "https://user@example.com:8080/path?query=1#fragment",
+            "https://example.com/%7Euser",
+            "mailto:someone@example.com",
+        ] {
+            let value = not_err!(StringOrUri::new(*valid));
+            assert_eq!(value.as_str(), *valid);
+        }
+
+        for invalid in &[
+            ":",
+            "1http://example.com",
+            "https://example.com/a path",
+            "https://example.com/%7",
+            "https://example.com/%zz",
+            "héllo:world",
+        ] {
+            match StringOrUri::new(*invalid) {
+                Err(Error::InvalidUri(ref value)) if value == invalid => {}
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn string_or_uri_comparison() {
+        let uri = |value: &str| not_err!(StringOrUri::new(value));
+
+        assert_eq!(uri("https://EXAMPLE.com/a"), uri("hTTps://example.COM/a"));
+        assert_eq!(
+            uri("https://example.com/%7e"),
+            uri("https://example.com/%7E")
+        );
+        assert_eq!(uri("urn:Example"), uri("URN:Example"));
+        assert_ne!(uri("https://example.com/A"), uri("https://example.com/a"));
+        assert_ne!(
+            uri("https://User@example.com"),
+            uri("https://user@example.com")
+        );
+        assert_ne!(uri("urn:Example"), uri("urn:example"));
+        assert_ne!(uri("Issuer"), uri("issuer"));
+
+        let set: HashSet<_> = vec![uri("https://EXAMPLE.com"), uri("https://example.com")]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn string_or_uri_serde() {
+        let value: StringOrUri = not_err!(serde_json::from_str(r#""https://example.com""#));
+        assert!(value.is_uri());
+        assert_eq!(
+            not_err!(serde_json::to_string(&value)),
+            r#""https://example.com""#
+        );
+
+        assert!(serde_json::from_str::<StringOrUri>(r#""bad: uri""#).is_err());
+    }
+}
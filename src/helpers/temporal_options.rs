@@ -1,24 +1,83 @@
+use std::fmt;
+use std::sync::Arc;
+
 use chrono::{DateTime, Duration, Utc};
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+use super::clock::{Clock, DebugClock, SystemClock};
+
+#[derive(Clone)]
 /// Options for validating temporal claims
 ///
 ///
 /// To deal with clock drifts, you might want to provide an `epsilon` error margin in the form of a
-/// `chrono::Duration` to allow time comparisons to fall within the margin.
+/// `chrono::Duration` to allow time comparisons to fall within the margin. The margin can be
+/// overridden for each of the `exp`, `nbf` and `iat` claims.
 pub struct TemporalOptions {
     /// Allow for some leeway for clock drifts, limited to this duration during temporal validation
     pub epsilon: Duration,
 
-    /// Specify a time to use in temporal validation instead of `Now`
+    /// Leeway for the `exp` claim, instead of `epsilon`
+    pub expiry_epsilon: Option<Duration>,
+
+    /// Leeway for the `nbf` claim, instead of `epsilon`
+    pub not_before_epsilon: Option<Duration>,
+
+    /// Leeway for the `iat` claim, instead of `epsilon`
+    pub issued_at_epsilon: Option<Duration>,
+
+    /// Specify a time to use in temporal validation instead of `Now`.
+    /// This takes precedence over `clock`.
     pub now: Option<DateTime<Utc>>,
+
+    /// The source of the current time during temporal validation. Defaults to the system clock.
+    pub clock: Arc<dyn Clock>,
+}
+
+impl TemporalOptions {
+    /// Returns the time to use in temporal validation: `now` if provided, or the time
+    /// according to `clock` otherwise
+    pub fn current_time(&self) -> DateTime<Utc> {
+        self.now.unwrap_or_else(|| self.clock.now())
+    }
+
+    /// Returns the leeway for the `exp` claim: `expiry_epsilon` if provided, or `epsilon`
+    pub fn epsilon_for_expiry(&self) -> Duration {
+        self.expiry_epsilon.unwrap_or(self.epsilon)
+    }
+
+    /// Returns the leeway for the `nbf` claim: `not_before_epsilon` if provided, or `epsilon`
+    pub fn epsilon_for_not_before(&self) -> Duration {
+        self.not_before_epsilon.unwrap_or(self.epsilon)
+    }
+
+    /// Returns the leeway for the `iat` claim: `issued_at_epsilon` if provided, or `epsilon`
+    pub fn epsilon_for_issued_at(&self) -> Duration {
+        self.issued_at_epsilon.unwrap_or(self.epsilon)
+    }
 }
 
 impl Default for TemporalOptions {
     fn default() -> Self {
         TemporalOptions {
             epsilon: Duration::seconds(0),
+            expiry_epsilon: None,
+            not_before_epsilon: None,
+            issued_at_epsilon: None,
             now: None,
+            clock: Arc::new(SystemClock),
         }
     }
 }
+
+impl fmt::Debug for TemporalOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TemporalOptions")
+            .field("epsilon", &self.epsilon)
+            .field("expiry_epsilon", &self.expiry_epsilon)
+            .field("not_before_epsilon", &self.not_before_epsilon)
+            .field("issued_at_epsilon", &self.issued_at_epsilon)
+            .field("now", &self.now)
+            .field("clock", &DebugClock)
+            .finish()
+    }
+}
@@ -0,0 +1,53 @@
+use serde_json::{Map, Value};
+
+use crate::errors::ValidationError;
+
+/// A user-provided check that is run against the claims of a token during validation
+///
+/// Validators receive the full claims set, registered and private claims alike, in their JSON
+/// form and may veto the token by returning an error. This allows business-specific checks like
+/// tenant IDs or scopes to run as part of the same validation pass.
+///
+/// Validators are registered in [`crate::ValidationOptions`] and are run in order after all
+/// other claims have been validated. They are only run when validating a whole
+/// [`crate::ClaimsSet`].
+///
+/// Closures with the signature `Fn(&Map<String, Value>) -> Result<(), ValidationError>` implement
+/// this trait.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{ClaimsSet, RegisteredClaims, ValidationOptions};
+/// use biscuit::errors::ValidationError;
+/// use serde_json::{json, Map, Value};
+///
+/// let options = ValidationOptions {
+///     validators: vec![Arc::new(|claims: &Map<String, Value>| {
+///         match claims.get("tenant") {
+///             Some(tenant) if tenant == "acme" => Ok(()),
+///             _ => Err(ValidationError::CustomValidation("Unknown tenant".to_string())),
+///         }
+///     })],
+///     ..Default::default()
+/// };
+///
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims::default(),
+///     private: json!({ "tenant": "ecorp" }),
+/// };
+/// assert!(claims.validate(options).is_err());
+/// ```
+pub trait ClaimsValidator: Send + Sync {
+    /// Validate the claims, returning an error to reject the token
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError>;
+}
+
+impl<F> ClaimsValidator for F
+where
+    F: Fn(&Map<String, Value>) -> Result<(), ValidationError> + Send + Sync,
+{
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        self(claims)
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::replay::LruState;
+use crate::errors::Error;
+use crate::jws::{Header, Secret};
+use crate::{ClaimsSet, Empty, ValidationOptions};
+
+/// A bounded, in-memory cache of tokens whose signatures have been verified, so that a token
+/// that is presented again, such as a bearer token sent with every request, is not verified again.
+///
+/// Only the signature verification is skipped: [`VerificationCache::decode`] checks the
+/// algorithm and validates the header and the claims of cached tokens on every call, like
+/// [`crate::decode`]. Tokens are cached until their `exp` claim, and tokens without an `exp`
+/// claim are never cached. The least recently used tokens are evicted when the cache is full.
+///
+/// A cache verifies signatures with the secret it was created with, so that a token verified
+/// with one secret is never returned for another.
+///
+/// # Examples
+/// ```
+/// use chrono::Duration;
+/// use biscuit::{Empty, Token, Validation, ValidationOptions, VerificationCache};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+///
+/// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+/// let token = Token::builder()
+///     .expires_in(Duration::minutes(5))
+///     .sign(&secret, SignatureAlgorithm::HS256)
+///     .unwrap();
+/// let options = ValidationOptions {
+///     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+///     ..Default::default()
+/// };
+///
+/// let cache = VerificationCache::<Empty>::new(secret, 1024);
+/// let claims = cache.decode(&token, &options).unwrap();
+/// assert_eq!(cache.len(), 1);
+/// // The signature is not verified again
+/// assert_eq!(cache.decode(&token, &options).unwrap(), claims);
+/// ```
+pub struct VerificationCache<T> {
+    secret: Secret,
+    capacity: usize,
+    inner: Mutex<LruState<(Header<Empty>, ClaimsSet<T>)>>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for VerificationCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerificationCache")
+            .field("capacity", &self.capacity)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T> VerificationCache<T>
+where
+    T: Clone + Serialize + DeserializeOwned,
+{
+    /// Create a cache that verifies signatures with `secret` and remembers at most `capacity`
+    /// tokens.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(secret: Secret, capacity: usize) -> Self {
+        assert!(capacity > 0, "Verification cache capacity must be non-zero");
+        Self {
+            secret,
+            capacity,
+            inner: Mutex::new(Default::default()),
+        }
+    }
+
+    /// The maximum number of tokens remembered
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of tokens currently remembered, including any that have expired but have not
+    /// been purged yet.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether no tokens are currently remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decode and validate `token` like [`crate::decode`] with the secret of the cache, skipping
+    /// the signature verification if the token was verified before
+    pub fn decode(&self, token: &str, options: &ValidationOptions) -> Result<ClaimsSet<T>, Error> {
+        options.size_limits.check(token)?;
+        let now = options.temporal_options.current_time();
+        let cached = self.lock().get(token, now).cloned();
+        let (header, claims) = match cached {
+            Some((header, claims)) => {
+                crate::check_algorithm(header.registered.algorithm, options)?;
+                (header, claims)
+            }
+            None => {
+                let (header, claims) =
+                    crate::verify_signature::<ClaimsSet<T>>(token, &self.secret, options)?;
+                if let Some(expiry) = claims.registered.expiry {
+                    if *expiry > now {
+                        let mut state = self.lock();
+                        state.make_room(self.capacity, now);
+                        state.touch(token, Some(*expiry), (header.clone(), claims.clone()));
+                    }
+                }
+                (header, claims)
+            }
+        };
+
+        header.registered.validate(options)?;
+        claims.validate(options.clone())?;
+        Ok(claims)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruState<(Header<Empty>, ClaimsSet<T>)>> {
+        // Entries are only inserted once they are complete, so recover from poisoning.
+        self.inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::*;
+    use crate::errors::ValidationError;
+    use crate::jwa::SignatureAlgorithm;
+    use crate::{FixedClock, TemporalOptions, Token, Validation};
+
+    fn options(now: i64) -> ValidationOptions {
+        ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            temporal_options: TemporalOptions {
+                now: Some(Utc.timestamp(now, 0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn token(secret: &Secret, subject: &str, expires_in: Option<i64>) -> String {
+        let mut builder = Token::builder()
+            .subject(subject)
+            .clock(FixedClock(Utc.timestamp(1000, 0)));
+        if let Some(seconds) = expires_in {
+            builder = builder.expires_in(Duration::seconds(seconds));
+        }
+        not_err!(builder.sign(secret, SignatureAlgorithm::HS256))
+    }
+
+    #[test]
+    fn verification_cache_skips_verification_of_cached_tokens() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let cache = VerificationCache::<Empty>::new(secret.clone(), 2);
+        let token = token(&secret, "alice", Some(60));
+
+        let claims = not_err!(cache.decode(&token, &options(1000)));
+        assert_eq!(claims.registered.subject.as_deref(), Some("alice"));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(not_err!(cache.decode(&token, &options(1010))), claims);
+
+        // The algorithm and the claims are still validated
+        let options_without_hs256 = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::RS256]),
+            ..options(1010)
+        };
+        match cache.decode(&token, &options_without_hs256) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        // Expired tokens are verified again
+        match cache.decode(&token, &options(1061)) {
+            Err(Error::ValidationError(ValidationError::Expired(_))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn verification_cache_verifies_with_its_own_secret() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let other_secret = Secret::Bytes("other".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = token(&secret, "alice", Some(60));
+
+        let cache = VerificationCache::<Empty>::new(secret, 2);
+        let _ = not_err!(cache.decode(&token, &options(1000)));
+        assert_eq!(cache.len(), 1);
+
+        let other_cache = VerificationCache::<Empty>::new(other_secret, 2);
+        match other_cache.decode(&token, &options(1010)) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(other_cache.is_empty());
+    }
+
+    #[test]
+    fn verification_cache_only_caches_tokens_with_expiry() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let cache = VerificationCache::<Empty>::new(secret.clone(), 2);
+
+        let _ = not_err!(cache.decode(&token(&secret, "alice", None), &options(1000)));
+        assert!(cache.is_empty());
+
+        for subject in &["alice", "bob", "carol"] {
+            let token = token(&secret, subject, Some(60));
+            let _ = not_err!(cache.decode(&token, &options(1000)));
+        }
+        assert_eq!(cache.len(), 2);
+    }
+}
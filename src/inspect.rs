@@ -0,0 +1,199 @@
+//! Inspection of tokens for debugging
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::errors::{DecodeError, Error};
+use crate::Timestamp;
+
+/// The registered claims holding NumericDates, which are rendered in human time
+const TIMESTAMP_CLAIMS: &[&str] = &["exp", "nbf", "iat", "auth_time", "toe"];
+
+/// The decoded header and claims of a token, produced by [`inspect`] without verifying the token
+///
+/// The `Display` implementation renders a report for debugging, with the NumericDate claims
+/// in human time.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenReport {
+    /// The JOSE header of the token
+    pub header: Map<String, Value>,
+    /// The claims of the token. This is `None` for encrypted tokens, and for signed tokens with
+    /// a payload that is not a JSON object.
+    pub claims: Option<Map<String, Value>>,
+    /// Whether the token is a JWE, rather than a JWS
+    pub encrypted: bool,
+}
+
+/// Decode the header and the claims of a compact JWS or JWE without verifying or decrypting it,
+/// for debugging
+///
+/// ## Warning
+/// Nothing in the report can be trusted, since the token is not verified.
+///
+/// # Examples
+/// ```
+/// use biscuit::inspect;
+///
+/// let token = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+///      eyJpc3MiOiJodHRwczovL3d3dy5hY21lLmNvbS8iLCJzdWIiOiJKb2huIERvZSIsImF1ZCI6Imh0dHBzOi8vYWNtZ\
+///      S1jdXN0b21lci5jb20vIiwibmJmIjoxMjM0LCJjb21wYW55IjoiQUNNRSIsImRlcGFydG1lbnQiOiJUb2lsZXQgQ2\
+///      xlYW5pbmcifQ.VFCl2un1Kc17odzOe2Ehf4DVrWddu3U4Ux3GFpOZHtc";
+/// let report = inspect(token).unwrap();
+/// assert_eq!(report.algorithm(), Some("HS256"));
+/// assert!(report
+///     .to_string()
+///     .contains("nbf: 1234 (1970-01-01 00:20:34 UTC)"));
+/// ```
+pub fn inspect(token: &str) -> Result<TokenReport, Error> {
+    let compact = crate::Compact::decode(token);
+    let header: Map<String, Value> = compact.part(0)?;
+    match compact.len() {
+        3 => Ok(TokenReport {
+            header,
+            claims: compact.part(1).ok(),
+            encrypted: false,
+        }),
+        5 => Ok(TokenReport {
+            header,
+            claims: None,
+            encrypted: true,
+        }),
+        actual => Err(DecodeError::PartsLengthError {
+            actual,
+            expected: 3,
+        })?,
+    }
+}
+
+impl TokenReport {
+    /// The `alg` header
+    pub fn algorithm(&self) -> Option<&str> {
+        self.header.get("alg").and_then(Value::as_str)
+    }
+
+    /// The `kid` header
+    pub fn key_id(&self) -> Option<&str> {
+        self.header.get("kid").and_then(Value::as_str)
+    }
+
+    /// The `typ` header
+    pub fn media_type(&self) -> Option<&str> {
+        self.header.get("typ").and_then(Value::as_str)
+    }
+
+    /// The `exp` claim, if it is a valid NumericDate
+    pub fn expiry(&self) -> Option<Timestamp> {
+        self.timestamp("exp")
+    }
+
+    /// The `nbf` claim, if it is a valid NumericDate
+    pub fn not_before(&self) -> Option<Timestamp> {
+        self.timestamp("nbf")
+    }
+
+    /// The `iat` claim, if it is a valid NumericDate
+    pub fn issued_at(&self) -> Option<Timestamp> {
+        self.timestamp("iat")
+    }
+
+    fn timestamp(&self, claim: &str) -> Option<Timestamp> {
+        let value = self.claims.as_ref()?.get(claim)?;
+        Timestamp::deserialize(value).ok()
+    }
+}
+
+impl fmt::Display for TokenReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", if self.encrypted { "JWE" } else { "JWS" })?;
+        writeln!(f, "Header:")?;
+        write_claims(f, &self.header)?;
+        match self.claims {
+            Some(ref claims) => {
+                writeln!(f, "Claims:")?;
+                write_claims(f, claims)
+            }
+            None if self.encrypted => writeln!(f, "Claims: encrypted"),
+            None => writeln!(f, "Claims: payload is not a JSON object"),
+        }
+    }
+}
+
+fn write_claims(f: &mut fmt::Formatter<'_>, claims: &Map<String, Value>) -> fmt::Result {
+    for (name, value) in claims {
+        write!(f, "  {}: {}", name, value)?;
+        if TIMESTAMP_CLAIMS.contains(&name.as_str()) {
+            if let Ok(timestamp) = Timestamp::deserialize(value) {
+                write!(f, " ({})", *timestamp)?;
+            }
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jws::{self, RegisteredHeader, Secret};
+    use crate::ClaimsSet;
+
+    #[test]
+    fn inspect_signed_token() {
+        let token = not_err!(jws::Compact::<ClaimsSet<Value>, crate::Empty>::new_decoded(
+            From::from(RegisteredHeader {
+                key_id: Some("key-1".to_string()),
+                ..Default::default()
+            }),
+            ClaimsSet {
+                registered: crate::RegisteredClaims {
+                    issuer: Some("https://www.acme.com".to_string()),
+                    expiry: Some(1_600_000_000.into()),
+                    ..Default::default()
+                },
+                private: json!({ "roles": ["admin"] }),
+            },
+        )
+        .into_encoded(&Secret::Bytes(b"secret".to_vec()).danger_allow_weak_keys()))
+        .unwrap_encoded()
+        .to_string();
+
+        let report = not_err!(inspect(&token));
+        assert!(!report.encrypted);
+        assert_eq!(report.algorithm(), Some("HS256"));
+        assert_eq!(report.key_id(), Some("key-1"));
+        assert_eq!(report.media_type(), Some("JWT"));
+        assert_eq!(report.expiry(), Some(1_600_000_000.into()));
+        assert_eq!(report.issued_at(), None);
+
+        assert_eq!(
+            report.to_string(),
+            "JWS\n\
+             Header:\n  \
+               alg: \"HS256\"\n  \
+               typ: \"JWT\"\n  \
+               kid: \"key-1\"\n\
+             Claims:\n  \
+               iss: \"https://www.acme.com\"\n  \
+               exp: 1600000000 (2020-09-13 12:26:40 UTC)\n  \
+               roles: [\"admin\"]\n"
+        );
+    }
+
+    #[test]
+    fn inspect_other_tokens() {
+        let report = not_err!(inspect(
+            "eyJhbGciOiJSU0EtT0FFUC0yNTYiLCJlbmMiOiJBMjU2R0NNIn0.a.b.c.d"
+        ));
+        assert!(report.encrypted);
+        assert_eq!(report.algorithm(), Some("RSA-OAEP-256"));
+        assert!(report.to_string().ends_with("Claims: encrypted\n"));
+
+        let report = not_err!(inspect("eyJhbGciOiJub25lIn0.SGVsbG8.")); // payload "Hello"
+        assert_eq!(report.claims, None);
+
+        assert!(inspect("eyJhbGciOiJub25lIn0.e30").is_err());
+    }
+}
@@ -5,33 +5,53 @@
 //! Typically, you will not use these directly, but as part of a JWS or JWE.
 
 use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+#[cfg(any(feature = "rsa", feature = "ecdsa"))]
+use std::sync::Arc;
 
+use data_encoding::BASE64URL_NOPAD;
 use once_cell::sync::Lazy;
+#[cfg(feature = "jwe")]
+use ring::aead;
 use ring::constant_time::verify_slices_are_equal;
+use ring::hmac;
 use ring::rand::SystemRandom;
+#[cfg(any(feature = "rsa", feature = "ecdsa"))]
+use ring::signature;
+#[cfg(any(feature = "rsa", feature = "ecdsa"))]
 use ring::signature::KeyPair;
-use ring::{aead, hmac, rand, signature};
+#[cfg(feature = "jwe")]
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::errors::Error;
+use crate::errors::{Error, SignatureFailure, ValidationError};
+#[cfg(feature = "jwe")]
 use crate::jwk;
 use crate::jws::Secret;
+#[cfg(feature = "jwe")]
 use crate::Empty;
 
 pub use ring::rand::SecureRandom;
 
+/// Minimum length of RSA keys, in bits, from RFC 7518
+pub(crate) const MINIMUM_RSA_KEY_BITS: usize = 2048;
+
 /// AES GCM Tag Size, in bytes
+#[cfg(feature = "jwe")]
 const AES_GCM_TAG_SIZE: usize = 128 / 8;
 /// AES GCM Nonce length, in bytes
+#[cfg(feature = "jwe")]
 const AES_GCM_NONCE_LENGTH: usize = 96 / 8;
 
 /// A zeroed AES GCM Nonce EncryptionOptions
+#[cfg(feature = "jwe")]
 static AES_GCM_ZEROED_NONCE: Lazy<EncryptionOptions> = Lazy::new(|| EncryptionOptions::AES_GCM {
     nonce: vec![0; AES_GCM_NONCE_LENGTH],
 });
 
 /// A default `None` `EncryptionOptions`
+#[cfg(feature = "jwe")]
 pub(crate) const NONE_ENCRYPTION_OPTIONS: &EncryptionOptions = &EncryptionOptions::None;
 
 /// Options to be passed in while performing an encryption operation, if required by the algorithm.
@@ -236,6 +256,137 @@ impl Default for ContentEncryptionAlgorithm {
     }
 }
 
+/// Implements `name`, `Display` and `FromStr` for an algorithm enum, with the names used in the
+/// `alg` and `enc` headers
+macro_rules! algorithm_names {
+    ($algorithm:ident { $($variant:ident => $name:expr,)* }) => {
+        impl $algorithm {
+            /// Every algorithm, including those unsupported by this crate
+            pub const ALL: &'static [$algorithm] = &[$($algorithm::$variant,)*];
+
+            /// The name of the algorithm, as serialized in headers
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($algorithm::$variant => $name,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $algorithm {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl FromStr for $algorithm {
+            type Err = Error;
+
+            /// Parses the name of the algorithm, as serialized in headers. Returns
+            /// [`Error::UnknownAlgorithm`] with the supported names if no algorithm has this name.
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                $algorithm::ALL
+                    .iter()
+                    .find(|algorithm| algorithm.name() == name)
+                    .cloned()
+                    .ok_or_else(|| Error::UnknownAlgorithm {
+                        name: name.to_string(),
+                        supported: $algorithm::ALL.iter().map(|algorithm| algorithm.name()).collect(),
+                    })
+            }
+        }
+    };
+}
+
+algorithm_names!(SignatureAlgorithm {
+    None => "none",
+    HS256 => "HS256",
+    HS384 => "HS384",
+    HS512 => "HS512",
+    RS256 => "RS256",
+    RS384 => "RS384",
+    RS512 => "RS512",
+    ES256 => "ES256",
+    ES384 => "ES384",
+    ES512 => "ES512",
+    PS256 => "PS256",
+    PS384 => "PS384",
+    PS512 => "PS512",
+});
+
+algorithm_names!(KeyManagementAlgorithm {
+    RSA1_5 => "RSA1_5",
+    RSA_OAEP => "RSA-OAEP",
+    RSA_OAEP_256 => "RSA-OAEP-256",
+    A128KW => "A128KW",
+    A192KW => "A192KW",
+    A256KW => "A256KW",
+    DirectSymmetricKey => "dir",
+    ECDH_ES => "ECDH-ES",
+    ECDH_ES_A128KW => "ECDH-ES+A128KW",
+    ECDH_ES_A192KW => "ECDH-ES+A192KW",
+    ECDH_ES_A256KW => "ECDH-ES+A256KW",
+    A128GCMKW => "A128GCMKW",
+    A192GCMKW => "A192GCMKW",
+    A256GCMKW => "A256GCMKW",
+    PBES2_HS256_A128KW => "PBES2-HS256+A128KW",
+    PBES2_HS384_A192KW => "PBES2-HS384+A192KW",
+    PBES2_HS512_A256KW => "PBES2-HS512+A256KW",
+});
+
+algorithm_names!(ContentEncryptionAlgorithm {
+    A128CBC_HS256 => "A128CBC-HS256",
+    A192CBC_HS384 => "A192CBC-HS384",
+    A256CBC_HS512 => "A256CBC-HS512",
+    A128GCM => "A128GCM",
+    A192GCM => "A192GCM",
+    A256GCM => "A256GCM",
+});
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Algorithm::Signature(algorithm) => fmt::Display::fmt(&algorithm, f),
+            Algorithm::KeyManagement(algorithm) => fmt::Display::fmt(&algorithm, f),
+            Algorithm::ContentEncryption(algorithm) => fmt::Display::fmt(&algorithm, f),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = Error;
+
+    /// Parses the name of any algorithm, trying signature, key management and content encryption
+    /// algorithms in turn
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if let Ok(algorithm) = name.parse() {
+            return Ok(Algorithm::Signature(algorithm));
+        }
+        if let Ok(algorithm) = name.parse() {
+            return Ok(Algorithm::KeyManagement(algorithm));
+        }
+        if let Ok(algorithm) = name.parse() {
+            return Ok(Algorithm::ContentEncryption(algorithm));
+        }
+        Err(Error::UnknownAlgorithm {
+            name: name.to_string(),
+            supported: SignatureAlgorithm::ALL
+                .iter()
+                .map(|algorithm| algorithm.name())
+                .chain(
+                    KeyManagementAlgorithm::ALL
+                        .iter()
+                        .map(|algorithm| algorithm.name()),
+                )
+                .chain(
+                    ContentEncryptionAlgorithm::ALL
+                        .iter()
+                        .map(|algorithm| algorithm.name()),
+                )
+                .collect(),
+        })
+    }
+}
+
 impl EncryptionOptions {
     /// Description of the type of key
     pub fn description(&self) -> &'static str {
@@ -253,19 +404,41 @@ impl fmt::Display for EncryptionOptions {
 }
 
 impl SignatureAlgorithm {
+    /// Whether the algorithm is allowed by the Financial-grade API (FAPI) security profiles,
+    /// which only allow `PS256` and `ES256`
+    ///
+    /// RSA PKCS#1 v1.5 (`RS256`, `RS384` and `RS512`), HMAC and `none` are not allowed. Check
+    /// this before signing tokens for a FAPI ecosystem, and use [`crate::ValidationOptions::fapi`]
+    /// to verify them.
+    pub fn is_fapi_compliant(self) -> bool {
+        self == SignatureAlgorithm::PS256 || self == SignatureAlgorithm::ES256
+    }
+
     /// Take some bytes and sign it according to the algorithm and secret provided.
+    ///
+    /// Returns [`ValidationError::UnsecuredToken`] for [`SignatureAlgorithm::None`]. Unsecured
+    /// tokens are only made with [`crate::jws::UnsecuredJwt`].
     pub fn sign(self, data: &[u8], secret: &Secret) -> Result<Vec<u8>, Error> {
-        use self::SignatureAlgorithm::*;
+        self.sign_with_rng(data, secret, &SystemRandom::new())
+    }
 
-        match self {
-            None => Self::sign_none(secret),
-            HS256 | HS384 | HS512 => Self::sign_hmac(data, secret, self),
-            RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => Self::sign_rsa(data, secret, self),
-            ES256 | ES384 | ES512 => Self::sign_ecdsa(data, secret, self),
-        }
+    /// Sign some bytes like [`SignatureAlgorithm::sign`], with randomness from `rng` instead of
+    /// the system's random number generator. Only the RSA and ECDSA algorithms use randomness.
+    pub fn sign_with_rng(
+        self,
+        data: &[u8],
+        secret: &Secret,
+        rng: &dyn SecureRandom,
+    ) -> Result<Vec<u8>, Error> {
+        Ok(SigningKey::new(self, secret)?.sign(data, rng)?.to_vec())
     }
 
     /// Verify signature based on the algorithm and secret provided.
+    ///
+    /// Returns [`ValidationError::InvalidSignature`] if the signature does not match, and other
+    /// errors if the secret cannot be used with the algorithm. Returns
+    /// [`ValidationError::UnsecuredToken`] for [`SignatureAlgorithm::None`], since unsecured
+    /// tokens are only decoded with [`crate::jws::UnsecuredJwt`].
     pub fn verify(
         self,
         expected_signature: &[u8],
@@ -275,197 +448,399 @@ impl SignatureAlgorithm {
         use self::SignatureAlgorithm::*;
 
         match self {
-            None => Self::verify_none(expected_signature, secret),
+            None => Err(ValidationError::UnsecuredToken)?,
             HS256 | HS384 | HS512 => Self::verify_hmac(expected_signature, data, secret, self),
-            RS256 | RS384 | RS512 | PS256 | PS384 | PS512 | ES256 | ES384 | ES512 => {
-                Self::verify_public_key(expected_signature, data, secret, self)
+            #[cfg(feature = "rsa")]
+            RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => {
+                Self::verify_rsa(expected_signature, data, secret, self)
             }
+            #[cfg(feature = "ecdsa")]
+            ES256 | ES384 | ES512 => Self::verify_ecdsa(expected_signature, data, secret, self),
+            #[cfg(not(feature = "rsa"))]
+            RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => Err(Error::UnsupportedOperation),
+            #[cfg(not(feature = "ecdsa"))]
+            ES256 | ES384 | ES512 => Err(Error::UnsupportedOperation),
         }
     }
 
-    /// Returns the type of operations the key is meant for
-    fn sign_none(secret: &Secret) -> Result<Vec<u8>, Error> {
-        match *secret {
-            Secret::None => {}
-            _ => Err("Invalid secret type. `None` should be provided".to_string())?,
-        };
-        Ok(vec![])
-    }
-
-    fn sign_hmac(
+    /// Verify a signature like [`SignatureAlgorithm::verify`], and find out why it is invalid
+    ///
+    /// Returns `Ok(None)` if the signature is valid, and the reason if it is invalid. Other
+    /// errors, such as a secret that cannot be used with the algorithm, are returned as is.
+    /// This is meant for diagnosing failures, as it gives attackers more information than
+    /// [`ValidationError::InvalidSignature`].
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::errors::SignatureFailure;
+    /// use biscuit::jwa::SignatureAlgorithm;
+    /// use biscuit::jws::Secret;
+    ///
+    /// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+    /// let signature = SignatureAlgorithm::HS256.sign(b"data", &secret).unwrap();
+    /// let failure = SignatureAlgorithm::HS256
+    ///     .diagnose(&signature[..16], b"data", &secret)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     failure,
+    ///     Some(SignatureFailure::WrongLength {
+    ///         expected: 32,
+    ///         actual: 16
+    ///     })
+    /// );
+    /// ```
+    pub fn diagnose(
+        self,
+        expected_signature: &[u8],
         data: &[u8],
         secret: &Secret,
-        algorithm: SignatureAlgorithm,
-    ) -> Result<Vec<u8>, Error> {
-        let secret = match *secret {
-            Secret::Bytes(ref secret) => secret,
-            _ => Err("Invalid secret type. A byte array is required".to_string())?,
-        };
-
-        let algorithm = match algorithm {
-            SignatureAlgorithm::HS256 => &hmac::HMAC_SHA256,
-            SignatureAlgorithm::HS384 => &hmac::HMAC_SHA384,
-            SignatureAlgorithm::HS512 => &hmac::HMAC_SHA512,
-            _ => unreachable!("Should not happen"),
-        };
-        let key = hmac::Key::new(*algorithm, secret);
-        Ok(hmac::sign(&key, data).as_ref().to_vec())
+    ) -> Result<Option<SignatureFailure>, Error> {
+        match self.verify(expected_signature, data, secret) {
+            Ok(()) => Ok(None),
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {
+                let actual = expected_signature.len();
+                let failure = match self.signature_length(secret) {
+                    Some(expected) if expected != actual => {
+                        SignatureFailure::WrongLength { expected, actual }
+                    }
+                    _ => SignatureFailure::Mismatch,
+                };
+                Ok(Some(failure))
+            }
+            Err(e) => Err(e),
+        }
     }
 
-    fn sign_rsa(
-        data: &[u8],
-        secret: &Secret,
-        algorithm: SignatureAlgorithm,
-    ) -> Result<Vec<u8>, Error> {
-        let key_pair = match *secret {
-            Secret::RsaKeyPair(ref key_pair) => key_pair,
-            _ => Err("Invalid secret type. A RsaKeyPair is required".to_string())?,
-        };
-
-        let rng = rand::SystemRandom::new();
-        let mut signature = vec![0; key_pair.public_modulus_len()];
-        let padding_algorithm: &dyn signature::RsaEncoding = match algorithm {
-            SignatureAlgorithm::RS256 => &signature::RSA_PKCS1_SHA256,
-            SignatureAlgorithm::RS384 => &signature::RSA_PKCS1_SHA384,
-            SignatureAlgorithm::RS512 => &signature::RSA_PKCS1_SHA512,
-            SignatureAlgorithm::PS256 => &signature::RSA_PSS_SHA256,
-            SignatureAlgorithm::PS384 => &signature::RSA_PSS_SHA384,
-            SignatureAlgorithm::PS512 => &signature::RSA_PSS_SHA512,
-            _ => unreachable!("Should not happen"),
-        };
+    /// The length of the signatures made with the algorithm and `secret`, if it is known
+    fn signature_length(self, secret: &Secret) -> Option<usize> {
+        use self::SignatureAlgorithm::*;
 
-        key_pair.sign(padding_algorithm, &rng, data, &mut signature)?;
-        Ok(signature)
+        match self {
+            None => Option::None,
+            HS256 => Some(32),
+            HS384 => Some(48),
+            HS512 => Some(64),
+            ES256 => Some(64),
+            ES384 => Some(96),
+            ES512 => Some(132),
+            RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => match *secret {
+                Secret::RsaKeyPair(ref key_pair) => Some(key_pair.public_modulus_len()),
+                Secret::RSAModulusExponent { ref n, .. } => Some(n.to_bytes_be().len()),
+                _ => Option::None,
+            },
+        }
     }
 
-    fn sign_ecdsa(
+    fn verify_hmac(
+        expected_signature: &[u8],
         data: &[u8],
         secret: &Secret,
         algorithm: SignatureAlgorithm,
-    ) -> Result<Vec<u8>, Error> {
-        let key_pair = match *secret {
-            Secret::EcdsaKeyPair(ref key_pair) => key_pair,
-            _ => Err("Invalid secret type. An EcdsaKeyPair is required".to_string())?,
-        };
-        if let SignatureAlgorithm::ES512 = algorithm {
-            // See https://github.com/briansmith/ring/issues/268
-            Err(Error::UnsupportedOperation)
-        } else {
-            let rng = rand::SystemRandom::new();
-            let sig = key_pair.as_ref().sign(&rng, data)?;
-            Ok(sig.as_ref().to_vec())
-        }
+    ) -> Result<(), Error> {
+        let actual_signature = hmac::sign(&SigningKey::hmac_key(secret, algorithm)?, data);
+        verify_slices_are_equal(expected_signature, actual_signature.as_ref())
+            .map_err(|_| ValidationError::InvalidSignature)?;
+        Ok(())
     }
 
-    fn verify_none(expected_signature: &[u8], secret: &Secret) -> Result<(), Error> {
-        match *secret {
-            Secret::None => {}
-            _ => Err("Invalid secret type. `None` should be provided".to_string())?,
-        };
+    /// Whether this is one of the HMAC algorithms, `HS256`, `HS384` or `HS512`
+    pub(crate) fn is_hmac(self) -> bool {
+        [
+            SignatureAlgorithm::HS256,
+            SignatureAlgorithm::HS384,
+            SignatureAlgorithm::HS512,
+        ]
+        .contains(&self)
+    }
 
-        if expected_signature.is_empty() {
-            Ok(())
-        } else {
-            Err(Error::UnspecifiedCryptographicError)
+    /// Verify the base64url encoded HMAC `encoded_signature` of the concatenation of the `data`
+    /// slices, without allocating
+    ///
+    /// The signature is decoded into an array on the stack, and the HMAC is computed over each
+    /// slice in turn, so the parts of a token do not have to be joined first.
+    pub(crate) fn verify_hmac_parts(
+        self,
+        encoded_signature: &[u8],
+        data: &[&[u8]],
+        secret: &Secret,
+    ) -> Result<(), Error> {
+        let key = SigningKey::hmac_key(secret, self)?;
+        let len = BASE64URL_NOPAD.decode_len(encoded_signature.len())?;
+        if len > MAX_HMAC_LEN {
+            Err(ValidationError::InvalidSignature)?
         }
+        let mut expected_signature = [0; MAX_HMAC_LEN];
+        let len = BASE64URL_NOPAD
+            .decode_mut(encoded_signature, &mut expected_signature[..len])
+            .map_err(|partial| partial.error)?;
+
+        let mut context = hmac::Context::with_key(&key);
+        for data in data {
+            context.update(data);
+        }
+        verify_slices_are_equal(&expected_signature[..len], context.sign().as_ref())
+            .map_err(|_| ValidationError::InvalidSignature)?;
+        Ok(())
     }
 
-    fn verify_hmac(
+    #[cfg(feature = "rsa")]
+    fn verify_rsa(
         expected_signature: &[u8],
         data: &[u8],
         secret: &Secret,
         algorithm: SignatureAlgorithm,
     ) -> Result<(), Error> {
-        let actual_signature = Self::sign_hmac(data, secret, algorithm)?;
-        verify_slices_are_equal(expected_signature, actual_signature.as_ref())?;
+        let verification_algorithm = match algorithm {
+            SignatureAlgorithm::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+            SignatureAlgorithm::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+            SignatureAlgorithm::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
+            SignatureAlgorithm::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
+            SignatureAlgorithm::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
+            SignatureAlgorithm::PS512 => &signature::RSA_PSS_2048_8192_SHA512,
+            _ => unreachable!("Should not happen"),
+        };
+
+        let result = match *secret {
+            Secret::PublicKey(ref public_key) => {
+                signature::UnparsedPublicKey::new(verification_algorithm, public_key.as_slice())
+                    .verify(data, expected_signature)
+            }
+            Secret::RsaKeyPair(ref keypair) => {
+                signature::UnparsedPublicKey::new(verification_algorithm, keypair.public_key())
+                    .verify(data, expected_signature)
+            }
+            Secret::RSAModulusExponent { ref n, ref e } => {
+                if n.bits() < MINIMUM_RSA_KEY_BITS as u64 {
+                    Err(Error::WeakKey {
+                        minimum_bits: MINIMUM_RSA_KEY_BITS,
+                        actual_bits: n.bits() as usize,
+                    })?
+                }
+                let public_key = signature::RsaPublicKeyComponents {
+                    n: n.to_bytes_be(),
+                    e: e.to_bytes_be(),
+                };
+                public_key.verify(verification_algorithm, data, expected_signature)
+            }
+            _ => Err(wrong_secret_type(
+                "Secret::PublicKey, Secret::RsaKeyPair or Secret::RSAModulusExponent",
+                secret,
+            ))?,
+        };
+        result.map_err(|_| ValidationError::InvalidSignature)?;
         Ok(())
     }
 
-    fn verify_public_key(
+    #[cfg(feature = "ecdsa")]
+    fn verify_ecdsa(
         expected_signature: &[u8],
         data: &[u8],
         secret: &Secret,
         algorithm: SignatureAlgorithm,
     ) -> Result<(), Error> {
-        match *secret {
-            Secret::PublicKey(ref public_key) => {
-                let verification_algorithm: &dyn signature::VerificationAlgorithm = match algorithm
-                {
-                    SignatureAlgorithm::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
-                    SignatureAlgorithm::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
-                    SignatureAlgorithm::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
-                    SignatureAlgorithm::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
-                    SignatureAlgorithm::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
-                    SignatureAlgorithm::PS512 => &signature::RSA_PSS_2048_8192_SHA512,
-                    SignatureAlgorithm::ES256 => &signature::ECDSA_P256_SHA256_FIXED,
-                    SignatureAlgorithm::ES384 => &signature::ECDSA_P384_SHA384_FIXED,
-                    SignatureAlgorithm::ES512 => Err(Error::UnsupportedOperation)?,
-                    _ => unreachable!("Should not happen"),
-                };
+        let verification_algorithm = match algorithm {
+            SignatureAlgorithm::ES256 => &signature::ECDSA_P256_SHA256_FIXED,
+            SignatureAlgorithm::ES384 => &signature::ECDSA_P384_SHA384_FIXED,
+            SignatureAlgorithm::ES512 => Err(Error::UnsupportedOperation)?,
+            _ => unreachable!("Should not happen"),
+        };
 
-                let public_key = signature::UnparsedPublicKey::new(
-                    verification_algorithm,
-                    public_key.as_slice(),
-                );
-                public_key.verify(data, expected_signature)?;
-                Ok(())
-            }
-            Secret::RsaKeyPair(ref keypair) => {
-                let verification_algorithm: &dyn signature::VerificationAlgorithm = match algorithm
-                {
-                    SignatureAlgorithm::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
-                    SignatureAlgorithm::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
-                    SignatureAlgorithm::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
-                    SignatureAlgorithm::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
-                    SignatureAlgorithm::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
-                    SignatureAlgorithm::PS512 => &signature::RSA_PSS_2048_8192_SHA512,
-                    _ => unreachable!("Should not happen"),
-                };
+        let public_key = match *secret {
+            Secret::PublicKey(ref public_key) => public_key.as_slice(),
+            Secret::EcdsaKeyPair(ref keypair) => keypair.public_key().as_ref(),
+            _ => Err(wrong_secret_type(
+                "Secret::PublicKey or Secret::EcdsaKeyPair",
+                secret,
+            ))?,
+        };
+        signature::UnparsedPublicKey::new(verification_algorithm, public_key)
+            .verify(data, expected_signature)
+            .map_err(|_| ValidationError::InvalidSignature)?;
+        Ok(())
+    }
+}
 
-                let public_key =
-                    signature::UnparsedPublicKey::new(verification_algorithm, keypair.public_key());
-                public_key.verify(data, expected_signature)?;
-                Ok(())
-            }
-            Secret::RSAModulusExponent { ref n, ref e } => {
-                let params = match algorithm {
-                    SignatureAlgorithm::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
-                    SignatureAlgorithm::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
-                    SignatureAlgorithm::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
-                    SignatureAlgorithm::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
-                    SignatureAlgorithm::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
-                    SignatureAlgorithm::PS512 => &signature::RSA_PSS_2048_8192_SHA512,
-                    _ => unreachable!("(n,e) secret with a non-rsa algorithm should not happen"),
-                };
+/// The error for a `secret` that cannot be used with an algorithm, which needs the `expected`
+/// variants of [`Secret`]
+///
+/// This prevents key and algorithm confusion, such as an RSA public key being used as an HMAC
+/// secret.
+pub(crate) fn wrong_secret_type(expected: &str, secret: &Secret) -> Error {
+    Error::WrongKeyType {
+        expected: expected.to_string(),
+        actual: secret.variant_name().to_string(),
+    }
+}
 
-                let n_big_endian = n.to_bytes_be();
-                let e_big_endian = e.to_bytes_be();
-                let public_key = signature::RsaPublicKeyComponents {
-                    n: n_big_endian,
-                    e: e_big_endian,
+/// A secret prepared to sign with an algorithm, which can be reused to sign many times
+pub(crate) enum SigningKey {
+    Hmac(Box<hmac::Key>),
+    #[cfg(feature = "rsa")]
+    Rsa(
+        Arc<signature::RsaKeyPair>,
+        &'static dyn signature::RsaEncoding,
+    ),
+    #[cfg(feature = "ecdsa")]
+    Ecdsa(Arc<signature::EcdsaKeyPair>),
+}
+
+impl SigningKey {
+    pub(crate) fn new(algorithm: SignatureAlgorithm, secret: &Secret) -> Result<Self, Error> {
+        use self::SignatureAlgorithm::*;
+
+        match algorithm {
+            None => Err(ValidationError::UnsecuredToken)?,
+            HS256 | HS384 | HS512 => Ok(SigningKey::Hmac(Box::new(Self::hmac_key(
+                secret, algorithm,
+            )?))),
+            #[cfg(feature = "rsa")]
+            RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => {
+                let key_pair = match *secret {
+                    Secret::RsaKeyPair(ref key_pair) => key_pair,
+                    _ => Err(wrong_secret_type("Secret::RsaKeyPair", secret))?,
                 };
-                public_key.verify(params, data, expected_signature)?;
-                Ok(())
-            }
-            Secret::EcdsaKeyPair(ref keypair) => {
-                let verification_algorithm: &dyn signature::VerificationAlgorithm = match algorithm
-                {
-                    SignatureAlgorithm::ES256 => &signature::ECDSA_P256_SHA256_FIXED,
-                    SignatureAlgorithm::ES384 => &signature::ECDSA_P384_SHA384_FIXED,
-                    SignatureAlgorithm::ES512 => Err(Error::UnsupportedOperation)?,
+                let padding_algorithm: &'static dyn signature::RsaEncoding = match algorithm {
+                    RS256 => &signature::RSA_PKCS1_SHA256,
+                    RS384 => &signature::RSA_PKCS1_SHA384,
+                    RS512 => &signature::RSA_PKCS1_SHA512,
+                    PS256 => &signature::RSA_PSS_SHA256,
+                    PS384 => &signature::RSA_PSS_SHA384,
+                    PS512 => &signature::RSA_PSS_SHA512,
                     _ => unreachable!("Should not happen"),
                 };
+                Ok(SigningKey::Rsa(key_pair.clone(), padding_algorithm))
+            }
+            #[cfg(feature = "ecdsa")]
+            ES256 | ES384 | ES512 => {
+                let key_pair = match *secret {
+                    Secret::EcdsaKeyPair(ref key_pair) => key_pair,
+                    _ => Err(wrong_secret_type("Secret::EcdsaKeyPair", secret))?,
+                };
+                if let ES512 = algorithm {
+                    // See https://github.com/briansmith/ring/issues/268
+                    Err(Error::UnsupportedOperation)
+                } else {
+                    Ok(SigningKey::Ecdsa(key_pair.clone()))
+                }
+            }
+            #[cfg(not(feature = "rsa"))]
+            RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => Err(Error::UnsupportedOperation),
+            #[cfg(not(feature = "ecdsa"))]
+            ES256 | ES384 | ES512 => Err(Error::UnsupportedOperation),
+        }
+    }
+
+    fn hmac_key(secret: &Secret, algorithm: SignatureAlgorithm) -> Result<hmac::Key, Error> {
+        let secret = secret.hmac_bytes(algorithm)?;
+        let algorithm = match algorithm {
+            SignatureAlgorithm::HS256 => &hmac::HMAC_SHA256,
+            SignatureAlgorithm::HS384 => &hmac::HMAC_SHA384,
+            SignatureAlgorithm::HS512 => &hmac::HMAC_SHA512,
+            _ => unreachable!("Should not happen"),
+        };
+        Ok(hmac::Key::new(*algorithm, secret))
+    }
+
+    /// The length of the signatures made with this key, in bytes
+    pub(crate) fn signature_len(&self) -> usize {
+        match *self {
+            SigningKey::Hmac(ref key) => key.algorithm().digest_algorithm().output_len,
+            #[cfg(feature = "rsa")]
+            SigningKey::Rsa(ref key_pair, _) => key_pair.public_modulus_len(),
+            // The signature holds both coordinates, like the uncompressed public key minus its tag
+            #[cfg(feature = "ecdsa")]
+            SigningKey::Ecdsa(ref key_pair) => key_pair.public_key().as_ref().len() - 1,
+        }
+    }
 
-                let public_key =
-                    signature::UnparsedPublicKey::new(verification_algorithm, keypair.public_key());
-                public_key.verify(data, expected_signature)?;
-                Ok(())
+    #[cfg_attr(not(any(feature = "rsa", feature = "ecdsa")), allow(unused_variables))]
+    pub(crate) fn sign(
+        &self,
+        data: &[u8],
+        rng: &dyn SecureRandom,
+    ) -> Result<SignatureBuffer, Error> {
+        match *self {
+            SigningKey::Hmac(ref key) => {
+                Ok(SignatureBuffer::from_slice(hmac::sign(key, data).as_ref()))
+            }
+            #[cfg(feature = "rsa")]
+            SigningKey::Rsa(ref key_pair, padding_algorithm) => {
+                let mut signature = SignatureBuffer::zeroed(key_pair.public_modulus_len())?;
+                key_pair.sign(
+                    padding_algorithm,
+                    rng,
+                    data,
+                    &mut signature.bytes[..signature.len],
+                )?;
+                Ok(signature)
             }
-            _ => unreachable!("This is a private method and should not be called erroneously."),
+            #[cfg(feature = "ecdsa")]
+            SigningKey::Ecdsa(ref key_pair) => Ok(SignatureBuffer::from_slice(
+                key_pair.sign(rng, data)?.as_ref(),
+            )),
         }
     }
 }
 
+/// The length of the longest signature that can be verified, which is made with an 8192 bit RSA
+/// key
+pub(crate) const MAX_SIGNATURE_LEN: usize = 1024;
+
+/// The length of the longest HMAC, made with `HS512`
+const MAX_HMAC_LEN: usize = 64;
+
+/// A signature held on the stack, since signatures are never longer than [`MAX_SIGNATURE_LEN`]
+pub(crate) struct SignatureBuffer {
+    bytes: [u8; MAX_SIGNATURE_LEN],
+    len: usize,
+}
+
+impl SignatureBuffer {
+    fn from_slice(signature: &[u8]) -> Self {
+        let mut bytes = [0; MAX_SIGNATURE_LEN];
+        bytes[..signature.len()].copy_from_slice(signature);
+        SignatureBuffer {
+            bytes,
+            len: signature.len(),
+        }
+    }
+
+    #[cfg(feature = "rsa")]
+    fn zeroed(len: usize) -> Result<Self, Error> {
+        if len > MAX_SIGNATURE_LEN {
+            Err(Error::UnsupportedOperation)?
+        }
+        Ok(SignatureBuffer {
+            bytes: [0; MAX_SIGNATURE_LEN],
+            len,
+        })
+    }
+
+    /// Decode a base64url encoded signature
+    ///
+    /// Returns [`ValidationError::InvalidSignature`] if it is longer than any signature that can
+    /// be verified.
+    pub(crate) fn from_base64(encoded: &[u8]) -> Result<Self, Error> {
+        let len = BASE64URL_NOPAD.decode_len(encoded.len())?;
+        if len > MAX_SIGNATURE_LEN {
+            Err(ValidationError::InvalidSignature)?
+        }
+        let mut bytes = [0; MAX_SIGNATURE_LEN];
+        let len = BASE64URL_NOPAD
+            .decode_mut(encoded, &mut bytes[..len])
+            .map_err(|partial| partial.error)?;
+        Ok(SignatureBuffer { bytes, len })
+    }
+}
+
+impl Deref for SignatureBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
 impl KeyManagementAlgorithm {
     /// Returns the type of operations that the algorithm is intended to support
     pub fn algorithm_type(self) -> KeyManagementAlgorithmType {
@@ -484,7 +859,10 @@ impl KeyManagementAlgorithm {
             }
         }
     }
+}
 
+#[cfg(feature = "jwe")]
+impl KeyManagementAlgorithm {
     /// Return the Content Encryption Key (CEK) based on the key management algorithm
     ///
     /// If the algorithm is `dir` or `DirectSymmetricKey`, the key provided is the CEK.
@@ -630,6 +1008,7 @@ impl KeyManagementAlgorithm {
     }
 }
 
+#[cfg(feature = "jwe")]
 impl ContentEncryptionAlgorithm {
     /// Convenience function to generate a new random key with the required length
     pub fn generate_key(self) -> Result<Vec<u8>, Error> {
@@ -740,6 +1119,7 @@ pub(crate) fn rng() -> &'static SystemRandom {
 }
 
 /// Encrypt a payload with AES GCM
+#[cfg(feature = "jwe")]
 fn aes_gcm_encrypt<T: Serialize + DeserializeOwned>(
     algorithm: &'static aead::Algorithm,
     payload: &[u8],
@@ -772,6 +1152,7 @@ fn aes_gcm_encrypt<T: Serialize + DeserializeOwned>(
 }
 
 /// Decrypts a payload with AES GCM
+#[cfg(feature = "jwe")]
 fn aes_gcm_decrypt<T: Serialize + DeserializeOwned>(
     algorithm: &'static aead::Algorithm,
     encrypted: &EncryptionResult,
@@ -797,6 +1178,7 @@ fn aes_gcm_decrypt<T: Serialize + DeserializeOwned>(
     Ok(plaintext.to_vec())
 }
 
+#[cfg(feature = "jwe")]
 pub(crate) fn random_aes_gcm_nonce() -> Result<Vec<u8>, Error> {
     let mut nonce: Vec<u8> = vec![0; AES_GCM_NONCE_LENGTH];
     rng().fill(&mut nonce)?;
@@ -805,25 +1187,120 @@ pub(crate) fn random_aes_gcm_nonce() -> Result<Vec<u8>, Error> {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "jwe")]
     use ring::constant_time::verify_slices_are_equal;
 
     use super::*;
+    #[cfg(feature = "jwe")]
     use crate::jwa;
     use crate::CompactPart;
 
     #[test]
-    fn sign_and_verify_none() {
-        let expected_signature: Vec<u8> = vec![];
-        let actual_signature = not_err!(
-            SignatureAlgorithm::None.sign("payload".to_string().as_bytes(), &Secret::None,)
+    fn algorithm_names_round_trip() {
+        for algorithm in SignatureAlgorithm::ALL {
+            let serialized = not_err!(serde_json::to_value(algorithm));
+            assert_eq!(serialized, algorithm.to_string());
+            assert_eq!(
+                not_err!(algorithm.name().parse::<SignatureAlgorithm>()),
+                *algorithm
+            );
+        }
+        for algorithm in KeyManagementAlgorithm::ALL {
+            let serialized = not_err!(serde_json::to_value(algorithm));
+            assert_eq!(serialized, algorithm.to_string());
+            assert_eq!(
+                not_err!(algorithm.name().parse::<KeyManagementAlgorithm>()),
+                *algorithm
+            );
+        }
+        for algorithm in ContentEncryptionAlgorithm::ALL {
+            let serialized = not_err!(serde_json::to_value(algorithm));
+            assert_eq!(serialized, algorithm.to_string());
+            assert_eq!(
+                not_err!(algorithm.name().parse::<ContentEncryptionAlgorithm>()),
+                *algorithm
+            );
+        }
+
+        assert_eq!(
+            not_err!("RSA-OAEP".parse::<Algorithm>()),
+            Algorithm::KeyManagement(KeyManagementAlgorithm::RSA_OAEP)
+        );
+        assert_eq!(
+            Algorithm::ContentEncryption(ContentEncryptionAlgorithm::A256GCM).to_string(),
+            "A256GCM"
         );
-        assert_eq!(expected_signature, actual_signature);
+    }
 
-        not_err!(SignatureAlgorithm::None.verify(
-            vec![].as_slice(),
-            "payload".to_string().as_bytes(),
-            &Secret::None
+    #[test]
+    fn unknown_algorithm_names() {
+        match "rs256".parse::<SignatureAlgorithm>() {
+            Err(Error::UnknownAlgorithm { name, supported }) => {
+                assert_eq!(name, "rs256");
+                assert_eq!(supported.len(), SignatureAlgorithm::ALL.len());
+                assert!(supported.contains(&"RS256"));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let error = "XYZ".parse::<Algorithm>().unwrap_err();
+        assert!(error.to_string().contains("none, HS256"));
+        assert!(error.to_string().contains("A256GCM"));
+    }
+
+    #[test]
+    fn sign_and_verify_none_are_rejected() {
+        match SignatureAlgorithm::None.sign(b"payload", &Secret::None) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match SignatureAlgorithm::None.verify(&[], b"payload", &Secret::None) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn signature_buffer_decodes_base64() {
+        let signature = not_err!(SignatureBuffer::from_base64(b"c2lnbmF0dXJl"));
+        assert_eq!(&*signature, b"signature");
+
+        let longest = BASE64URL_NOPAD.encode(&[7; MAX_SIGNATURE_LEN]);
+        assert_eq!(
+            not_err!(SignatureBuffer::from_base64(longest.as_bytes())).len(),
+            MAX_SIGNATURE_LEN
+        );
+        let too_long = BASE64URL_NOPAD.encode(&[7; MAX_SIGNATURE_LEN + 1]);
+        match SignatureBuffer::from_base64(too_long.as_bytes()) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+    }
+
+    #[test]
+    fn verify_hmac_parts() {
+        let secret = Secret::Bytes(vec![7; 48]);
+        let signature = not_err!(SignatureAlgorithm::HS384.sign(b"header.payload", &secret));
+        let encoded = BASE64URL_NOPAD.encode(&signature);
+
+        let parts: [&[u8]; 3] = [b"header", b".", b"payload"];
+        not_err!(SignatureAlgorithm::HS384.verify_hmac_parts(encoded.as_bytes(), &parts, &secret));
+        not_err!(SignatureAlgorithm::HS384.verify_hmac_parts(
+            encoded.as_bytes(),
+            &[b"header.payload"],
+            &secret
         ));
+
+        let tampered: [&[u8]; 3] = [b"header", b".", b"payloaD"];
+        let too_long = BASE64URL_NOPAD.encode(&[7; 65]);
+        for (signature, parts) in &[(&encoded, &tampered), (&too_long, &parts)] {
+            match SignatureAlgorithm::HS384.verify_hmac_parts(signature.as_bytes(), *parts, &secret)
+            {
+                Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
     }
 
     #[test]
@@ -833,14 +1310,14 @@ mod tests {
 
         let actual_signature = not_err!(SignatureAlgorithm::HS256.sign(
             "payload".to_string().as_bytes(),
-            &Secret::bytes_from_str("secret"),
+            &Secret::bytes_from_str("secret").danger_allow_weak_keys(),
         ));
         assert_eq!(&*not_err!(actual_signature.to_base64()), expected_base64);
 
         not_err!(SignatureAlgorithm::HS256.verify(
             expected_bytes.as_slice(),
             "payload".to_string().as_bytes(),
-            &Secret::bytes_from_str("secret"),
+            &Secret::bytes_from_str("secret").danger_allow_weak_keys(),
         ));
     }
 
@@ -851,6 +1328,7 @@ mod tests {
     /// ```
     ///
     /// The base64 encoding from this command will be in `STANDARD` form and not URL_SAFE.
+    #[cfg(feature = "rsa")]
     #[test]
     fn sign_and_verify_rs256() {
         let private_key =
@@ -879,6 +1357,7 @@ mod tests {
         ));
     }
 
+    #[cfg(feature = "rsa")]
     #[test]
     fn sign_and_verify_rs256_key_params() {
         use num_bigint::BigUint;
@@ -917,6 +1396,7 @@ mod tests {
     }
 
     /// This signature is non-deterministic.
+    #[cfg(feature = "rsa")]
     #[test]
     fn sign_and_verify_ps256_round_trip() {
         let private_key =
@@ -935,7 +1415,26 @@ mod tests {
         ));
     }
 
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn sign_ps256_with_rng() {
+        let private_key =
+            Secret::rsa_keypair_from_file("test/fixtures/rsa_private_key.der").unwrap();
+        let public_key = Secret::public_key_from_file("test/fixtures/rsa_public_key.der").unwrap();
+        let rng = ring::test::rand::FixedByteRandom { byte: 0 };
+
+        // The salt comes from `rng`, so the signature is deterministic
+        let signature =
+            not_err!(SignatureAlgorithm::PS256.sign_with_rng(b"payload", &private_key, &rng));
+        assert_eq!(
+            signature,
+            not_err!(SignatureAlgorithm::PS256.sign_with_rng(b"payload", &private_key, &rng))
+        );
+        not_err!(SignatureAlgorithm::PS256.verify(&signature, b"payload", &public_key));
+    }
+
     /// This signature is non-deterministic.
+    #[cfg(feature = "rsa")]
     #[test]
     fn sign_and_verify_ps256_round_trip_with_keypair() {
         let key = Secret::rsa_keypair_from_file("test/fixtures/rsa_private_key.der").unwrap();
@@ -959,6 +1458,7 @@ mod tests {
     /// ```
     ///
     /// The base64 encoding from this command will be in `STANDARD` form and not URL_SAFE.
+    #[cfg(feature = "rsa")]
     #[test]
     fn verify_ps256() {
         use data_encoding::BASE64;
@@ -981,6 +1481,7 @@ mod tests {
     }
 
     /// This signature is non-deterministic.
+    #[cfg(feature = "ecdsa")]
     #[test]
     fn sign_and_verify_es256_round_trip() {
         let private_key = Secret::ecdsa_keypair_from_file(
@@ -1004,6 +1505,7 @@ mod tests {
     }
 
     /// This signature is non-deterministic.
+    #[cfg(feature = "ecdsa")]
     #[test]
     fn sign_and_verify_es256_round_trip_with_keypair() {
         let key = Secret::ecdsa_keypair_from_file(
@@ -1024,6 +1526,7 @@ mod tests {
     }
 
     /// Test case from https://github.com/briansmith/ring/blob/a13b8e2/src/ec/suite_b/ecdsa_verify_fixed_tests.txt
+    #[cfg(feature = "ecdsa")]
     #[test]
     fn verify_es256() {
         use data_encoding::HEXUPPER;
@@ -1043,6 +1546,7 @@ mod tests {
     }
 
     /// Test case from https://github.com/briansmith/ring/blob/a13b8e2/src/ec/suite_b/ecdsa_verify_fixed_tests.txt
+    #[cfg(feature = "ecdsa")]
     #[test]
     fn verify_es384() {
         use data_encoding::HEXUPPER;
@@ -1063,6 +1567,7 @@ mod tests {
         ));
     }
 
+    #[cfg(feature = "ecdsa")]
     #[test]
     #[should_panic(expected = "UnsupportedOperation")]
     fn verify_es512() {
@@ -1075,21 +1580,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "UnspecifiedCryptographicError")]
-    fn invalid_none() {
-        let invalid_signature = "broken".to_string();
-        let signature_bytes = invalid_signature.as_bytes();
-        SignatureAlgorithm::None
-            .verify(
-                signature_bytes,
-                "payload".to_string().as_bytes(),
-                &Secret::None,
-            )
-            .unwrap();
-    }
-
-    #[test]
-    #[should_panic(expected = "UnspecifiedCryptographicError")]
+    #[should_panic(expected = "InvalidSignature")]
     fn invalid_hs256() {
         let invalid_signature = "broken".to_string();
         let signature_bytes = invalid_signature.as_bytes();
@@ -1097,13 +1588,14 @@ mod tests {
             .verify(
                 signature_bytes,
                 "payload".to_string().as_bytes(),
-                &Secret::Bytes("secret".to_string().into_bytes()),
+                &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             )
             .unwrap();
     }
 
+    #[cfg(feature = "rsa")]
     #[test]
-    #[should_panic(expected = "UnspecifiedCryptographicError")]
+    #[should_panic(expected = "InvalidSignature")]
     fn invalid_rs256() {
         let public_key = Secret::public_key_from_file("test/fixtures/rsa_public_key.der").unwrap();
         let invalid_signature = "broken".to_string();
@@ -1117,8 +1609,9 @@ mod tests {
             .unwrap();
     }
 
+    #[cfg(feature = "rsa")]
     #[test]
-    #[should_panic(expected = "UnspecifiedCryptographicError")]
+    #[should_panic(expected = "InvalidSignature")]
     fn invalid_ps256() {
         let public_key = Secret::public_key_from_file("test/fixtures/rsa_public_key.der").unwrap();
         let invalid_signature = "broken".to_string();
@@ -1132,8 +1625,9 @@ mod tests {
             .unwrap();
     }
 
+    #[cfg(feature = "ecdsa")]
     #[test]
-    #[should_panic(expected = "UnspecifiedCryptographicError")]
+    #[should_panic(expected = "InvalidSignature")]
     fn invalid_es256() {
         let public_key = Secret::public_key_from_file("test/fixtures/rsa_public_key.der").unwrap();
         let invalid_signature = "broken".to_string();
@@ -1147,6 +1641,144 @@ mod tests {
             .unwrap();
     }
 
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn diagnose_signature_failures() {
+        let secret = Secret::bytes_from_str("secret").danger_allow_weak_keys();
+        let signature = not_err!(SignatureAlgorithm::HS256.sign(b"payload", &secret));
+        assert_eq!(
+            not_err!(SignatureAlgorithm::HS256.diagnose(&signature, b"payload", &secret)),
+            None
+        );
+        assert_eq!(
+            not_err!(SignatureAlgorithm::HS256.diagnose(&signature, b"other", &secret)),
+            Some(SignatureFailure::Mismatch)
+        );
+
+        let key_pair = not_err!(Secret::rsa_keypair_from_file(
+            "test/fixtures/rsa_private_key.der"
+        ));
+        assert_eq!(
+            not_err!(SignatureAlgorithm::RS256.diagnose(&signature, b"payload", &key_pair)),
+            Some(SignatureFailure::WrongLength {
+                expected: 256,
+                actual: 32
+            })
+        );
+        assert!(SignatureAlgorithm::None
+            .diagnose(b"sig", b"payload", &Secret::None)
+            .is_err());
+        assert!(SignatureAlgorithm::HS256
+            .diagnose(&signature, b"payload", &key_pair)
+            .is_err());
+    }
+
+    #[cfg(all(feature = "rsa", feature = "ecdsa"))]
+    #[test]
+    fn mismatched_secrets_are_rejected() {
+        let public_key = not_err!(Secret::public_key_from_file(
+            "test/fixtures/rsa_public_key.der"
+        ));
+        let rsa_key_pair = not_err!(Secret::rsa_keypair_from_file(
+            "test/fixtures/rsa_private_key.der"
+        ));
+        let hmac_secret = Secret::bytes_from_str("secret");
+        let payload = b"payload";
+
+        let expect_wrong_key_type =
+            |result: Result<(), Error>, expected: &str, actual: &str| match result {
+                Err(Error::WrongKeyType {
+                    expected: ref e,
+                    actual: ref a,
+                }) => assert_eq!((e.as_str(), a.as_str()), (expected, actual)),
+                other => panic!("Unexpected result {:?}", other),
+            };
+
+        // An RSA public key used as an HMAC secret
+        expect_wrong_key_type(
+            SignatureAlgorithm::HS256.verify(b"signature", payload, &public_key),
+            "Secret::Bytes",
+            "Secret::PublicKey",
+        );
+        // An HMAC secret used as an RSA key
+        expect_wrong_key_type(
+            SignatureAlgorithm::RS256.verify(b"signature", payload, &hmac_secret),
+            "Secret::PublicKey, Secret::RsaKeyPair or Secret::RSAModulusExponent",
+            "Secret::Bytes",
+        );
+        // An RSA key pair used for ECDSA
+        expect_wrong_key_type(
+            SignatureAlgorithm::ES256.verify(b"signature", payload, &rsa_key_pair),
+            "Secret::PublicKey or Secret::EcdsaKeyPair",
+            "Secret::RsaKeyPair",
+        );
+        expect_wrong_key_type(
+            SignatureAlgorithm::ES256
+                .sign(payload, &rsa_key_pair)
+                .map(|_| ()),
+            "Secret::EcdsaKeyPair",
+            "Secret::RsaKeyPair",
+        );
+    }
+
+    fn expect_weak_key(result: Result<(), Error>, minimum: usize, actual: usize) {
+        match result {
+            Err(Error::WeakKey {
+                minimum_bits,
+                actual_bits,
+            }) => assert_eq!((minimum_bits, actual_bits), (minimum, actual)),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn weak_keys_are_rejected() {
+        let short = Secret::Bytes(vec![0; 31]);
+        expect_weak_key(
+            SignatureAlgorithm::HS256
+                .sign(b"payload", &short)
+                .map(|_| ()),
+            256,
+            248,
+        );
+        expect_weak_key(
+            SignatureAlgorithm::HS256.verify(b"signature", b"payload", &short),
+            256,
+            248,
+        );
+        let secret = Secret::Bytes(vec![0; 32]);
+        let signature = not_err!(SignatureAlgorithm::HS256.sign(b"payload", &secret));
+        not_err!(SignatureAlgorithm::HS256.verify(&signature, b"payload", &secret));
+        expect_weak_key(
+            SignatureAlgorithm::HS512
+                .sign(b"payload", &secret)
+                .map(|_| ()),
+            512,
+            256,
+        );
+
+        // The escape hatch accepts the same key
+        let weak = short.danger_allow_weak_keys();
+        let signature = not_err!(SignatureAlgorithm::HS256.sign(b"payload", &weak));
+        not_err!(SignatureAlgorithm::HS256.verify(&signature, b"payload", &weak));
+    }
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn weak_rsa_keys_are_rejected() {
+        use num_bigint::BigUint;
+
+        let rsa = Secret::RSAModulusExponent {
+            n: BigUint::from(1u32) << 1023,
+            e: BigUint::from(65537u32),
+        };
+        expect_weak_key(
+            SignatureAlgorithm::RS256.verify(b"signature", b"payload", &rsa),
+            2048,
+            1024,
+        );
+    }
+
     #[test]
     fn rng_is_created() {
         let rng = rng();
@@ -1154,6 +1786,7 @@ mod tests {
         rng.fill(&mut random).unwrap();
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes_gcm_128_encryption_round_trip_fixed_key_nonce() {
         const PAYLOAD: &str = "这个世界值得我们奋战！";
@@ -1181,6 +1814,7 @@ mod tests {
         assert_eq!(payload, PAYLOAD);
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes_gcm_128_encryption_round_trip() {
         const PAYLOAD: &str = "这个世界值得我们奋战！";
@@ -1209,6 +1843,7 @@ mod tests {
         assert_eq!(payload, PAYLOAD);
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes_gcm_256_encryption_round_trip() {
         const PAYLOAD: &str = "这个世界值得我们奋战！";
@@ -1237,6 +1872,7 @@ mod tests {
         assert_eq!(payload, PAYLOAD);
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes_gcm_256_encryption_round_trip_fixed_key_nonce() {
         const PAYLOAD: &str = "这个世界值得我们奋战！";
@@ -1265,6 +1901,7 @@ mod tests {
     }
 
     /// `KeyManagementAlgorithm::DirectSymmetricKey` returns the same key when CEK is requested
+    #[cfg(feature = "jwe")]
     #[test]
     fn dir_cek_returns_provided_key() {
         let mut key: Vec<u8> = vec![0; 256 / 8];
@@ -1288,6 +1925,7 @@ mod tests {
     }
 
     /// `KeyManagementAlgorithm::A128GCMKW` returns a random key with the right length when CEK is requested
+    #[cfg(feature = "jwe")]
     #[test]
     fn cek_aes128gcmkw_returns_right_key_length() {
         let mut key: Vec<u8> = vec![0; 128 / 8];
@@ -1317,6 +1955,7 @@ mod tests {
     }
 
     /// `KeyManagementAlgorithm::A256GCMKW` returns a random key with the right length when CEK is requested
+    #[cfg(feature = "jwe")]
     #[test]
     fn cek_aes256gcmkw_returns_right_key_length() {
         let mut key: Vec<u8> = vec![0; 256 / 8];
@@ -1345,6 +1984,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes128gcmkw_key_encryption_round_trip() {
         let mut key: Vec<u8> = vec![0; 128 / 8];
@@ -1377,6 +2017,7 @@ mod tests {
         .is_ok());
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes256gcmkw_key_encryption_round_trip() {
         let mut key: Vec<u8> = vec![0; 256 / 8];
@@ -1410,6 +2051,7 @@ mod tests {
     }
 
     /// `ContentEncryptionAlgorithm::A128GCM` generates CEK of the right length
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes128gcm_key_length() {
         let enc_alg = jwa::ContentEncryptionAlgorithm::A128GCM;
@@ -1418,6 +2060,7 @@ mod tests {
     }
 
     /// `ContentEncryptionAlgorithm::A256GCM` generates CEK of the right length
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes256gcm_key_length() {
         let enc_alg = jwa::ContentEncryptionAlgorithm::A256GCM;
@@ -1425,6 +2068,7 @@ mod tests {
         assert_eq!(cek.len(), 256 / 8);
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes128gcm_encryption_round_trip() {
         let mut key: Vec<u8> = vec![0; 128 / 8];
@@ -1453,6 +2097,7 @@ mod tests {
         assert!(verify_slices_are_equal(payload.as_bytes(), &decrypted_payload).is_ok());
     }
 
+    #[cfg(feature = "jwe")]
     #[test]
     fn aes1256gcm_encryption_round_trip() {
         let mut key: Vec<u8> = vec![0; 256 / 8];
@@ -4,6 +4,7 @@
 //! Most commonly, JWE is used to encrypt a JWS payload, which is a signed JWT. For most common use,
 //! you will want to look at the  [`Compact`](enum.Compact.html) enum.
 use std::fmt;
+use std::str::FromStr;
 
 use data_encoding::BASE64URL_NOPAD;
 
@@ -40,6 +41,27 @@ impl Serialize for CompressionAlgorithm {
     }
 }
 
+impl fmt::Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            CompressionAlgorithm::Deflate => f.write_str("DEF"),
+            CompressionAlgorithm::Other(ref other) => f.write_str(other),
+        }
+    }
+}
+
+impl FromStr for CompressionAlgorithm {
+    type Err = Error;
+
+    /// This never fails, since unknown names are user-defined algorithms
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DEF" => CompressionAlgorithm::Deflate,
+            other => CompressionAlgorithm::Other(other.to_string()),
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for CompressionAlgorithm {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -600,6 +622,7 @@ where
 impl<P, H> Compact<crate::ClaimsSet<P>, H>
 where
     crate::ClaimsSet<P>: CompactPart,
+    P: Serialize,
     H: Serialize + DeserializeOwned + Clone,
 {
     /// Validate the temporal claims in the decoded token
@@ -609,8 +632,15 @@ where
     /// By default, no temporal claims (namely `iat`, `exp`, `nbf`)
     /// are required, and they will pass validation if they are missing.
     pub fn validate(&self, options: crate::ValidationOptions) -> Result<(), Error> {
-        self.payload()?.registered.validate(options)?;
-        Ok(())
+        self.payload()?.validate(options)
+    }
+
+    /// Validate the claims in the decoded token, running every check and reporting every
+    /// failure in a [`crate::errors::ValidationReport`].
+    ///
+    /// See [`crate::ClaimsSet::validate_all`].
+    pub fn validate_all(&self, options: crate::ValidationOptions) -> Result<(), Error> {
+        self.payload()?.validate_all(options)
     }
 }
 
@@ -862,8 +892,9 @@ mod tests {
             }),
             claims,
         );
-        let jws =
-            not_err!(jws.into_encoded(&jws::Secret::Bytes("secret".to_string().into_bytes())));
+        let jws = not_err!(jws.into_encoded(
+            &jws::Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
+        ));
 
         // Construct the encryption key
         let key = cek_oct_key(256 / 8);
@@ -937,8 +968,9 @@ mod tests {
             }),
             claims,
         );
-        let jws =
-            not_err!(jws.into_encoded(&jws::Secret::Bytes("secret".to_string().into_bytes())));
+        let jws = not_err!(jws.into_encoded(
+            &jws::Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
+        ));
 
         // Construct the encryption key
         let key = cek_oct_key(256 / 8);
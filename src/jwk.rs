@@ -455,11 +455,11 @@ pub struct RSAKeyParameters {
 
 impl RSAKeyParameters {
     /// Construct a `jws::Secret` RSA public key for signature verification
-    pub fn jws_public_key_secret(&self) -> jws::Secret {
-        jws::Secret::RSAModulusExponent {
-            n: self.n.clone(),
-            e: self.e.clone(),
-        }
+    ///
+    /// Returns [`Error::WeakKey`] if the modulus is shorter than 2048 bits, like
+    /// [`jws::Secret::rsa_modulus_exponent`].
+    pub fn jws_public_key_secret(&self) -> Result<jws::Secret, Error> {
+        jws::Secret::rsa_modulus_exponent(self.n.clone(), self.e.clone())
     }
 }
 
@@ -2,24 +2,42 @@
 //!
 //! Defined in [RFC 7515](https://tools.ietf.org/html/rfc7515). For most common use,
 //! you will want to look at the  [`Compact`](enum.Compact.html) enum.
+mod batch;
 mod compact;
 mod flattened;
-
+mod pinned;
+mod signer;
+mod streaming;
+pub mod typed;
+mod unsecured;
+mod verified;
+
+pub use batch::BatchSigner;
+pub(crate) use compact::BorrowedCompact;
 pub use compact::Compact;
 pub use flattened::{Signable, SignedData};
-
-use crate::errors::Error;
-use crate::jwa::SignatureAlgorithm;
+pub use pinned::PinnedHeader;
+pub use signer::Signer;
+pub use streaming::{StreamingSigner, StreamingVerifier};
+pub use unsecured::UnsecuredJwt;
+pub use verified::VerifiedPayload;
+
+use crate::errors::{Error, ValidationError, ValidationReport};
+use crate::jwa::{self, SignatureAlgorithm};
 use crate::jwk;
-use crate::{CompactJson, Empty};
+use crate::{CompactJson, Empty, Validation, ValidationOptions};
 
 use num_bigint::BigUint;
 use ring::signature;
 use serde::{self, de::DeserializeOwned, Deserialize, Serialize};
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 /// The secrets used to sign and/or encrypt tokens
+///
+/// More kinds of secrets may be added, so matches on a `Secret` need a wildcard arm.
 #[derive(Clone)]
+#[non_exhaustive]
 pub enum Secret {
     /// Used with the `None` algorithm variant.
     None,
@@ -29,9 +47,18 @@ pub enum Secret {
     /// ```
     /// use biscuit::jws::Secret;
     ///
-    /// let secret = Secret::bytes_from_str("secret");
+    /// let secret = Secret::bytes_from_str("a secret of at least 32 bytes for HS256");
     /// ```
+    ///
+    /// As required by [RFC 7518](https://tools.ietf.org/html/rfc7518#section-3.2), the secret
+    /// must be at least as long as the hash output of the algorithm: 32 bytes for `HS256`,
+    /// 48 bytes for `HS384` and 64 bytes for `HS512`. Shorter secrets are rejected with
+    /// [`Error::WeakKey`] when signing or verifying, unless they are made with
+    /// [`Secret::danger_allow_weak_keys`].
     Bytes(Vec<u8>),
+    /// Bytes used for HMAC secret which are not checked against the minimum length of the
+    /// algorithm. Construct it with [`Secret::danger_allow_weak_keys`].
+    WeakBytes(Vec<u8>),
     /// An RSA Key pair constructed from a DER-encoded private key
     ///
     /// To generate a private key, use
@@ -54,9 +81,12 @@ pub enum Secret {
     ///
     /// # Examples
     /// ```
+    /// # #[cfg(feature = "rsa")]
+    /// # {
     /// use biscuit::jws::Secret;
     ///
     /// let secret = Secret::rsa_keypair_from_file("test/fixtures/rsa_private_key.der");
+    /// # }
     /// ```
     RsaKeyPair(Arc<signature::RsaKeyPair>),
     /// An ECDSA Key pair constructed from a PKCS8 DER encoded private key
@@ -70,9 +100,12 @@ pub enum Secret {
     ///
     /// # Examples
     /// ```
+    /// # #[cfg(feature = "ecdsa")]
+    /// # {
     /// use biscuit::jws::Secret;
     ///
     /// let secret = Secret::ecdsa_keypair_from_file(biscuit::jwa::SignatureAlgorithm::ES256, "test/fixtures/ecdsa_private_key.p8");
+    /// # }
     /// ```
     EcdsaKeyPair(Arc<signature::EcdsaKeyPair>),
     /// Bytes of a DER encoded RSA Public Key
@@ -154,8 +187,9 @@ pub enum Secret {
     PublicKey(Vec<u8>),
     /// Use the modulus (`n`) and exponent (`e`) of an RSA key directly
     ///
-    /// These parameters can be obtained from a JWK directly using
-    /// [`jwk::RSAKeyParameters::jws_public_key_secret`]
+    /// Construct it with [`Secret::rsa_modulus_exponent`], or from a JWK with
+    /// [`jwk::RSAKeyParameters::jws_public_key_secret`], which reject moduli that are shorter than
+    /// 2048 bits.
     RSAModulusExponent {
         /// RSA modulus
         n: BigUint,
@@ -165,6 +199,19 @@ pub enum Secret {
 }
 
 impl Secret {
+    /// The name of the variant, used in errors
+    pub(crate) fn variant_name(&self) -> &'static str {
+        match *self {
+            Secret::None => "Secret::None",
+            Secret::Bytes(_) => "Secret::Bytes",
+            Secret::WeakBytes(_) => "Secret::WeakBytes",
+            Secret::RsaKeyPair(_) => "Secret::RsaKeyPair",
+            Secret::EcdsaKeyPair(_) => "Secret::EcdsaKeyPair",
+            Secret::PublicKey(_) => "Secret::PublicKey",
+            Secret::RSAModulusExponent { .. } => "Secret::RSAModulusExponent",
+        }
+    }
+
     fn read_bytes(path: &str) -> Result<Vec<u8>, Error> {
         use std::fs::File;
         use std::io::prelude::*;
@@ -182,29 +229,108 @@ impl Secret {
         Secret::Bytes(secret.to_string().into_bytes())
     }
 
+    /// Allow an HMAC secret to be shorter than the minimum length of the algorithm, by turning
+    /// [`Secret::Bytes`] into [`Secret::WeakBytes`]. Other secrets are returned as is.
+    ///
+    /// ## Warning
+    /// Short secrets can be brute forced. This is meant for tests, and for verifying tokens that
+    /// were signed with a weak secret that cannot be replaced yet.
+    ///
+    /// RSA keys shorter than 2048 bits are always rejected by *ring*, so there is no such escape
+    /// hatch for them.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::jwa::SignatureAlgorithm;
+    /// use biscuit::jws::Secret;
+    ///
+    /// let secret = Secret::bytes_from_str("secret");
+    /// assert!(SignatureAlgorithm::HS256.sign(b"data", &secret).is_err());
+    ///
+    /// let secret = secret.danger_allow_weak_keys();
+    /// assert!(SignatureAlgorithm::HS256.sign(b"data", &secret).is_ok());
+    /// ```
+    pub fn danger_allow_weak_keys(self) -> Self {
+        match self {
+            Secret::Bytes(bytes) => Secret::WeakBytes(bytes),
+            other => other,
+        }
+    }
+
+    /// The bytes of an HMAC secret, checked against the minimum length of `algorithm`
+    pub(crate) fn hmac_bytes(&self, algorithm: SignatureAlgorithm) -> Result<&[u8], Error> {
+        let minimum = match algorithm {
+            SignatureAlgorithm::HS256 => 32,
+            SignatureAlgorithm::HS384 => 48,
+            SignatureAlgorithm::HS512 => 64,
+            _ => Err(Error::UnsupportedOperation)?,
+        };
+        match *self {
+            Secret::Bytes(ref bytes) if bytes.len() < minimum => Err(Error::WeakKey {
+                minimum_bits: minimum * 8,
+                actual_bits: bytes.len() * 8,
+            }),
+            Secret::Bytes(ref bytes) | Secret::WeakBytes(ref bytes) => Ok(bytes),
+            _ => Err(jwa::wrong_secret_type("Secret::Bytes", self)),
+        }
+    }
+
+    #[cfg(feature = "rsa")]
     /// Convenience function to get the RSA Keypair from a DER encoded RSA private key.
     /// See example in the [`Secret::RsaKeyPair`] variant documentation for usage.
     pub fn rsa_keypair_from_file(path: &str) -> Result<Self, Error> {
-        let der = Self::read_bytes(path)?;
-        let key_pair = signature::RsaKeyPair::from_der(der.as_slice())?;
+        Self::rsa_keypair_from_der(&Self::read_bytes(path)?)
+    }
+
+    /// Get the RSA Keypair from a DER encoded RSA private key, like
+    /// [`Secret::rsa_keypair_from_file`]
+    #[cfg(feature = "rsa")]
+    pub fn rsa_keypair_from_der(der: &[u8]) -> Result<Self, Error> {
+        let key_pair = signature::RsaKeyPair::from_der(der)?;
         Ok(Secret::RsaKeyPair(Arc::new(key_pair)))
     }
 
+    #[cfg(feature = "ecdsa")]
     /// Convenience function to get the ECDSA Keypair from a PKCS8-DER encoded EC private key.
     pub fn ecdsa_keypair_from_file(
         algorithm: SignatureAlgorithm,
         path: &str,
     ) -> Result<Self, Error> {
-        let der = Self::read_bytes(path)?;
+        Self::ecdsa_keypair_from_der(algorithm, &Self::read_bytes(path)?)
+    }
+
+    /// Get the ECDSA Keypair from a PKCS8-DER encoded EC private key, like
+    /// [`Secret::ecdsa_keypair_from_file`]
+    #[cfg(feature = "ecdsa")]
+    pub fn ecdsa_keypair_from_der(
+        algorithm: SignatureAlgorithm,
+        der: &[u8],
+    ) -> Result<Self, Error> {
         let ring_algorithm = match algorithm {
             SignatureAlgorithm::ES256 => &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
             SignatureAlgorithm::ES384 => &signature::ECDSA_P384_SHA384_FIXED_SIGNING,
             _ => return Err(Error::UnsupportedOperation),
         };
-        let key_pair = signature::EcdsaKeyPair::from_pkcs8(ring_algorithm, der.as_slice())?;
+        let key_pair = signature::EcdsaKeyPair::from_pkcs8(ring_algorithm, der)?;
         Ok(Secret::EcdsaKeyPair(Arc::new(key_pair)))
     }
 
+    /// Use the modulus (`n`) and exponent (`e`) of an RSA public key, as a
+    /// [`Secret::RSAModulusExponent`]
+    ///
+    /// Returns [`Error::WeakKey`] if the modulus is shorter than 2048 bits, the minimum from
+    /// RFC 7518.
+    pub fn rsa_modulus_exponent(n: BigUint, e: BigUint) -> Result<Self, Error> {
+        let bits = n.bits() as usize;
+        if bits < jwa::MINIMUM_RSA_KEY_BITS {
+            Err(Error::WeakKey {
+                minimum_bits: jwa::MINIMUM_RSA_KEY_BITS,
+                actual_bits: bits,
+            })?
+        }
+        Ok(Secret::RSAModulusExponent { n, e })
+    }
+
     /// Convenience function to create a Public key from a DER encoded RSA or ECDSA public key
     /// See examples in the [`Secret::PublicKey`] variant documentation for usage.
     pub fn public_key_from_file(path: &str) -> Result<Self, Error> {
@@ -213,13 +339,19 @@ impl Secret {
     }
 }
 
-impl From<jwk::RSAKeyParameters> for Secret {
-    fn from(rsa: jwk::RSAKeyParameters) -> Self {
-        rsa.jws_public_key_secret()
+impl TryFrom<jwk::RSAKeyParameters> for Secret {
+    type Error = Error;
+
+    fn try_from(rsa: jwk::RSAKeyParameters) -> Result<Self, Error> {
+        Secret::rsa_modulus_exponent(rsa.n, rsa.e)
     }
 }
 
 /// JWS Header, consisting of the registered fields and other custom fields
+///
+/// Headers are serialized deterministically in the order of [`HeaderOrder::Declared`], unless
+/// `T` itself serializes its fields in an unspecified order, like a `HashMap` does. Use
+/// [`HeaderOrder::Sorted`] to produce identical tokens regardless.
 #[derive(Debug, Eq, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub struct Header<T> {
     /// Registered header fields
@@ -232,6 +364,16 @@ pub struct Header<T> {
 
 impl<T: Serialize + DeserializeOwned> CompactJson for Header<T> {}
 
+/// The order of the fields of a [`Header`] when it is encoded
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HeaderOrder {
+    /// The registered fields in the order they are declared in [`RegisteredHeader`], followed
+    /// by the private fields in the order they are serialized
+    Declared,
+    /// Every field sorted by name, including the fields of nested objects such as `jwk`
+    Sorted,
+}
+
 impl Header<Empty> {
     /// Convenience function to create a header with only registered headers
     pub fn from_registered_header(registered: RegisteredHeader) -> Self {
@@ -345,9 +487,126 @@ impl Default for RegisteredHeader {
     }
 }
 
+impl RegisteredHeader {
+    /// Validates the header against the `token_type`, `algorithms` and `critical_headers` of
+    /// the `ValidationOptions`, stopping at the first failure
+    pub fn validate(&self, options: &ValidationOptions) -> Result<(), ValidationError> {
+        match self.validation_failures(options).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates the header like [`RegisteredHeader::validate`], but reports every failure
+    pub fn validate_all(&self, options: &ValidationOptions) -> Result<(), ValidationReport> {
+        let failures = self.validation_failures(options);
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationReport::new(failures))
+        }
+    }
+
+    fn validation_failures(&self, options: &ValidationOptions) -> Vec<ValidationError> {
+        let mut failures = vec![];
+        if let Validation::Validate(ref expected) = options.token_type {
+            let matches = self
+                .media_type
+                .as_ref()
+                .map(|media_type| media_type_matches(media_type, expected))
+                .unwrap_or(false);
+            if !matches {
+                failures.push(ValidationError::InvalidTokenType(self.media_type.clone()));
+            }
+        }
+        if let Some(ref media_type) = self.media_type {
+            if options
+                .rejected_token_types
+                .iter()
+                .any(|rejected| media_type_matches(media_type, rejected))
+            {
+                failures.push(ValidationError::InvalidTokenType(Some(media_type.clone())));
+            }
+        }
+        if self.algorithm == SignatureAlgorithm::None && !options.allow_unsecured_tokens {
+            failures.push(ValidationError::UnsecuredToken);
+        } else if let Validation::Validate(ref algorithms) = options.algorithms {
+            if !algorithms.contains(&self.algorithm) {
+                failures.push(ValidationError::WrongAlgorithmHeader);
+            }
+        }
+        if let (Validation::Validate(ref understood), Some(ref critical)) =
+            (&options.critical_headers, &self.critical)
+        {
+            let unsupported: Vec<String> = critical
+                .iter()
+                .filter(|header| !understood.contains(header))
+                .cloned()
+                .collect();
+            // An empty list is not allowed by RFC7515#4.1.11
+            if critical.is_empty() || !unsupported.is_empty() {
+                failures.push(ValidationError::UnsupportedCriticalHeaders(unsupported));
+            }
+        }
+        failures
+    }
+}
+
+/// Compares media types as described in [RFC7515#4.1.9](https://tools.ietf.org/html/rfc7515#section-4.1.9):
+/// case-insensitively, with an optional `application/` prefix
+fn media_type_matches(actual: &str, expected: &str) -> bool {
+    fn normalize(media_type: &str) -> String {
+        let media_type = media_type.to_ascii_lowercase();
+        match media_type.find('/') {
+            Some(slash) if media_type[..slash] == *"application" => {
+                media_type[slash + 1..].to_string()
+            }
+            _ => media_type,
+        }
+    }
+    normalize(actual) == normalize(expected)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::RegisteredHeader;
+    use std::convert::TryFrom;
+
+    use num_bigint::BigUint;
+
+    use super::{RegisteredHeader, Secret};
+    use crate::errors::{Error, ValidationError};
+    use crate::jwa::SignatureAlgorithm;
+    use crate::jwk::RSAKeyParameters;
+    use crate::{Validation, ValidationOptions};
+
+    #[test]
+    fn rsa_modulus_exponent_rejects_weak_keys() {
+        let e = BigUint::from(65537u32);
+        let weak = RSAKeyParameters {
+            n: BigUint::from(1u32) << 2046,
+            e: e.clone(),
+            ..Default::default()
+        };
+        let expect_weak_key = |result: Result<Secret, Error>| match result {
+            Err(Error::WeakKey {
+                minimum_bits: 2048,
+                actual_bits: 2047,
+            }) => {}
+            Err(other) => panic!("Unexpected error {:?}", other),
+            Ok(secret) => panic!("Unexpected {}", secret.variant_name()),
+        };
+        expect_weak_key(Secret::rsa_modulus_exponent(weak.n.clone(), e.clone()));
+        expect_weak_key(weak.jws_public_key_secret());
+        expect_weak_key(Secret::try_from(weak));
+
+        let n: BigUint = BigUint::from(1u32) << 2047;
+        let _ = not_err!(Secret::rsa_modulus_exponent(n.clone(), e.clone()));
+        let _ = not_err!(Secret::try_from(RSAKeyParameters {
+            n,
+            e,
+            ..Default::default()
+        }));
+    }
 
     #[test]
     fn header_serialization_round_trip_no_optional() {
@@ -376,4 +635,97 @@ mod tests {
         let decoded: RegisteredHeader = not_err!(serde_json::from_str(&encoded));
         assert_eq!(decoded, expected);
     }
+
+    #[test]
+    fn header_validation() {
+        let options = ValidationOptions {
+            token_type: Validation::Validate("at+jwt".to_string()),
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::RS256]),
+            ..Default::default()
+        };
+
+        for media_type in &["at+jwt", "AT+JWT", "application/at+jwt"] {
+            let header = RegisteredHeader {
+                algorithm: SignatureAlgorithm::RS256,
+                media_type: Some(media_type.to_string()),
+                ..Default::default()
+            };
+            not_err!(header.validate(&options));
+        }
+
+        let header = RegisteredHeader::default();
+        assert_eq!(
+            header.validate(&options),
+            Err(ValidationError::InvalidTokenType(Some("JWT".to_string())))
+        );
+        let report = header.validate_all(&options).unwrap_err();
+        assert_eq!(
+            report.errors(),
+            &[
+                ValidationError::InvalidTokenType(Some("JWT".to_string())),
+                ValidationError::WrongAlgorithmHeader
+            ]
+        );
+
+        not_err!(header.validate(&Default::default()));
+    }
+
+    #[test]
+    fn header_validation_rejected_token_types() {
+        let options = ValidationOptions {
+            rejected_token_types: vec![crate::oauth::ACCESS_TOKEN_MEDIA_TYPE.to_string()],
+            ..Default::default()
+        };
+        let header = |media_type: Option<&str>| RegisteredHeader {
+            media_type: media_type.map(String::from),
+            ..Default::default()
+        };
+
+        not_err!(header(None).validate(&options));
+        not_err!(header(Some("JWT")).validate(&options));
+        assert_eq!(
+            header(Some("application/AT+JWT")).validate(&options),
+            Err(ValidationError::InvalidTokenType(Some(
+                "application/AT+JWT".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn header_validation_critical_headers() {
+        let options = ValidationOptions {
+            critical_headers: Validation::Validate(vec!["exp".to_string()]),
+            ..Default::default()
+        };
+        let header = |critical: Option<Vec<&str>>| RegisteredHeader {
+            critical: critical.map(|critical| critical.into_iter().map(String::from).collect()),
+            ..Default::default()
+        };
+
+        not_err!(header(None).validate(&options));
+        not_err!(header(Some(vec!["exp"])).validate(&options));
+        assert_eq!(
+            header(Some(vec!["exp", "b64"])).validate(&options),
+            Err(ValidationError::UnsupportedCriticalHeaders(vec![
+                "b64".to_string()
+            ]))
+        );
+        assert_eq!(
+            header(Some(vec![])).validate(&options),
+            Err(ValidationError::UnsupportedCriticalHeaders(vec![]))
+        );
+
+        // No extension headers are understood by default
+        not_err!(header(None).validate(&Default::default()));
+        assert_eq!(
+            header(Some(vec!["exp"])).validate(&Default::default()),
+            Err(ValidationError::UnsupportedCriticalHeaders(vec![
+                "exp".to_string()
+            ]))
+        );
+        not_err!(header(Some(vec!["exp"])).validate(&ValidationOptions {
+            critical_headers: Validation::Ignored,
+            ..Default::default()
+        }));
+    }
 }
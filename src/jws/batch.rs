@@ -0,0 +1,151 @@
+use data_encoding::BASE64URL_NOPAD;
+use serde::Serialize;
+
+use super::{Header, Secret, Signer};
+use crate::errors::Error;
+use crate::CompactPart;
+
+/// Signs many payloads with the same header and secret
+///
+/// The secret is prepared for the algorithm in the header as a [`Signer`], and the header is
+/// serialized, once when the signer is created. They are then reused for every payload that is
+/// signed.
+///
+/// # Examples
+/// ```
+/// use biscuit::{ClaimsSet, Empty, RegisteredClaims};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::{BatchSigner, Header, RegisteredHeader, Secret};
+///
+/// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+/// let header = Header::<Empty>::from(RegisteredHeader {
+///     algorithm: SignatureAlgorithm::HS256,
+///     ..Default::default()
+/// });
+/// let signer = BatchSigner::new(&header, &secret).unwrap();
+///
+/// let claims: Vec<ClaimsSet<Empty>> = (0..100)
+///     .map(|i| ClaimsSet {
+///         registered: RegisteredClaims {
+///             subject: Some(format!("user-{}", i)),
+///             ..Default::default()
+///         },
+///         private: Empty {},
+///     })
+///     .collect();
+/// let tokens = signer.sign_all(&claims).unwrap();
+/// assert_eq!(tokens.len(), 100);
+/// ```
+pub struct BatchSigner {
+    header: String,
+    signer: Signer,
+}
+
+impl BatchSigner {
+    /// Prepare to sign with `secret` and the algorithm in `header`
+    pub fn new<H: Serialize>(header: &Header<H>, secret: &Secret) -> Result<Self, Error> {
+        Ok(BatchSigner {
+            header: BASE64URL_NOPAD.encode(&serde_json::to_vec(header)?),
+            signer: Signer::new(header.registered.algorithm, secret)?,
+        })
+    }
+
+    /// Sign `payload`, returning the token in its compact form
+    pub fn sign<T: CompactPart>(&self, payload: &T) -> Result<String, Error> {
+        let payload = payload.to_bytes()?;
+        let mut token = String::with_capacity(
+            self.header.len()
+                + BASE64URL_NOPAD.encode_len(payload.len())
+                + BASE64URL_NOPAD.encode_len(self.signer.signature_len())
+                + 2,
+        );
+        token.push_str(&self.header);
+        token.push('.');
+        BASE64URL_NOPAD.encode_append(&payload, &mut token);
+
+        let signature = self.signer.sign_to_buffer(token.as_bytes())?;
+        token.push('.');
+        BASE64URL_NOPAD.encode_append(&signature, &mut token);
+        Ok(token)
+    }
+
+    /// Sign every payload, returning the tokens in the same order, or the first error
+    pub fn sign_all<'a, T, I>(&self, payloads: I) -> Result<Vec<String>, Error>
+    where
+        T: CompactPart + 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        payloads
+            .into_iter()
+            .map(|payload| self.sign(payload))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwa::SignatureAlgorithm;
+    use crate::jws::{Compact, RegisteredHeader};
+    use crate::{ClaimsSet, Empty, RegisteredClaims};
+
+    fn claims(subject: &str) -> ClaimsSet<Empty> {
+        ClaimsSet {
+            registered: RegisteredClaims {
+                subject: Some(subject.to_string()),
+                ..Default::default()
+            },
+            private: Empty {},
+        }
+    }
+
+    fn header(algorithm: SignatureAlgorithm) -> Header<Empty> {
+        From::from(RegisteredHeader {
+            algorithm,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn batch_signer_matches_compact_encoding() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let signer = not_err!(BatchSigner::new(
+            &header(SignatureAlgorithm::HS256),
+            &secret
+        ));
+        let batch = vec![claims("alice"), claims("bob")];
+
+        let tokens = not_err!(signer.sign_all(&batch));
+        for (token, claims) in tokens.iter().zip(batch) {
+            let expected =
+                not_err!(
+                    Compact::new_decoded(header(SignatureAlgorithm::HS256), claims).encode(&secret)
+                );
+            assert_eq!(*token, expected.unwrap_encoded().to_string());
+        }
+    }
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn batch_signer_signs_with_key_pairs() {
+        let private_key = not_err!(Secret::rsa_keypair_from_file(
+            "test/fixtures/rsa_private_key.der"
+        ));
+        let public_key = not_err!(Secret::public_key_from_file(
+            "test/fixtures/rsa_public_key.der"
+        ));
+        let signer = not_err!(BatchSigner::new(
+            &header(SignatureAlgorithm::PS256),
+            &private_key
+        ));
+
+        for subject in &["alice", "bob"] {
+            let token = not_err!(signer.sign(&claims(subject)));
+            let decoded = not_err!(Compact::<ClaimsSet<Empty>, Empty>::new_encoded(&token)
+                .decode(&public_key, SignatureAlgorithm::PS256));
+            assert_eq!(*not_err!(decoded.payload()), claims(subject));
+        }
+
+        assert!(BatchSigner::new(&header(SignatureAlgorithm::HS256), &private_key).is_err());
+    }
+}
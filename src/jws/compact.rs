@@ -1,13 +1,14 @@
+use data_encoding::BASE64URL_NOPAD;
 use serde::de::DeserializeOwned;
 use serde::{self, Deserialize, Serialize};
 use std::str;
 
 use crate::errors::{DecodeError, Error, ValidationError};
-use crate::jwa::{Algorithm, SignatureAlgorithm};
+use crate::jwa::{Algorithm, SignatureAlgorithm, SignatureBuffer};
 use crate::jwk::{AlgorithmParameters, JWKSet};
 use crate::CompactPart;
 
-use super::{Header, Secret};
+use super::{Header, HeaderOrder, Secret, Signer};
 
 /// Compact representation of a JWS
 ///
@@ -65,26 +66,47 @@ where
     /// Encode the JWT passed and sign the payload using the algorithm from the header and the secret
     /// The secret is dependent on the signing algorithm
     pub fn encode(&self, secret: &Secret) -> Result<Self, Error> {
+        self.encode_with_header_order(secret, HeaderOrder::Declared)
+    }
+
+    /// Encode the JWT like [`Compact::encode`], with the header fields in the given `order`
+    pub fn encode_with_header_order(
+        &self,
+        secret: &Secret,
+        order: HeaderOrder,
+    ) -> Result<Self, Error> {
         match *self {
             Compact::Decoded {
                 ref header,
                 ref payload,
             } => {
-                let mut compact = crate::Compact::with_capacity(3);
-                compact.push(header)?;
-                compact.push(payload)?;
-                let encoded_payload = compact.encode();
-                let signature = header
-                    .registered
-                    .algorithm
-                    .sign(encoded_payload.as_bytes(), secret)?;
-                compact.push(&signature)?;
-                Ok(Compact::Encoded(compact))
+                let signer = Signer::new(header.registered.algorithm, secret)?;
+                Ok(Compact::Encoded(sign(header, order, payload, &signer)?))
             }
             Compact::Encoded(_) => Err(Error::UnsupportedOperation),
         }
     }
 
+    /// Encode the JWT like [`Compact::encode`], signing with a prepared [`Signer`] instead of a
+    /// secret
+    ///
+    /// Returns [`ValidationError::WrongAlgorithmHeader`] if the algorithm in the header is not
+    /// the algorithm of `signer`.
+    pub fn encode_with_signer(&self, signer: &Signer) -> Result<Self, Error> {
+        match *self {
+            Compact::Decoded {
+                ref header,
+                ref payload,
+            } => Ok(Compact::Encoded(sign(
+                header,
+                HeaderOrder::Declared,
+                payload,
+                signer,
+            )?)),
+            Compact::Encoded(_) => Err(Error::UnsupportedOperation),
+        }
+    }
+
     /// Consumes self and convert into decoded form, verifying the signature, if any.
     /// If the token is already decoded, this is a no-op
     // TODO: Is the no-op dangerous? What if the secret between the previous decode and this time is different?
@@ -105,24 +127,7 @@ where
         match *self {
             Compact::Decoded { .. } => Err(Error::UnsupportedOperation),
             Compact::Encoded(ref encoded) => {
-                if encoded.len() != 3 {
-                    Err(DecodeError::PartsLengthError {
-                        actual: encoded.len(),
-                        expected: 3,
-                    })?
-                }
-
-                let signature: Vec<u8> = encoded.part(2)?;
-                let payload = &encoded.parts[0..2].join(".");
-
-                algorithm
-                    .verify(signature.as_ref(), payload.as_ref(), secret)
-                    .map_err(|_| ValidationError::InvalidSignature)?;
-
-                let header: Header<H> = encoded.part(0)?;
-                if header.registered.algorithm != algorithm {
-                    Err(ValidationError::WrongAlgorithmHeader)?;
-                }
+                let header = verify(encoded, secret, algorithm)?;
                 let decoded_claims: T = encoded.part(1)?;
 
                 Ok(Self::new_decoded(header, decoded_claims))
@@ -130,6 +135,37 @@ where
         }
     }
 
+    /// Verify the signature of `token` and decode it like [`Compact::decode`], without first
+    /// copying its parts into an encoded `Compact`
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::{Empty, Token, JWT};
+    /// use biscuit::jwa::SignatureAlgorithm;
+    /// use biscuit::jws::Secret;
+    ///
+    /// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+    /// let token = Token::builder()
+    ///     .subject("John Doe")
+    ///     .sign(&secret, SignatureAlgorithm::HS256)
+    ///     .unwrap();
+    ///
+    /// let decoded = JWT::<Empty, Empty>::decode_str(&token, &secret, SignatureAlgorithm::HS256)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     decoded.payload().unwrap().registered.subject.as_deref(),
+    ///     Some("John Doe")
+    /// );
+    /// ```
+    pub fn decode_str(
+        token: &str,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, Error> {
+        let (header, parts) = verify_str(token, secret, algorithm)?;
+        Ok(Self::new_decoded(header, parts.payload()?))
+    }
+
     /// Decode a token into the JWT struct and verify its signature using a JWKS
     ///
     /// If the JWK does not contain an optional algorithm parameter, you will have to specify
@@ -154,7 +190,7 @@ where
                     })?
                 }
 
-                let signature: Vec<u8> = encoded.part(2)?;
+                let signature = SignatureBuffer::from_base64(encoded.parts[2].as_ref())?;
                 let payload = &encoded.parts[0..2].join(".");
 
                 let header: Header<H> = encoded.part(0)?;
@@ -196,14 +232,12 @@ where
                 };
 
                 let secret = match &jwk.algorithm {
-                    AlgorithmParameters::RSA(rsa) => rsa.jws_public_key_secret(),
+                    AlgorithmParameters::RSA(rsa) => rsa.jws_public_key_secret()?,
                     AlgorithmParameters::OctetKey(oct) => Secret::Bytes(oct.value.clone()),
                     _ => Err(ValidationError::UnsupportedKeyAlgorithm)?,
                 };
 
-                algorithm
-                    .verify(signature.as_ref(), payload.as_ref(), &secret)
-                    .map_err(|_| ValidationError::InvalidSignature)?;
+                algorithm.verify(signature.as_ref(), payload.as_ref(), &secret)?;
 
                 let decoded_claims: T = encoded.part(1)?;
 
@@ -319,17 +353,97 @@ where
 impl<P, H> Compact<crate::ClaimsSet<P>, H>
 where
     crate::ClaimsSet<P>: CompactPart,
+    P: Serialize,
     H: Serialize + DeserializeOwned,
 {
+    /// Consumes self, fills in the registered claims configured in the `IssuanceOptions` and
+    /// then converts it into encoded form like [`Compact::into_encoded`].
+    ///
+    /// Returns an error if the token is already encoded, since its claims can no longer be
+    /// changed.
+    pub fn into_encoded_with_options(
+        self,
+        secret: &Secret,
+        options: &crate::IssuanceOptions,
+    ) -> Result<Self, Error> {
+        let signer = match self {
+            Compact::Encoded(_) => Err(Error::UnsupportedOperation)?,
+            Compact::Decoded { ref header, .. } => {
+                Signer::new(header.registered.algorithm, secret)?
+            }
+        };
+        self.into_encoded_with_signer(&signer, options)
+    }
+
+    /// Consumes self, fills in the registered claims configured in the `IssuanceOptions` and
+    /// signs it with a prepared [`Signer`], like [`Compact::into_encoded_with_options`]
+    ///
+    /// Returns [`ValidationError::WrongAlgorithmHeader`] if the algorithm in the header is not
+    /// the algorithm of `signer`.
+    pub fn into_encoded_with_signer(
+        self,
+        signer: &Signer,
+        options: &crate::IssuanceOptions,
+    ) -> Result<Self, Error> {
+        let token = self.sign_with_options(signer, options)?;
+        Ok(Compact::Encoded(crate::Compact::decode(&token)))
+    }
+
+    /// Fill in the registered claims configured in `options`, and sign the token with `signer`,
+    /// returning it in its compact form without splitting it into parts
+    pub(crate) fn sign_with_options(
+        self,
+        signer: &Signer,
+        options: &crate::IssuanceOptions,
+    ) -> Result<String, Error> {
+        match self {
+            Compact::Encoded(_) => Err(Error::UnsupportedOperation),
+            Compact::Decoded {
+                header,
+                mut payload,
+            } => {
+                payload.registered.apply_issuance_options(options)?;
+                crate::record_signing(
+                    options.metrics.as_deref(),
+                    &*options.clock,
+                    header.registered.algorithm,
+                    || {
+                        if options.canonical_claims {
+                            let claims = crate::to_canonical_json(&payload)?;
+                            sign_compact(&header, options.header_order, &claims, signer)
+                        } else {
+                            sign_compact(&header, options.header_order, &payload, signer)
+                        }
+                    },
+                )
+            }
+        }
+    }
+
     /// Validate the temporal claims in the decoded token
     ///
     /// If `None` is provided for options, the defaults will apply.
     ///
     /// By default, no temporal claims (namely `iat`, `exp`, `nbf`)
     /// are required, and they will pass validation if they are missing.
+    ///
+    /// The header is validated first, see [`RegisteredHeader::validate`](super::RegisteredHeader::validate).
     pub fn validate(&self, options: crate::ValidationOptions) -> Result<(), Error> {
-        self.payload()?.registered.validate(options)?;
-        Ok(())
+        self.header()?.registered.validate(&options)?;
+        self.payload()?.validate(options)
+    }
+
+    /// Validate the claims in the decoded token, running every check and reporting every
+    /// failure in a [`crate::errors::ValidationReport`].
+    ///
+    /// See [`crate::ClaimsSet::validate_all`]. The `jti` of a token whose header fails
+    /// validation is neither checked against the denylist nor recorded in the replay cache.
+    pub fn validate_all(&self, options: crate::ValidationOptions) -> Result<(), Error> {
+        let errors = match self.header()?.registered.validate_all(&options) {
+            Ok(()) => vec![],
+            Err(report) => report.into_errors(),
+        };
+        self.payload()?.validate_all_after(options, errors)
     }
 }
 
@@ -347,6 +461,173 @@ impl<T: CompactPart, H: Serialize + DeserializeOwned> CompactPart for Compact<T,
     }
 }
 
+/// The parts of a compact JWS, borrowed from its encoded form
+///
+/// The signing input is the slice of the token up to the second `.`, so it can be verified
+/// without joining the parts again.
+pub(crate) struct BorrowedCompact<'a> {
+    header: &'a str,
+    payload: &'a str,
+    signing_input: &'a str,
+    signature: &'a str,
+}
+
+impl<'a> BorrowedCompact<'a> {
+    /// Split `token` into its three parts
+    pub(crate) fn split(token: &'a str) -> Result<Self, Error> {
+        let mut dots = token.match_indices('.').map(|(index, _)| index);
+        match (dots.next(), dots.next(), dots.next()) {
+            (Some(first), Some(second), None) => Ok(BorrowedCompact {
+                header: &token[..first],
+                payload: &token[first + 1..second],
+                signing_input: &token[..second],
+                signature: &token[second + 1..],
+            }),
+            _ => Err(DecodeError::PartsLengthError {
+                actual: token.split('.').count(),
+                expected: 3,
+            })?,
+        }
+    }
+
+    /// The base64url encoded header
+    pub(crate) fn encoded_header(&self) -> &'a str {
+        self.header
+    }
+
+    /// Decode the header, which is not verified
+    pub(crate) fn header<H: Serialize + DeserializeOwned>(&self) -> Result<Header<H>, Error> {
+        CompactPart::from_base64(&self.header)
+    }
+
+    /// Decode the payload, which is not verified
+    pub(crate) fn payload<T: CompactPart>(&self) -> Result<T, Error> {
+        CompactPart::from_base64(&self.payload)
+    }
+
+    /// Check that the token has no signature, for tokens with the `none` algorithm
+    pub(crate) fn verify_unsecured(&self) -> Result<(), Error> {
+        super::unsecured::check_signature(self.signature.as_bytes())
+    }
+
+    /// Verify the signature over the signing input with `secret` and `algorithm`
+    pub(crate) fn verify(
+        &self,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<(), Error> {
+        if algorithm.is_hmac() {
+            algorithm.verify_hmac_parts(
+                self.signature.as_bytes(),
+                &[self.signing_input.as_bytes()],
+                secret,
+            )?;
+            return Ok(());
+        }
+
+        let signature = SignatureBuffer::from_base64(self.signature.as_bytes())?;
+        algorithm.verify(&signature, self.signing_input.as_bytes(), secret)?;
+        Ok(())
+    }
+}
+
+/// Verify the signature of `token` like [`verify`], borrowing its parts instead of copying
+/// them, and return its header and its borrowed parts
+pub(super) fn verify_str<'a, H: Serialize + DeserializeOwned>(
+    token: &'a str,
+    secret: &Secret,
+    algorithm: SignatureAlgorithm,
+) -> Result<(Header<H>, BorrowedCompact<'a>), Error> {
+    let parts = BorrowedCompact::split(token)?;
+    parts.verify(secret, algorithm)?;
+
+    let header: Header<H> = parts.header()?;
+    if header.registered.algorithm != algorithm {
+        Err(ValidationError::WrongAlgorithmHeader)?;
+    }
+    Ok((header, parts))
+}
+
+/// Verify the signature of `encoded` with `secret` and return its header
+pub(super) fn verify<H: Serialize + DeserializeOwned>(
+    encoded: &crate::Compact,
+    secret: &Secret,
+    algorithm: SignatureAlgorithm,
+) -> Result<Header<H>, Error> {
+    if encoded.len() != 3 {
+        Err(DecodeError::PartsLengthError {
+            actual: encoded.len(),
+            expected: 3,
+        })?
+    }
+
+    if algorithm.is_hmac() {
+        let data = [encoded.parts[0].as_ref(), b".", encoded.parts[1].as_ref()];
+        algorithm.verify_hmac_parts(encoded.parts[2].as_ref(), &data, secret)?;
+    } else {
+        let signature = SignatureBuffer::from_base64(encoded.parts[2].as_ref())?;
+        let payload = &encoded.parts[0..2].join(".");
+
+        algorithm.verify(signature.as_ref(), payload.as_ref(), secret)?;
+    }
+
+    let header: Header<H> = encoded.part(0)?;
+    if header.registered.algorithm != algorithm {
+        Err(ValidationError::WrongAlgorithmHeader)?;
+    }
+    Ok(header)
+}
+
+/// Encode `header` in the given `order` and `payload`, and sign them with `signer`, which must
+/// sign with the algorithm in `header`
+fn sign<H: Serialize>(
+    header: &Header<H>,
+    order: HeaderOrder,
+    payload: &dyn CompactPart,
+    signer: &Signer,
+) -> Result<crate::Compact, Error> {
+    Ok(crate::Compact::decode(&sign_compact(
+        header, order, payload, signer,
+    )?))
+}
+
+/// Sign like [`sign`], returning the token in its compact form
+///
+/// The parts are encoded straight into one buffer, which is allocated with the length of the
+/// token, so that the only other allocations are for the JSON of the header, the payload and
+/// the signature.
+fn sign_compact<H: Serialize>(
+    header: &Header<H>,
+    order: HeaderOrder,
+    payload: &dyn CompactPart,
+    signer: &Signer,
+) -> Result<String, Error> {
+    if header.registered.algorithm != signer.algorithm() {
+        Err(ValidationError::WrongAlgorithmHeader)?
+    }
+
+    let header = match order {
+        HeaderOrder::Declared => serde_json::to_vec(header)?,
+        HeaderOrder::Sorted => {
+            serde_json::to_vec(&crate::sort_json_keys(serde_json::to_value(header)?))?
+        }
+    };
+    let payload = payload.to_bytes()?;
+    let mut token = String::with_capacity(
+        BASE64URL_NOPAD.encode_len(header.len())
+            + BASE64URL_NOPAD.encode_len(payload.len())
+            + BASE64URL_NOPAD.encode_len(signer.signature_len())
+            + 2,
+    );
+    BASE64URL_NOPAD.encode_append(&header, &mut token);
+    token.push('.');
+    BASE64URL_NOPAD.encode_append(&payload, &mut token);
+    let signature = signer.sign_to_buffer(token.as_bytes())?;
+    token.push('.');
+    BASE64URL_NOPAD.encode_append(&signature, &mut token);
+    Ok(token)
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::{self, FromStr};
@@ -354,8 +635,9 @@ mod tests {
     use serde::{Deserialize, Serialize};
 
     use super::{Compact, Header, Secret, SignatureAlgorithm};
-    use crate::jwk::JWKSet;
-    use crate::jws::RegisteredHeader;
+    use crate::errors::{Error, ValidationError};
+    use crate::jwk::{JWKSet, JWK};
+    use crate::jws::{RegisteredHeader, UnsecuredJwt};
     use crate::{ClaimsSet, CompactJson, Empty, RegisteredClaims, SingleOrMultiple};
 
     #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -412,7 +694,7 @@ mod tests {
     }
 
     #[test]
-    fn compact_jws_round_trip_none() {
+    fn compact_jws_rejects_none() {
         let expected_token = "eyJhbGciOiJub25lIiwidHlwIjoiSldUIn0.\
             eyJpc3MiOiJodHRwczovL3d3dy5hY21lLmNvbS8iLCJzdWIiOiJKb2huIERvZSIsImF1ZCI6Imh0dHBzOi8vY\
             WNtZS1jdXN0b21lci5jb20vIiwibmJmIjoxMjM0LCJjb21wYW55IjoiQUNNRSIsImRlcGFydG1lbnQiOiJUb2l\
@@ -434,19 +716,57 @@ mod tests {
             },
         };
 
-        let expected_jwt = Compact::new_decoded(
-            From::from(RegisteredHeader {
-                algorithm: SignatureAlgorithm::None,
-                ..Default::default()
-            }),
-            expected_claims.clone(),
-        );
-        let token = not_err!(expected_jwt.into_encoded(&Secret::None));
-        assert_eq!(expected_token, not_err!(token.encoded()).to_string());
+        let header = From::from(RegisteredHeader {
+            algorithm: SignatureAlgorithm::None,
+            ..Default::default()
+        });
+        let jwt = Compact::new_decoded(header, expected_claims.clone());
+        match jwt.encode(&Secret::None) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let token = UnsecuredJwt::danger_new(jwt.header().unwrap().clone(), expected_claims);
+        assert_eq!(expected_token, not_err!(token.encode()));
 
-        let biscuit = not_err!(token.into_decoded(&Secret::None, SignatureAlgorithm::None));
-        let actual_claims = not_err!(biscuit.payload());
-        assert_eq!(expected_claims, *actual_claims);
+        let encoded = Compact::<ClaimsSet<PrivateClaims>, Empty>::new_encoded(expected_token);
+        match encoded.into_decoded(&Secret::None, SignatureAlgorithm::None) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compact_jws_verification_rejects_unsecured_tokens() {
+        let header = RegisteredHeader {
+            key_id: Some("key".to_string()),
+            ..Default::default()
+        };
+        let token = not_err!(UnsecuredJwt::danger_new(
+            From::from(header),
+            ClaimsSet::<Empty>::default()
+        )
+        .encode());
+        assert!(token.ends_with('.'));
+        let encoded = Compact::<ClaimsSet<Empty>, Empty>::new_encoded(&token);
+
+        let mut jwk = JWK::new_octet_key(b"", Empty {});
+        jwk.common.key_id = Some("key".to_string());
+        let jwks = JWKSet { keys: vec![jwk] };
+
+        let results = vec![
+            encoded.decode(&Secret::None, SignatureAlgorithm::None),
+            encoded
+                .clone()
+                .into_decoded(&Secret::None, SignatureAlgorithm::None),
+            Compact::decode_str(&token, &Secret::None, SignatureAlgorithm::None),
+            encoded.decode_with_jwks(&jwks, Some(SignatureAlgorithm::None)),
+        ];
+        for result in results {
+            match result {
+                Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
     }
 
     #[test]
@@ -474,17 +794,349 @@ mod tests {
             }),
             expected_claims.clone(),
         );
-        let token =
-            not_err!(expected_jwt.into_encoded(&Secret::Bytes("secret".to_string().into_bytes())));
+        let token = not_err!(expected_jwt.into_encoded(
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
+        ));
         assert_eq!(HS256_PAYLOAD, not_err!(token.encoded()).to_string());
 
         let biscuit = not_err!(token.into_decoded(
-            &Secret::Bytes("secret".to_string().into_bytes()),
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256
         ));
         assert_eq!(expected_claims, *not_err!(biscuit.payload()));
     }
 
+    #[test]
+    fn compact_jws_decode_reports_wrong_key_type() {
+        use crate::errors::Error;
+        use crate::{Validation, ValidationOptions};
+
+        let public_key = not_err!(Secret::public_key_from_file(
+            "test/fixtures/rsa_public_key.der"
+        ));
+        let assert_wrong_key_type = |result: Result<(), Error>| match result {
+            Err(Error::WrongKeyType {
+                ref expected,
+                ref actual,
+            }) => {
+                assert_eq!(expected, "Secret::Bytes");
+                assert_eq!(actual, "Secret::PublicKey");
+            }
+            other => panic!("Unexpected result {:?}", other),
+        };
+
+        let token = Compact::<ClaimsSet<PrivateClaims>, Empty>::new_encoded(HS256_PAYLOAD);
+        assert_wrong_key_type(
+            token
+                .into_decoded(&public_key, SignatureAlgorithm::HS256)
+                .map(|_| ()),
+        );
+        assert_wrong_key_type(
+            Compact::<ClaimsSet<PrivateClaims>, Empty>::decode_str(
+                HS256_PAYLOAD,
+                &public_key,
+                SignatureAlgorithm::HS256,
+            )
+            .map(|_| ()),
+        );
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+        assert_wrong_key_type(
+            crate::decode::<PrivateClaims>(HS256_PAYLOAD, &public_key, &options).map(|_| ()),
+        );
+    }
+
+    #[test]
+    fn compact_jws_decode_reports_weak_keys() {
+        use crate::errors::Error;
+        use crate::{Validation, ValidationOptions};
+
+        let secret = Secret::bytes_from_str("secret");
+        let assert_weak_key = |result: Result<(), Error>| match result {
+            Err(Error::WeakKey {
+                minimum_bits,
+                actual_bits,
+            }) => assert_eq!((minimum_bits, actual_bits), (256, 48)),
+            other => panic!("Unexpected result {:?}", other),
+        };
+
+        let token = Compact::<ClaimsSet<PrivateClaims>, Empty>::new_encoded(HS256_PAYLOAD);
+        assert_weak_key(
+            token
+                .into_decoded(&secret, SignatureAlgorithm::HS256)
+                .map(|_| ()),
+        );
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+        assert_weak_key(
+            crate::decode::<PrivateClaims>(HS256_PAYLOAD, &secret, &options).map(|_| ()),
+        );
+        let _ = not_err!(crate::decode::<PrivateClaims>(
+            HS256_PAYLOAD,
+            &secret.danger_allow_weak_keys(),
+            &options
+        ));
+    }
+
+    #[test]
+    fn compact_jws_decode_str() {
+        use crate::errors::{DecodeError, Error};
+
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let decoded = not_err!(Compact::<ClaimsSet<PrivateClaims>, Empty>::decode_str(
+            HS256_PAYLOAD,
+            &secret,
+            SignatureAlgorithm::HS256
+        ));
+        let expected = not_err!(Compact::<ClaimsSet<PrivateClaims>, Empty>::new_encoded(
+            HS256_PAYLOAD
+        )
+        .into_decoded(&secret, SignatureAlgorithm::HS256));
+        assert_eq!(decoded, expected);
+
+        let tampered = format!("{}x", HS256_PAYLOAD);
+        match Compact::<ClaimsSet<PrivateClaims>, Empty>::decode_str(
+            &tampered,
+            &secret,
+            SignatureAlgorithm::HS256,
+        ) {
+            Err(Error::DecodeBase64(_)) | Err(Error::ValidationError(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let nested = format!("{}.{}", HS256_PAYLOAD, "extra");
+        match Compact::<ClaimsSet<PrivateClaims>, Empty>::decode_str(
+            &nested,
+            &secret,
+            SignatureAlgorithm::HS256,
+        ) {
+            Err(Error::DecodeError(DecodeError::PartsLengthError { actual, expected })) => {
+                assert_eq!((actual, expected), (4, 3))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compact_jws_encode_with_issuance_options() {
+        use chrono::{Duration, TimeZone, Utc};
+        use std::sync::Arc;
+
+        use crate::{FixedClock, IssuanceOptions};
+
+        let claims = ClaimsSet::<Empty> {
+            registered: RegisteredClaims {
+                not_before: Some(1234.into()),
+                ..Default::default()
+            },
+            private: Default::default(),
+        };
+        let options = IssuanceOptions {
+            expires_in: Some(Duration::seconds(300)),
+            not_before_in: Some(Duration::zero()),
+            issued_at: true,
+            id: Some(Arc::new(crate::RandomUuid)),
+            clock: Arc::new(FixedClock(Utc.timestamp(1000, 0))),
+            ..Default::default()
+        };
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+
+        let jwt = Compact::new_decoded(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            claims,
+        );
+        let token = not_err!(jwt.into_encoded_with_options(&secret, &options));
+        let decoded = not_err!(token
+            .clone()
+            .into_decoded(&secret, SignatureAlgorithm::HS256));
+        let registered = &not_err!(decoded.payload()).registered;
+        assert_eq!(registered.expiry, Some(1300.into()));
+        assert_eq!(registered.issued_at, Some(1000.into()));
+        assert_eq!(registered.id.as_ref().map(String::len), Some(36));
+        // Claims that are already set are kept
+        assert_eq!(registered.not_before, Some(1234.into()));
+
+        assert!(token.into_encoded_with_options(&secret, &options).is_err());
+    }
+
+    #[test]
+    fn compact_jws_encode_with_sorted_header() {
+        use std::collections::HashMap;
+
+        use serde_json::{Map, Value};
+
+        use crate::jws::HeaderOrder;
+
+        let mut private = HashMap::new();
+        let _ = private.insert("zeta".to_string(), 1);
+        let _ = private.insert("alpha".to_string(), 2);
+        let jwt = Compact::new_decoded(
+            Header {
+                registered: RegisteredHeader {
+                    algorithm: SignatureAlgorithm::HS256,
+                    key_id: Some("key-1".to_string()),
+                    ..Default::default()
+                },
+                private,
+            },
+            ClaimsSet::<Empty> {
+                registered: Default::default(),
+                private: Default::default(),
+            },
+        );
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+
+        let encoded = not_err!(jwt.encode_with_header_order(&secret, HeaderOrder::Sorted));
+        let header: Map<String, Value> = not_err!(encoded.clone().unwrap_encoded().part(0));
+        let names: Vec<&str> = header.keys().map(String::as_str).collect();
+        assert_eq!(names, ["alg", "alpha", "kid", "typ", "zeta"]);
+
+        let declared = not_err!(jwt.encode(&secret));
+        let header: Map<String, Value> = not_err!(declared.unwrap_encoded().part(0));
+        let names: Vec<&str> = header.keys().take(3).map(String::as_str).collect();
+        assert_eq!(names, ["alg", "typ", "kid"]);
+
+        // The signature covers the header as encoded
+        let _ = not_err!(encoded.into_decoded(&secret, SignatureAlgorithm::HS256));
+    }
+
+    #[test]
+    fn compact_jws_encode_with_canonical_claims() {
+        use serde_json::json;
+
+        use crate::IssuanceOptions;
+
+        let claims = |private| ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some("https://www.acme.com".to_string()),
+                ..Default::default()
+            },
+            private,
+        };
+        let options = IssuanceOptions {
+            canonical_claims: true,
+            ..Default::default()
+        };
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let encode = |private| {
+            let jwt = Compact::<_, Empty>::new_decoded(
+                From::from(RegisteredHeader {
+                    algorithm: SignatureAlgorithm::HS256,
+                    ..Default::default()
+                }),
+                claims(private),
+            );
+            not_err!(jwt.into_encoded_with_options(&secret, &options)).unwrap_encoded()
+        };
+
+        let first = encode(json!({ "roles": ["admin"], "level": 1.0 }));
+        let second = encode(json!({ "level": 1, "roles": ["admin"] }));
+        assert_eq!(first, second);
+        let payload: Vec<u8> = not_err!(first.part(1));
+        assert_eq!(
+            payload,
+            br#"{"iss":"https://www.acme.com","level":1,"roles":["admin"]}"#.to_vec()
+        );
+    }
+
+    #[test]
+    fn compact_jws_validate_checks_header() {
+        use crate::errors::Error;
+        use crate::{Validation, ValidationOptions};
+
+        let jwt = Compact::new_decoded(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::None,
+                ..Default::default()
+            }),
+            ClaimsSet::<Empty> {
+                registered: Default::default(),
+                private: Default::default(),
+            },
+        );
+        match jwt.validate(Default::default()) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        not_err!(jwt.validate(ValidationOptions {
+            allow_unsecured_tokens: true,
+            ..Default::default()
+        }));
+
+        let options = ValidationOptions {
+            allow_unsecured_tokens: true,
+            token_type: Validation::Validate("at+jwt".to_string()),
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            claim_presence_options: crate::ClaimPresenceOptions::strict(),
+            ..Default::default()
+        };
+        match jwt.validate(options.clone()) {
+            Err(Error::ValidationError(ValidationError::InvalidTokenType(_))) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match jwt.validate_all(options) {
+            Err(Error::ValidationReport(report)) => {
+                assert_eq!(report.len(), 3);
+                assert_eq!(report.errors()[1], ValidationError::WrongAlgorithmHeader);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compact_jws_validate_all_skips_replay_checks_after_header_failures() {
+        use std::sync::Arc;
+
+        use crate::{InMemoryDenylist, InMemoryReplayCache, Validation, ValidationOptions};
+
+        let jwt = Compact::new_decoded(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            ClaimsSet::<Empty> {
+                registered: RegisteredClaims {
+                    id: Some("id".to_string()),
+                    ..Default::default()
+                },
+                private: Default::default(),
+            },
+        );
+        let denylist = Arc::new(InMemoryDenylist::default());
+        denylist.revoke_id("id");
+        let cache = Arc::new(InMemoryReplayCache::new(16));
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::RS256]),
+            denylist: Validation::Validate(denylist),
+            id: Validation::Validate(cache.clone()),
+            ..Default::default()
+        };
+
+        match jwt.validate_all(options) {
+            Err(Error::ValidationReport(report)) => assert_eq!(
+                report.into_errors(),
+                vec![ValidationError::WrongAlgorithmHeader]
+            ),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(cache.is_empty());
+
+        not_err!(jwt.validate_all(ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            id: Validation::Validate(cache.clone()),
+            ..Default::default()
+        }));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[cfg(feature = "rsa")]
     #[test]
     fn compact_jws_round_trip_rs256() {
         let expected_token = "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.\
@@ -529,6 +1181,7 @@ mod tests {
         assert_eq!(expected_claims, *not_err!(biscuit.payload()));
     }
 
+    #[cfg(feature = "ecdsa")]
     #[test]
     fn compact_jws_verify_es256() {
         use data_encoding::HEXUPPER;
@@ -581,10 +1234,11 @@ mod tests {
         };
 
         let expected_jwt = Compact::new_decoded(header.clone(), expected_claims);
-        let token =
-            not_err!(expected_jwt.into_encoded(&Secret::Bytes("secret".to_string().into_bytes())));
+        let token = not_err!(expected_jwt.into_encoded(
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
+        ));
         let biscuit = not_err!(token.into_decoded(
-            &Secret::Bytes("secret".to_string().into_bytes()),
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256
         ));
         assert_eq!(header, *not_err!(biscuit.header()));
@@ -596,7 +1250,7 @@ mod tests {
         let token =
             Compact::<PrivateClaims, Empty>::new_encoded("eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9");
         let claims = token.decode(
-            &Secret::Bytes("secret".to_string().into_bytes()),
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256,
         );
         let _ = claims.unwrap();
@@ -611,12 +1265,13 @@ mod tests {
              pKscJVk7-aHxfmQKlaZxh5uhuKhGMAa-1F5IX5mfUwI",
         );
         let claims = token.decode(
-            &Secret::Bytes("secret".to_string().into_bytes()),
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256,
         );
         let _ = claims.unwrap();
     }
 
+    #[cfg(feature = "rsa")]
     #[test]
     #[should_panic(expected = "InvalidSignature")]
     fn compact_jws_decode_token_invalid_signature_rs256() {
@@ -639,7 +1294,7 @@ mod tests {
              pKscJVk7-aHxfmQKlaZxh5uhuKhGMAa-1F5IX5mfUwI",
         );
         let claims = token.decode(
-            &Secret::Bytes("secret".to_string().into_bytes()),
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256,
         );
         let _ = claims.unwrap();
@@ -665,12 +1320,13 @@ mod tests {
             }),
             payload.clone(),
         );
-        let token =
-            not_err!(expected_jwt.into_encoded(&Secret::Bytes("secret".to_string().into_bytes())));
+        let token = not_err!(expected_jwt.into_encoded(
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
+        ));
         assert_eq!(expected_token, not_err!(token.encoded()).to_string());
 
         let biscuit = not_err!(token.into_decoded(
-            &Secret::Bytes("secret".to_string().into_bytes()),
+            &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256
         ));
         assert_eq!(payload, *not_err!(biscuit.payload()));
@@ -845,6 +1501,7 @@ mod tests {
         let _ = token.decode_with_jwks(&jwks, None).unwrap();
     }
 
+    #[cfg(feature = "rsa")]
     #[test]
     fn compact_jws_decode_with_jwks_rsa() {
         let token = Compact::<PrivateClaims, Empty>::new_encoded(
@@ -1088,4 +1745,48 @@ mod tests {
         let signature = not_err!(encoded_token.signature());
         assert_eq!(signature, expected_signature);
     }
+
+    #[cfg(all(feature = "rsa", feature = "ecdsa"))]
+    #[test]
+    fn compact_jws_is_signed_into_a_buffer_of_its_length() {
+        use super::{HeaderOrder, Signer};
+
+        let hmac = Secret::Bytes(vec![7; 64]);
+        let rsa = not_err!(Secret::rsa_keypair_from_file(
+            "test/fixtures/rsa_private_key.der"
+        ));
+        let ecdsa = not_err!(Secret::ecdsa_keypair_from_file(
+            SignatureAlgorithm::ES256,
+            "test/fixtures/ecdsa_private_key.p8"
+        ));
+        let claims = ClaimsSet::<Empty> {
+            registered: RegisteredClaims {
+                subject: Some("John Doe".to_string()),
+                ..Default::default()
+            },
+            private: Empty {},
+        };
+
+        for (algorithm, secret) in &[
+            (SignatureAlgorithm::HS512, &hmac),
+            (SignatureAlgorithm::PS256, &rsa),
+            (SignatureAlgorithm::ES256, &ecdsa),
+        ] {
+            let header = From::from(RegisteredHeader {
+                algorithm: *algorithm,
+                ..Default::default()
+            });
+            let signer = not_err!(Signer::new(*algorithm, secret));
+            let token = not_err!(super::sign_compact(
+                &header,
+                HeaderOrder::Declared,
+                &claims,
+                &signer
+            ));
+            assert_eq!(token.len(), token.capacity());
+
+            let compact = Compact::<ClaimsSet<Empty>, Empty>::new_encoded(&token);
+            assert_eq!(not_err!(compact.encoded()).encode(), token);
+        }
+    }
 }
@@ -155,6 +155,8 @@ impl SignedData {
     ///
     /// # Example
     /// ```
+    /// # #[cfg(feature = "ecdsa")]
+    /// # {
     /// use biscuit::jwa::SignatureAlgorithm;
     /// use biscuit::jws::{Header, RegisteredHeader, Secret, Signable, SignedData};
     /// use biscuit::Empty;
@@ -174,6 +176,7 @@ impl SignedData {
     ///     &ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref())?;
     /// let secret = Secret::EcdsaKeyPair(Arc::new(keypair));
     /// let signed = SignedData::sign(data, secret)?;
+    /// # }
     /// # Ok::<(), biscuit::errors::Error>(())
     /// ```
     pub fn sign(data: Signable, secret: Secret) -> Result<Self, Error> {
@@ -211,6 +214,8 @@ impl SignedData {
     ///
     /// # Example
     /// ```
+    /// # #[cfg(feature = "ecdsa")]
+    /// # {
     /// use biscuit::jwa::SignatureAlgorithm;
     /// use biscuit::jws::{Secret, SignedData};
     /// use data_encoding::HEXUPPER;
@@ -227,6 +232,7 @@ impl SignedData {
     ///     secret,
     ///     SignatureAlgorithm::ES256
     /// )?;
+    /// # }
     /// # Ok::<(), biscuit::errors::Error>(())
     /// ```
     pub fn verify_flattened(
@@ -235,9 +241,7 @@ impl SignedData {
         algorithm: SignatureAlgorithm,
     ) -> Result<Self, Error> {
         let raw: FlattenedRaw = serde_json::from_slice(data)?;
-        algorithm
-            .verify(&raw.signature, &raw.signing_input(), &secret)
-            .map_err(|_| ValidationError::InvalidSignature)?;
+        algorithm.verify(&raw.signature, &raw.signing_input(), &secret)?;
         let protected_header_registered: RegisteredHeader =
             serde_json::from_slice(&raw.protected_header)?;
         if protected_header_registered.algorithm != algorithm {
@@ -313,6 +317,7 @@ mod tests {
     use serde::{Deserialize, Serialize};
 
     use super::{Header, Secret, Signable, SignedData};
+    use crate::errors::{Error, ValidationError};
     use crate::jwa::SignatureAlgorithm;
     use crate::jws::RegisteredHeader;
     use crate::{ClaimsSet, CompactJson, CompactPart, Empty, RegisteredClaims, SingleOrMultiple};
@@ -334,7 +339,7 @@ mod tests {
     ddu3U4Ux3GFpOZHtc\"}";
 
     #[test]
-    fn flattened_jws_round_trip_none() {
+    fn flattened_jws_rejects_none() {
         let expected_value = not_err!(serde_json::to_value(
             "{\"protected\":\"eyJhbGciOiJub25lIiwidHlwIjoiSldUIn0\",\
             \"payload\":\"eyJpc3MiOiJodHRwczovL3d3dy5hY21lLmNvbS8iLCJz\
@@ -344,43 +349,24 @@ mod tests {
             \"signature\":\"\"}"
         ));
 
-        let expected_claims = ClaimsSet::<PrivateClaims> {
-            registered: RegisteredClaims {
-                issuer: Some(not_err!(FromStr::from_str("https://www.acme.com/"))),
-                subject: Some(not_err!(FromStr::from_str("John Doe"))),
-                audience: Some(SingleOrMultiple::Single(not_err!(FromStr::from_str(
-                    "https://acme-customer.com/"
-                )))),
-                not_before: Some(1234.into()),
+        let signable = not_err!(Signable::new::<Empty>(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::None,
                 ..Default::default()
-            },
-            private: PrivateClaims {
-                department: "Toilet Cleaning".to_string(),
-                company: "ACME".to_string(),
-            },
-        };
-
-        let expected_jwt = not_err!(SignedData::sign(
-            not_err!(Signable::new::<Empty>(
-                From::from(RegisteredHeader {
-                    algorithm: SignatureAlgorithm::None,
-                    ..Default::default()
-                }),
-                expected_claims.to_bytes().unwrap(),
-            )),
-            Secret::None,
+            }),
+            b"{}".to_vec(),
         ));
-        let token = expected_jwt.serialize_flattened();
-        assert_eq!(expected_value, not_err!(serde_json::to_value(&token)));
+        match SignedData::sign(signable, Secret::None) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
 
-        let biscuit: SignedData = not_err!(SignedData::verify_flattened(
-            token.as_bytes(),
-            Secret::None,
-            SignatureAlgorithm::None,
-        ));
-        let actual_claims: ClaimsSet<PrivateClaims> =
-            not_err!(biscuit.data().deserialize_json_payload());
-        assert_eq!(&expected_claims, &actual_claims);
+        let token = not_err!(serde_json::from_value::<String>(expected_value));
+        match SignedData::verify_flattened(token.as_bytes(), Secret::None, SignatureAlgorithm::None)
+        {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
     }
 
     #[test]
@@ -409,7 +395,7 @@ mod tests {
                 }),
                 expected_claims.to_bytes().unwrap(),
             )),
-            Secret::Bytes("secret".to_string().into_bytes())
+            Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
         ));
         let token = expected_jwt.serialize_flattened();
         assert_eq!(
@@ -419,7 +405,7 @@ mod tests {
 
         let biscuit = not_err!(SignedData::verify_flattened(
             token.as_bytes(),
-            Secret::Bytes("secret".to_string().into_bytes()),
+            Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256
         ));
         assert_eq!(
@@ -428,6 +414,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "rsa")]
     #[test]
     fn flattened_jws_round_trip_rs256() {
         let expected_value = not_err!(serde_json::to_value(
@@ -488,6 +475,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "ecdsa")]
     #[test]
     fn flattened_jws_verify_es256() {
         use data_encoding::HEXUPPER;
@@ -554,12 +542,12 @@ mod tests {
                 header.clone(),
                 expected_claims.to_bytes().unwrap()
             )),
-            Secret::Bytes("secret".to_string().into_bytes())
+            Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
         ));
         let token = expected_jwt.serialize_flattened();
         let biscuit = not_err!(SignedData::verify_flattened(
             token.as_bytes(),
-            Secret::Bytes("secret".to_string().into_bytes()),
+            Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256,
         ));
         assert_eq!(
@@ -578,12 +566,13 @@ mod tests {
              \"payload\":\"eyJzdWIiOiJiQGIuY29tIiwiY29tcGFueSI6IkFDTUUifQ\",\
              \"signature\":\"pKscJVk7-aHxfmQKlaZxh5uhuKhGMAa-1F5IX5mfUwI\"}"
                 .as_bytes(),
-            Secret::Bytes("secret".to_string().into_bytes()),
+            Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256,
         );
         let _ = claims.unwrap();
     }
 
+    #[cfg(feature = "rsa")]
     #[test]
     #[should_panic(expected = "InvalidSignature")]
     fn flattened_jws_decode_token_invalid_signature_rs256() {
@@ -607,7 +596,7 @@ mod tests {
              \"payload\":\"eyJzdWIiOiJiQGIuY29tIiwiY29tcGFueSI6IkFDTUUifQ\",\
              \"signature\":\"pKscJVk7-aHxfmQKlaZxh5uhuKhGMAa-1F5IX5mfUwI\"}"
                 .as_bytes(),
-            Secret::Bytes("secret".to_string().into_bytes()),
+            Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
             SignatureAlgorithm::HS256,
         );
         let _ = claims.unwrap();
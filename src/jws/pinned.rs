@@ -0,0 +1,105 @@
+use std::borrow::Cow;
+
+use data_encoding::BASE64URL_NOPAD;
+use serde::Deserialize;
+
+use crate::errors::{Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+
+/// The `alg` and `kid` headers that every token must have, when they are known in advance
+///
+/// Set in [`crate::ValidationOptions::pinned_header`] to have [`crate::decode`] reject tokens
+/// with any other `alg` or `kid` header early, by parsing only these two fields of the header,
+/// before the rest of the header and the claims are deserialized and the signature is verified.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PinnedHeader {
+    /// The expected `alg` header
+    pub algorithm: SignatureAlgorithm,
+    /// The expected `kid` header, or `None` to accept any `kid` header, or none
+    pub key_id: Option<String>,
+}
+
+/// The only fields of a header that are parsed to check it against a [`PinnedHeader`]
+#[derive(Deserialize)]
+struct AlgorithmAndKeyId<'a> {
+    #[serde(rename = "alg")]
+    algorithm: SignatureAlgorithm,
+    #[serde(rename = "kid", borrow, default)]
+    key_id: Option<Cow<'a, str>>,
+}
+
+impl PinnedHeader {
+    /// Check the base64url encoded `header` of a token
+    ///
+    /// Returns [`ValidationError::WrongAlgorithmHeader`] if the `alg` header is not `algorithm`,
+    /// and [`ValidationError::KeyNotFound`] if the `kid` header is not `key_id`.
+    pub fn check(&self, header: &str) -> Result<(), Error> {
+        let header = BASE64URL_NOPAD.decode(header.as_bytes())?;
+        let header: AlgorithmAndKeyId<'_> = serde_json::from_slice(&header)?;
+        if header.algorithm != self.algorithm {
+            Err(ValidationError::WrongAlgorithmHeader)?
+        }
+        if let Some(ref key_id) = self.key_id {
+            if header.key_id.as_deref() != Some(key_id.as_str()) {
+                Err(ValidationError::KeyNotFound)?
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jws::Secret;
+    use crate::{Empty, Token, Validation, ValidationOptions};
+
+    fn pinned(key_id: Option<&str>) -> PinnedHeader {
+        PinnedHeader {
+            algorithm: SignatureAlgorithm::HS256,
+            key_id: key_id.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn pinned_header_checks_algorithm_and_key_id() {
+        let header = BASE64URL_NOPAD.encode(br#"{"typ":"JWT","alg":"HS256","kid":"key-1"}"#);
+        not_err!(pinned(None).check(&header));
+        not_err!(pinned(Some("key-1")).check(&header));
+
+        match pinned(Some("key-2")).check(&header) {
+            Err(Error::ValidationError(ValidationError::KeyNotFound)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let header = BASE64URL_NOPAD.encode(br#"{"alg":"HS512"}"#);
+        match pinned(None).check(&header) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match pinned(Some("key-1")).check(&BASE64URL_NOPAD.encode(br#"{"alg":"HS256"}"#)) {
+            Err(Error::ValidationError(ValidationError::KeyNotFound)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_checks_pinned_header() {
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let token = not_err!(Token::builder()
+            .key_id("key-1")
+            .sign(&secret, SignatureAlgorithm::HS256));
+
+        let mut options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            pinned_header: Some(pinned(Some("key-1"))),
+            ..Default::default()
+        };
+        let _ = not_err!(crate::decode::<Empty>(&token, &secret, &options));
+
+        options.pinned_header = Some(pinned(Some("key-2")));
+        match crate::decode::<Empty>(&token, &secret, &options) {
+            Err(Error::ValidationError(ValidationError::KeyNotFound)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use ring::rand::SystemRandom;
+
+use super::Secret;
+use crate::errors::Error;
+use crate::jwa::{SignatureAlgorithm, SignatureBuffer, SigningKey};
+
+/// A secret prepared to sign with an algorithm, for issuing many tokens with the same key
+///
+/// Preparing the secret, such as setting up the HMAC key, happens once when the signer is
+/// created, and the random number generator is created then too. Unlike [`super::BatchSigner`],
+/// the header of each token can differ, as long as its algorithm is the algorithm of the signer.
+/// A `Signer` can be shared between threads.
+///
+/// # Examples
+/// ```
+/// use biscuit::{ClaimsSet, Empty, RegisteredClaims};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::{Compact, RegisteredHeader, Secret, Signer};
+///
+/// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+/// let signer = Signer::new(SignatureAlgorithm::HS256, &secret).unwrap();
+///
+/// for key_id in &["key-1", "key-2"] {
+///     let header = From::from(RegisteredHeader {
+///         algorithm: SignatureAlgorithm::HS256,
+///         key_id: Some(key_id.to_string()),
+///         ..Default::default()
+///     });
+///     let claims = ClaimsSet::<Empty> {
+///         registered: RegisteredClaims {
+///             subject: Some("John Doe".to_string()),
+///             ..Default::default()
+///         },
+///         private: Empty {},
+///     };
+///     let token = Compact::new_decoded(header, claims)
+///         .encode_with_signer(&signer)
+///         .unwrap();
+/// }
+/// ```
+pub struct Signer {
+    algorithm: SignatureAlgorithm,
+    key: SigningKey,
+    rng: SystemRandom,
+}
+
+impl Signer {
+    /// Prepare `secret` to sign with `algorithm`
+    ///
+    /// Returns the same errors as [`SignatureAlgorithm::sign`] if `secret` cannot be used with
+    /// `algorithm`.
+    pub fn new(algorithm: SignatureAlgorithm, secret: &Secret) -> Result<Self, Error> {
+        Ok(Signer {
+            algorithm,
+            key: SigningKey::new(algorithm, secret)?,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// The algorithm that this signer signs with
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+
+    /// The length of the signatures made by this signer, in bytes
+    pub(crate) fn signature_len(&self) -> usize {
+        self.key.signature_len()
+    }
+
+    /// Sign `data`, like [`SignatureAlgorithm::sign`]
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(self.sign_to_buffer(data)?.to_vec())
+    }
+
+    /// Sign `data` into a buffer on the stack
+    pub(crate) fn sign_to_buffer(&self, data: &[u8]) -> Result<SignatureBuffer, Error> {
+        self.key.sign(data, &self.rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ValidationError;
+    use crate::jws::{Compact, RegisteredHeader};
+    use crate::{ClaimsSet, Empty};
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn signer_signs_like_the_algorithm() {
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let signer = not_err!(Signer::new(SignatureAlgorithm::HS256, &secret));
+        assert_eq!(signer.algorithm(), SignatureAlgorithm::HS256);
+
+        for data in &[&b"first"[..], &b"second"[..]] {
+            let signature = not_err!(signer.sign(data));
+            assert_eq!(signature.len(), signer.signature_len());
+            assert_eq!(
+                signature,
+                not_err!(SignatureAlgorithm::HS256.sign(data, &secret))
+            );
+        }
+
+        let key_pair = not_err!(Secret::rsa_keypair_from_file(
+            "test/fixtures/rsa_private_key.der"
+        ));
+        assert!(Signer::new(SignatureAlgorithm::HS256, &key_pair).is_err());
+    }
+
+    #[test]
+    fn signer_rejects_headers_with_other_algorithms() {
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let signer = not_err!(Signer::new(SignatureAlgorithm::HS256, &secret));
+        let token = |algorithm| {
+            let header = From::from(RegisteredHeader {
+                algorithm,
+                ..Default::default()
+            });
+            Compact::<ClaimsSet<Empty>, Empty>::new_decoded(header, Default::default())
+        };
+
+        let encoded = not_err!(token(SignatureAlgorithm::HS256).encode_with_signer(&signer));
+        let expected = not_err!(token(SignatureAlgorithm::HS256).encode(&secret));
+        assert_eq!(
+            not_err!(encoded.encoded()).to_string(),
+            expected.unwrap_encoded().to_string()
+        );
+        match token(SignatureAlgorithm::HS512).encode_with_signer(&signer) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Incremental signing and verification of JWS with a [detached payload](https://tools.ietf.org/html/rfc7515#appendix-F),
+//! so that large payloads do not have to be held in memory
+//!
+//! Only the HMAC algorithms are supported, since ring can only sign and verify RSA and ECDSA
+//! signatures over a complete message.
+//!
+//! # Examples
+//! ```
+//! use biscuit::Empty;
+//! use biscuit::jwa::SignatureAlgorithm;
+//! use biscuit::jws::{Header, RegisteredHeader, Secret, StreamingSigner, StreamingVerifier};
+//!
+//! let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+//! let header = Header::<Empty>::from(RegisteredHeader {
+//!     algorithm: SignatureAlgorithm::HS256,
+//!     ..Default::default()
+//! });
+//!
+//! let mut signer = StreamingSigner::new(&header, &secret).unwrap();
+//! signer.update(b"A large ");
+//! signer.update(b"payload");
+//! let token = signer.finish();
+//!
+//! let mut verifier =
+//!     StreamingVerifier::<Empty>::new(&token, &secret, SignatureAlgorithm::HS256).unwrap();
+//! verifier.update(b"A large payload");
+//! let header = verifier.finish().unwrap();
+//! assert_eq!(header.registered.algorithm, SignatureAlgorithm::HS256);
+//! ```
+use data_encoding::BASE64URL_NOPAD;
+use ring::constant_time::verify_slices_are_equal;
+use ring::hmac;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{Header, Secret};
+use crate::errors::{DecodeError, Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+
+/// Signs a JWS with a detached payload that is provided in chunks
+///
+/// [`StreamingSigner::finish`] returns the JWS in compact form with an empty payload part,
+/// `<header>..<signature>`, which is sent along with the payload.
+pub struct StreamingSigner {
+    header: String,
+    input: SigningInput,
+}
+
+impl StreamingSigner {
+    /// Start signing with `secret` and the algorithm in `header`
+    ///
+    /// Returns [`Error::UnsupportedOperation`] if the algorithm is not an HMAC algorithm.
+    pub fn new<H: Serialize>(header: &Header<H>, secret: &Secret) -> Result<Self, Error> {
+        let algorithm = header.registered.algorithm;
+        let header = BASE64URL_NOPAD.encode(&serde_json::to_vec(header)?);
+        let input = SigningInput::new(&header, secret, algorithm)?;
+        Ok(StreamingSigner { header, input })
+    }
+
+    /// Add the next chunk of the payload
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.input.update(chunk);
+    }
+
+    /// Finish signing, and return the JWS in compact form with a detached payload
+    pub fn finish(self) -> String {
+        let signature = self.input.finish();
+        format!(
+            "{}..{}",
+            self.header,
+            BASE64URL_NOPAD.encode(signature.as_ref())
+        )
+    }
+}
+
+/// Verifies a JWS with a detached payload that is provided in chunks
+///
+/// The header can only be read once the signature is verified by [`StreamingVerifier::finish`].
+pub struct StreamingVerifier<H> {
+    header: Header<H>,
+    signature: Vec<u8>,
+    input: SigningInput,
+}
+
+impl<H: Serialize + DeserializeOwned> StreamingVerifier<H> {
+    /// Start verifying `token`, a JWS in compact form with a detached payload, with `secret`
+    ///
+    /// Returns [`ValidationError::WrongAlgorithmHeader`] if the header does not use `algorithm`,
+    /// and [`Error::UnsupportedOperation`] if `algorithm` is not an HMAC algorithm.
+    pub fn new(token: &str, secret: &Secret, algorithm: SignatureAlgorithm) -> Result<Self, Error> {
+        let compact = crate::Compact::decode(token);
+        if compact.len() != 3 {
+            Err(DecodeError::PartsLengthError {
+                actual: compact.len(),
+                expected: 3,
+            })?
+        }
+        if !compact.parts[1].is_empty() {
+            Err(DecodeError::InvalidToken)?
+        }
+
+        let header: Header<H> = compact.part(0)?;
+        if header.registered.algorithm != algorithm {
+            Err(ValidationError::WrongAlgorithmHeader)?
+        }
+        Ok(StreamingVerifier {
+            header,
+            signature: compact.part(2)?,
+            input: SigningInput::new(&compact.parts[0], secret, algorithm)?,
+        })
+    }
+
+    /// Add the next chunk of the payload
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.input.update(chunk);
+    }
+
+    /// Finish verifying, and return the header if the signature is valid
+    pub fn finish(self) -> Result<Header<H>, Error> {
+        let signature = self.input.finish();
+        verify_slices_are_equal(&self.signature, signature.as_ref())
+            .map_err(|_| ValidationError::InvalidSignature)?;
+        Ok(self.header)
+    }
+}
+
+/// The HMAC of the signing input, `<header>.<payload>`, with the payload base64url encoded as it
+/// arrives
+struct SigningInput {
+    context: hmac::Context,
+    /// Payload bytes that do not fill a whole base64 quantum of 3 bytes yet
+    remainder: Vec<u8>,
+}
+
+impl SigningInput {
+    fn new(
+        encoded_header: &str,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, Error> {
+        let secret = secret.hmac_bytes(algorithm)?;
+        let algorithm = match algorithm {
+            SignatureAlgorithm::HS256 => hmac::HMAC_SHA256,
+            SignatureAlgorithm::HS384 => hmac::HMAC_SHA384,
+            SignatureAlgorithm::HS512 => hmac::HMAC_SHA512,
+            _ => Err(Error::UnsupportedOperation)?,
+        };
+
+        let mut context = hmac::Context::with_key(&hmac::Key::new(algorithm, secret));
+        context.update(encoded_header.as_bytes());
+        context.update(b".");
+        Ok(SigningInput {
+            context,
+            remainder: Vec::with_capacity(3),
+        })
+    }
+
+    fn update(&mut self, mut chunk: &[u8]) {
+        if !self.remainder.is_empty() {
+            let needed = (3 - self.remainder.len()).min(chunk.len());
+            self.remainder.extend_from_slice(&chunk[..needed]);
+            chunk = &chunk[needed..];
+            if self.remainder.len() < 3 {
+                return;
+            }
+            self.context
+                .update(BASE64URL_NOPAD.encode(&self.remainder).as_bytes());
+            self.remainder.clear();
+        }
+
+        let whole = chunk.len() - chunk.len() % 3;
+        self.context
+            .update(BASE64URL_NOPAD.encode(&chunk[..whole]).as_bytes());
+        self.remainder.extend_from_slice(&chunk[whole..]);
+    }
+
+    fn finish(mut self) -> hmac::Tag {
+        self.context
+            .update(BASE64URL_NOPAD.encode(&self.remainder).as_bytes());
+        self.context.sign()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jws::{Compact, RegisteredHeader};
+    use crate::Empty;
+
+    fn header(algorithm: SignatureAlgorithm) -> Header<Empty> {
+        From::from(RegisteredHeader {
+            algorithm,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn streaming_signature_matches_compact_signature() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let payload: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        let compact = not_err!(Compact::<Vec<u8>, Empty>::new_decoded(
+            header(SignatureAlgorithm::HS256),
+            payload.clone()
+        )
+        .into_encoded(&secret))
+        .unwrap_encoded();
+        let mut detached = compact.parts.clone();
+        detached[1] = not_err!("".parse());
+        let detached = crate::Compact { parts: detached }.encode();
+
+        for chunk_size in 1..8 {
+            let mut signer = not_err!(StreamingSigner::new(
+                &header(SignatureAlgorithm::HS256),
+                &secret
+            ));
+            for chunk in payload.chunks(chunk_size) {
+                signer.update(chunk);
+            }
+            assert_eq!(signer.finish(), detached);
+
+            let mut verifier = not_err!(StreamingVerifier::<Empty>::new(
+                &detached,
+                &secret,
+                SignatureAlgorithm::HS256
+            ));
+            for chunk in payload.chunks(chunk_size) {
+                verifier.update(chunk);
+            }
+            let _ = not_err!(verifier.finish());
+        }
+    }
+
+    #[test]
+    fn streaming_verifier_rejects_invalid_tokens() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let mut signer = not_err!(StreamingSigner::new(
+            &header(SignatureAlgorithm::HS256),
+            &secret
+        ));
+        signer.update(b"payload");
+        let token = signer.finish();
+
+        let mut verifier = not_err!(StreamingVerifier::<Empty>::new(
+            &token,
+            &secret,
+            SignatureAlgorithm::HS256
+        ));
+        verifier.update(b"tampered");
+        match verifier.finish() {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        match StreamingVerifier::<Empty>::new(&token, &secret, SignatureAlgorithm::HS384) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+        match StreamingSigner::new(&header(SignatureAlgorithm::RS256), &secret) {
+            Err(Error::UnsupportedOperation) => {}
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+    }
+}
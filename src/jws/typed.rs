@@ -0,0 +1,285 @@
+//! Typestate representation of compact JWS
+//!
+//! [`Compact`] checks at runtime whether a token is encoded or decoded, so reading the payload of
+//! a token that was never verified is only caught when the program runs. The types in this module
+//! split the two states so that this is caught by the compiler instead: an [`Encoded`] token only
+//! exposes its payload by verifying its signature, which produces a [`Decoded`] token.
+//!
+//! # Examples
+//! ```
+//! use biscuit::{ClaimsSet, Empty, RegisteredClaims};
+//! use biscuit::jwa::SignatureAlgorithm;
+//! use biscuit::jws::{RegisteredHeader, Secret};
+//! use biscuit::jws::typed::{Decoded, Encoded};
+//!
+//! let secret = Secret::Bytes("secret that is at least 32 bytes long".to_string().into_bytes());
+//! let decoded = Decoded::<ClaimsSet<Empty>, Empty>::new(
+//!     From::from(RegisteredHeader {
+//!         algorithm: SignatureAlgorithm::HS256,
+//!         ..Default::default()
+//!     }),
+//!     ClaimsSet {
+//!         registered: RegisteredClaims {
+//!             issuer: Some("https://www.acme.com".to_string()),
+//!             ..Default::default()
+//!         },
+//!         private: Empty {},
+//!     },
+//! );
+//! let token = decoded.encode(&secret).unwrap().to_string();
+//!
+//! let encoded = Encoded::<ClaimsSet<Empty>, Empty>::new(&token);
+//! // `encoded.payload()` does not compile
+//! let decoded = encoded.decode(&secret, SignatureAlgorithm::HS256).unwrap();
+//! assert_eq!(
+//!     decoded.payload().registered.issuer.as_deref(),
+//!     Some("https://www.acme.com")
+//! );
+//! ```
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{Compact, Header, Secret};
+use crate::errors::Error;
+use crate::jwa::SignatureAlgorithm;
+use crate::jwk::JWKSet;
+use crate::CompactPart;
+
+/// An encoded compact JWS whose signature has not been verified, with a payload of type `T` and
+/// additional header fields of type `H`
+///
+/// The payload can only be read by verifying the signature with [`Encoded::decode`] or
+/// [`Encoded::decode_with_jwks`].
+///
+/// ```compile_fail
+/// use biscuit::{ClaimsSet, Empty};
+/// use biscuit::jws::typed::Encoded;
+///
+/// let encoded = Encoded::<ClaimsSet<Empty>, Empty>::new("e30.e30.");
+/// let claims = encoded.payload();
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Encoded<T, H> {
+    token: crate::Compact,
+    _marker: PhantomData<(T, H)>,
+}
+
+/// A decoded compact JWS, either constructed to be signed or verified from an [`Encoded`] token
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Decoded<T, H> {
+    header: Header<H>,
+    payload: T,
+}
+
+impl<T, H> Encoded<T, H>
+where
+    T: CompactPart,
+    H: Serialize + DeserializeOwned,
+{
+    /// Parse an encoded token
+    pub fn new(token: &str) -> Self {
+        crate::Compact::decode(token).into()
+    }
+
+    /// The encoded parts of the token
+    pub fn as_compact(&self) -> &crate::Compact {
+        &self.token
+    }
+
+    /// Consumes self and returns the encoded parts of the token
+    pub fn into_compact(self) -> crate::Compact {
+        self.token
+    }
+
+    /// Verify the signature of the token with `secret` and decode it, like [`Compact::decode`]
+    pub fn decode(
+        &self,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Decoded<T, H>, Error> {
+        self.to_untyped()
+            .decode(secret, algorithm)?
+            .try_into_typed()
+    }
+
+    /// Verify the signature of the token with a key from `jwks` and decode it, like
+    /// [`Compact::decode_with_jwks`]
+    pub fn decode_with_jwks<J>(
+        &self,
+        jwks: &JWKSet<J>,
+        expected_algorithm: Option<SignatureAlgorithm>,
+    ) -> Result<Decoded<T, H>, Error> {
+        self.to_untyped()
+            .decode_with_jwks(jwks, expected_algorithm)?
+            .try_into_typed()
+    }
+
+    /// Without verifying the token, retrieve a copy of the header. This is usually needed to find
+    /// the key to verify the token with.
+    ///
+    /// ## Warning
+    /// Use this at your own risk. It is not advisable to trust unverified content.
+    pub fn unverified_header(&self) -> Result<Header<H>, Error> {
+        self.token.part(0)
+    }
+
+    fn to_untyped(&self) -> Compact<T, H> {
+        Compact::Encoded(self.token.clone())
+    }
+}
+
+impl<T, H> From<crate::Compact> for Encoded<T, H> {
+    fn from(token: crate::Compact) -> Self {
+        Encoded {
+            token,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, H> fmt::Display for Encoded<T, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.token, f)
+    }
+}
+
+impl<T, H> Decoded<T, H>
+where
+    T: CompactPart,
+    H: Serialize + DeserializeOwned,
+{
+    /// New decoded token, which can be signed with [`Decoded::encode`]
+    pub fn new(header: Header<H>, payload: T) -> Self {
+        Decoded { header, payload }
+    }
+
+    /// The header of the token
+    pub fn header(&self) -> &Header<H> {
+        &self.header
+    }
+
+    /// A mutable reference to the header of the token
+    pub fn header_mut(&mut self) -> &mut Header<H> {
+        &mut self.header
+    }
+
+    /// The payload of the token
+    pub fn payload(&self) -> &T {
+        &self.payload
+    }
+
+    /// A mutable reference to the payload of the token
+    pub fn payload_mut(&mut self) -> &mut T {
+        &mut self.payload
+    }
+
+    /// Consumes self and returns the header and the payload
+    pub fn into_parts(self) -> (Header<H>, T) {
+        (self.header, self.payload)
+    }
+
+    /// Sign the token with `secret` and the algorithm in the header, like [`Compact::encode`]
+    pub fn encode(&self, secret: &Secret) -> Result<Encoded<T, H>, Error> {
+        let mut compact = crate::Compact::with_capacity(3);
+        compact.push(&self.header)?;
+        compact.push(&self.payload)?;
+        let signature = self
+            .header
+            .registered
+            .algorithm
+            .sign(compact.encode().as_bytes(), secret)?;
+        compact.push(&signature)?;
+        Ok(compact.into())
+    }
+}
+
+impl<P, H> Decoded<crate::ClaimsSet<P>, H>
+where
+    crate::ClaimsSet<P>: CompactPart,
+    P: Serialize,
+    H: Serialize + DeserializeOwned,
+{
+    /// Validate the header and the claims of the token, like [`Compact::validate`]
+    pub fn validate(&self, options: crate::ValidationOptions) -> Result<(), Error> {
+        self.header.registered.validate(&options)?;
+        self.payload.validate(options)
+    }
+}
+
+impl<T, H> From<Encoded<T, H>> for Compact<T, H> {
+    fn from(token: Encoded<T, H>) -> Self {
+        Compact::Encoded(token.token)
+    }
+}
+
+impl<T, H> From<Decoded<T, H>> for Compact<T, H> {
+    fn from(token: Decoded<T, H>) -> Self {
+        Compact::Decoded {
+            header: token.header,
+            payload: token.payload,
+        }
+    }
+}
+
+impl<T, H> Compact<T, H> {
+    /// Convert a decoded token into its typestate representation, or return
+    /// [`Error::UnsupportedOperation`] if it is encoded
+    fn try_into_typed(self) -> Result<Decoded<T, H>, Error> {
+        match self {
+            Compact::Decoded { header, payload } => Ok(Decoded { header, payload }),
+            Compact::Encoded(_) => Err(Error::UnsupportedOperation),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ValidationError;
+    use crate::jws::RegisteredHeader;
+    use crate::{ClaimsSet, Empty, RegisteredClaims, Validation, ValidationOptions};
+
+    type Claims = ClaimsSet<Empty>;
+
+    #[test]
+    fn typed_round_trip() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let decoded = Decoded::<Claims, Empty>::new(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            ClaimsSet {
+                registered: RegisteredClaims {
+                    issuer: Some("https://www.acme.com".to_string()),
+                    ..Default::default()
+                },
+                private: Empty {},
+            },
+        );
+        let encoded = not_err!(decoded.encode(&secret));
+
+        // Both representations produce the same token
+        let untyped = not_err!(Compact::from(decoded.clone()).into_encoded(&secret));
+        assert_eq!(untyped.unwrap_encoded().to_string(), encoded.to_string());
+
+        let header = not_err!(encoded.unverified_header());
+        assert_eq!(header.registered.algorithm, SignatureAlgorithm::HS256);
+
+        let verified = not_err!(encoded.decode(&secret, SignatureAlgorithm::HS256));
+        assert_eq!(verified, decoded);
+        not_err!(verified.validate(ValidationOptions {
+            issuer: Validation::Validate("https://www.acme.com".to_string()),
+            ..Default::default()
+        }));
+
+        let other_secret = Secret::Bytes("other".to_string().into_bytes()).danger_allow_weak_keys();
+        match encoded.decode(&other_secret, SignatureAlgorithm::HS256) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
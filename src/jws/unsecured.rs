@@ -0,0 +1,215 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::Header;
+use crate::errors::{DecodeError, Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+use crate::{ClaimsSet, ValidationOptions};
+
+/// An [unsecured JWT](https://tools.ietf.org/html/rfc7519#section-6), with the `none` algorithm
+/// and no signature
+///
+/// Unsecured JWTs can be forged by anyone, so they should only be used when the integrity of the
+/// token is protected by other means. This type keeps them apart from signed tokens: it can only
+/// be constructed with [`UnsecuredJwt::danger_new`] or [`UnsecuredJwt::danger_decode`], and
+/// [`UnsecuredJwt::danger_decode`] only accepts tokens with the `none` algorithm.
+///
+/// # Examples
+/// ```
+/// use biscuit::{ClaimsSet, Empty, RegisteredClaims};
+/// use biscuit::jws::{RegisteredHeader, UnsecuredJwt};
+///
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims {
+///         issuer: Some("https://www.acme.com".to_string()),
+///         ..Default::default()
+///     },
+///     private: Empty {},
+/// };
+/// let token = UnsecuredJwt::danger_new(From::from(RegisteredHeader::default()), claims)
+///     .encode()
+///     .unwrap();
+/// assert!(token.ends_with('.'));
+///
+/// let decoded = UnsecuredJwt::<Empty, Empty>::danger_decode(&token).unwrap();
+/// assert_eq!(
+///     decoded.claims().registered.issuer.as_deref(),
+///     Some("https://www.acme.com")
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsecuredJwt<T, H> {
+    header: Header<H>,
+    claims: ClaimsSet<T>,
+}
+
+impl<T, H> UnsecuredJwt<T, H>
+where
+    T: Serialize + DeserializeOwned,
+    H: Serialize + DeserializeOwned,
+{
+    /// Create an unsecured JWT. The algorithm in `header` is replaced with `none`.
+    pub fn danger_new(mut header: Header<H>, claims: ClaimsSet<T>) -> Self {
+        header.registered.algorithm = SignatureAlgorithm::None;
+        UnsecuredJwt { header, claims }
+    }
+
+    /// Decode an unsecured JWT
+    ///
+    /// Returns [`ValidationError::WrongAlgorithmHeader`] if the token does not use the `none`
+    /// algorithm, and [`ValidationError::InvalidSignature`] if it has a signature.
+    pub fn danger_decode(token: &str) -> Result<Self, Error> {
+        let compact = crate::Compact::decode(token);
+        if compact.len() != 3 {
+            Err(DecodeError::PartsLengthError {
+                actual: compact.len(),
+                expected: 3,
+            })?
+        }
+
+        let header: Header<H> = compact.part(0)?;
+        if header.registered.algorithm != SignatureAlgorithm::None {
+            Err(ValidationError::WrongAlgorithmHeader)?
+        }
+        let signature: Vec<u8> = compact.part(2)?;
+        check_signature(&signature)?;
+
+        Ok(UnsecuredJwt {
+            header,
+            claims: compact.part(1)?,
+        })
+    }
+
+    /// Encode the token in its compact form, with an empty signature
+    pub fn encode(&self) -> Result<String, Error> {
+        let mut compact = crate::Compact::with_capacity(3);
+        compact.push(&self.header)?;
+        compact.push(&self.claims)?;
+        compact.push(&Vec::<u8>::new())?;
+        Ok(compact.encode())
+    }
+
+    /// The header of the token
+    pub fn header(&self) -> &Header<H> {
+        &self.header
+    }
+
+    /// The claims set of the token
+    pub fn claims(&self) -> &ClaimsSet<T> {
+        &self.claims
+    }
+
+    /// Consumes self and returns the header and the claims set
+    pub fn into_parts(self) -> (Header<H>, ClaimsSet<T>) {
+        (self.header, self.claims)
+    }
+
+    /// Validate the header and the claims of the token, like [`super::Compact::validate`]
+    ///
+    /// Unsecured tokens are only accepted if `options.allow_unsecured_tokens` is set, and the
+    /// `none` algorithm must then be accepted by `options.algorithms` if it is validated.
+    pub fn validate(&self, options: ValidationOptions) -> Result<(), Error> {
+        self.header.registered.validate(&options)?;
+        self.claims.validate(options)
+    }
+}
+
+/// Check that an unsecured JWT has no signature
+///
+/// This is how [`crate::decode`] verifies tokens with the `none` algorithm, once the options
+/// have allowed unsecured tokens.
+pub(crate) fn check_signature(signature: &[u8]) -> Result<(), Error> {
+    if signature.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidSignature)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jws::{self, RegisteredHeader, Secret};
+    use crate::{CompactPart, Empty, RegisteredClaims, Validation};
+
+    fn claims() -> ClaimsSet<Empty> {
+        ClaimsSet {
+            registered: RegisteredClaims {
+                subject: Some("John Doe".to_string()),
+                ..Default::default()
+            },
+            private: Empty {},
+        }
+    }
+
+    #[test]
+    fn unsecured_jwt_round_trip() {
+        let token = UnsecuredJwt::danger_new(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            claims(),
+        );
+        assert_eq!(
+            token.header().registered.algorithm,
+            SignatureAlgorithm::None
+        );
+
+        let encoded = not_err!(token.encode());
+        let decoded = not_err!(UnsecuredJwt::<Empty, Empty>::danger_decode(&encoded));
+        assert_eq!(decoded, token);
+
+        match decoded.validate(ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::None]),
+            ..Default::default()
+        }) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match decoded.validate(ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            allow_unsecured_tokens: true,
+            ..Default::default()
+        }) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        not_err!(decoded.validate(ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::None]),
+            allow_unsecured_tokens: true,
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn unsecured_jwt_rejects_signed_tokens() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let signed = not_err!(jws::Compact::<ClaimsSet<Empty>, Empty>::new_decoded(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            claims(),
+        )
+        .into_encoded(&secret))
+        .unwrap_encoded();
+
+        match UnsecuredJwt::<Empty, Empty>::danger_decode(&signed.to_string()) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        // A `none` header with a signature
+        let mut parts: Vec<String> = signed.to_string().split('.').map(str::to_string).collect();
+        let header = Header::<Empty>::from(RegisteredHeader {
+            algorithm: SignatureAlgorithm::None,
+            ..Default::default()
+        });
+        parts[0] = not_err!(header.to_base64()).unwrap();
+        match UnsecuredJwt::<Empty, Empty>::danger_decode(&parts.join(".")) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
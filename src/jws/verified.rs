@@ -0,0 +1,189 @@
+use serde::de::{Deserialize, DeserializeOwned};
+use serde::Serialize;
+use serde_json::value::RawValue;
+
+use super::{compact, Header, Secret};
+use crate::errors::Error;
+use crate::jwa::SignatureAlgorithm;
+
+/// The payload of a compact JWS whose signature has been verified, kept as the decoded bytes
+///
+/// Unlike [`super::Compact::decode`], which deserializes the payload into an owned type, the
+/// payload can be deserialized into types that borrow from it with
+/// [`VerifiedPayload::deserialize`]. Strings in the payload that contain escape sequences
+/// cannot be borrowed, so use `Cow<'a, str>` with `#[serde(borrow)]` for claims that might.
+///
+/// Registered claims are owned by [`crate::RegisteredClaims`], so the claims of a borrowed type
+/// must be validated by the caller, or by deserializing a [`crate::ClaimsSet`] as well.
+///
+/// # Examples
+/// ```
+/// use std::borrow::Cow;
+///
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::{Secret, VerifiedPayload};
+/// use biscuit::{Compact, Empty};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct BorrowedClaims<'a> {
+///     #[serde(borrow)]
+///     sub: Cow<'a, str>,
+///     company: &'a str,
+/// }
+///
+/// let token = Compact::decode(
+///     "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+///      eyJpc3MiOiJodHRwczovL3d3dy5hY21lLmNvbS8iLCJzdWIiOiJKb2huIERvZSIsImF1ZCI6Imh0dHBzOi8vYWNtZ\
+///      S1jdXN0b21lci5jb20vIiwibmJmIjoxMjM0LCJjb21wYW55IjoiQUNNRSIsImRlcGFydG1lbnQiOiJUb2lsZXQgQ2\
+///      xlYW5pbmcifQ.VFCl2un1Kc17odzOe2Ehf4DVrWddu3U4Ux3GFpOZHtc",
+/// );
+/// let payload = VerifiedPayload::<Empty>::decode(
+///     &token,
+///     // The token was signed with a secret that is too short for HS256
+///     &Secret::bytes_from_str("secret").danger_allow_weak_keys(),
+///     SignatureAlgorithm::HS256,
+/// )
+/// .unwrap();
+///
+/// let claims: BorrowedClaims = payload.deserialize().unwrap();
+/// assert_eq!(claims.sub, "John Doe");
+/// assert_eq!(claims.company, "ACME");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerifiedPayload<H> {
+    header: Header<H>,
+    payload: Vec<u8>,
+}
+
+impl<H: Serialize + DeserializeOwned> VerifiedPayload<H> {
+    /// Verify the signature of `token` with `secret` and decode its payload, checking the header
+    /// like [`super::Compact::decode`]
+    pub fn decode(
+        token: &crate::Compact,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, Error> {
+        let header = compact::verify(token, secret, algorithm)?;
+        Ok(VerifiedPayload {
+            header,
+            payload: token.part(1)?,
+        })
+    }
+
+    /// Verify and decode `token` like [`VerifiedPayload::decode`], borrowing its parts instead
+    /// of splitting it into an encoded `Compact` first
+    pub fn decode_str(
+        token: &str,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, Error> {
+        let (header, parts) = compact::verify_str(token, secret, algorithm)?;
+        Ok(VerifiedPayload {
+            header,
+            payload: parts.payload()?,
+        })
+    }
+}
+
+impl<H> VerifiedPayload<H> {
+    /// The header of the token
+    pub fn header(&self) -> &Header<H> {
+        &self.header
+    }
+
+    /// The decoded bytes of the payload
+    pub fn bytes(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// The payload as raw JSON, which is only checked to be valid JSON, deferring its
+    /// deserialization until it is needed
+    pub fn raw_json(&self) -> Result<&RawValue, Error> {
+        Ok(serde_json::from_slice(&self.payload)?)
+    }
+
+    /// Deserialize the payload from JSON into `T`, which may borrow from the payload
+    pub fn deserialize<'a, T: Deserialize<'a>>(&'a self) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.payload)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::errors::ValidationError;
+    use crate::jws::RegisteredHeader;
+    use crate::{ClaimsSet, Empty, RegisteredClaims};
+
+    #[derive(Deserialize)]
+    struct BorrowedClaims<'a> {
+        #[serde(borrow)]
+        iss: Cow<'a, str>,
+        #[serde(borrow)]
+        sub: Cow<'a, str>,
+    }
+
+    #[test]
+    fn verified_payload_borrows_claims() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(
+            super::super::Compact::<ClaimsSet<Empty>, Empty>::new_decoded(
+                From::from(RegisteredHeader {
+                    algorithm: SignatureAlgorithm::HS256,
+                    ..Default::default()
+                }),
+                ClaimsSet {
+                    registered: RegisteredClaims {
+                        issuer: Some("https://www.acme.com".to_string()),
+                        subject: Some("\"John\" Doe".to_string()),
+                        ..Default::default()
+                    },
+                    private: Empty {},
+                },
+            )
+            .into_encoded(&secret)
+        )
+        .unwrap_encoded();
+
+        let payload = not_err!(VerifiedPayload::<Empty>::decode(
+            &token,
+            &secret,
+            SignatureAlgorithm::HS256
+        ));
+        assert_eq!(
+            payload.header().registered.algorithm,
+            SignatureAlgorithm::HS256
+        );
+
+        let claims: BorrowedClaims = not_err!(payload.deserialize());
+        if let Cow::Owned(_) = claims.iss {
+            panic!("Claim is copied");
+        }
+        assert_eq!(claims.iss, "https://www.acme.com");
+        // Escaped strings are copied
+        if let Cow::Borrowed(_) = claims.sub {
+            panic!("Escaped claim is borrowed");
+        }
+        assert_eq!(claims.sub, "\"John\" Doe");
+
+        let raw = not_err!(payload.raw_json());
+        assert_eq!(raw.get().as_bytes(), payload.bytes());
+
+        let owned: ClaimsSet<Empty> = not_err!(payload.deserialize());
+        assert_eq!(
+            owned.registered.issuer.as_deref(),
+            Some("https://www.acme.com")
+        );
+
+        let other_secret = Secret::Bytes("other".to_string().into_bytes()).danger_allow_weak_keys();
+        match VerifiedPayload::<Empty>::decode(&token, &other_secret, SignatureAlgorithm::HS256) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
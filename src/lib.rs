@@ -28,12 +28,30 @@
 //!
 //! See [`JWT`] for common usage examples.
 //!
+//! ## Cargo Features
+//! The `rsa`, `ecdsa` and `jwe` features are enabled by default. Services that only use the HMAC
+//! algorithms can disable them to build less code:
+//!
+//! ```toml
+//! biscuit = { version = "0.6.0-beta1", default-features = false }
+//! ```
+//!
+//! Signing or verifying with an algorithm whose feature is disabled returns
+//! [`errors::Error::UnsupportedOperation`].
+//!
 //! ## Supported Features
 //! The crate does not support all, and probably will never support all of
 //! the features described in the various RFCs, including some algorithms and verification.
 //!
 //! See the [documentation](https://github.com/lawliet89/biscuit/blob/master/doc/supported.md) for more information.
 //!
+//! ## WebAssembly
+//! The crate builds for `wasm32-unknown-unknown`. The system clock is read from JavaScript, and
+//! ring draws random numbers from the Web Crypto API. Keys cannot be loaded from files, so use the
+//! constructors that take bytes, such as [`jws::Secret::rsa_keypair_from_der`], or a
+//! [`jwk::JWKSet`] fetched by the application. [`verify_batch`] verifies tokens on the current
+//! thread, since there are no threads to spawn.
+//!
 //! ## References
 //! - [JWT Handbook](https://auth0.com/e-books/jwt-handbook) — great introduction to JWT
 //! - [IANA JOSE Registry](https://www.iana.org/assignments/jose/jose.xhtml)
@@ -103,39 +121,63 @@
 // See regression in nightly: https://github.com/rust-lang/rust/issues/70814
 #![cfg_attr(feature = "strict", allow(unused_braces))]
 
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
+use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display};
-use std::iter;
 use std::ops::Deref;
 use std::str::{self, FromStr};
+use std::sync::Arc;
 
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use data_encoding::BASE64URL_NOPAD;
 use serde::de::{self, DeserializeOwned};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-mod helpers;
-pub use crate::helpers::*;
-
 #[cfg(test)]
 #[macro_use]
 mod test;
 
+mod helpers;
+pub use crate::helpers::*;
+
+#[cfg(feature = "derive")]
+pub use biscuit_derive::{claims, Claims};
+
 #[macro_use]
 mod serde_custom;
+pub use crate::serde_custom::{numeric_date, option_numeric_date};
 
 #[macro_use]
 mod macros;
 
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod cookie;
+#[cfg(feature = "cwt")]
+pub mod cwt;
+pub mod document;
 pub mod errors;
 pub mod jwa;
+#[cfg(feature = "jwe")]
 pub mod jwe;
 pub mod jwk;
 pub mod jws;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod oauth;
+pub mod oidc;
+pub mod providers;
+pub mod sd_jwt;
+pub mod secevent;
+pub mod spiffe;
 
 pub mod digest;
 
-use crate::errors::{Error, ValidationError};
+mod inspect;
+pub use crate::inspect::{inspect, TokenReport};
+
+use crate::errors::{DecodeError, Error, ValidationError, ValidationReport};
+use crate::jwa::SignatureAlgorithm;
 
 /// A convenience type alias of the common "JWT" which is a secured/unsecured compact JWS.
 /// Type `T` is the type of the private claims, and type `H` is the type of private header fields
@@ -158,7 +200,8 @@ use crate::errors::{Error, ValidationError};
 ///     department: String,
 /// }
 ///
-/// let signing_secret = Secret::Bytes("secret".to_string().into_bytes());
+/// // The expected token was signed with a secret that is too short for HS256
+/// let signing_secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
 ///
 /// let expected_token = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
 ///        eyJpc3MiOiJodHRwczovL3d3dy5hY21lLmNvbS8iLCJzdWIiOiJKb2huIERvZSIsImF1ZCI6Imh0dHBzOi8vYWNtZ\
@@ -261,7 +304,7 @@ pub type JWT<T, H> = jws::Compact<ClaimsSet<T>, H>;
 /// );
 ///
 /// let jws = expected_jwt
-///     .into_encoded(&Secret::Bytes("secret".to_string().into_bytes()))
+///     .into_encoded(&Secret::Bytes("secret that is at least 32 bytes long".to_string().into_bytes()))
 ///     .unwrap();
 ///
 /// // Encrypt the token
@@ -318,8 +361,407 @@ pub type JWT<T, H> = jws::Compact<ClaimsSet<T>, H>;
 /// nonce_counter = nonce_counter + 1u8;
 /// # }
 /// ```
+#[cfg(feature = "jwe")]
 pub type JWE<T, H, I> = jwe::Compact<JWT<T, H>, I>;
 
+/// Sign `claims` with `secret` and the algorithm in `header`, returning the compact JWS
+///
+/// This is the counterpart of [`decode`], equivalent to encoding a [`JWT`] constructed with
+/// [`jws::Compact::new_decoded`].
+///
+/// # Examples
+/// ```
+/// use biscuit::{decode, encode, ClaimsSet, RegisteredClaims, Validation, ValidationOptions};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::{RegisteredHeader, Secret};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct PrivateClaims {
+///     company: String,
+/// }
+///
+/// let secret = Secret::Bytes("secret that is at least 32 bytes long".to_string().into_bytes());
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims {
+///         issuer: Some("https://www.acme.com".to_string()),
+///         ..Default::default()
+///     },
+///     private: PrivateClaims {
+///         company: "ACME".to_string(),
+///     },
+/// };
+/// let header = RegisteredHeader {
+///     algorithm: SignatureAlgorithm::HS256,
+///     ..Default::default()
+/// };
+///
+/// let token = encode(From::from(header), claims, &secret).unwrap();
+///
+/// let options = ValidationOptions {
+///     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+///     ..Default::default()
+/// };
+/// let claims = decode::<PrivateClaims>(&token, &secret, &options).unwrap();
+/// assert_eq!(claims.private.company, "ACME");
+/// ```
+pub fn encode<T, H>(
+    header: jws::Header<H>,
+    claims: ClaimsSet<T>,
+    secret: &jws::Secret,
+) -> Result<String, Error>
+where
+    T: Serialize + DeserializeOwned,
+    H: Serialize + DeserializeOwned,
+{
+    Ok(jws::Compact::new_decoded(header, claims)
+        .into_encoded(secret)?
+        .unwrap_encoded()
+        .to_string())
+}
+
+/// Verify the signature of a compact JWS `token` with `secret`, validate it according to
+/// `options`, and deserialize its claims set
+///
+/// The token must be signed with one of the algorithms accepted by `options.algorithms`, which is
+/// required so that the algorithm is never chosen by the token alone. Returns
+/// [`ValidationError::MissingAlgorithm`] if no algorithm is accepted, and
+/// [`ValidationError::WrongAlgorithmHeader`] if the token was signed with any other algorithm.
+///
+/// The header is validated along with the claims, like [`jws::Compact::validate`] does.
+///
+/// # Examples
+/// ```
+/// use biscuit::{decode, Validation, ValidationOptions};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct PrivateClaims {
+///     company: String,
+///     department: String,
+/// }
+///
+/// let token = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+///      eyJpc3MiOiJodHRwczovL3d3dy5hY21lLmNvbS8iLCJzdWIiOiJKb2huIERvZSIsImF1ZCI6Imh0dHBzOi8vYWNtZ\
+///      S1jdXN0b21lci5jb20vIiwibmJmIjoxMjM0LCJjb21wYW55IjoiQUNNRSIsImRlcGFydG1lbnQiOiJUb2lsZXQgQ2\
+///      xlYW5pbmcifQ.VFCl2un1Kc17odzOe2Ehf4DVrWddu3U4Ux3GFpOZHtc";
+/// let options = ValidationOptions {
+///     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+///     ..Default::default()
+/// };
+///
+/// let claims = decode::<PrivateClaims>(
+///     token,
+///     // The token was signed with a secret that is too short for HS256
+///     &Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys(),
+///     &options,
+/// )
+/// .unwrap();
+/// assert_eq!(claims.private.company, "ACME");
+/// ```
+pub fn decode<T>(
+    token: &str,
+    secret: &jws::Secret,
+    options: &ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    record_verification(
+        options.metrics.as_deref(),
+        &*options.temporal_options.clock,
+        || {
+            let (header, claims) = verify_signature::<ClaimsSet<T>>(token, secret, options)?;
+            header.registered.validate(options)?;
+            claims.validate(options.clone())?;
+            Ok((header.registered.algorithm, claims))
+        },
+    )
+}
+
+/// Decode and validate a token like [`decode`], into a struct of custom claims that implements
+/// [`Claims`], such as one deriving it with the `derive` feature
+///
+/// # Examples
+/// ```
+/// use biscuit::{decode_claims, Claims, CompactJson, RegisteredClaims, Token, Validation, ValidationOptions};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct MyClaims {
+///     #[serde(flatten)]
+///     registered: RegisteredClaims,
+///     tenant: String,
+/// }
+///
+/// impl Claims for MyClaims {
+///     fn registered(&self) -> &RegisteredClaims {
+///         &self.registered
+///     }
+/// }
+///
+/// impl CompactJson for MyClaims {}
+///
+/// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+/// let token = Token::builder()
+///     .subject("John Doe")
+///     .claim("tenant", "ACME")
+///     .sign(&secret, SignatureAlgorithm::HS256)
+///     .unwrap();
+/// let options = ValidationOptions {
+///     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+///     required_claims: vec!["tenant".to_string()],
+///     ..Default::default()
+/// };
+///
+/// let claims: MyClaims = decode_claims(&token, &secret, &options).unwrap();
+/// assert_eq!(claims.subject(), Some("John Doe"));
+/// assert_eq!(claims.tenant, "ACME");
+/// ```
+pub fn decode_claims<C>(
+    token: &str,
+    secret: &jws::Secret,
+    options: &ValidationOptions,
+) -> Result<C, Error>
+where
+    C: Claims + CompactJson,
+{
+    record_verification(
+        options.metrics.as_deref(),
+        &*options.temporal_options.clock,
+        || {
+            let (header, claims) = verify_signature::<C>(token, secret, options)?;
+            header.registered.validate(options)?;
+            Claims::validate(&claims, options.clone())?;
+            Ok((header.registered.algorithm, claims))
+        },
+    )
+}
+
+/// Check the `alg` header of `token` against `options` and verify its signature, which is
+/// everything that [`decode`] does before validating the header and the claims in payload `P`
+pub(crate) fn verify_signature<P>(
+    token: &str,
+    secret: &jws::Secret,
+    options: &ValidationOptions,
+) -> Result<(jws::Header<Empty>, P), Error>
+where
+    P: CompactPart,
+{
+    let token = normalize_token(token, options)?;
+    let parts = jws::BorrowedCompact::split(&token)?;
+    let header = check_header(&parts, options)?;
+    let algorithm = header.registered.algorithm;
+    if options.json_limits != JsonLimits::default() {
+        let claims: Vec<u8> = parts.payload()?;
+        options.json_limits.check(&claims)?;
+    }
+    verify_parts(&parts, secret, algorithm)?;
+    Ok((header, parts.payload()?))
+}
+
+/// Verify the signature of a token whose header passed [`check_header`]
+///
+/// Tokens with the `none` algorithm only get here if `options` allow unsecured tokens, and are
+/// checked like [`jws::UnsecuredJwt::danger_decode`] does, since
+/// [`SignatureAlgorithm::verify`] rejects them.
+fn verify_parts(
+    parts: &jws::BorrowedCompact<'_>,
+    secret: &jws::Secret,
+    algorithm: SignatureAlgorithm,
+) -> Result<(), Error> {
+    if algorithm == SignatureAlgorithm::None {
+        parts.verify_unsecured()
+    } else {
+        parts.verify(secret, algorithm)
+    }
+}
+
+/// Check the size of `token` against `options`, and normalize its base64 encoding if
+/// `options` tolerate deviations
+fn normalize_token<'a>(token: &'a str, options: &ValidationOptions) -> Result<Cow<'a, str>, Error> {
+    options.size_limits.check(token)?;
+    if options.base64 == Base64Tolerance::default() {
+        Ok(Cow::Borrowed(token))
+    } else {
+        Ok(Cow::Owned(
+            Compact::decode_with_tolerance(token, &options.base64).encode(),
+        ))
+    }
+}
+
+/// Check the pinned header and the `alg` header of a token against `options`, and parse its
+/// header, before its signature is verified
+fn check_header(
+    parts: &jws::BorrowedCompact<'_>,
+    options: &ValidationOptions,
+) -> Result<jws::Header<Empty>, Error> {
+    if let Some(ref pinned) = options.pinned_header {
+        pinned.check(parts.encoded_header())?;
+    }
+    let header: jws::Header<Empty> = parts.header()?;
+    check_algorithm(header.registered.algorithm, options)?;
+    Ok(header)
+}
+
+/// Check that `algorithm` is one of the algorithms accepted by `options`, which must be listed
+pub(crate) fn check_algorithm(
+    algorithm: SignatureAlgorithm,
+    options: &ValidationOptions,
+) -> Result<(), Error> {
+    if algorithm == SignatureAlgorithm::None && !options.allow_unsecured_tokens {
+        Err(ValidationError::UnsecuredToken)?
+    }
+    match options.algorithms {
+        Validation::Ignored => Err(ValidationError::MissingAlgorithm)?,
+        Validation::Validate(ref algorithms) if !algorithms.contains(&algorithm) => {
+            Err(ValidationError::WrongAlgorithmHeader)?
+        }
+        Validation::Validate(_) => Ok(()),
+    }
+}
+
+/// Verify the signature of a compact JWS `token` whose payload is encoded as the
+/// `content_type` media type, and validate it like [`decode`]
+///
+/// The `cty` header must match `content_type`, and the claims are taken out of the decoded
+/// payload with `into_claims`. Since the payload is not JSON, `json_limits` are checked against
+/// the claims once the signature is verified, as if the claims were encoded in JSON.
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+pub(crate) fn decode_with_content_type<T, P, F>(
+    token: &str,
+    secret: &jws::Secret,
+    options: &ValidationOptions,
+    content_type: &str,
+    into_claims: F,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+    P: CompactPart,
+    F: FnOnce(P) -> ClaimsSet<T>,
+{
+    record_verification(
+        options.metrics.as_deref(),
+        &*options.temporal_options.clock,
+        || {
+            let token = normalize_token(token, options)?;
+            let parts = jws::BorrowedCompact::split(&token)?;
+            let header = check_header(&parts, options)?;
+            match header.registered.content_type {
+                Some(ref cty) if cty.eq_ignore_ascii_case(content_type) => {}
+                ref cty => Err(ValidationError::InvalidContentType(cty.clone()))?,
+            }
+            let algorithm = header.registered.algorithm;
+
+            verify_parts(&parts, secret, algorithm)?;
+            let claims = into_claims(parts.payload::<P>()?);
+            if options.json_limits != JsonLimits::default() {
+                options.json_limits.check(&serde_json::to_vec(&claims)?)?;
+            }
+            header.registered.validate(options)?;
+            claims.validate(options.clone())?;
+            Ok((algorithm, claims))
+        },
+    )
+}
+
+/// The number of threads that [`verify_batch`] verifies tokens with
+pub const DEFAULT_BATCH_WORKERS: usize = 4;
+
+/// Decode and validate many tokens concurrently, each like [`decode`], and return the result for
+/// each token in the same order as `tokens`
+///
+/// The tokens are split between [`DEFAULT_BATCH_WORKERS`] threads. Use
+/// [`verify_batch_with_workers`] to choose the number of threads.
+///
+/// # Examples
+/// ```
+/// use biscuit::{verify_batch, Empty, Token, Validation, ValidationOptions};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+///
+/// let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+/// let tokens: Vec<String> = (0..10)
+///     .map(|i| {
+///         Token::builder()
+///             .subject(format!("user-{}", i))
+///             .sign(&secret, SignatureAlgorithm::HS256)
+///             .unwrap()
+///     })
+///     .collect();
+/// let options = ValidationOptions {
+///     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+///     ..Default::default()
+/// };
+///
+/// let results = verify_batch::<Empty, _>(&tokens, &secret, &options);
+/// assert_eq!(results.len(), 10);
+/// assert_eq!(
+///     results[3].as_ref().unwrap().registered.subject.as_deref(),
+///     Some("user-3")
+/// );
+/// ```
+pub fn verify_batch<T, S>(
+    tokens: &[S],
+    secret: &jws::Secret,
+    options: &ValidationOptions,
+) -> Vec<Result<ClaimsSet<T>, Error>>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    S: AsRef<str>,
+{
+    verify_batch_with_workers(tokens, secret, options, DEFAULT_BATCH_WORKERS)
+}
+
+/// Decode and validate many tokens like [`verify_batch`], with up to `workers` threads
+///
+/// The tokens are verified on the current thread if `workers` is 0 or 1, and on WebAssembly.
+pub fn verify_batch_with_workers<T, S>(
+    tokens: &[S],
+    secret: &jws::Secret,
+    options: &ValidationOptions,
+    workers: usize,
+) -> Vec<Result<ClaimsSet<T>, Error>>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    S: AsRef<str>,
+{
+    let workers = workers.min(tokens.len());
+    if workers <= 1 || cfg!(target_arch = "wasm32") {
+        return tokens
+            .iter()
+            .map(|token| decode(token.as_ref(), secret, options))
+            .collect();
+    }
+
+    let chunk_size = (tokens.len() - 1) / workers + 1;
+    let handles: Vec<_> = tokens
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let chunk: Vec<String> = chunk.iter().map(|t| t.as_ref().to_string()).collect();
+            let secret = secret.clone();
+            let options = options.clone();
+            std::thread::spawn(move || {
+                chunk
+                    .iter()
+                    .map(|token| decode(token, &secret, &options))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .flat_map(|handle| match handle.join() {
+            Ok(results) => results,
+            Err(panic) => std::panic::resume_unwind(panic),
+        })
+        .collect()
+}
+
 /// An empty struct that derives Serialize and Deserialize. Can be used, for example, in places where a type
 /// for custom values (such as private claims in a `ClaimsSet`) is required but you have nothing to implement.
 ///
@@ -418,6 +860,111 @@ impl CompactPart for Vec<u8> {
     }
 }
 
+/// The string is used as is, without being serialized to JSON
+impl CompactPart for String {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.clone().into_bytes())
+    }
+
+    /// Convert a sequence of UTF-8 bytes into Self
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(str::from_utf8(bytes)?.to_string())
+    }
+}
+
+impl CompactJson for serde_json::Value {}
+
+impl CompactJson for serde_json::Map<String, serde_json::Value> {}
+
+/// Serialize `value` to canonical JSON, so that equal values always produce identical bytes
+///
+/// The canonical form follows the [JSON Canonicalization Scheme](https://tools.ietf.org/html/rfc8785)
+/// for the values that serde_json can represent:
+///
+/// - Object keys are sorted by their UTF-16 code units, at every level
+/// - There is no insignificant whitespace
+/// - Numbers with an integral value, like `1.0`, are written as integers
+///
+/// # Examples
+/// ```
+/// use serde_json::json;
+///
+/// let canonical = biscuit::to_canonical_json(&json!({ "b": 1.0, "a": [{ "d": 1, "c": 2 }] }));
+/// assert_eq!(canonical.unwrap(), br#"{"a":[{"c":2,"d":1}],"b":1}"#);
+/// ```
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let value = sort_json_keys(serde_json::to_value(value)?);
+    Ok(serde_json::to_vec(&value)?)
+}
+
+/// Recursively sort the keys of the JSON objects in `value` by their UTF-16 code units, and
+/// write numbers with an integral value as integers
+pub(crate) fn sort_json_keys(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::{Number, Value};
+
+    // Integers beyond 2^53 cannot be represented exactly by a float
+    const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+    match value {
+        Value::Number(number) => match number.as_f64() {
+            Some(float)
+                if number.is_f64() && float.fract() == 0.0 && float.abs() <= MAX_SAFE_INTEGER =>
+            {
+                Value::Number(Number::from(float as i64))
+            }
+            _ => Value::Number(number),
+        },
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_json_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_json_keys).collect()),
+        value => value,
+    }
+}
+
+/// An adapter to use any type that can be serialized and deserialized with serde as a `CompactPart`,
+/// by serializing it to JSON, without implementing [`CompactJson`] for it
+///
+/// # Examples
+/// ```
+/// use biscuit::{CompactPart, Json};
+///
+/// let part = Json(vec![1, 2, 3]);
+/// let encoded = part.to_base64().unwrap();
+/// assert_eq!(encoded.str(), "WzEsMiwzXQ");
+/// assert_eq!(Json::<Vec<u32>>::from_base64(&encoded).unwrap(), part);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Json<T>(pub T);
+
+impl<T> CompactPart for Json<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(serde_json::to_vec(&self.0)?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Json(serde_json::from_slice(bytes)?))
+    }
+}
+
+impl<T> Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
 /// A newtype wrapper around a string to indicate it's base64 URL encoded
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Base64Url(String);
@@ -524,8 +1071,15 @@ impl Compact {
     /// Encodes the various parts into Base64 URL encoding and then concatenates them with period '.'
     /// This corresponds to the various `Compact` representation in JWE and JWS, for example
     pub fn encode(&self) -> String {
-        let strings: Vec<&str> = self.parts.iter().map(Deref::deref).collect();
-        strings.join(".")
+        let length = self.parts.iter().map(|part| part.len() + 1).sum::<usize>();
+        let mut encoded = String::with_capacity(length.saturating_sub(1));
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                encoded.push('.');
+            }
+            encoded.push_str(part);
+        }
+        encoded
     }
 
     /// Convenience function to split an encoded compact representation into a list of `Base64Url`.
@@ -540,12 +1094,118 @@ impl Compact {
 
     /// Convenience function to retrieve a part at a certain index and decode into the type desired
     pub fn part<T: CompactPart>(&self, index: usize) -> Result<T, Error> {
-        let part = self
-            .parts
-            .get(index)
-            .ok_or_else(|| "Out of bounds".to_string())?;
+        let part = self.parts.get(index).ok_or(DecodeError::PartsLengthError {
+            expected: index + 1,
+            actual: self.parts.len(),
+        })?;
         CompactPart::from_base64(part)
     }
+
+    /// Split an encoded compact representation into a list of `Base64Url`, tolerating the
+    /// deviations from base64url allowed by `tolerance`
+    ///
+    /// Tolerated deviations are normalized to unpadded base64url, which is the form that
+    /// signatures are computed over. Any deviation that is not tolerated is left as is, and
+    /// fails when the part is decoded.
+    pub fn decode_with_tolerance(encoded: &str, tolerance: &Base64Tolerance) -> Self {
+        let mut compact = Self::decode(encoded);
+        for part in compact.parts.iter_mut() {
+            let mut normalized: String = part
+                .str()
+                .chars()
+                .filter(|c| !(tolerance.whitespace && c.is_whitespace()))
+                .map(|c| match c {
+                    '+' if tolerance.standard_alphabet => '-',
+                    '/' if tolerance.standard_alphabet => '_',
+                    c => c,
+                })
+                .collect();
+            if tolerance.padding {
+                let unpadded = normalized.trim_end_matches('=').len();
+                normalized.truncate(unpadded);
+            }
+            *part = Base64Url(normalized);
+        }
+        compact
+    }
+}
+
+/// Deviations from the base64url encoding of [RFC 7515](https://tools.ietf.org/html/rfc7515#section-2)
+/// that are tolerated when decoding the parts of a compact token
+///
+/// The default is strict: every deviation is rejected. Some gateways pad tokens or wrap them
+/// across lines, and those tokens can be accepted with [`Base64Tolerance::lenient`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Base64Tolerance {
+    /// Tolerate trailing `=` padding
+    pub padding: bool,
+    /// Tolerate the `+` and `/` characters of the standard base64 alphabet
+    pub standard_alphabet: bool,
+    /// Tolerate whitespace, such as line breaks, within the token
+    pub whitespace: bool,
+}
+
+impl Base64Tolerance {
+    /// Tolerate every deviation
+    pub fn lenient() -> Self {
+        Base64Tolerance {
+            padding: true,
+            standard_alphabet: true,
+            whitespace: true,
+        }
+    }
+}
+
+/// Limits on the size of encoded tokens, which are checked before anything is decoded
+///
+/// Decoding a token allocates memory in proportion to its size, so services accepting tokens
+/// from untrusted clients should bound it. There are no limits by default.
+///
+/// # Examples
+/// ```
+/// use biscuit::{SizeLimits, ValidationOptions};
+///
+/// let options = ValidationOptions {
+///     size_limits: SizeLimits {
+///         max_token_length: Some(8 * 1024),
+///         max_segment_length: Some(4 * 1024),
+///     },
+///     ..Default::default()
+/// };
+/// assert!(options.size_limits.check(&"a".repeat(4097)).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SizeLimits {
+    /// Maximum length of the whole token, in bytes
+    pub max_token_length: Option<usize>,
+    /// Maximum length of each part of the token, in bytes
+    pub max_segment_length: Option<usize>,
+}
+
+impl SizeLimits {
+    /// Check the encoded `token` against the limits
+    pub fn check(&self, token: &str) -> Result<(), DecodeError> {
+        if let Some(max) = self.max_token_length {
+            if token.len() > max {
+                Err(DecodeError::TokenTooLong {
+                    max,
+                    actual: token.len(),
+                })?
+            }
+        }
+        if let Some(max) = self.max_segment_length {
+            for (index, segment) in token.split('.').enumerate() {
+                if segment.len() > max {
+                    Err(DecodeError::SegmentTooLong {
+                        index,
+                        max,
+                        actual: segment.len(),
+                    })?
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for Compact {
@@ -598,6 +1258,9 @@ impl<'de> Deserialize<'de> for Compact {
 /// Represents a choice between a single value or multiple values.
 /// This value is serialized by serde [untagged](https://serde.rs/enum-representations.html).
 ///
+/// This is used for the `aud` claim, and can be reused for private claims that allow either form.
+/// Both forms can be iterated over or converted to a `Vec` in the same way.
+///
 /// # Examples
 /// ```
 /// use biscuit::SingleOrMultiple;
@@ -644,37 +1307,130 @@ pub enum SingleOrMultiple<T> {
     Multiple(Vec<T>),
 }
 
-impl<T> SingleOrMultiple<T>
-where
-    T: Clone + Debug + Eq + PartialEq + Serialize + DeserializeOwned + Send + Sync,
-{
+impl<T> SingleOrMultiple<T> {
     /// Checks whether this enum, regardless of single or multiple value contains `value`.
     pub fn contains<Q: ?Sized>(&self, value: &Q) -> bool
     where
         T: Borrow<Q>,
         Q: PartialEq,
     {
-        match *self {
-            SingleOrMultiple::Single(ref single) => single.borrow() == value,
-            SingleOrMultiple::Multiple(ref vector) => {
-                vector.iter().map(Borrow::borrow).any(|v| v == value)
-            }
-        }
+        self.as_slice()
+            .iter()
+            .map(Borrow::borrow)
+            .any(|v| v == value)
     }
 
     /// Yields an iterator for the single value or the list
     pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a T> + 'a> {
+        Box::new(self.as_slice().iter())
+    }
+
+    /// Returns the number of values
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns whether there are no values, which can only be the case for an empty `Multiple`
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    /// Returns the first value, if any
+    pub fn first(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    /// Returns the values as a slice, regardless of single or multiple value
+    pub fn as_slice(&self) -> &[T] {
         match *self {
-            SingleOrMultiple::Single(ref single) => Box::new(iter::once(single)),
-            SingleOrMultiple::Multiple(ref vector) => Box::new(vector.iter()),
+            SingleOrMultiple::Single(ref single) => std::slice::from_ref(single),
+            SingleOrMultiple::Multiple(ref vector) => vector,
+        }
+    }
+
+    /// Consumes self and returns the values as a `Vec`, regardless of single or multiple value
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            SingleOrMultiple::Single(single) => vec![single],
+            SingleOrMultiple::Multiple(vector) => vector,
         }
     }
 }
 
+impl<T> From<T> for SingleOrMultiple<T> {
+    fn from(single: T) -> Self {
+        SingleOrMultiple::Single(single)
+    }
+}
+
+impl<T> From<Vec<T>> for SingleOrMultiple<T> {
+    fn from(vector: Vec<T>) -> Self {
+        SingleOrMultiple::Multiple(vector)
+    }
+}
+
+impl<T> From<SingleOrMultiple<T>> for Vec<T> {
+    fn from(values: SingleOrMultiple<T>) -> Self {
+        values.into_vec()
+    }
+}
+
+impl<T> IntoIterator for SingleOrMultiple<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SingleOrMultiple<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
 /// Wrapper around `DateTime<Utc>` to allow us to do custom de(serialization)
+///
+/// This represents a [NumericDate](https://tools.ietf.org/html/rfc7519#section-2) which is
+/// serialized as the number of seconds since the Unix Epoch, and is used for the temporal
+/// registered claims `exp`, `nbf` and `iat`. The wrapped `chrono::DateTime<Utc>` can be
+/// accessed by dereferencing or converting the `Timestamp`.
+///
+/// When deserializing, non-integer values and numbers encoded as strings are also accepted, and
+/// are truncated to whole seconds.
+///
+/// You can also use this type for temporal claims in your own private claims.
+///
+/// With the `time` feature enabled, `Timestamp` can also be converted to and from
+/// `time::OffsetDateTime` with `TryFrom`, since the two types cover different ranges of dates.
+/// Serialization is unaffected by which crate you convert from.
+///
+/// # Examples
+/// ```
+/// use biscuit::Timestamp;
+/// use chrono::{DateTime, TimeZone, Utc};
+///
+/// let timestamp: Timestamp = serde_json::from_str("1234").unwrap();
+/// let datetime: DateTime<Utc> = timestamp.into();
+/// assert_eq!(datetime, Utc.timestamp(1234, 0));
+/// assert_eq!(serde_json::to_string(&timestamp).unwrap(), "1234");
+/// ```
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Timestamp(DateTime<Utc>);
 
+impl Timestamp {
+    /// Create a `Timestamp` from the number of seconds since the Unix Epoch, returning `None`
+    /// if it is out of the range representable by `chrono::DateTime`
+    pub fn from_seconds(seconds: i64) -> Option<Self> {
+        NaiveDateTime::from_timestamp_opt(seconds, 0)
+            .map(|naive| Timestamp(DateTime::<Utc>::from_utc(naive, Utc)))
+    }
+}
+
 impl Deref for Timestamp {
     type Target = DateTime<Utc>;
     fn deref(&self) -> &Self::Target {
@@ -694,17 +1450,46 @@ impl From<Timestamp> for DateTime<Utc> {
     }
 }
 
+/// # Panics
+/// Panics if the number of seconds is out of the range representable by `chrono::DateTime`.
+/// Use [`Timestamp::from_seconds`] to handle this case.
 impl From<i64> for Timestamp {
     fn from(timestamp: i64) -> Self {
         DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(timestamp, 0), Utc).into()
     }
 }
 
-impl Serialize for Timestamp {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
+#[cfg(feature = "time")]
+impl TryFrom<time::OffsetDateTime> for Timestamp {
+    type Error = Error;
+
+    /// Returns [`Error::TimestampOutOfRange`] if `datetime` is outside of the range of
+    /// `chrono::DateTime`, which is possible with the `large-dates` feature of `time`
+    fn try_from(datetime: time::OffsetDateTime) -> Result<Self, Self::Error> {
+        use chrono::TimeZone;
+
+        Utc.timestamp_opt(datetime.unix_timestamp(), datetime.nanosecond())
+            .single()
+            .map(Timestamp)
+            .ok_or_else(|| Error::TimestampOutOfRange(datetime.unix_timestamp()))
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Timestamp> for time::OffsetDateTime {
+    type Error = time::error::ComponentRange;
+
+    fn try_from(ts: Timestamp) -> Result<Self, Self::Error> {
+        time::OffsetDateTime::from_unix_timestamp(ts.timestamp())?
+            .replace_nanosecond(ts.timestamp_subsec_nanos())
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
         serializer.serialize_i64(self.timestamp())
     }
 }
@@ -714,14 +1499,72 @@ impl<'de> Deserialize<'de> for Timestamp {
     where
         D: Deserializer<'de>,
     {
-        let timestamp = i64::deserialize(deserializer)?;
-        Ok(Timestamp(DateTime::<Utc>::from_utc(
-            NaiveDateTime::from_timestamp(timestamp, 0),
-            Utc,
-        )))
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+/// Visitor for NumericDates, which accepts integers, floats and strings containing either,
+/// truncating fractional seconds.
+///
+/// RFC 7519 allows non-integer values, and some issuers encode them as strings.
+struct TimestampVisitor;
+
+impl TimestampVisitor {
+    fn from_float<E: de::Error>(
+        value: f64,
+        unexpected: de::Unexpected<'_>,
+    ) -> Result<Timestamp, E> {
+        let seconds = value.trunc();
+        // Anything this large is far outside of the range `chrono` supports anyway, and the
+        // bound keeps the cast below lossless.
+        if seconds.is_finite() && seconds.abs() < 1e15 {
+            if let Some(timestamp) = Timestamp::from_seconds(seconds as i64) {
+                return Ok(timestamp);
+            }
+        }
+        Err(E::invalid_value(unexpected, &TimestampVisitor))
+    }
+}
+
+impl<'de> de::Visitor<'de> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a NumericDate within the supported range")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Timestamp::from_seconds(value)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Signed(value), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        let invalid = || E::invalid_value(de::Unexpected::Unsigned(value), &TimestampVisitor);
+        let seconds = i64::try_from(value).map_err(|_| invalid())?;
+        Timestamp::from_seconds(seconds).ok_or_else(invalid)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Self::from_float(value, de::Unexpected::Float(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        let trimmed = value.trim();
+        if let Ok(seconds) = trimmed.parse::<i64>() {
+            return self
+                .visit_i64(seconds)
+                .map_err(|_: E| E::invalid_value(de::Unexpected::Str(value), &TimestampVisitor));
+        }
+        match trimmed.parse::<f64>() {
+            Ok(seconds) => Self::from_float(seconds, de::Unexpected::Str(value)),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+        }
     }
 }
 
+/// The names of the claims in [`RegisteredClaims`]
+pub(crate) const REGISTERED_CLAIMS: &[&str] = &["iss", "sub", "aud", "exp", "nbf", "iat", "jti"];
+
 /// Registered claims defined by [RFC7519#4.1](https://tools.ietf.org/html/rfc7519#section-4.1)
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
 pub struct RegisteredClaims {
@@ -792,7 +1635,7 @@ impl ClaimPresenceOptions {
     }
 }
 
-#[derive(Eq, PartialEq, Clone)]
+#[derive(Clone)]
 /// Options for claims validation
 ///
 /// If a claim is missing, it passes validation unless the claim is marked as required within the
@@ -821,6 +1664,11 @@ pub struct ValidationOptions {
     pub not_before: Validation<()>,
     /// Validation options for `exp` or `Expiry` claim if present
     pub expiry: Validation<()>,
+    /// Validation options for the lifetime of a token with an `exp` claim
+    /// Parameter shows the maximum time between the `iat` claim, or the current time if the
+    /// token has no `iat` claim, and the `exp` claim. This rejects tokens with absurd lifetimes
+    /// minted by a misconfigured issuer.
+    pub max_validity: Validation<Duration>,
 
     /// Validation options for `iss` or `Issuer` claim if present
     /// Parameter must match the issuer in the token exactly.
@@ -829,6 +1677,101 @@ pub struct ValidationOptions {
     /// Validation options for `aud` or `Audience` claim if present
     /// Token must include an audience with the value of the parameter
     pub audience: Validation<String>,
+
+    /// Validation options for `jti` or `JWT ID` claim if present
+    /// Tokens whose `jti` has already been seen by the [`ReplayCache`] are rejected. Otherwise,
+    /// the `jti` is recorded in the cache until the token expires.
+    ///
+    /// The `jti` is only checked and recorded after all other claims have passed validation.
+    pub id: Validation<Arc<dyn ReplayCache>>,
+
+    /// Validation options for revoked tokens
+    /// Tokens are rejected if the [`Denylist`] reports that they have been revoked, based on
+    /// their `jti`, `sub` and `iat` claims.
+    ///
+    /// The denylist is consulted after all other claims have passed validation, but before the
+    /// `jti` is recorded.
+    pub denylist: Validation<Arc<dyn Denylist>>,
+
+    /// Additional claims, registered or private, that must be present in the token.
+    /// Claims are identified by their JSON key, and a claim with a `null` value is considered missing.
+    ///
+    /// Private claims can only be checked when validating a whole [`ClaimsSet`], for example with
+    /// [`ClaimsSet::validate`] or [`jws::Compact::validate`]. Missing claims are reported together
+    /// with the claims required by `claim_presence_options`.
+    pub required_claims: Vec<String>,
+
+    /// Claims, registered or private, that must not be present in the token.
+    /// A claim with a `null` value is considered missing.
+    ///
+    /// Like `required_claims`, these are only checked when validating a whole [`ClaimsSet`].
+    pub forbidden_claims: Vec<String>,
+
+    /// Custom validators that are run against the claims of the token after the registered
+    /// claims have been validated. Any validator may reject the token.
+    ///
+    /// Like `required_claims`, validators are only run when validating a whole [`ClaimsSet`].
+    pub validators: Vec<Arc<dyn ClaimsValidator>>,
+
+    /// Validation options for the `typ` header of a JWS.
+    /// Parameter is the expected media type, which is compared case-insensitively and with an
+    /// optional `application/` prefix. Tokens without a `typ` header are rejected.
+    ///
+    /// Headers are only validated when validating a [`jws::Compact`], or with
+    /// [`jws::RegisteredHeader::validate`].
+    pub token_type: Validation<String>,
+
+    /// Media types for the `typ` header of a JWS that are rejected, compared like `token_type`.
+    /// Tokens without a `typ` header are not rejected.
+    ///
+    /// This keeps tokens of one class from being accepted where another is expected, such as an
+    /// access token where an ID token is expected. Like `token_type`, this is only checked when
+    /// headers are validated.
+    pub rejected_token_types: Vec<String>,
+
+    /// Validation options for the `alg` header of a JWS.
+    /// Parameter is the list of algorithms that are accepted.
+    ///
+    /// Like `token_type`, this is only checked when headers are validated.
+    pub algorithms: Validation<Vec<SignatureAlgorithm>>,
+
+    /// Accept unsecured tokens, with the `none` algorithm and no signature.
+    ///
+    /// Unsecured tokens can be forged by anyone, so they are rejected by default, even if `none`
+    /// is listed in `algorithms`. Like `token_type`, this is only checked when headers are
+    /// validated, and by [`decode`]. The lower level verification APIs, such as
+    /// [`jws::Compact::into_decoded`], never accept unsecured tokens, which are decoded with
+    /// [`jws::UnsecuredJwt::danger_decode`] instead.
+    pub allow_unsecured_tokens: bool,
+
+    /// Validation options for the `crit` header of a JWS.
+    /// Parameter is the list of extension headers that are understood. Tokens whose `crit`
+    /// header lists any other header are rejected, as required by
+    /// [RFC7515#4.1.11](https://tools.ietf.org/html/rfc7515#section-4.1.11).
+    ///
+    /// By default no extension headers are understood, so every token with a `crit` header is
+    /// rejected. Like `token_type`, this is only checked when headers are validated.
+    pub critical_headers: Validation<Vec<String>>,
+
+    /// Deviations from base64url that are tolerated when decoding a token with [`decode`].
+    /// Strict by default.
+    pub base64: Base64Tolerance,
+
+    /// Limits on the size of a token decoded with [`decode`]. Unlimited by default.
+    pub size_limits: SizeLimits,
+
+    /// Limits on the JSON of the claims of a token decoded with [`decode`], which are checked
+    /// before the signature is verified. Unlimited by default. The claims of CBOR and
+    /// MessagePack tokens are checked as if they were JSON, after the signature is verified.
+    pub json_limits: JsonLimits,
+
+    /// The `alg` and `kid` headers that tokens decoded with [`decode`] must have, which are
+    /// checked before anything else is deserialized. Not pinned by default.
+    pub pinned_header: Option<jws::PinnedHeader>,
+
+    /// Record the outcome and latency of verifying tokens with [`decode`] or
+    /// [`providers::verify`] in these [`Metrics`]
+    pub metrics: Option<Arc<dyn Metrics>>,
 }
 
 impl Default for ValidationOptions {
@@ -837,15 +1780,482 @@ impl Default for ValidationOptions {
             expiry: Validation::Validate(()),
             not_before: Validation::Validate(()),
             issued_at: Validation::Validate(Duration::max_value()),
+            max_validity: Validation::Ignored,
 
             claim_presence_options: Default::default(),
             temporal_options: Default::default(),
             audience: Default::default(),
             issuer: Default::default(),
+            id: Default::default(),
+            denylist: Default::default(),
+            required_claims: Default::default(),
+            forbidden_claims: Default::default(),
+            validators: Default::default(),
+            token_type: Default::default(),
+            rejected_token_types: Default::default(),
+            algorithms: Default::default(),
+            allow_unsecured_tokens: false,
+            critical_headers: Validation::Validate(vec![]),
+            base64: Default::default(),
+            size_limits: Default::default(),
+            json_limits: Default::default(),
+            pinned_header: None,
+            metrics: None,
+        }
+    }
+}
+
+impl ValidationOptions {
+    /// Strict options for when the issuer and the audience are checked by other means
+    ///
+    /// The token must be signed and must not have a `crit` header. The `exp` and `iat` claims
+    /// are required, and the temporal claims are validated without any leeway.
+    /// Use [`ValidationOptions::rfc8725`] instead if the issuer and the audience are known.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    /// use chrono::Duration;
+    ///
+    /// let mut options = ValidationOptions::strict();
+    /// options.issued_at = biscuit::Validation::Validate(Duration::hours(1));
+    /// ```
+    pub fn strict() -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                expiry: Required,
+                issued_at: Required,
+                ..Default::default()
+            },
+            algorithms: Validation::Validate(signed_algorithms()),
+            critical_headers: Validation::Validate(vec![]),
+            ..Default::default()
+        }
+    }
+
+    /// Options following the [JSON Web Token Best Current Practices](https://tools.ietf.org/html/rfc8725)
+    /// for tokens issued by `issuer` to `audience`, signed with one of `algorithms`
+    ///
+    /// - Only the `algorithms` listed are accepted, and never [`SignatureAlgorithm::None`],
+    ///   even if it is listed.
+    /// - The `iss`, `aud` and `exp` claims are required, and `iss` and `aud` must match.
+    /// - Tokens with a `crit` header are rejected, since no extensions are understood.
+    ///
+    /// Key and algorithm confusion is prevented regardless of the options, since the algorithm
+    /// used for verification is always chosen by the caller and must match the `alg` header and
+    /// the type of the key.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    /// use biscuit::jwa::SignatureAlgorithm;
+    ///
+    /// let options = ValidationOptions::rfc8725(
+    ///     "https://issuer.example.com".to_string(),
+    ///     "https://api.example.com".to_string(),
+    ///     vec![SignatureAlgorithm::ES256],
+    /// );
+    /// ```
+    pub fn rfc8725(issuer: String, audience: String, algorithms: Vec<SignatureAlgorithm>) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                issuer: Required,
+                audience: Required,
+                expiry: Required,
+                ..Default::default()
+            },
+            issuer: Validation::Validate(issuer),
+            audience: Validation::Validate(audience),
+            algorithms: Validation::Validate(
+                algorithms
+                    .into_iter()
+                    .filter(|algorithm| *algorithm != SignatureAlgorithm::None)
+                    .collect(),
+            ),
+            critical_headers: Validation::Validate(vec![]),
+            ..Default::default()
+        }
+    }
+
+    /// Options for the Financial-grade API (FAPI) security profiles, for tokens issued by
+    /// `issuer` to `audience`
+    ///
+    /// - Only `PS256` and `ES256` are accepted, as listed by
+    ///   [`SignatureAlgorithm::is_fapi_compliant`]. RSA PKCS#1 v1.5, HMAC and `none` are
+    ///   rejected.
+    /// - The `exp`, `aud` and `jti` claims are required, and `iss` and `aud` must match.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    ///
+    /// let options = ValidationOptions::fapi(
+    ///     "https://as.bank.example.com".to_string(),
+    ///     "https://api.bank.example.com".to_string(),
+    /// );
+    /// ```
+    pub fn fapi(issuer: String, audience: String) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                expiry: Required,
+                audience: Required,
+                id: Required,
+                ..Default::default()
+            },
+            issuer: Validation::Validate(issuer),
+            audience: Validation::Validate(audience),
+            algorithms: Validation::Validate(
+                signed_algorithms()
+                    .into_iter()
+                    .filter(|algorithm| algorithm.is_fapi_compliant())
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// Options for validating access tokens issued in the
+    /// [JWT Profile for OAuth 2.0 Access Tokens](https://tools.ietf.org/html/rfc9068), by the
+    /// expected `issuer` for the resource server identified by `audience`
+    ///
+    /// The `typ` header must be `at+jwt`, and the token must be signed. The `iss`, `exp`, `aud`,
+    /// `sub`, `client_id`, `iat` and `jti` claims are required, and `iss` and `aud` must match.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    ///
+    /// let options = ValidationOptions::jwt_access_token(
+    ///     "https://authorization-server.example.com/".to_string(),
+    ///     "https://rs.example.com/".to_string(),
+    /// );
+    /// ```
+    pub fn jwt_access_token(issuer: String, audience: String) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                issuer: Required,
+                expiry: Required,
+                audience: Required,
+                subject: Required,
+                issued_at: Required,
+                id: Required,
+                ..Default::default()
+            },
+            issuer: Validation::Validate(issuer),
+            audience: Validation::Validate(audience),
+            required_claims: vec!["client_id".to_string()],
+            token_type: Validation::Validate(oauth::ACCESS_TOKEN_MEDIA_TYPE.to_string()),
+            algorithms: Validation::Validate(signed_algorithms()),
+            ..Default::default()
+        }
+    }
+
+    /// Options for validating [Security Event Tokens](https://tools.ietf.org/html/rfc8417) issued
+    /// by `issuer` to `audience`
+    ///
+    /// The `typ` header must be `secevent+jwt`. The `iss`, `iat`, `jti`, `aud` and `events`
+    /// claims are required, `iss` and `aud` must match, and the `events` claim must be an object
+    /// of event payloads.
+    ///
+    /// As recommended by [RFC8417#4.3](https://tools.ietf.org/html/rfc8417#section-4.3), the `exp`
+    /// claim is forbidden so that a SET cannot be confused with an ID or access token.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    ///
+    /// let options = ValidationOptions::security_event_token(
+    ///     "https://idp.example.com/".to_string(),
+    ///     "https://sp.example.com/feedback".to_string(),
+    /// );
+    /// ```
+    pub fn security_event_token(issuer: String, audience: String) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                issuer: Required,
+                audience: Required,
+                issued_at: Required,
+                id: Required,
+                ..Default::default()
+            },
+            expiry: Validation::Ignored,
+            issuer: Validation::Validate(issuer),
+            audience: Validation::Validate(audience),
+            required_claims: vec!["events".to_string()],
+            forbidden_claims: vec!["exp".to_string()],
+            validators: vec![Arc::new(
+                |claims: &serde_json::Map<String, serde_json::Value>| {
+                    secevent::SecurityEventClaims::from_claims(claims).map(|_| ())
+                },
+            )],
+            token_type: Validation::Validate(secevent::MEDIA_TYPE.to_string()),
+            algorithms: Validation::Validate(signed_algorithms()),
+            ..Default::default()
+        }
+    }
+
+    /// Options for validating OpenID Connect
+    /// [Back-Channel Logout](https://openid.net/specs/openid-connect-backchannel-1_0.html) tokens
+    /// issued by `issuer` to the relying party identified by `client_id`
+    ///
+    /// The `iss`, `aud`, `iat` and `jti` claims are required, `iss` and `aud` must match, and the
+    /// token must have been issued at most `max_age` ago. The claims specific to logout tokens
+    /// are validated by [`oidc::LogoutToken`].
+    ///
+    /// Since the `logout+jwt` media type is only recommended, the `typ` header is not checked.
+    /// Set `token_type` to [`oidc::LOGOUT_TOKEN_MEDIA_TYPE`] to require it.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    /// use chrono::Duration;
+    ///
+    /// let options = ValidationOptions::logout_token(
+    ///     "https://server.example.com".to_string(),
+    ///     "s6BhdRkqt3".to_string(),
+    ///     Duration::minutes(2),
+    /// );
+    /// ```
+    pub fn logout_token(issuer: String, client_id: String, max_age: Duration) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                issuer: Required,
+                audience: Required,
+                issued_at: Required,
+                id: Required,
+                ..Default::default()
+            },
+            issued_at: Validation::Validate(max_age),
+            issuer: Validation::Validate(issuer),
+            audience: Validation::Validate(client_id),
+            validators: vec![Arc::new(oidc::LogoutToken)],
+            algorithms: Validation::Validate(signed_algorithms()),
+            ..Default::default()
+        }
+    }
+
+    /// Options for validating [JWT-Secured Authorization Requests](https://tools.ietf.org/html/rfc9101)
+    /// made by the client identified by `client_id` to the authorization server identified by
+    /// `issuer`
+    ///
+    /// The `typ` header must be `oauth-authz-req+jwt`, and the request object must be signed. The
+    /// `iss` claim must be the client ID, the `aud` claim must be the issuer, and the `client_id`
+    /// claim must be the client ID. The `request` and `request_uri` parameters are forbidden.
+    /// Use [`oauth::decrypt_nested`] first if the request object is encrypted.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    ///
+    /// let options = ValidationOptions::request_object(
+    ///     "s6BhdRkqt3".to_string(),
+    ///     "https://server.example.com".to_string(),
+    /// );
+    /// ```
+    pub fn request_object(client_id: String, issuer: String) -> Self {
+        use crate::Presence::Required;
+
+        let expected_client_id = client_id.clone();
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                issuer: Required,
+                audience: Required,
+                ..Default::default()
+            },
+            issuer: Validation::Validate(client_id),
+            audience: Validation::Validate(issuer),
+            required_claims: vec!["client_id".to_string()],
+            forbidden_claims: vec!["request".to_string(), "request_uri".to_string()],
+            validators: vec![Arc::new(
+                move |claims: &serde_json::Map<String, serde_json::Value>| match claims
+                    .get("client_id")
+                {
+                    Some(serde_json::Value::String(client_id))
+                        if *client_id == expected_client_id =>
+                    {
+                        Ok(())
+                    }
+                    Some(serde_json::Value::String(client_id)) => {
+                        Err(ValidationError::InvalidClientId(client_id.clone()))
+                    }
+                    // Reported by `required_claims`
+                    None | Some(serde_json::Value::Null) => Ok(()),
+                    Some(_) => Err(ValidationError::MalformedClaim("client_id".to_string())),
+                },
+            )],
+            token_type: Validation::Validate(oauth::REQUEST_OBJECT_MEDIA_TYPE.to_string()),
+            algorithms: Validation::Validate(signed_algorithms()),
+            ..Default::default()
+        }
+    }
+
+    /// Options for validating [JWT Secured Authorization Responses](https://openid.net/specs/oauth-v2-jarm.html)
+    /// (JARM) issued by the authorization server identified by `issuer` to the client identified
+    /// by `client_id`
+    ///
+    /// The response must be signed. The `iss`, `aud` and `exp` claims are required, the `iss`
+    /// claim must be the issuer and the `aud` claim must be the client ID. Use
+    /// [`oauth::decrypt_nested`] first if the response is encrypted, and
+    /// [`oauth::AuthorizationResponse::from_jwt`] to validate the response and extract its
+    /// parameters.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    ///
+    /// let options = ValidationOptions::authorization_response(
+    ///     "https://accounts.example.com".to_string(),
+    ///     "s6BhdRkqt3".to_string(),
+    /// );
+    /// ```
+    pub fn authorization_response(issuer: String, client_id: String) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                issuer: Required,
+                audience: Required,
+                expiry: Required,
+                ..Default::default()
+            },
+            issuer: Validation::Validate(issuer),
+            audience: Validation::Validate(client_id),
+            algorithms: Validation::Validate(signed_algorithms()),
+            ..Default::default()
+        }
+    }
+
+    /// Options for validating OpenID Connect
+    /// [ID tokens](https://openid.net/specs/openid-connect-core-1_0.html#IDTokenValidation) issued
+    /// by `issuer` to the relying party identified by `client_id`
+    ///
+    /// The token must be signed. The `iss`, `sub`, `aud`, `exp` and `iat` claims are required,
+    /// `iss` and `aud` must match, and the `azp` claim is validated by [`oidc::AuthorizedParty`].
+    /// ID tokens are usually not explicitly typed, so the `typ` header is not required, but
+    /// tokens typed as another class of token, such as `at+jwt` access tokens, are rejected.
+    ///
+    /// The `nonce` claim is not checked, since it depends on the authentication request. Add an
+    /// [`oidc::Nonce`] validator if a nonce was sent.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use biscuit::ValidationOptions;
+    /// use biscuit::oidc::Nonce;
+    ///
+    /// let mut options = ValidationOptions::oidc_id_token(
+    ///     "s6BhdRkqt3".to_string(),
+    ///     "https://server.example.com".to_string(),
+    /// );
+    /// options.validators.push(Arc::new(Nonce::new("n-0S6_WzA2Mj")));
+    /// ```
+    pub fn oidc_id_token(client_id: String, issuer: String) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                issuer: Required,
+                subject: Required,
+                audience: Required,
+                expiry: Required,
+                issued_at: Required,
+                ..Default::default()
+            },
+            issuer: Validation::Validate(issuer),
+            audience: Validation::Validate(client_id.clone()),
+            validators: vec![Arc::new(oidc::AuthorizedParty::new(client_id))],
+            rejected_token_types: vec![
+                oauth::ACCESS_TOKEN_MEDIA_TYPE.to_string(),
+                oauth::DPOP_PROOF_MEDIA_TYPE.to_string(),
+                oauth::REQUEST_OBJECT_MEDIA_TYPE.to_string(),
+                oidc::LOGOUT_TOKEN_MEDIA_TYPE.to_string(),
+                secevent::MEDIA_TYPE.to_string(),
+                sd_jwt::KEY_BINDING_MEDIA_TYPE.to_string(),
+            ],
+            algorithms: Validation::Validate(signed_algorithms()),
+            ..Default::default()
+        }
+    }
+
+    /// Options for validating [JWT-SVIDs](https://github.com/spiffe/spiffe/blob/main/standards/JWT-SVID.md)
+    /// for `audience`, whose subject is a SPIFFE ID in `trust_domain`
+    ///
+    /// The `sub`, `aud` and `exp` claims are required, `aud` must match, and the subject is
+    /// validated by [`spiffe::SpiffeSubject`]. Only the asymmetric [`spiffe::ALGORITHMS`] are
+    /// accepted.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::ValidationOptions;
+    ///
+    /// let options = ValidationOptions::jwt_svid(
+    ///     "spiffe://example.org/payments".to_string(),
+    ///     "example.org".to_string(),
+    /// );
+    /// ```
+    pub fn jwt_svid(audience: String, trust_domain: String) -> Self {
+        use crate::Presence::Required;
+
+        ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                subject: Required,
+                audience: Required,
+                expiry: Required,
+                ..Default::default()
+            },
+            audience: Validation::Validate(audience),
+            validators: vec![Arc::new(spiffe::SpiffeSubject::new(trust_domain))],
+            algorithms: Validation::Validate(spiffe::ALGORITHMS.to_vec()),
+            ..Default::default()
+        }
+    }
+
+    /// Lenient options for tests, which accept tokens regardless of their age
+    ///
+    /// The temporal claims are not validated, unsecured tokens are accepted, and base64
+    /// deviations are tolerated like [`Base64Tolerance::lenient`]. Every algorithm is accepted,
+    /// including [`SignatureAlgorithm::None`]. Claims and headers are otherwise validated like
+    /// the default options.
+    ///
+    /// ## Warning
+    /// These options accept expired and unsecured tokens, and must not be used outside of tests.
+    pub fn lenient_for_tests() -> Self {
+        let mut algorithms = signed_algorithms();
+        algorithms.push(SignatureAlgorithm::None);
+
+        ValidationOptions {
+            algorithms: Validation::Validate(algorithms),
+            issued_at: Validation::Ignored,
+            not_before: Validation::Ignored,
+            expiry: Validation::Ignored,
+            allow_unsecured_tokens: true,
+            base64: Base64Tolerance::lenient(),
+            ..Default::default()
         }
     }
 }
 
+/// Every signature algorithm except [`SignatureAlgorithm::None`]
+fn signed_algorithms() -> Vec<SignatureAlgorithm> {
+    use crate::jwa::SignatureAlgorithm::*;
+
+    vec![
+        HS256, HS384, HS512, RS256, RS384, RS512, ES256, ES384, ES512, PS256, PS384, PS512,
+    ]
+}
+
 impl RegisteredClaims {
     /// Validates that the token contains the claims defined as required
     pub fn validate_claim_presence(
@@ -901,10 +2311,12 @@ impl RegisteredClaims {
         match validation {
             Validation::Ignored => Ok(()),
             Validation::Validate(temporal_options) => {
-                let now = temporal_options.now.unwrap_or_else(Utc::now);
+                let now = temporal_options.current_time();
 
                 match self.expiry {
-                    Some(Timestamp(expiry)) if now - expiry > temporal_options.epsilon => {
+                    Some(Timestamp(expiry))
+                        if now - expiry > temporal_options.epsilon_for_expiry() =>
+                    {
                         Err(ValidationError::Expired(now - expiry))
                     }
                     _ => Ok(()),
@@ -921,10 +2333,12 @@ impl RegisteredClaims {
         match validation {
             Validation::Ignored => Ok(()),
             Validation::Validate(temporal_options) => {
-                let now = temporal_options.now.unwrap_or_else(Utc::now);
+                let now = temporal_options.current_time();
 
                 match self.not_before {
-                    Some(Timestamp(nbf)) if nbf - now > temporal_options.epsilon => {
+                    Some(Timestamp(nbf))
+                        if nbf - now > temporal_options.epsilon_for_not_before() =>
+                    {
                         Err(ValidationError::NotYetValid(nbf - now))
                     }
                     _ => Ok(()),
@@ -941,13 +2355,15 @@ impl RegisteredClaims {
         match validation {
             Validation::Ignored => Ok(()),
             Validation::Validate((max_age, temporal_options)) => {
-                let now = temporal_options.now.unwrap_or_else(Utc::now);
+                let now = temporal_options.current_time();
+
+                let epsilon = temporal_options.epsilon_for_issued_at();
 
                 match self.issued_at {
-                    Some(Timestamp(iat)) if iat - now > temporal_options.epsilon => {
+                    Some(Timestamp(iat)) if iat - now > epsilon => {
                         Err(ValidationError::NotYetValid(iat - now))
                     }
-                    Some(Timestamp(iat)) if now - iat > max_age - temporal_options.epsilon => {
+                    Some(Timestamp(iat)) if now - iat > max_age - epsilon => {
                         Err(ValidationError::TooOld(now - iat - max_age))
                     }
                     _ => Ok(()),
@@ -956,6 +2372,30 @@ impl RegisteredClaims {
         }
     }
 
+    /// Validates that if the token has an `exp` claim, it is no more than the Duration after the
+    /// `iat` claim, or after the current time if there is no `iat` claim
+    pub fn validate_validity(
+        &self,
+        validation: Validation<(Duration, TemporalOptions)>,
+    ) -> Result<(), ValidationError> {
+        match validation {
+            Validation::Ignored => Ok(()),
+            Validation::Validate((max_validity, temporal_options)) => {
+                let start = match self.issued_at {
+                    Some(Timestamp(iat)) => iat,
+                    None => temporal_options.current_time(),
+                };
+
+                match self.expiry {
+                    Some(Timestamp(expiry)) if expiry - start > max_validity => Err(
+                        ValidationError::ValidityTooLong(expiry - start - max_validity),
+                    ),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
     /// Validates that if the token has an `aud` claim, it contains an entry which matches the expected audience
     pub fn validate_aud(&self, validation: Validation<String>) -> Result<(), ValidationError> {
         match validation {
@@ -989,32 +2429,281 @@ impl RegisteredClaims {
         }
     }
 
+    /// Fills in the claims configured in the `IssuanceOptions` that are not already set,
+    /// relative to the current time according to its clock
+    ///
+    /// Returns an error if a `jti` could not be generated.
+    pub fn apply_issuance_options(&mut self, options: &IssuanceOptions) -> Result<(), Error> {
+        let now = options.clock.now();
+        if let (None, Some(expires_in)) = (self.expiry, options.expires_in) {
+            self.expiry = Some((now + expires_in).into());
+        }
+        if let (None, Some(not_before_in)) = (self.not_before, options.not_before_in) {
+            self.not_before = Some((now + not_before_in).into());
+        }
+        if self.issued_at.is_none() && options.issued_at {
+            self.issued_at = Some(now.into());
+        }
+        if let (None, Some(generator)) = (&self.id, &options.id) {
+            self.id = Some(generator.generate()?);
+        }
+        Ok(())
+    }
+
+    /// Validates that the `iss`, `sub` and `aud` claims, if present, are valid [`StringOrUri`]
+    /// values, meaning that any value containing a `:` must be a URI
+    pub fn validate_string_or_uri(&self) -> Result<(), ValidationError> {
+        let is_valid = |value: &String| StringOrUri::new(value.as_str()).is_ok();
+        if !self.issuer.iter().all(is_valid) {
+            Err(ValidationError::MalformedClaim("iss".to_string()))?
+        }
+        if !self.subject.iter().all(is_valid) {
+            Err(ValidationError::MalformedClaim("sub".to_string()))?
+        }
+        if !self
+            .audience
+            .iter()
+            .flat_map(|aud| aud.iter())
+            .all(is_valid)
+        {
+            Err(ValidationError::MalformedClaim("aud".to_string()))?
+        }
+        Ok(())
+    }
+
+    /// Validates that if the token has a `jti` claim, it has not been seen before by the [`ReplayCache`]
+    ///
+    /// The `jti` is recorded in the cache until the token expires, taking the leeway in the
+    /// `TemporalOptions` into account.
+    pub fn validate_jti(
+        &self,
+        validation: Validation<(Arc<dyn ReplayCache>, TemporalOptions)>,
+    ) -> Result<(), ValidationError> {
+        match validation {
+            Validation::Ignored => Ok(()),
+            Validation::Validate((cache, temporal_options)) => match self.id {
+                Some(ref jti) => {
+                    let now = temporal_options.current_time();
+                    let expires_at = self
+                        .expiry
+                        .map(|Timestamp(expiry)| expiry + temporal_options.epsilon_for_expiry());
+                    cache.check_and_store(jti, expires_at, now)
+                }
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Validates that the token has not been revoked, according to the [`Denylist`]
+    pub fn validate_not_revoked(
+        &self,
+        validation: Validation<Arc<dyn Denylist>>,
+    ) -> Result<(), ValidationError> {
+        match validation {
+            Validation::Ignored => Ok(()),
+            Validation::Validate(denylist) => denylist.check(
+                self.id.as_deref(),
+                self.subject.as_deref(),
+                self.issued_at.map(|Timestamp(issued_at)| issued_at),
+            ),
+        }
+    }
+
     /// Performs full validation of the token according to the `ValidationOptions` supplied
     ///
     /// First it validates that all claims marked as required are present
     /// Then it validates each claim marked to be validated if they are present in the token
     /// (even those that are not marked as required, but are present).
+    /// The `jti` is checked against the replay cache last, so that tokens which fail
+    /// validation otherwise are not recorded.
+    ///
+    /// Validation stops at the first failure. Use [`RegisteredClaims::validate_all`] to
+    /// find out about every failure instead.
     pub fn validate(&self, options: ValidationOptions) -> Result<(), ValidationError> {
-        self.validate_claim_presence(options.claim_presence_options)?;
-        self.validate_exp(options.expiry.map(|_| options.temporal_options))?;
-        self.validate_nbf(options.not_before.map(|_| options.temporal_options))?;
-        self.validate_iat(options.issued_at.map(|dur| (dur, options.temporal_options)))?;
-
-        self.validate_iss(options.issuer)?;
-        self.validate_aud(options.audience)?;
+        let mut failures = ValidationFailures::fail_fast();
+        validate_claims_set(self, None, options, &mut failures)
+    }
 
-        //        self.validate_sub(options.subject_validated)?;
-        //        self.validate_custom(options.custom_validation)?;
+    /// Performs full validation of the token like [`RegisteredClaims::validate`], but runs every
+    /// check and reports every failure instead of stopping at the first one.
+    ///
+    /// The `jti` is only checked against the replay cache if every other check passed.
+    pub fn validate_all(&self, options: ValidationOptions) -> Result<(), ValidationReport> {
+        let mut failures = ValidationFailures::collect_all();
+        // Failures are never returned early when collecting all of them
+        let _ = validate_claims_set(self, None, options, &mut failures);
+        failures.into_result()
+    }
 
-        Ok(())
+    /// Returns the registered claims marked as required in `options` that are missing
+    fn missing_claims(&self, options: ClaimPresenceOptions) -> Vec<String> {
+        match self.validate_claim_presence(options) {
+            Err(ValidationError::MissingRequiredClaims(missing_claims)) => missing_claims,
+            _ => vec![],
+        }
     }
 }
 
-/// A collection of claims, both [registered](https://tools.ietf.org/html/rfc7519#section-4.1) and your custom
-/// private claims.
-#[derive(Debug, Eq, PartialEq, Clone, Default, Serialize, Deserialize)]
-pub struct ClaimsSet<T> {
-    /// Registered claims defined by the RFC
+/// Accumulates validation failures, either by stopping at the first failure or by collecting
+/// every failure into a `ValidationReport`
+struct ValidationFailures {
+    fail_fast: bool,
+    errors: Vec<ValidationError>,
+}
+
+impl ValidationFailures {
+    fn fail_fast() -> Self {
+        Self {
+            fail_fast: true,
+            errors: vec![],
+        }
+    }
+
+    fn collect_all() -> Self {
+        Self {
+            fail_fast: false,
+            errors: vec![],
+        }
+    }
+
+    /// Record the result of a check. Returns the error if validation should stop.
+    fn check(&mut self, result: Result<(), ValidationError>) -> Result<(), ValidationError> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) if self.fail_fast => Err(e),
+            Err(e) => {
+                self.errors.push(e);
+                Ok(())
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn into_result(self) -> Result<(), ValidationReport> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationReport::new(self.errors))
+        }
+    }
+}
+
+/// Returns every claim required by `options` that is missing. Private claims in `required_claims`
+/// are only checked if the JSON form of the whole claims set is provided in `claims`.
+fn missing_claims(
+    registered: &RegisteredClaims,
+    claims: Option<&serde_json::Map<String, serde_json::Value>>,
+    options: &ValidationOptions,
+) -> Vec<String> {
+    let mut missing_claims = registered.missing_claims(options.claim_presence_options);
+    if let Some(claims) = claims {
+        for claim in &options.required_claims {
+            let present = claims
+                .get(claim)
+                .map(|value| !value.is_null())
+                .unwrap_or(false);
+            if !present && !missing_claims.contains(claim) {
+                missing_claims.push(claim.clone());
+            }
+        }
+    }
+    missing_claims
+}
+
+/// Returns every claim forbidden by `options` that is present in `claims`
+fn forbidden_claims(
+    claims: &serde_json::Map<String, serde_json::Value>,
+    options: &ValidationOptions,
+) -> Vec<String> {
+    options
+        .forbidden_claims
+        .iter()
+        .filter(|claim| {
+            claims
+                .get(*claim)
+                .map(|value| !value.is_null())
+                .unwrap_or(false)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Runs every check configured in `options`. `claims` is the JSON form of the whole claims set and
+/// is required for `required_claims`, `forbidden_claims` and custom validators; they are skipped
+/// if it is `None`.
+fn validate_claims_set(
+    registered: &RegisteredClaims,
+    claims: Option<&serde_json::Map<String, serde_json::Value>>,
+    options: ValidationOptions,
+    failures: &mut ValidationFailures,
+) -> Result<(), ValidationError> {
+    let missing_claims = missing_claims(registered, claims, &options);
+    if !missing_claims.is_empty() {
+        failures.check(Err(ValidationError::MissingRequiredClaims(missing_claims)))?;
+    }
+    if let Some(claims) = claims {
+        let forbidden_claims = forbidden_claims(claims, &options);
+        if !forbidden_claims.is_empty() {
+            failures.check(Err(ValidationError::ForbiddenClaims(forbidden_claims)))?;
+        }
+    }
+
+    let temporal_options = options.temporal_options;
+    failures.check(registered.validate_exp(options.expiry.map(|_| temporal_options.clone())))?;
+    failures
+        .check(registered.validate_nbf(options.not_before.map(|_| temporal_options.clone())))?;
+    failures.check(
+        registered.validate_iat(options.issued_at.map(|dur| (dur, temporal_options.clone()))),
+    )?;
+    failures.check(
+        registered.validate_validity(
+            options
+                .max_validity
+                .map(|dur| (dur, temporal_options.clone())),
+        ),
+    )?;
+
+    failures.check(registered.validate_iss(options.issuer))?;
+    failures.check(registered.validate_aud(options.audience))?;
+
+    if let Some(claims) = claims {
+        for validator in &options.validators {
+            failures.check(validator.validate(claims))?;
+        }
+    }
+
+    // Only consult the denylist and record the `jti` of tokens that are otherwise valid
+    if failures.is_empty() {
+        failures.check(registered.validate_not_revoked(options.denylist))?;
+    }
+    if failures.is_empty() {
+        failures
+            .check(registered.validate_jti(options.id.map(|cache| (cache, temporal_options))))?;
+    }
+
+    Ok(())
+}
+
+/// What to do when private claims have the same names as registered claims, when merging them
+/// with [`ClaimsSet::merge`]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ClaimCollisions {
+    /// Return [`Error::ClaimCollision`] with the colliding claims
+    Reject,
+    /// Keep the registered claims and drop the colliding private claims
+    RegisteredWins,
+    /// Replace the registered claims with the colliding private claims
+    PrivateWins,
+}
+
+/// A collection of claims, both [registered](https://tools.ietf.org/html/rfc7519#section-4.1) and your custom
+/// private claims.
+#[derive(Debug, Eq, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct ClaimsSet<T> {
+    /// Registered claims defined by the RFC
     #[serde(flatten)]
     pub registered: RegisteredClaims,
     /// Application specific claims
@@ -1024,6 +2713,300 @@ pub struct ClaimsSet<T> {
 
 impl<T> CompactJson for ClaimsSet<T> where T: Serialize + DeserializeOwned {}
 
+impl<T> ClaimsSet<T>
+where
+    T: Serialize,
+{
+    /// Validates that the claims set contains the registered claims marked as required in
+    /// `options.claim_presence_options` as well as every claim listed in `options.required_claims`.
+    ///
+    /// Every missing claim is reported in the returned error.
+    pub fn validate_claim_presence(&self, options: &ValidationOptions) -> Result<(), Error> {
+        let claims = self.to_json_map()?;
+        let missing_claims = missing_claims(&self.registered, Some(&claims), options);
+        if missing_claims.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::MissingRequiredClaims(missing_claims))?
+        }
+    }
+
+    /// Runs the custom `validators` from `options` against the claims set, in order
+    pub fn validate_custom(&self, options: &ValidationOptions) -> Result<(), Error> {
+        if options.validators.is_empty() {
+            return Ok(());
+        }
+        let claims = self.to_json_map()?;
+        for validator in &options.validators {
+            validator.validate(&claims)?;
+        }
+        Ok(())
+    }
+
+    /// Performs full validation of the claims set according to the `ValidationOptions` supplied
+    ///
+    /// This first validates that every required claim, registered or private, is present
+    /// before validating the registered claims like [`RegisteredClaims::validate`] does.
+    /// The custom `validators` are then run, and finally the `jti` is checked against the
+    /// replay cache.
+    ///
+    /// Validation stops at the first failure. Use [`ClaimsSet::validate_all`] to
+    /// find out about every failure instead.
+    pub fn validate(&self, options: ValidationOptions) -> Result<(), Error> {
+        let claims = self.json_map_if_needed(&options)?;
+        let mut failures = ValidationFailures::fail_fast();
+        validate_claims_set(&self.registered, claims.as_ref(), options, &mut failures)?;
+        Ok(())
+    }
+
+    /// Performs full validation of the claims set like [`ClaimsSet::validate`], but runs every
+    /// check and reports every failure in a [`ValidationReport`] instead of stopping at the first one.
+    ///
+    /// The `jti` is only checked against the replay cache if every other check passed.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::{ClaimPresenceOptions, ClaimsSet, Empty, Presence, RegisteredClaims, Validation,
+    ///               ValidationOptions};
+    /// use biscuit::errors::Error;
+    ///
+    /// let claims = ClaimsSet::<Empty> {
+    ///     registered: RegisteredClaims {
+    ///         issuer: Some("https://www.acme.com".to_string()),
+    ///         expiry: Some(1234.into()),
+    ///         ..Default::default()
+    ///     },
+    ///     private: Default::default(),
+    /// };
+    ///
+    /// let options = ValidationOptions {
+    ///     claim_presence_options: ClaimPresenceOptions {
+    ///         subject: Presence::Required,
+    ///         ..Default::default()
+    ///     },
+    ///     issuer: Validation::Validate("https://www.ecorp.com".to_string()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// match claims.validate_all(options) {
+    ///     Err(Error::ValidationReport(report)) => assert_eq!(report.len(), 3),
+    ///     _ => panic!("Expected validation to fail"),
+    /// }
+    /// ```
+    pub fn validate_all(&self, options: ValidationOptions) -> Result<(), Error> {
+        self.validate_all_after(options, vec![])
+    }
+
+    /// Validate the claims set like [`ClaimsSet::validate_all`], reporting `errors` from earlier
+    /// checks, such as of the header, first. The denylist and the replay cache are not consulted
+    /// if there are any.
+    pub(crate) fn validate_all_after(
+        &self,
+        options: ValidationOptions,
+        errors: Vec<ValidationError>,
+    ) -> Result<(), Error> {
+        let claims = self.json_map_if_needed(&options)?;
+        let mut failures = ValidationFailures::collect_all();
+        failures.errors = errors;
+        // Failures are never returned early when collecting all of them
+        let _ = validate_claims_set(&self.registered, claims.as_ref(), options, &mut failures);
+        failures.into_result()?;
+        Ok(())
+    }
+
+    /// Serialize the claims set into a JSON object, only if `options` requires it
+    fn json_map_if_needed(
+        &self,
+        options: &ValidationOptions,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>, Error> {
+        if options.required_claims.is_empty()
+            && options.forbidden_claims.is_empty()
+            && options.validators.is_empty()
+        {
+            Ok(None)
+        } else {
+            Ok(Some(self.to_json_map()?))
+        }
+    }
+
+    /// Merge the registered and private claims into a single claims set, resolving private claims
+    /// with the same names as registered claims according to `collisions`
+    ///
+    /// Serializing a claims set with colliding claims produces duplicate keys in the JSON object,
+    /// which cannot be deserialized again. The merged claims set has no collisions: private claims
+    /// named like registered claims are moved to the registered claims.
+    ///
+    /// A private claim that replaces a registered claim must have the type of that claim, or
+    /// [`Error::JsonError`] is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use biscuit::{ClaimCollisions, ClaimsSet, RegisteredClaims};
+    /// use serde_json::json;
+    ///
+    /// let claims = ClaimsSet {
+    ///     registered: RegisteredClaims {
+    ///         subject: Some("John Doe".to_string()),
+    ///         ..Default::default()
+    ///     },
+    ///     private: json!({ "sub": "Jane Doe", "company": "ACME" }),
+    /// };
+    ///
+    /// assert!(claims.merge(ClaimCollisions::Reject).is_err());
+    /// let merged = claims.merge(ClaimCollisions::PrivateWins).unwrap();
+    /// assert_eq!(merged.registered.subject.as_deref(), Some("Jane Doe"));
+    /// assert_eq!(serde_json::to_value(&merged.private).unwrap(), json!({ "company": "ACME" }));
+    /// ```
+    pub fn merge(
+        &self,
+        collisions: ClaimCollisions,
+    ) -> Result<ClaimsSet<serde_json::Map<String, serde_json::Value>>, Error> {
+        let mut merged = match serde_json::to_value(&self.registered)? {
+            serde_json::Value::Object(map) => map,
+            _ => unreachable!("registered claims are serialized to a JSON object"),
+        };
+        let private = match serde_json::to_value(&self.private)? {
+            serde_json::Value::Object(map) => map,
+            _ => Err(Error::NotAnObject)?,
+        };
+
+        let mut colliding = vec![];
+        for (name, value) in private {
+            if !merged.contains_key(&name) {
+                let _ = merged.insert(name, value);
+                continue;
+            }
+            match collisions {
+                ClaimCollisions::Reject => colliding.push(name),
+                ClaimCollisions::RegisteredWins => {}
+                ClaimCollisions::PrivateWins => {
+                    let _ = merged.insert(name, value);
+                }
+            }
+        }
+        if !colliding.is_empty() {
+            Err(Error::ClaimCollision(colliding))?
+        }
+
+        Ok(serde_json::from_value(serde_json::Value::Object(merged))?)
+    }
+
+    /// Serialize the claims set into a JSON object
+    fn to_json_map(&self) -> Result<serde_json::Map<String, serde_json::Value>, Error> {
+        match serde_json::to_value(self)? {
+            serde_json::Value::Object(map) => Ok(map),
+            _ => Err(Error::NotAnObject),
+        }
+    }
+}
+
+/// A struct of custom claims that holds the registered claims alongside the private claims,
+/// usually by flattening a [`RegisteredClaims`] field, as an alternative to [`ClaimsSet`]
+///
+/// With the `derive` feature, `#[derive(Claims)]` implements this trait along with
+/// [`CompactJson`]. Use [`numeric_date`] to serialize `DateTime<Utc>` private claims as
+/// NumericDates, or `#[biscuit::claims]`, which adds those serde attributes and derives `Claims`.
+/// Decode tokens into such a struct with [`decode_claims`].
+///
+/// # Examples
+/// ```
+/// use biscuit::{Claims, RegisteredClaims, ValidationOptions};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct MyClaims {
+///     #[serde(flatten)]
+///     registered: RegisteredClaims,
+///     tenant: String,
+/// }
+///
+/// impl Claims for MyClaims {
+///     fn registered(&self) -> &RegisteredClaims {
+///         &self.registered
+///     }
+/// }
+///
+/// let claims = MyClaims {
+///     registered: RegisteredClaims {
+///         issuer: Some("https://www.acme.com".to_string()),
+///         ..Default::default()
+///     },
+///     tenant: "ACME".to_string(),
+/// };
+/// assert_eq!(claims.issuer(), Some("https://www.acme.com"));
+/// assert!(claims.validate(ValidationOptions::default()).is_ok());
+/// ```
+pub trait Claims: Serialize {
+    /// The registered claims
+    fn registered(&self) -> &RegisteredClaims;
+
+    /// The `iss` claim
+    fn issuer(&self) -> Option<&str> {
+        self.registered().issuer.as_deref()
+    }
+
+    /// The `sub` claim
+    fn subject(&self) -> Option<&str> {
+        self.registered().subject.as_deref()
+    }
+
+    /// The `aud` claim
+    fn audience(&self) -> Option<&SingleOrMultiple<String>> {
+        self.registered().audience.as_ref()
+    }
+
+    /// The `exp` claim
+    fn expiry(&self) -> Option<Timestamp> {
+        self.registered().expiry
+    }
+
+    /// The `nbf` claim
+    fn not_before(&self) -> Option<Timestamp> {
+        self.registered().not_before
+    }
+
+    /// The `iat` claim
+    fn issued_at(&self) -> Option<Timestamp> {
+        self.registered().issued_at
+    }
+
+    /// The `jti` claim
+    fn id(&self) -> Option<&str> {
+        self.registered().id.as_deref()
+    }
+
+    /// Validates the claims like [`ClaimsSet::validate`]
+    fn validate(&self, options: ValidationOptions) -> Result<(), Error> {
+        let claims = if options.required_claims.is_empty()
+            && options.forbidden_claims.is_empty()
+            && options.validators.is_empty()
+        {
+            None
+        } else {
+            match serde_json::to_value(self)? {
+                serde_json::Value::Object(map) => Some(map),
+                _ => Err(Error::NotAnObject)?,
+            }
+        };
+        let mut failures = ValidationFailures::fail_fast();
+        validate_claims_set(self.registered(), claims.as_ref(), options, &mut failures)?;
+        Ok(())
+    }
+}
+
+impl<T> Claims for ClaimsSet<T>
+where
+    T: Serialize,
+{
+    fn registered(&self) -> &RegisteredClaims {
+        &self.registered
+    }
+
+    fn validate(&self, options: ValidationOptions) -> Result<(), Error> {
+        ClaimsSet::validate(self, options)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::{self, FromStr};
@@ -1067,6 +3050,29 @@ mod tests {
         assert!(!deserialized.values.contains("does not exist"));
     }
 
+    #[test]
+    fn single_or_multiple_helpers() {
+        let single: SingleOrMultiple<u32> = 1.into();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single.first(), Some(&1));
+        assert_eq!(single.as_slice(), &[1]);
+        assert!(single.contains(&1));
+        assert_eq!(single.clone().into_vec(), vec![1]);
+
+        let multiple: SingleOrMultiple<u32> = vec![1, 2, 3].into();
+        assert_eq!(multiple.len(), 3);
+        assert!(multiple.contains(&3));
+        assert!(!multiple.contains(&4));
+        assert_eq!((&multiple).into_iter().sum::<u32>(), 6);
+        assert_eq!(multiple.iter().count(), 3);
+        assert_eq!(Vec::from(multiple), vec![1, 2, 3]);
+
+        let empty = SingleOrMultiple::<u32>::Multiple(vec![]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.first(), None);
+        assert_eq!(empty.into_iter().count(), 0);
+    }
+
     #[test]
     fn multiple_strings_serialization_round_trip() {
         let test = SingleOrMultipleStrings {
@@ -1166,6 +3172,60 @@ mod tests {
         assert_eq!(fixed_time, deserialized);
     }
 
+    #[test]
+    fn timestamp_out_of_range_is_rejected() {
+        let result = serde_json::from_str::<Timestamp>("9223372036854775807");
+        assert!(result.is_err());
+
+        let result = serde_json::from_str::<RegisteredClaims>(r#"{"exp":-9223372036854775808}"#);
+        assert!(result.is_err());
+
+        assert!(Timestamp::from_seconds(9_223_372_036_854_775_807).is_none());
+        assert_eq!(Timestamp::from_seconds(1000), Some(1000.into()));
+    }
+
+    #[test]
+    fn timestamp_deserializes_non_integer_numeric_dates() {
+        let expected: Timestamp = 1234.into();
+        for input in &["1234", "1234.9", "1234e0", r#""1234""#, r#"" 1234.5 ""#] {
+            let timestamp: Timestamp = not_err!(serde_json::from_str(input));
+            assert_eq!(timestamp, expected, "{}", input);
+        }
+
+        let negative: Timestamp = not_err!(serde_json::from_str("-1234.9"));
+        assert_eq!(negative, (-1234).into());
+
+        for input in &[r#""soon""#, r#""""#, r#""NaN""#, "1e300", "true", "null"] {
+            assert!(
+                serde_json::from_str::<Timestamp>(input).is_err(),
+                "{}",
+                input
+            );
+        }
+
+        let claims: RegisteredClaims =
+            not_err!(serde_json::from_str(r#"{"exp":"1234","iat":1234.5}"#));
+        assert_eq!(claims.expiry, Some(expected));
+        assert_eq!(claims.issued_at, Some(expected));
+        assert_eq!(not_err!(serde_json::to_string(&expected)), "1234");
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn timestamp_converts_to_and_from_offset_date_time() {
+        let datetime = not_err!(time::OffsetDateTime::from_unix_timestamp(1234))
+            .to_offset(not_err!(time::UtcOffset::from_hms(2, 0, 0)));
+        let timestamp = not_err!(Timestamp::try_from(datetime));
+        assert_eq!(timestamp, 1234.into());
+        assert_eq!(not_err!(serde_json::to_string(&timestamp)), "1234");
+
+        let round_trip = not_err!(time::OffsetDateTime::try_from(timestamp));
+        assert_eq!(round_trip, datetime);
+
+        let far_future = Timestamp::from_seconds(1 << 40).unwrap();
+        assert!(time::OffsetDateTime::try_from(far_future).is_err());
+    }
+
     #[test]
     fn empty_registered_claims_serialization_round_trip() {
         let claim = RegisteredClaims::default();
@@ -1255,6 +3315,49 @@ mod tests {
         assert!(error.contains("duplicate field `sub`"));
     }
 
+    #[test]
+    fn merge_duplicate_claims() {
+        let claim = ClaimsSet::<InvalidPrivateClaim> {
+            registered: RegisteredClaims {
+                subject: Some("John Doe".to_string()),
+                ..Default::default()
+            },
+            private: InvalidPrivateClaim {
+                sub: "Jane Doe".to_string(),
+                company: "ACME".to_string(),
+            },
+        };
+
+        match claim.merge(ClaimCollisions::Reject) {
+            Err(Error::ClaimCollision(claims)) => assert_eq!(claims, vec!["sub".to_string()]),
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let merged = not_err!(claim.merge(ClaimCollisions::RegisteredWins));
+        assert_eq!(merged.registered.subject.as_deref(), Some("John Doe"));
+        let json = not_err!(serde_json::to_string(&merged));
+        assert_eq!(1, json.matches("\"sub\"").count());
+
+        let merged = not_err!(claim.merge(ClaimCollisions::PrivateWins));
+        assert_eq!(merged.registered.subject.as_deref(), Some("Jane Doe"));
+        assert_eq!(
+            serde_json::Value::Object(merged.private),
+            serde_json::json!({ "company": "ACME" })
+        );
+
+        // Private claims can only replace registered claims with values of the same type
+        let claim = ClaimsSet {
+            registered: RegisteredClaims {
+                expiry: Some(1234.into()),
+                ..Default::default()
+            },
+            private: serde_json::json!({ "exp": "tomorrow" }),
+        };
+        assert!(claim.merge(ClaimCollisions::PrivateWins).is_err());
+        let merged = not_err!(claim.merge(ClaimCollisions::RegisteredWins));
+        assert_eq!(merged.registered.expiry, Some(1234.into()));
+    }
+
     #[test]
     #[should_panic(expected = "MissingRequiredClaims([\"iat\"])")]
     fn validate_times_missing_iat() {
@@ -1409,6 +3512,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_times_with_clock() {
+        let registered_claims = RegisteredClaims {
+            expiry: Some(100.into()),
+            ..Default::default()
+        };
+
+        let temporal_options = TemporalOptions {
+            clock: Arc::new(FixedClock(Utc.timestamp(101, 0))),
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(ValidationError::Expired(Duration::seconds(1))),
+            registered_claims.validate_exp(Validation::Validate(temporal_options))
+        );
+
+        let temporal_options = TemporalOptions {
+            clock: Arc::new(|| Utc.timestamp(99, 0)),
+            ..Default::default()
+        };
+        not_err!(registered_claims.validate_exp(Validation::Validate(temporal_options)));
+
+        // `now` takes precedence over the clock
+        let temporal_options = TemporalOptions {
+            now: Some(Utc.timestamp(99, 0)),
+            clock: Arc::new(FixedClock(Utc.timestamp(101, 0))),
+            ..Default::default()
+        };
+        not_err!(registered_claims.validate_exp(Validation::Validate(temporal_options)));
+    }
+
     #[test]
     fn validate_times_valid_token_with_default_options() {
         let registered_claims = RegisteredClaims {
@@ -1445,6 +3579,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn apply_issuance_options_fills_in_missing_claims() {
+        let now = Utc.timestamp(1000, 0);
+        let options = IssuanceOptions {
+            expires_in: Some(Duration::seconds(60)),
+            not_before_in: Some(Duration::zero()),
+            issued_at: true,
+            id: Some(Arc::new(|| Ok("generated".to_string()))),
+            clock: Arc::new(FixedClock(now)),
+            ..Default::default()
+        };
+
+        let mut registered_claims = RegisteredClaims::default();
+        not_err!(registered_claims.apply_issuance_options(&options));
+        assert_eq!(registered_claims.expiry, Some(1060.into()));
+        assert_eq!(registered_claims.not_before, Some(1000.into()));
+        assert_eq!(registered_claims.issued_at, Some(1000.into()));
+
+        let mut registered_claims = RegisteredClaims {
+            issued_at: Some(10.into()),
+            ..Default::default()
+        };
+        not_err!(registered_claims.apply_issuance_options(&IssuanceOptions {
+            issued_at: true,
+            id: Some(Arc::new(|| Ok("generated".to_string()))),
+            ..Default::default()
+        }));
+        assert_eq!(registered_claims.issued_at, Some(10.into()));
+        assert_eq!(registered_claims.id, Some("generated".to_string()));
+        assert_eq!(registered_claims.expiry, None);
+    }
+
+    #[test]
+    fn validate_string_or_uri_claims() {
+        let registered_claims = RegisteredClaims {
+            issuer: Some("https://issuer.example.com".to_string()),
+            subject: Some("subject".to_string()),
+            audience: Some(SingleOrMultiple::Multiple(vec![
+                "audience".to_string(),
+                "urn:audience".to_string(),
+            ])),
+            ..Default::default()
+        };
+        not_err!(registered_claims.validate_string_or_uri());
+
+        let registered_claims = RegisteredClaims {
+            audience: Some(SingleOrMultiple::Multiple(vec![
+                "audience".to_string(),
+                "not a: uri".to_string(),
+            ])),
+            ..registered_claims
+        };
+        assert_eq!(
+            Err(ValidationError::MalformedClaim("aud".to_string())),
+            registered_claims.validate_string_or_uri()
+        );
+    }
+
     #[test]
     fn validate_audience_when_single() {
         let aud = SingleOrMultiple::Single("audience".to_string());
@@ -1530,23 +3722,688 @@ mod tests {
             issued_at: Validation::Validate(Duration::max_value()),
             audience: Validation::Validate("audience".to_string()),
             issuer: Validation::Validate("issuer".to_string()),
+            id: Validation::Validate(Arc::new(InMemoryReplayCache::new(1))),
+            ..Default::default()
         };
 
         not_err!(registered_claims.validate(validation_options));
     }
 
     #[test]
-    fn validate_times_valid_token_with_epsilon() {
-        let registered_claims = RegisteredClaims {
-            expiry: Some(99.into()),
-            not_before: Some(96.into()),
-            issued_at: Some(96.into()),
-            ..Default::default()
+    fn validate_claims_set_missing_private_claims() {
+        let claims_set = ClaimsSet::<PrivateClaims> {
+            registered: RegisteredClaims {
+                issuer: Some("issuer".to_string()),
+                ..Default::default()
+            },
+            private: PrivateClaims {
+                company: "ACME".to_string(),
+                department: "Toilet Cleaning".to_string(),
+            },
         };
 
-        let temporal_options = TemporalOptions {
-            now: Some(Utc.timestamp(100, 0)),
-            epsilon: Duration::seconds(10),
+        let validation_options = ValidationOptions {
+            claim_presence_options: ClaimPresenceOptions {
+                subject: Presence::Required,
+                ..Default::default()
+            },
+            required_claims: vec![
+                "iss".to_string(),
+                "company".to_string(),
+                "tenant".to_string(),
+                "sub".to_string(),
+                "scope".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let error = claims_set.validate(validation_options).unwrap_err();
+        match error {
+            Error::ValidationError(ValidationError::MissingRequiredClaims(missing)) => {
+                assert_eq!(missing, vec!["sub", "tenant", "scope"]);
+            }
+            other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_claim_presence_only_checks_presence() {
+        let claims_set = ClaimsSet::<PrivateClaims> {
+            registered: RegisteredClaims {
+                expiry: Some(1.into()),
+                ..Default::default()
+            },
+            private: PrivateClaims {
+                company: "ACME".to_string(),
+                department: "Toilet Cleaning".to_string(),
+            },
+        };
+
+        let validation_options = ValidationOptions {
+            required_claims: vec!["exp".to_string(), "company".to_string()],
+            ..Default::default()
+        };
+
+        not_err!(claims_set.validate_claim_presence(&validation_options));
+        assert!(claims_set.validate(validation_options).is_err());
+    }
+
+    #[test]
+    fn validate_claims_set_with_required_private_claims() {
+        let claims_set = ClaimsSet::<PrivateClaims> {
+            registered: RegisteredClaims {
+                issuer: Some("issuer".to_string()),
+                ..Default::default()
+            },
+            private: PrivateClaims {
+                company: "ACME".to_string(),
+                department: "Toilet Cleaning".to_string(),
+            },
+        };
+
+        let validation_options = ValidationOptions {
+            required_claims: vec!["iss".to_string(), "department".to_string()],
+            ..Default::default()
+        };
+
+        not_err!(claims_set.validate(validation_options));
+    }
+
+    #[test]
+    fn validate_claims_set_with_forbidden_claims() {
+        let claims_set = ClaimsSet::<PrivateClaims> {
+            registered: RegisteredClaims {
+                expiry: Some(Timestamp::from(Utc::now() + Duration::minutes(5))),
+                ..Default::default()
+            },
+            private: PrivateClaims {
+                company: "ACME".to_string(),
+                department: "Toilet Cleaning".to_string(),
+            },
+        };
+
+        let validation_options = ValidationOptions {
+            forbidden_claims: vec!["nonce".to_string()],
+            ..Default::default()
+        };
+        not_err!(claims_set.validate(validation_options));
+
+        let validation_options = ValidationOptions {
+            forbidden_claims: vec![
+                "exp".to_string(),
+                "nonce".to_string(),
+                "company".to_string(),
+            ],
+            ..Default::default()
+        };
+        match claims_set.validate(validation_options).unwrap_err() {
+            Error::ValidationError(ValidationError::ForbiddenClaims(claims)) => {
+                assert_eq!(claims, vec!["exp".to_string(), "company".to_string()])
+            }
+            other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_security_event_token_profile() {
+        let options = || {
+            ValidationOptions::security_event_token(
+                "https://idp.example.com/".to_string(),
+                "https://sp.example.com/".to_string(),
+            )
+        };
+        let mut claims_set = ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some("https://idp.example.com/".to_string()),
+                audience: Some(SingleOrMultiple::Single(
+                    "https://sp.example.com/".to_string(),
+                )),
+                issued_at: Some(Timestamp::from(Utc::now())),
+                id: Some("4d3559ec67504aaba65d40b0363faad8".to_string()),
+                ..Default::default()
+            },
+            private: secevent::SecurityEventClaims::default()
+                .with_event("urn:example:event", Default::default()),
+        };
+        not_err!(claims_set.validate(options()));
+
+        claims_set.registered.expiry = Some(Timestamp::from(Utc::now() + Duration::minutes(5)));
+        match claims_set.validate(options()).unwrap_err() {
+            Error::ValidationError(ValidationError::ForbiddenClaims(claims)) => {
+                assert_eq!(claims, vec!["exp".to_string()])
+            }
+            other => panic!("Unexpected error {:?}", other),
+        }
+        assert_eq!(
+            options().token_type,
+            Validation::Validate("secevent+jwt".to_string())
+        );
+
+        let secret = jws::Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let token = not_err!(Token::builder()
+            .issuer("https://idp.example.com/")
+            .audience("https://sp.example.com/")
+            .issued_now()
+            .id("4d3559ec67504aaba65d40b0363faad8")
+            .claim("events", serde_json::json!({ "urn:example:event": {} }))
+            .media_type(secevent::MEDIA_TYPE)
+            .sign(&secret, SignatureAlgorithm::HS256));
+        let decoded = not_err!(decode::<secevent::SecurityEventClaims>(
+            &token,
+            &secret,
+            &options()
+        ));
+        assert_eq!(decoded.private, claims_set.private);
+    }
+
+    #[test]
+    fn validate_logout_token_profile() {
+        let options = || {
+            ValidationOptions::logout_token(
+                "https://server.example.com".to_string(),
+                "s6BhdRkqt3".to_string(),
+                Duration::minutes(2),
+            )
+        };
+        let claims_set = |issued_at| ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some("https://server.example.com".to_string()),
+                audience: Some(SingleOrMultiple::Single("s6BhdRkqt3".to_string())),
+                issued_at: Some(Timestamp::from(issued_at)),
+                id: Some("bWJq".to_string()),
+                subject: Some("248289761001".to_string()),
+                ..Default::default()
+            },
+            private: serde_json::json!({ "events": { oidc::BACKCHANNEL_LOGOUT_EVENT: {} } }),
+        };
+
+        not_err!(claims_set(Utc::now()).validate(options()));
+        match claims_set(Utc::now() - Duration::minutes(5))
+            .validate(options())
+            .unwrap_err()
+        {
+            Error::ValidationError(ValidationError::TooOld(_)) => {}
+            other => panic!("Unexpected error {:?}", other),
+        }
+
+        let secret = jws::Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let header = From::from(jws::RegisteredHeader {
+            algorithm: SignatureAlgorithm::HS256,
+            ..Default::default()
+        });
+        let token =
+            not_err!(jws::Compact::new_decoded(header, claims_set(Utc::now())).encode(&secret));
+        let token = token.unwrap_encoded().to_string();
+        let decoded = not_err!(decode::<serde_json::Value>(&token, &secret, &options()));
+        assert_eq!(decoded.registered.id, Some("bWJq".to_string()));
+    }
+
+    #[test]
+    fn validate_claims_set_with_custom_validators() {
+        let claims_set = ClaimsSet::<PrivateClaims> {
+            registered: Default::default(),
+            private: PrivateClaims {
+                company: "ACME".to_string(),
+                department: "Toilet Cleaning".to_string(),
+            },
+        };
+
+        let company_is = |expected: &'static str| {
+            Arc::new(move |claims: &serde_json::Map<String, serde_json::Value>| {
+                if claims.get("company").and_then(|c| c.as_str()) == Some(expected) {
+                    Ok(())
+                } else {
+                    Err(ValidationError::CustomValidation(format!(
+                        "company is not {}",
+                        expected
+                    )))
+                }
+            })
+        };
+
+        let validation_options = ValidationOptions {
+            validators: vec![company_is("ACME")],
+            ..Default::default()
+        };
+        not_err!(claims_set.validate(validation_options));
+
+        let validation_options = ValidationOptions {
+            validators: vec![company_is("ACME"), company_is("ECorp")],
+            ..Default::default()
+        };
+        match claims_set.validate(validation_options).unwrap_err() {
+            Error::ValidationError(ValidationError::CustomValidation(reason)) => {
+                assert_eq!(reason, "company is not ECorp")
+            }
+            other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_jti_not_recorded_when_custom_validation_fails() {
+        let cache = Arc::new(InMemoryReplayCache::new(16));
+        let claims_set = ClaimsSet::<Empty> {
+            registered: RegisteredClaims {
+                id: Some("id".to_string()),
+                ..Default::default()
+            },
+            private: Default::default(),
+        };
+
+        let validation_options = ValidationOptions {
+            id: Validation::Validate(cache.clone()),
+            validators: vec![Arc::new(
+                |_: &serde_json::Map<String, serde_json::Value>| {
+                    Err(ValidationError::CustomValidation("rejected".to_string()))
+                },
+            )],
+            ..Default::default()
+        };
+
+        assert!(claims_set.validate(validation_options).is_err());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn validate_all_reports_every_failure() {
+        let registered_claims = RegisteredClaims {
+            expiry: Some(1.into()),
+            audience: Some(SingleOrMultiple::Single("audience".to_string())),
+            ..Default::default()
+        };
+
+        let validation_options = ValidationOptions {
+            temporal_options: TemporalOptions {
+                now: Some(Utc.timestamp(2, 0)),
+                ..Default::default()
+            },
+            claim_presence_options: ClaimPresenceOptions {
+                subject: Presence::Required,
+                ..Default::default()
+            },
+            audience: Validation::Validate("another audience".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "sub".to_string()
+            ])),
+            registered_claims.validate(validation_options.clone())
+        );
+
+        let report = registered_claims
+            .validate_all(validation_options)
+            .unwrap_err();
+        assert_eq!(
+            report.errors(),
+            &[
+                ValidationError::MissingRequiredClaims(vec!["sub".to_string()]),
+                ValidationError::Expired(Duration::seconds(1)),
+                ValidationError::InvalidAudience(SingleOrMultiple::Single("audience".to_string())),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn validate_all_claims_set_includes_custom_validators() {
+        let claims_set = ClaimsSet::<PrivateClaims> {
+            registered: RegisteredClaims {
+                id: Some("id".to_string()),
+                issuer: Some("issuer".to_string()),
+                ..Default::default()
+            },
+            private: PrivateClaims {
+                company: "ACME".to_string(),
+                department: "Toilet Cleaning".to_string(),
+            },
+        };
+        let cache = Arc::new(InMemoryReplayCache::new(16));
+
+        let validation_options = ValidationOptions {
+            issuer: Validation::Validate("another issuer".to_string()),
+            required_claims: vec!["tenant".to_string()],
+            validators: vec![Arc::new(
+                |_: &serde_json::Map<String, serde_json::Value>| {
+                    Err(ValidationError::CustomValidation("rejected".to_string()))
+                },
+            )],
+            id: Validation::Validate(cache.clone()),
+            ..Default::default()
+        };
+
+        match claims_set.validate_all(validation_options).unwrap_err() {
+            Error::ValidationReport(report) => assert_eq!(
+                report.into_errors(),
+                vec![
+                    ValidationError::MissingRequiredClaims(vec!["tenant".to_string()]),
+                    ValidationError::InvalidIssuer("issuer".to_string()),
+                    ValidationError::CustomValidation("rejected".to_string()),
+                ]
+            ),
+            other => panic!("Unexpected error {:?}", other),
+        }
+        assert!(cache.is_empty());
+
+        not_err!(claims_set.validate_all(Default::default()));
+    }
+
+    #[test]
+    fn rfc8725_options_never_accept_none() {
+        let options = ValidationOptions::rfc8725(
+            "issuer".to_string(),
+            "audience".to_string(),
+            vec![SignatureAlgorithm::None, SignatureAlgorithm::RS256],
+        );
+        assert_eq!(
+            options.algorithms,
+            Validation::Validate(vec![SignatureAlgorithm::RS256])
+        );
+        assert_eq!(options.critical_headers, Validation::Validate(vec![]));
+
+        let registered_claims = RegisteredClaims {
+            issuer: Some("issuer".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            registered_claims.validate(options),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "exp".to_string(),
+                "aud".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn strict_options_require_temporal_claims() {
+        let options = ValidationOptions::strict();
+        assert_eq!(
+            options.algorithms,
+            Validation::Validate(signed_algorithms())
+        );
+        assert_eq!(options.critical_headers, Validation::Validate(vec![]));
+
+        assert_eq!(
+            RegisteredClaims::default().validate(options.clone()),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "exp".to_string(),
+                "iat".to_string()
+            ]))
+        );
+
+        let registered_claims = RegisteredClaims {
+            issued_at: Some(Utc::now().into()),
+            expiry: Some((Utc::now() - Duration::seconds(1)).into()),
+            ..Default::default()
+        };
+        match registered_claims.validate(options) {
+            Err(ValidationError::Expired(_)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_fapi_options() {
+        use crate::jwa::SignatureAlgorithm::*;
+
+        let options = ValidationOptions::fapi(
+            "https://as.example.com".to_string(),
+            "https://api.example.com".to_string(),
+        );
+        assert_eq!(options.algorithms, Validation::Validate(vec![ES256, PS256]));
+        for algorithm in &[None, HS256, RS256, RS384, RS512, ES384, PS384] {
+            assert!(!algorithm.is_fapi_compliant(), "{:?}", algorithm);
+        }
+
+        assert_eq!(
+            RegisteredClaims::default().validate(options.clone()),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "exp".to_string(),
+                "aud".to_string(),
+                "jti".to_string()
+            ]))
+        );
+        not_err!(RegisteredClaims {
+            audience: Some(SingleOrMultiple::Single(
+                "https://api.example.com".to_string()
+            )),
+            expiry: Some((Utc::now() + Duration::minutes(5)).into()),
+            id: Some("id".to_string()),
+            ..Default::default()
+        }
+        .validate(options));
+    }
+
+    #[test]
+    fn validate_oidc_id_token() {
+        let options = ValidationOptions::oidc_id_token(
+            "s6BhdRkqt3".to_string(),
+            "https://server.example.com".to_string(),
+        );
+        let registered = RegisteredClaims {
+            issuer: Some("https://server.example.com".to_string()),
+            subject: Some("24400320".to_string()),
+            audience: Some(SingleOrMultiple::Multiple(vec![
+                "s6BhdRkqt3".to_string(),
+                "other".to_string(),
+            ])),
+            expiry: Some((Utc::now() + Duration::minutes(5)).into()),
+            issued_at: Some(Utc::now().into()),
+            ..Default::default()
+        };
+
+        let claims = ClaimsSet {
+            registered: registered.clone(),
+            private: serde_json::json!({ "azp": "s6BhdRkqt3" }),
+        };
+        not_err!(claims.validate(options.clone()));
+
+        let claims = ClaimsSet {
+            registered,
+            private: serde_json::json!({ "azp": "other" }),
+        };
+        match claims.validate(options.clone()) {
+            Err(Error::ValidationError(ValidationError::InvalidAuthorizedParty(azp))) => {
+                assert_eq!(azp, "other")
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        // An access token is not accepted as an ID token
+        let header = jws::RegisteredHeader {
+            algorithm: SignatureAlgorithm::RS256,
+            media_type: Some(oauth::ACCESS_TOKEN_MEDIA_TYPE.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            header.validate(&options),
+            Err(ValidationError::InvalidTokenType(Some(
+                "at+jwt".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn lenient_options_accept_expired_tokens() {
+        let registered_claims = RegisteredClaims {
+            expiry: Some(1.into()),
+            not_before: Some((Utc::now() + Duration::days(1)).into()),
+            ..Default::default()
+        };
+        not_err!(registered_claims.validate(ValidationOptions::lenient_for_tests()));
+    }
+
+    #[test]
+    fn lenient_options_decode_signed_and_unsecured_tokens() {
+        let options = ValidationOptions::lenient_for_tests();
+        let builder = Token::builder()
+            .subject("John Doe")
+            .expires_in(Duration::days(-1));
+
+        let secret = jws::Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let token = not_err!(builder.sign(&secret, SignatureAlgorithm::HS256));
+        let claims = not_err!(decode::<Empty>(&token, &secret, &options));
+        assert_eq!(claims.registered.subject, Some("John Doe".to_string()));
+
+        let token = not_err!(builder.danger_unsecured());
+        let claims = not_err!(decode::<Empty>(&token, &jws::Secret::None, &options));
+        assert_eq!(claims.registered.subject, Some("John Doe".to_string()));
+    }
+
+    #[test]
+    fn validate_jwt_access_token_profile() {
+        let options = ValidationOptions::jwt_access_token(
+            "https://as.example.com".to_string(),
+            "https://rs.example.com".to_string(),
+        );
+        let registered = RegisteredClaims {
+            issuer: Some("https://as.example.com".to_string()),
+            audience: Some(SingleOrMultiple::Single(
+                "https://rs.example.com".to_string(),
+            )),
+            subject: Some("5ba552d67".to_string()),
+            expiry: Some((Utc::now() + Duration::minutes(5)).into()),
+            issued_at: Some(Utc::now().into()),
+            id: Some("dbe39bf3a3ba4238a513f51d6e1691c4".to_string()),
+            ..Default::default()
+        };
+
+        let claims = ClaimsSet {
+            registered: registered.clone(),
+            private: serde_json::json!({ "client_id": "s6BhdRkqt3" }),
+        };
+        not_err!(claims.validate(options.clone()));
+
+        let claims = ClaimsSet {
+            registered,
+            private: serde_json::json!({}),
+        };
+        match claims.validate(options) {
+            Err(Error::ValidationError(ValidationError::MissingRequiredClaims(missing))) => {
+                assert_eq!(missing, vec!["client_id".to_string()])
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_jti_catch_replayed_token() {
+        let registered_claims = RegisteredClaims {
+            id: Some("id".to_string()),
+            expiry: Some(999.into()),
+            ..Default::default()
+        };
+
+        let validation_options = ValidationOptions {
+            temporal_options: TemporalOptions {
+                now: Some(Utc.timestamp(100, 0)),
+                ..Default::default()
+            },
+            id: Validation::Validate(Arc::new(InMemoryReplayCache::new(16))),
+            ..Default::default()
+        };
+
+        not_err!(registered_claims.validate(validation_options.clone()));
+        assert_eq!(
+            Err(ValidationError::Replayed("id".to_string())),
+            registered_claims.validate(validation_options)
+        );
+    }
+
+    #[test]
+    fn validate_jti_not_recorded_when_validation_fails() {
+        let cache = Arc::new(InMemoryReplayCache::new(16));
+        let registered_claims = RegisteredClaims {
+            id: Some("id".to_string()),
+            issuer: Some("issuer".to_string()),
+            ..Default::default()
+        };
+
+        let validation_options = ValidationOptions {
+            issuer: Validation::Validate("another issuer".to_string()),
+            id: Validation::Validate(cache.clone()),
+            ..Default::default()
+        };
+
+        assert!(registered_claims.validate(validation_options).is_err());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn validate_denylist_rejects_revoked_tokens() {
+        let cache = Arc::new(InMemoryReplayCache::new(16));
+        let denylist = Arc::new(InMemoryDenylist::default());
+        let registered_claims = RegisteredClaims {
+            id: Some("id".to_string()),
+            ..Default::default()
+        };
+
+        let validation_options = ValidationOptions {
+            id: Validation::Validate(cache.clone()),
+            denylist: Validation::Validate(denylist.clone()),
+            ..Default::default()
+        };
+
+        denylist.revoke_id("id");
+        assert_eq!(
+            Err(ValidationError::Revoked),
+            registered_claims.validate(validation_options)
+        );
+        // The `jti` of a revoked token is not recorded
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn validate_max_validity() {
+        let temporal_options = TemporalOptions {
+            now: Some(Utc.timestamp(100, 0)),
+            ..Default::default()
+        };
+        let validation_options = ValidationOptions {
+            temporal_options: temporal_options.clone(),
+            max_validity: Validation::Validate(Duration::seconds(60)),
+            ..Default::default()
+        };
+        let claims = |issued_at: Option<i64>, expiry: i64| RegisteredClaims {
+            issued_at: issued_at.map(Into::into),
+            expiry: Some(expiry.into()),
+            ..Default::default()
+        };
+
+        not_err!(claims(Some(90), 150).validate(validation_options.clone()));
+        assert_eq!(
+            claims(Some(90), 160).validate(validation_options.clone()),
+            Err(ValidationError::ValidityTooLong(Duration::seconds(10)))
+        );
+        // Without `iat`, the validity is measured from now
+        not_err!(claims(None, 160).validate(validation_options.clone()));
+        assert_eq!(
+            claims(None, 100 + 365 * 24 * 3600).validate(validation_options),
+            Err(ValidationError::ValidityTooLong(
+                Duration::days(365) - Duration::seconds(60)
+            ))
+        );
+        not_err!(
+            RegisteredClaims::default().validate_validity(Validation::Validate((
+                Duration::seconds(60),
+                temporal_options
+            )))
+        );
+    }
+
+    #[test]
+    fn validate_times_valid_token_with_epsilon() {
+        let registered_claims = RegisteredClaims {
+            expiry: Some(99.into()),
+            not_before: Some(96.into()),
+            issued_at: Some(96.into()),
+            ..Default::default()
+        };
+
+        let temporal_options = TemporalOptions {
+            now: Some(Utc.timestamp(100, 0)),
+            epsilon: Duration::seconds(10),
+            ..Default::default()
         };
 
         let validation_options = ValidationOptions {
@@ -1563,6 +4420,28 @@ mod tests {
         not_err!(registered_claims.validate(validation_options));
     }
 
+    #[test]
+    fn validate_times_with_per_claim_epsilon() {
+        let registered_claims = RegisteredClaims {
+            expiry: Some(99.into()),
+            not_before: Some(160.into()),
+            ..Default::default()
+        };
+
+        let temporal_options = TemporalOptions {
+            now: Some(Utc.timestamp(100, 0)),
+            epsilon: Duration::seconds(10),
+            expiry_epsilon: Some(Duration::seconds(0)),
+            not_before_epsilon: Some(Duration::minutes(5)),
+            ..Default::default()
+        };
+        not_err!(registered_claims.validate_nbf(Validation::Validate(temporal_options.clone())));
+        assert_eq!(
+            Err(ValidationError::Expired(Duration::seconds(1))),
+            registered_claims.validate_exp(Validation::Validate(temporal_options))
+        );
+    }
+
     #[test]
     fn compact_part_round_trip() {
         let test_value = PrivateClaims {
@@ -1590,6 +4469,36 @@ mod tests {
         assert_eq!(actual_value, test_value);
     }
 
+    #[test]
+    fn compact_part_string_round_trip() {
+        let test_value = "Hello, world".to_string();
+
+        let base64 = not_err!(test_value.to_base64());
+        let expected_base64 = "SGVsbG8sIHdvcmxk";
+        assert_eq!(base64.str(), expected_base64);
+
+        let actual_value = not_err!(String::from_base64(&base64));
+        assert_eq!(actual_value, test_value);
+
+        assert!(String::from_bytes(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn compact_part_json_value_round_trip() {
+        let test_value = serde_json::json!({ "company": "ACME" });
+
+        let base64 = not_err!(test_value.to_base64());
+        let expected_base64 = "eyJjb21wYW55IjoiQUNNRSJ9";
+        assert_eq!(base64.str(), expected_base64);
+
+        let actual_value = not_err!(serde_json::Value::from_base64(&base64));
+        assert_eq!(actual_value, test_value);
+
+        let actual_value =
+            not_err!(Json::<std::collections::HashMap<String, String>>::from_base64(&base64));
+        assert_eq!(actual_value["company"], "ACME");
+    }
+
     #[test]
     fn compact_part_base64_url_round_trip() {
         let test_value = Base64Url("AQIDBAU".to_string());
@@ -1601,4 +4510,291 @@ mod tests {
         let actual_value = not_err!(Base64Url::from_base64(&base64));
         assert_eq!(actual_value, test_value);
     }
+
+    #[test]
+    fn encode_and_decode_round_trip() {
+        let secret = jws::Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(encode(
+            From::from(jws::RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            ClaimsSet::<PrivateClaims> {
+                registered: RegisteredClaims {
+                    issuer: Some("https://www.acme.com".to_string()),
+                    ..Default::default()
+                },
+                private: PrivateClaims {
+                    company: "ACME".to_string(),
+                    department: "Toilet Cleaning".to_string(),
+                },
+            },
+            &secret,
+        ));
+        let options = |algorithms| ValidationOptions {
+            algorithms,
+            issuer: Validation::Validate("https://www.acme.com".to_string()),
+            ..Default::default()
+        };
+
+        let claims = not_err!(decode::<PrivateClaims>(
+            &token,
+            &secret,
+            &options(Validation::Validate(vec![SignatureAlgorithm::HS256]))
+        ));
+        assert_eq!(claims.private.company, "ACME");
+
+        let expect_error =
+            |options: &ValidationOptions, expected: ValidationError| match decode::<PrivateClaims>(
+                &token, &secret, options,
+            ) {
+                Err(Error::ValidationError(error)) => assert_eq!(error, expected),
+                other => panic!("Unexpected result {:?}", other),
+            };
+        expect_error(
+            &options(Validation::Ignored),
+            ValidationError::MissingAlgorithm,
+        );
+        expect_error(
+            &options(Validation::Validate(vec![SignatureAlgorithm::RS256])),
+            ValidationError::WrongAlgorithmHeader,
+        );
+        expect_error(
+            &ValidationOptions {
+                issuer: Validation::Validate("https://www.ecorp.com".to_string()),
+                ..options(Validation::Validate(vec![SignatureAlgorithm::HS256]))
+            },
+            ValidationError::InvalidIssuer("https://www.acme.com".to_string()),
+        );
+
+        let other_secret =
+            jws::Secret::Bytes("other".to_string().into_bytes()).danger_allow_weak_keys();
+        match decode::<PrivateClaims>(
+            &token,
+            &other_secret,
+            &options(Validation::Validate(vec![SignatureAlgorithm::HS256])),
+        ) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compact_decode_with_tolerance() {
+        // "?>?" is "Pz4_" in base64url and "Pz4/" in standard base64
+        let deviant = "Pz4/.Pz4=.Pz\n4 _";
+        let strict = Compact::decode_with_tolerance(deviant, &Default::default());
+        assert_eq!(strict, Compact::decode(deviant));
+        for index in 0..3 {
+            assert!(strict.part::<Vec<u8>>(index).is_err());
+        }
+
+        let lenient = Compact::decode_with_tolerance(deviant, &Base64Tolerance::lenient());
+        assert_eq!(lenient.encode(), "Pz4_.Pz4.Pz4_");
+        assert_eq!(not_err!(lenient.part::<Vec<u8>>(0)), b"?>?");
+
+        let tolerance = Base64Tolerance {
+            padding: true,
+            ..Default::default()
+        };
+        let padded = Compact::decode_with_tolerance(deviant, &tolerance);
+        assert_eq!(padded.encode(), "Pz4/.Pz4.Pz\n4 _");
+    }
+
+    #[test]
+    fn decode_padded_token() {
+        let secret = jws::Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(encode(
+            From::from(jws::RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            ClaimsSet::<Empty> {
+                registered: RegisteredClaims {
+                    issuer: Some("https://www.acme.com".to_string()),
+                    ..Default::default()
+                },
+                private: Empty {},
+            },
+            &secret,
+        ));
+        let padded: Vec<String> = token
+            .split('.')
+            .map(|part| {
+                let padding = (4 - part.len() % 4) % 4;
+                format!("{}{}", part, "=".repeat(padding))
+            })
+            .collect();
+        let padded = padded.join(".");
+        assert_ne!(padded, token);
+
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+        assert!(decode::<Empty>(&padded, &secret, &options).is_err());
+
+        let options = ValidationOptions {
+            base64: Base64Tolerance {
+                padding: true,
+                ..Default::default()
+            },
+            ..options
+        };
+        let claims = not_err!(decode::<Empty>(&padded, &secret, &options));
+        assert_eq!(
+            claims.registered.issuer.as_deref(),
+            Some("https://www.acme.com")
+        );
+    }
+
+    #[test]
+    fn decode_rejects_unsecured_tokens() {
+        let token = not_err!(jws::UnsecuredJwt::<Empty, Empty>::danger_new(
+            Default::default(),
+            ClaimsSet {
+                registered: Default::default(),
+                private: Empty {},
+            },
+        )
+        .encode());
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::None]),
+            ..Default::default()
+        };
+        match decode::<Empty>(&token, &jws::Secret::None, &options) {
+            Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let options = ValidationOptions {
+            allow_unsecured_tokens: true,
+            ..options
+        };
+        let _ = not_err!(decode::<Empty>(&token, &jws::Secret::None, &options));
+    }
+
+    #[test]
+    fn decode_rejects_oversized_token() {
+        let secret = jws::Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(encode(
+            From::from(jws::RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            ClaimsSet::<Empty> {
+                registered: RegisteredClaims {
+                    issuer: Some("https://www.acme.com".to_string()),
+                    ..Default::default()
+                },
+                private: Empty {},
+            },
+            &secret,
+        ));
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            size_limits: SizeLimits {
+                max_token_length: Some(token.len()),
+                max_segment_length: Some(43),
+            },
+            ..Default::default()
+        };
+        let _ = not_err!(decode::<Empty>(&token, &secret, &options));
+
+        let longer = format!("{}A", token);
+        match decode::<Empty>(&longer, &secret, &options) {
+            Err(Error::DecodeError(DecodeError::TokenTooLong { max, actual })) => {
+                assert_eq!((max, actual), (token.len(), token.len() + 1))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let options = ValidationOptions {
+            size_limits: SizeLimits {
+                max_segment_length: Some(42),
+                ..options.size_limits
+            },
+            ..options
+        };
+        match decode::<Empty>(&token, &secret, &options) {
+            Err(Error::DecodeError(DecodeError::SegmentTooLong { index, max, actual })) => {
+                assert_eq!((index, max, actual), (2, 42, 43))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_checks_json_limits() {
+        let secret = jws::Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(encode(
+            From::from(jws::RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            ClaimsSet {
+                registered: Default::default(),
+                private: serde_json::json!({ "a": 1, "b": 2 }),
+            },
+            &secret,
+        ));
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            json_limits: JsonLimits {
+                max_claims: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let _ = not_err!(decode::<serde_json::Value>(&token, &secret, &options));
+
+        let options = ValidationOptions {
+            json_limits: JsonLimits {
+                max_claims: Some(1),
+                ..Default::default()
+            },
+            ..options
+        };
+        match decode::<serde_json::Value>(&token, &secret, &options) {
+            Err(Error::DecodeError(DecodeError::JsonLimitExceeded { limit, max })) => {
+                assert_eq!((limit, max), ("claims", 1))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn verify_batch_returns_results_in_order() {
+        let secret = jws::Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let other_secret =
+            jws::Secret::Bytes("other".to_string().into_bytes()).danger_allow_weak_keys();
+        let tokens: Vec<String> = (0..7)
+            .map(|i| {
+                let secret = if i % 3 == 0 { &other_secret } else { &secret };
+                not_err!(Token::builder()
+                    .id(i.to_string())
+                    .sign(secret, SignatureAlgorithm::HS256))
+            })
+            .collect();
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+
+        for workers in 0..9 {
+            let results =
+                verify_batch_with_workers::<Empty, _>(&tokens, &secret, &options, workers);
+            assert_eq!(results.len(), tokens.len());
+            for (i, result) in results.iter().enumerate() {
+                match *result {
+                    Ok(ref claims) if i % 3 != 0 => {
+                        assert_eq!(claims.registered.id, Some(i.to_string()))
+                    }
+                    Err(Error::ValidationError(ValidationError::InvalidSignature))
+                        if i % 3 == 0 => {}
+                    ref other => panic!("Unexpected result {:?} for token {}", other, i),
+                }
+            }
+        }
+    }
 }
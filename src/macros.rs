@@ -7,6 +7,7 @@ macro_rules! unexpected_key_type_error {
     };
 }
 
+#[cfg(feature = "jwe")]
 macro_rules! unexpected_encryption_options_error {
     ($expected:expr, $actual:expr) => {
         Error::WrongEncryptionOptions {
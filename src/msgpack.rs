@@ -0,0 +1,530 @@
+//! Claims sets encoded in [MessagePack](https://github.com/msgpack/msgpack/blob/master/spec.md)
+//! instead of JSON
+//!
+//! Like [`crate::cbor`], this makes tokens smaller while keeping them ordinary JWSs, signed and
+//! validated with the same [`Secret`]s and [`ValidationOptions`]. MessagePack has no registered
+//! media type for `cty`, so it is only suited to tokens that are issued and verified by services
+//! that agree on it, like internal services. Tokens made with [`encode`] have a `cty` header of
+//! [`CONTENT_TYPE`], which [`decode`] requires, so the codec of a token is chosen when it is
+//! encoded and checked when it is decoded.
+//!
+//! Claims are converted to MessagePack through their JSON representation. Binary values are
+//! decoded as base64url strings, and extension types are rejected.
+//!
+//! This module is only available with the `msgpack` feature.
+//!
+//! # Examples
+//! ```
+//! use biscuit::msgpack;
+//! use biscuit::jwa::SignatureAlgorithm;
+//! use biscuit::jws::{RegisteredHeader, Secret};
+//! use biscuit::{ClaimsSet, RegisteredClaims, Validation, ValidationOptions};
+//! use serde_json::{json, Value};
+//!
+//! let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+//! let claims = ClaimsSet {
+//!     registered: RegisteredClaims {
+//!         subject: Some("billing".to_string()),
+//!         ..Default::default()
+//!     },
+//!     private: json!({ "scopes": ["invoices:read"] }),
+//! };
+//! let token = msgpack::encode(From::from(RegisteredHeader::default()), &claims, &secret).unwrap();
+//!
+//! let options = ValidationOptions {
+//!     algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+//!     ..Default::default()
+//! };
+//! let decoded: ClaimsSet<Value> = msgpack::decode(&token, &secret, &options).unwrap();
+//! assert_eq!(decoded, claims);
+//! ```
+use std::convert::TryFrom;
+use std::ops::Deref;
+
+use data_encoding::BASE64URL_NOPAD;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::errors::{DecodeError, Error};
+use crate::jws::{self, Header, Secret};
+use crate::{ClaimsSet, CompactPart, ValidationOptions};
+
+/// The `cty` header of a JWS whose payload is MessagePack
+pub const CONTENT_TYPE: &str = "msgpack";
+
+/// The deepest nesting of arrays and maps that is decoded, which keeps malicious input from
+/// exhausting the stack
+const MAX_DEPTH: usize = 128;
+
+/// Serialize `value` to MessagePack, through its JSON representation
+///
+/// Integers, strings, arrays and maps are encoded in their shortest form, and floats as single
+/// precision if that is lossless.
+pub fn to_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+    write(&mut bytes, &serde_json::to_value(value)?);
+    Ok(bytes)
+}
+
+/// Deserialize a `T` from MessagePack, through its JSON representation
+///
+/// Returns [`DecodeError::InvalidMessagePack`] if `bytes` are not a single well-formed value, or
+/// cannot be represented as JSON.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    let mut reader = Reader { bytes, position: 0 };
+    let value = reader.value(0)?;
+    if reader.position != bytes.len() {
+        Err(invalid("trailing bytes after the value"))?
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+fn write(bytes: &mut Vec<u8>, value: &Value) {
+    match *value {
+        Value::Null => bytes.push(0xc0),
+        Value::Bool(false) => bytes.push(0xc2),
+        Value::Bool(true) => bytes.push(0xc3),
+        Value::Number(ref number) => {
+            if let Some(value) = number.as_u64() {
+                write_unsigned(bytes, value);
+            } else if let Some(value) = number.as_i64() {
+                write_negative(bytes, value);
+            } else {
+                let value = number.as_f64().unwrap_or_default();
+                let single = value as f32;
+                if f64::from(single) == value {
+                    bytes.push(0xca);
+                    bytes.extend_from_slice(&single.to_bits().to_be_bytes());
+                } else {
+                    bytes.push(0xcb);
+                    bytes.extend_from_slice(&value.to_bits().to_be_bytes());
+                }
+            }
+        }
+        Value::String(ref value) => {
+            write_length(bytes, value.len(), 0xa0, 32, [0xd9, 0xda, 0xdb]);
+            bytes.extend_from_slice(value.as_bytes());
+        }
+        Value::Array(ref values) => {
+            write_length(bytes, values.len(), 0x90, 16, [0, 0xdc, 0xdd]);
+            for value in values {
+                write(bytes, value);
+            }
+        }
+        Value::Object(ref map) => {
+            write_length(bytes, map.len(), 0x80, 16, [0, 0xde, 0xdf]);
+            for (key, value) in map {
+                write(bytes, &Value::String(key.clone()));
+                write(bytes, value);
+            }
+        }
+    }
+}
+
+fn write_unsigned(bytes: &mut Vec<u8>, value: u64) {
+    if value <= 0x7f {
+        bytes.push(value as u8);
+    } else if value <= 0xff {
+        bytes.extend_from_slice(&[0xcc, value as u8]);
+    } else if value <= 0xffff {
+        bytes.push(0xcd);
+        bytes.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= 0xffff_ffff {
+        bytes.push(0xce);
+        bytes.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        bytes.push(0xcf);
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn write_negative(bytes: &mut Vec<u8>, value: i64) {
+    if value >= -32 {
+        bytes.push(value as u8);
+    } else if let Ok(value) = i8::try_from(value) {
+        bytes.extend_from_slice(&[0xd0, value as u8]);
+    } else if let Ok(value) = i16::try_from(value) {
+        bytes.push(0xd1);
+        bytes.extend_from_slice(&value.to_be_bytes());
+    } else if let Ok(value) = i32::try_from(value) {
+        bytes.push(0xd2);
+        bytes.extend_from_slice(&value.to_be_bytes());
+    } else {
+        bytes.push(0xd3);
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Write the marker of a string, array or map of `length`, as a fix type if it is shorter than
+/// `fix_limit`, or with the `markers` of the 8, 16 and 32 bit lengths otherwise. A marker of
+/// zero means that there is no 8 bit length.
+fn write_length(bytes: &mut Vec<u8>, length: usize, fix: u8, fix_limit: usize, markers: [u8; 3]) {
+    if length < fix_limit {
+        bytes.push(fix | length as u8);
+    } else if length <= 0xff && markers[0] != 0 {
+        bytes.extend_from_slice(&[markers[0], length as u8]);
+    } else if length <= 0xffff {
+        bytes.push(markers[1]);
+        bytes.extend_from_slice(&(length as u16).to_be_bytes());
+    } else {
+        bytes.push(markers[2]);
+        bytes.extend_from_slice(&(length as u32).to_be_bytes());
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::DecodeError(DecodeError::InvalidMessagePack(reason.to_string()))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of input"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn unsigned(&mut self, length: usize) -> Result<u64, Error> {
+        Ok(self
+            .take(length)?
+            .iter()
+            .fold(0, |value, byte| value << 8 | u64::from(*byte)))
+    }
+
+    fn signed(&mut self, length: usize) -> Result<i64, Error> {
+        // Sign extend from the most significant bit of the value
+        let shift = 64 - 8 * length as u32;
+        Ok(((self.unsigned(length)? << shift) as i64) >> shift)
+    }
+
+    /// Read the length of a string, array or map, which takes `size` bytes
+    fn length(&mut self, size: usize) -> Result<usize, Error> {
+        let length = self.unsigned(size)? as usize;
+        self.check_length(length)
+    }
+
+    fn check_length(&self, length: usize) -> Result<usize, Error> {
+        // Every value takes at least a byte, so longer lengths cannot be valid
+        if length > self.bytes.len() - self.position {
+            Err(invalid("unexpected end of input"))?
+        }
+        Ok(length)
+    }
+
+    fn string(&mut self, length: usize) -> Result<Value, Error> {
+        let string = std::str::from_utf8(self.take(length)?)
+            .map_err(|_| invalid("a string is not UTF-8"))?;
+        Ok(Value::String(string.to_string()))
+    }
+
+    fn binary(&mut self, length: usize) -> Result<Value, Error> {
+        Ok(Value::String(BASE64URL_NOPAD.encode(self.take(length)?)))
+    }
+
+    fn array(&mut self, length: usize, depth: usize) -> Result<Value, Error> {
+        let mut values = Vec::with_capacity(length);
+        for _ in 0..length {
+            values.push(self.value(depth + 1)?);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn map(&mut self, length: usize, depth: usize) -> Result<Value, Error> {
+        let mut map = Map::with_capacity(length);
+        for _ in 0..length {
+            let key = match self.value(depth + 1)? {
+                Value::String(key) => key,
+                _ => Err(invalid("a map key is not a string"))?,
+            };
+            let _ = map.insert(key, self.value(depth + 1)?);
+        }
+        Ok(Value::Object(map))
+    }
+
+    fn float(value: f64) -> Result<Value, Error> {
+        match Number::from_f64(value) {
+            Some(number) => Ok(Value::Number(number)),
+            None => Err(invalid("a float is not finite")),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            Err(invalid("values are nested too deeply"))?
+        }
+        let marker = self.take(1)?[0];
+        match marker {
+            0x00..=0x7f => Ok(Value::from(marker)),
+            0x80..=0x8f => self.map(self.check_length(usize::from(marker & 0x0f))?, depth),
+            0x90..=0x9f => self.array(self.check_length(usize::from(marker & 0x0f))?, depth),
+            0xa0..=0xbf => self.string(usize::from(marker & 0x1f)),
+            0xc0 => Ok(Value::Null),
+            0xc2 => Ok(Value::Bool(false)),
+            0xc3 => Ok(Value::Bool(true)),
+            0xc4 => {
+                let length = self.length(1)?;
+                self.binary(length)
+            }
+            0xc5 => {
+                let length = self.length(2)?;
+                self.binary(length)
+            }
+            0xc6 => {
+                let length = self.length(4)?;
+                self.binary(length)
+            }
+            0xca => Self::float(f64::from(f32::from_bits(self.unsigned(4)? as u32))),
+            0xcb => Self::float(f64::from_bits(self.unsigned(8)?)),
+            0xcc => Ok(Value::from(self.unsigned(1)?)),
+            0xcd => Ok(Value::from(self.unsigned(2)?)),
+            0xce => Ok(Value::from(self.unsigned(4)?)),
+            0xcf => Ok(Value::from(self.unsigned(8)?)),
+            0xd0 => Ok(Value::from(self.signed(1)?)),
+            0xd1 => Ok(Value::from(self.signed(2)?)),
+            0xd2 => Ok(Value::from(self.signed(4)?)),
+            0xd3 => Ok(Value::from(self.signed(8)?)),
+            0xd9 => {
+                let length = self.length(1)?;
+                self.string(length)
+            }
+            0xda => {
+                let length = self.length(2)?;
+                self.string(length)
+            }
+            0xdb => {
+                let length = self.length(4)?;
+                self.string(length)
+            }
+            0xdc => {
+                let length = self.length(2)?;
+                self.array(length, depth)
+            }
+            0xdd => {
+                let length = self.length(4)?;
+                self.array(length, depth)
+            }
+            0xde => {
+                let length = self.length(2)?;
+                self.map(length, depth)
+            }
+            0xdf => {
+                let length = self.length(4)?;
+                self.map(length, depth)
+            }
+            0xe0..=0xff => Ok(Value::from(i64::from(marker as i8))),
+            0xc7..=0xc9 | 0xd4..=0xd8 => Err(invalid("extension types are not supported")),
+            _ => Err(invalid("reserved marker")),
+        }
+    }
+}
+
+/// An adapter to use any type that can be serialized and deserialized with serde as a
+/// `CompactPart`, by serializing it to MessagePack
+///
+/// # Examples
+/// ```
+/// use biscuit::CompactPart;
+/// use biscuit::msgpack::MessagePack;
+///
+/// let part = MessagePack(vec![1, 2, 3]);
+/// let encoded = part.to_base64().unwrap();
+/// assert_eq!(encoded.str(), "kwECAw");
+/// assert_eq!(MessagePack::<Vec<u32>>::from_base64(&encoded).unwrap(), part);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct MessagePack<T>(pub T);
+
+impl<T> CompactPart for MessagePack<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        to_vec(&self.0)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(MessagePack(from_slice(bytes)?))
+    }
+}
+
+impl<T> Deref for MessagePack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Sign `claims` encoded in MessagePack with `secret`, and return the token in its compact form
+///
+/// The `cty` header is set to [`CONTENT_TYPE`].
+pub fn encode<T, H>(
+    mut header: Header<H>,
+    claims: &ClaimsSet<T>,
+    secret: &Secret,
+) -> Result<String, Error>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    H: Serialize + DeserializeOwned,
+{
+    header.registered.content_type = Some(CONTENT_TYPE.to_string());
+    Ok(
+        jws::Compact::new_decoded(header, MessagePack(claims.clone()))
+            .into_encoded(secret)?
+            .unwrap_encoded()
+            .to_string(),
+    )
+}
+
+/// Verify the signature of a token made with [`encode`], validate it according to `options`,
+/// and deserialize its claims set, like [`crate::decode`]
+///
+/// Returns [`ValidationError::InvalidContentType`] if the `cty` header of the token is not
+/// [`CONTENT_TYPE`].
+///
+/// [`ValidationError::InvalidContentType`]: crate::errors::ValidationError::InvalidContentType
+pub fn decode<T>(
+    token: &str,
+    secret: &Secret,
+    options: &ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    crate::decode_with_content_type(
+        token,
+        secret,
+        options,
+        CONTENT_TYPE,
+        |MessagePack(claims)| claims,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::errors::ValidationError;
+    use crate::jwa::SignatureAlgorithm;
+    use crate::jws::RegisteredHeader;
+    use crate::{RegisteredClaims, Validation};
+
+    #[test]
+    fn messagepack_encoding() {
+        let examples: Vec<(Value, &str)> = vec![
+            (json!(null), "c0"),
+            (json!(true), "c3"),
+            (json!(127), "7f"),
+            (json!(128), "cc80"),
+            (json!(65536), "ce00010000"),
+            (json!(-1), "ff"),
+            (json!(-33), "d0df"),
+            (json!(-129), "d1ff7f"),
+            (json!(1.5), "ca3fc00000"),
+            (json!(1.1), "cb3ff199999999999a"),
+            (json!("a"), "a161"),
+            (json!([1, "a"]), "9201a161"),
+            (json!({ "a": [] }), "81a16190"),
+        ];
+        for (value, hex) in examples {
+            let bytes = not_err!(data_encoding::HEXLOWER.decode(hex.as_bytes()));
+            assert_eq!(not_err!(to_vec(&value)), bytes);
+            assert_eq!(not_err!(from_slice::<Value>(&bytes)), value);
+        }
+
+        let long = "x".repeat(300);
+        let bytes = not_err!(to_vec(&long));
+        assert_eq!(&bytes[..3], &[0xda, 0x01, 0x2c]);
+        assert_eq!(not_err!(from_slice::<String>(&bytes)), long);
+
+        // Binary values are decoded as base64url strings
+        assert_eq!(
+            not_err!(from_slice::<Value>(&[0xc4, 0x02, 0xff, 0xfe])),
+            json!("__4")
+        );
+    }
+
+    #[test]
+    fn malformed_messagepack_is_rejected() {
+        let malformed: Vec<&[u8]> = vec![
+            // Truncated
+            &[0xcd, 0x01],
+            &[0xa2, 0x61],
+            // Extension type
+            &[0xd4, 0x01, 0x00],
+            // Trailing bytes
+            &[0x01, 0x02],
+            // A map key that is not a string
+            &[0x81, 0x01, 0x02],
+            // A length that is longer than the input
+            &[0xdd, 0xff, 0xff, 0xff, 0xff],
+        ];
+        for bytes in malformed {
+            match from_slice::<Value>(bytes) {
+                Err(Error::DecodeError(DecodeError::InvalidMessagePack(_))) => {}
+                other => panic!("Unexpected result {:?} for {:?}", other, bytes),
+            }
+        }
+        let nested = vec![0x91; MAX_DEPTH + 2];
+        assert!(from_slice::<Value>(&nested).is_err());
+    }
+
+    #[test]
+    fn messagepack_claims_round_trip() {
+        let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+        let claims = ClaimsSet {
+            registered: RegisteredClaims {
+                subject: Some("billing".to_string()),
+                expiry: Some((chrono::Utc::now().timestamp() + 60).into()),
+                ..Default::default()
+            },
+            private: json!({ "retries": -3, "ratio": 0.25 }),
+        };
+        let token = not_err!(encode(
+            From::from(RegisteredHeader::default()),
+            &claims,
+            &secret
+        ));
+        assert!(not_err!(to_vec(&claims)).len() < not_err!(serde_json::to_vec(&claims)).len());
+
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        };
+        let decoded: ClaimsSet<Value> = not_err!(decode(&token, &secret, &options));
+        assert_eq!(decoded, claims);
+
+        // The codec is checked when the token is decoded
+        match crate::decode::<Value>(&token, &secret, &options) {
+            Err(Error::JsonError(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        #[cfg(feature = "cbor")]
+        match crate::cbor::decode::<Value>(&token, &secret, &options) {
+            Err(Error::ValidationError(ValidationError::InvalidContentType(Some(cty)))) => {
+                assert_eq!(cty, CONTENT_TYPE)
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let json_token = not_err!(crate::encode(
+            From::from(RegisteredHeader::default()),
+            claims,
+            &secret
+        ));
+        match decode::<Value>(&json_token, &secret, &options) {
+            Err(Error::ValidationError(ValidationError::InvalidContentType(None))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
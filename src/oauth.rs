@@ -0,0 +1,927 @@
+//! Helpers for OAuth 2.0 clients
+//!
+//! [`ClientAssertion`] builds the JWT that a client presents to authenticate itself at the token
+//! endpoint of an authorization server, as defined by [RFC7523](https://tools.ietf.org/html/rfc7523)
+//! and used by the `private_key_jwt` and `client_secret_jwt` authentication methods of
+//! [OpenID Connect](https://openid.net/specs/openid-connect-core-1_0.html#ClientAuthentication).
+//!
+//! [`RequestObject`] builds the `request` parameter of a
+//! [JWT-Secured Authorization Request](https://tools.ietf.org/html/rfc9101), which the
+//! authorization server validates with
+//! [`ValidationOptions::request_object`](crate::ValidationOptions::request_object).
+//!
+//! [`AuthorizationResponse`] validates and extracts the parameters of a
+//! [JWT Secured Authorization Response](https://openid.net/specs/oauth-v2-jarm.html) (JARM).
+//!
+//! [`bearer_token`] extracts the token from the `Authorization` header of a request to a
+//! resource server, for use in web framework integrations.
+//!
+//! [`IntrospectionResponse`] is the response of
+//! [token introspection](https://tools.ietf.org/html/rfc7662), which [`validate_or_introspect`]
+//! falls back to for tokens that are not JWTs.
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::Duration;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::errors::{Error, ValidationError};
+use crate::helpers::DebugClock;
+use crate::jwa::SignatureAlgorithm;
+#[cfg(feature = "jwe")]
+use crate::jwa::{ContentEncryptionAlgorithm, EncryptionOptions, KeyManagementAlgorithm};
+#[cfg(feature = "jwe")]
+use crate::jwe;
+use crate::jwk::JWKSet;
+#[cfg(feature = "jwe")]
+use crate::jwk::JWK;
+use crate::jws::{self, RegisteredHeader, Secret};
+use crate::{
+    ClaimsSet, Clock, Empty, IssuanceOptions, RandomUuid, RegisteredClaims, Scopes,
+    SingleOrMultiple, SystemClock, Timestamp, ValidationOptions,
+};
+
+/// The value of the `client_assertion_type` parameter for a JWT client assertion
+pub const CLIENT_ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// The media type for the `typ` header of a request object
+pub const REQUEST_OBJECT_MEDIA_TYPE: &str = "oauth-authz-req+jwt";
+
+/// The media type for the `typ` header of a [JWT access token](https://tools.ietf.org/html/rfc9068)
+pub const ACCESS_TOKEN_MEDIA_TYPE: &str = "at+jwt";
+
+/// The media type for the `typ` header of a [DPoP proof](https://tools.ietf.org/html/rfc9449)
+pub const DPOP_PROOF_MEDIA_TYPE: &str = "dpop+jwt";
+
+/// A JWT carrying OAuth request or response parameters as its private claims
+pub type ParametersJwt = crate::JWT<Map<String, Value>, Empty>;
+
+#[derive(Clone)]
+/// Builds JWT client assertions, as defined by
+/// [RFC7523#2.2](https://tools.ietf.org/html/rfc7523#section-2.2)
+///
+/// The assertion has the client ID as its `iss` and `sub`, the token endpoint as its `aud`, a
+/// short expiry, and a random `jti` so that it cannot be replayed. Send it as the
+/// `client_assertion` parameter, with [`CLIENT_ASSERTION_TYPE`] as the `client_assertion_type`.
+///
+/// Sign with the client's private key for `private_key_jwt`, or with an HMAC algorithm and the
+/// client secret for `client_secret_jwt`.
+///
+/// # Examples
+/// ```
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+/// use biscuit::oauth::ClientAssertion;
+///
+/// let secret = Secret::bytes_from_str("client secret that is at least 32 bytes long");
+/// let assertion = ClientAssertion::new("s6BhdRkqt3", "https://server.example.com/token")
+///     .sign(SignatureAlgorithm::HS256, &secret)
+///     .unwrap();
+/// ```
+pub struct ClientAssertion {
+    /// The client ID, used as the `iss` and `sub` of the assertion
+    pub client_id: String,
+
+    /// The URL of the token endpoint, used as the `aud` of the assertion
+    pub token_endpoint: String,
+
+    /// How long the assertion is valid for. Defaults to 5 minutes.
+    pub expires_in: Duration,
+
+    /// The ID of the signing key, set as the `kid` header if present
+    pub key_id: Option<String>,
+
+    /// The source of the time of issue. Defaults to the system clock.
+    pub clock: Arc<dyn Clock>,
+}
+
+impl ClientAssertion {
+    /// Create a builder of assertions for `client_id` at `token_endpoint`
+    pub fn new<C, T>(client_id: C, token_endpoint: T) -> Self
+    where
+        C: Into<String>,
+        T: Into<String>,
+    {
+        ClientAssertion {
+            client_id: client_id.into(),
+            token_endpoint: token_endpoint.into(),
+            expires_in: Duration::minutes(5),
+            key_id: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the ID of the signing key
+    pub fn with_key_id<S: Into<String>>(mut self, key_id: S) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Sign a new assertion with `algorithm` and `secret`, returning it in its compact form
+    ///
+    /// Every assertion has a new `jti`. Returns [`Error::UnsupportedOperation`] for
+    /// [`SignatureAlgorithm::None`], since assertions must be signed.
+    pub fn sign(&self, algorithm: SignatureAlgorithm, secret: &Secret) -> Result<String, Error> {
+        if algorithm == SignatureAlgorithm::None {
+            Err(Error::UnsupportedOperation)?
+        }
+
+        let header = From::from(RegisteredHeader {
+            algorithm,
+            key_id: self.key_id.clone(),
+            ..Default::default()
+        });
+        let claims = ClaimsSet::<Empty> {
+            registered: RegisteredClaims {
+                issuer: Some(self.client_id.clone()),
+                subject: Some(self.client_id.clone()),
+                audience: Some(SingleOrMultiple::Single(self.token_endpoint.clone())),
+                ..Default::default()
+            },
+            private: Default::default(),
+        };
+        let options = IssuanceOptions {
+            expires_in: Some(self.expires_in),
+            issued_at: true,
+            id: Some(Arc::new(RandomUuid)),
+            clock: Arc::clone(&self.clock),
+            ..Default::default()
+        };
+
+        jws::Compact::new_decoded(header, claims)
+            .sign_with_options(&jws::Signer::new(algorithm, secret)?, &options)
+    }
+}
+
+impl fmt::Debug for ClientAssertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientAssertion")
+            .field("client_id", &self.client_id)
+            .field("token_endpoint", &self.token_endpoint)
+            .field("expires_in", &self.expires_in)
+            .field("key_id", &self.key_id)
+            .field("clock", &DebugClock)
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+/// Builds request objects for JWT-Secured Authorization Requests, as defined by
+/// [RFC9101](https://tools.ietf.org/html/rfc9101)
+///
+/// The authorization request `parameters` are the private claims of the request object. The
+/// `iss` and `client_id` claims are the client ID, the `aud` claim is the issuer of the
+/// authorization server, and the `iat`, `exp` and `jti` claims are filled in when signing.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "rsa")]
+/// # {
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::Secret;
+/// use biscuit::oauth::RequestObject;
+/// use serde_json::json;
+///
+/// let parameters = json!({
+///     "response_type": "code",
+///     "redirect_uri": "https://client.example.org/cb",
+///     "scope": "openid",
+///     "state": "af0ifjsldkj",
+/// });
+///
+/// let secret = Secret::rsa_keypair_from_file("test/fixtures/rsa_private_key.der").unwrap();
+/// let request = RequestObject::new(
+///     "s6BhdRkqt3",
+///     "https://server.example.com",
+///     parameters.as_object().unwrap().clone(),
+/// )
+/// .sign(SignatureAlgorithm::RS256, &secret)
+/// .unwrap();
+/// # }
+/// ```
+pub struct RequestObject {
+    /// The client ID, used as the `iss` and `client_id` of the request object
+    pub client_id: String,
+
+    /// The issuer of the authorization server, used as the `aud` of the request object
+    pub audience: String,
+
+    /// The authorization request parameters
+    pub parameters: Map<String, Value>,
+
+    /// How long the request object is valid for. Defaults to 5 minutes.
+    pub expires_in: Duration,
+
+    /// The ID of the signing key, set as the `kid` header if present
+    pub key_id: Option<String>,
+
+    /// The source of the time of issue. Defaults to the system clock.
+    pub clock: Arc<dyn Clock>,
+}
+
+impl RequestObject {
+    /// Create a builder of request objects from `client_id` to the authorization server
+    /// identified by `audience`, with the authorization request `parameters`
+    pub fn new<C, A>(client_id: C, audience: A, parameters: Map<String, Value>) -> Self
+    where
+        C: Into<String>,
+        A: Into<String>,
+    {
+        RequestObject {
+            client_id: client_id.into(),
+            audience: audience.into(),
+            parameters,
+            expires_in: Duration::minutes(5),
+            key_id: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the ID of the signing key
+    pub fn with_key_id<S: Into<String>>(mut self, key_id: S) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Sign a new request object with `algorithm` and `secret`, returning it in its compact
+    /// form
+    ///
+    /// Returns [`Error::UnsupportedOperation`] for [`SignatureAlgorithm::None`], and
+    /// [`Error::ReservedClaim`] if the parameters contain a registered claim, or the `request`
+    /// or `request_uri` parameters which are not allowed in a request object.
+    pub fn sign(&self, algorithm: SignatureAlgorithm, secret: &Secret) -> Result<String, Error> {
+        Ok(self.signed(algorithm, secret)?.unwrap_encoded().to_string())
+    }
+
+    /// Sign a new request object like [`RequestObject::sign`], and then encrypt it for the
+    /// authorization server as a nested JWT with `key` and the algorithms of `header`
+    ///
+    /// The `cty` header is set to `JWT` if it is not set.
+    #[cfg(feature = "jwe")]
+    pub fn sign_and_encrypt<K: Serialize + DeserializeOwned>(
+        &self,
+        algorithm: SignatureAlgorithm,
+        secret: &Secret,
+        mut header: jwe::RegisteredHeader,
+        key: &JWK<K>,
+        options: &EncryptionOptions,
+    ) -> Result<String, Error> {
+        if header.content_type.is_none() {
+            header.content_type = Some("JWT".to_string());
+        }
+        let signed = self.signed(algorithm, secret)?;
+        Ok(jwe::Compact::new_decrypted(From::from(header), signed)
+            .into_encrypted(key, options)?
+            .unwrap_encrypted()
+            .to_string())
+    }
+
+    fn signed(
+        &self,
+        algorithm: SignatureAlgorithm,
+        secret: &Secret,
+    ) -> Result<ParametersJwt, Error> {
+        if algorithm == SignatureAlgorithm::None {
+            Err(Error::UnsupportedOperation)?
+        }
+        for parameter in crate::REGISTERED_CLAIMS
+            .iter()
+            .chain(&["request", "request_uri"])
+        {
+            if self.parameters.contains_key(*parameter) {
+                Err(Error::ReservedClaim(parameter.to_string()))?
+            }
+        }
+
+        let header = From::from(RegisteredHeader {
+            algorithm,
+            media_type: Some(REQUEST_OBJECT_MEDIA_TYPE.to_string()),
+            key_id: self.key_id.clone(),
+            ..Default::default()
+        });
+        let mut parameters = self.parameters.clone();
+        let _ = parameters.insert(
+            "client_id".to_string(),
+            Value::String(self.client_id.clone()),
+        );
+        let claims = ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some(self.client_id.clone()),
+                audience: Some(SingleOrMultiple::Single(self.audience.clone())),
+                ..Default::default()
+            },
+            private: parameters,
+        };
+        let options = IssuanceOptions {
+            expires_in: Some(self.expires_in),
+            issued_at: true,
+            id: Some(Arc::new(RandomUuid)),
+            clock: Arc::clone(&self.clock),
+            ..Default::default()
+        };
+
+        jws::Compact::new_decoded(header, claims).into_encoded_with_options(secret, &options)
+    }
+}
+
+impl fmt::Debug for RequestObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestObject")
+            .field("client_id", &self.client_id)
+            .field("audience", &self.audience)
+            .field("parameters", &self.parameters)
+            .field("expires_in", &self.expires_in)
+            .field("key_id", &self.key_id)
+            .field("clock", &DebugClock)
+            .finish()
+    }
+}
+
+/// Decrypt a nested JWT, like an encrypted request object, with `key` and the expected
+/// algorithms
+///
+/// The signed JWT that is returned is still encoded, and must then be decoded and validated.
+#[cfg(feature = "jwe")]
+pub fn decrypt_nested<K: Serialize + DeserializeOwned>(
+    token: &str,
+    key: &JWK<K>,
+    cek_algorithm: KeyManagementAlgorithm,
+    enc_algorithm: ContentEncryptionAlgorithm,
+) -> Result<ParametersJwt, Error> {
+    let (_, signed) = jwe::Compact::<ParametersJwt, Empty>::new_encrypted(token)
+        .into_decrypted(key, cek_algorithm, enc_algorithm)?
+        .unwrap_decrypted();
+    Ok(signed)
+}
+
+/// The parameters of an authorization response, carried in the `response` parameter of a
+/// [JARM](https://openid.net/specs/oauth-v2-jarm.html) response
+///
+/// Successful responses have a `code` or other parameters depending on the response type, while
+/// error responses have an `error`.
+///
+/// # Examples
+/// ```
+/// use biscuit::{ClaimsSet, RegisteredClaims, SingleOrMultiple, ValidationOptions};
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::jws::{RegisteredHeader, Secret};
+/// use biscuit::oauth::{AuthorizationResponse, ParametersJwt};
+/// use chrono::{Duration, Utc};
+/// use serde_json::json;
+///
+/// # let secret = Secret::Bytes("secret that is at least 32 bytes long".to_string().into_bytes());
+/// # let response = ParametersJwt::new_decoded(
+/// #     From::from(RegisteredHeader {
+/// #         algorithm: SignatureAlgorithm::HS256,
+/// #         ..Default::default()
+/// #     }),
+/// #     ClaimsSet {
+/// #         registered: RegisteredClaims {
+/// #             issuer: Some("https://accounts.example.com".to_string()),
+/// #             audience: Some(SingleOrMultiple::Single("s6BhdRkqt3".to_string())),
+/// #             expiry: Some((Utc::now() + Duration::minutes(10)).into()),
+/// #             ..Default::default()
+/// #         },
+/// #         private: json!({ "code": "PyyFaux2o7Q0YfXBU32jhw.5FXSQpvr8akv9CeRDSd0QA", "state": "S8NJ7uqk5fY4EjNvP_G_FtyJu6pUsvH9jsYni9dMAJw" })
+/// #             .as_object()
+/// #             .unwrap()
+/// #             .clone(),
+/// #     },
+/// # )
+/// # .into_encoded(&secret)
+/// # .unwrap()
+/// # .unwrap_encoded()
+/// # .to_string();
+/// let jwt = ParametersJwt::new_encoded(&response)
+///     .into_decoded(&secret, SignatureAlgorithm::HS256)
+///     .unwrap();
+/// let options = ValidationOptions::authorization_response(
+///     "https://accounts.example.com".to_string(),
+///     "s6BhdRkqt3".to_string(),
+/// );
+///
+/// let response = AuthorizationResponse::from_jwt(&jwt, options).unwrap();
+/// assert_eq!(response.code.as_deref(), Some("PyyFaux2o7Q0YfXBU32jhw.5FXSQpvr8akv9CeRDSd0QA"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub struct AuthorizationResponse {
+    /// The authorization code
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub code: Option<String>,
+
+    /// The state sent in the authorization request, which must be checked by the client
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state: Option<String>,
+
+    /// The error code of an error response
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+
+    /// The description of the error of an error response
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error_description: Option<String>,
+
+    /// The URI of a page describing the error of an error response
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error_uri: Option<String>,
+
+    /// Other parameters of the response, like the `access_token` of an implicit response
+    #[serde(flatten)]
+    pub additional: Map<String, Value>,
+}
+
+impl AuthorizationResponse {
+    /// Validate a decoded response JWT according to `options`, as created by
+    /// [`ValidationOptions::authorization_response`], and extract its parameters
+    pub fn from_jwt(jwt: &ParametersJwt, options: ValidationOptions) -> Result<Self, Error> {
+        jwt.validate(options)?;
+        let parameters = &jwt.payload()?.private;
+        Ok(Deserialize::deserialize(Value::Object(parameters.clone()))?)
+    }
+
+    /// Returns whether this is an error response
+    pub fn is_error(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+/// Extract the token from the value of an `Authorization` header using the `Bearer` scheme, as
+/// defined by [RFC6750#2.1](https://tools.ietf.org/html/rfc6750#section-2.1)
+///
+/// The scheme is matched case-insensitively. Returns `None` if the header uses another scheme,
+/// or if the token contains characters that are not allowed.
+///
+/// # Examples
+/// ```
+/// use biscuit::oauth::bearer_token;
+///
+/// assert_eq!(bearer_token("Bearer mF_9.B5f-4.1JqM"), Some("mF_9.B5f-4.1JqM"));
+/// assert_eq!(bearer_token("Basic YWxhZGRpbjpvcGVuc2VzYW1l"), None);
+/// ```
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    let authorization = authorization.trim();
+    let separator = authorization.find(' ')?;
+    if !authorization[..separator].eq_ignore_ascii_case("Bearer") {
+        return None;
+    }
+
+    let token = authorization[separator..].trim_start_matches(' ');
+    let unpadded = token.trim_end_matches('=');
+    let valid = !unpadded.is_empty()
+        && unpadded
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"-._~+/".contains(&byte));
+    if valid {
+        Some(token)
+    } else {
+        None
+    }
+}
+
+/// The response of the introspection endpoint of an authorization server, as defined by
+/// [RFC7662#2.2](https://tools.ietf.org/html/rfc7662#section-2.2)
+///
+/// Only `active` is required. The other members are usually only present for active tokens.
+///
+/// # Examples
+/// ```
+/// use biscuit::oauth::IntrospectionResponse;
+///
+/// let response: IntrospectionResponse = serde_json::from_str(
+///     r#"{
+///         "active": true,
+///         "client_id": "l238j323ds-23ij4",
+///         "scope": "read write dolphin",
+///         "sub": "Z5O3upPC88QrAjx00dis",
+///         "exp": 1419356238
+///     }"#,
+/// )
+/// .unwrap();
+/// assert!(response.scopes().contains("dolphin"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub struct IntrospectionResponse {
+    /// Whether the token is currently active
+    pub active: bool,
+
+    /// The scopes of the token
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub scope: Option<Scopes>,
+
+    /// The client that the token was issued to
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub client_id: Option<String>,
+
+    /// A human-readable identifier of the resource owner who authorized the token
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub username: Option<String>,
+
+    /// The type of the token, like `Bearer`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub token_type: Option<String>,
+
+    /// When the token expires
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub exp: Option<Timestamp>,
+
+    /// When the token was issued
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub iat: Option<Timestamp>,
+
+    /// When the token starts being valid
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nbf: Option<Timestamp>,
+
+    /// The subject of the token, usually the resource owner
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sub: Option<String>,
+
+    /// The audience of the token
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub aud: Option<SingleOrMultiple<String>>,
+
+    /// The issuer of the token
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub iss: Option<String>,
+
+    /// The identifier of the token
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jti: Option<String>,
+
+    /// Other members of the response
+    #[serde(flatten)]
+    pub additional: Map<String, Value>,
+}
+
+impl IntrospectionResponse {
+    /// The scopes of the token, which are empty if the response has none
+    pub fn scopes(&self) -> Scopes {
+        self.scope.clone().unwrap_or_default()
+    }
+}
+
+/// A client of the introspection endpoint of an authorization server
+///
+/// This crate does not make HTTP requests: implement this with an HTTP client, which posts the
+/// token to the endpoint and parses the [`IntrospectionResponse`].
+pub trait Introspector {
+    /// Introspect `token`
+    fn introspect(&self, token: &str) -> Result<IntrospectionResponse, Error>;
+}
+
+/// An access token validated by [`validate_or_introspect`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValidatedToken<T> {
+    /// The claims set of a JWT that was validated locally
+    Jwt(ClaimsSet<T>),
+    /// The response of the authorization server for an opaque token, which is active
+    Introspected(IntrospectionResponse),
+}
+
+/// Validate an access token locally if it is a JWT, or else introspect it
+///
+/// A token whose first part decodes to a JWS header is verified with `jwks` and validated with
+/// `options`, like [`providers::verify`](crate::providers::verify), and is rejected without
+/// being introspected if it is invalid. Other tokens are opaque, and are introspected with
+/// `introspector`.
+///
+/// Returns [`ValidationError::InactiveToken`] if the authorization server reports that an
+/// opaque token is not active.
+pub fn validate_or_introspect<T, I>(
+    token: &str,
+    jwks: &JWKSet<Empty>,
+    options: ValidationOptions,
+    introspector: &I,
+) -> Result<ValidatedToken<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+    I: Introspector + ?Sized,
+{
+    let compact = crate::Compact::decode(token);
+    let is_jwt = compact.len() == 3 && compact.part::<jws::Header<Empty>>(0).is_ok();
+    if is_jwt {
+        return crate::providers::verify(token, jwks, options).map(ValidatedToken::Jwt);
+    }
+
+    let response = introspector.introspect(token)?;
+    if !response.active {
+        Err(ValidationError::InactiveToken)?
+    }
+    Ok(ValidatedToken::Introspected(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use serde_json::json;
+
+    use super::*;
+    #[cfg(feature = "jwe")]
+    use crate::jwa::random_aes_gcm_nonce;
+    use crate::{FixedClock, Timestamp};
+
+    #[test]
+    fn client_assertion_claims() {
+        let secret =
+            Secret::Bytes("client secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let assertion = ClientAssertion {
+            clock: Arc::new(FixedClock(Utc.timestamp(1000, 0))),
+            ..ClientAssertion::new("s6BhdRkqt3", "https://server.example.com/token")
+        }
+        .with_key_id("key-1");
+
+        let token = not_err!(assertion.sign(SignatureAlgorithm::HS256, &secret));
+        let decoded = not_err!(jws::Compact::<ClaimsSet<Empty>, Empty>::new_encoded(&token)
+            .into_decoded(&secret, SignatureAlgorithm::HS256));
+
+        let header = not_err!(decoded.header());
+        assert_eq!(header.registered.key_id.as_deref(), Some("key-1"));
+
+        let claims = &not_err!(decoded.payload()).registered;
+        assert_eq!(claims.issuer.as_deref(), Some("s6BhdRkqt3"));
+        assert_eq!(claims.subject.as_deref(), Some("s6BhdRkqt3"));
+        assert_eq!(
+            claims.audience,
+            Some(SingleOrMultiple::Single(
+                "https://server.example.com/token".to_string()
+            ))
+        );
+        assert_eq!(claims.issued_at, Some(1000.into()));
+        assert_eq!(claims.expiry, Some(1300.into()));
+        assert!(claims.id.is_some());
+
+        let other = not_err!(assertion.sign(SignatureAlgorithm::HS256, &secret));
+        assert_ne!(token, other);
+
+        assert!(assertion
+            .sign(SignatureAlgorithm::None, &Secret::None)
+            .is_err());
+    }
+
+    fn request_object() -> RequestObject {
+        let parameters = json!({
+            "response_type": "code",
+            "redirect_uri": "https://client.example.org/cb",
+            "state": "af0ifjsldkj",
+        });
+        RequestObject::new(
+            "s6BhdRkqt3",
+            "https://server.example.com",
+            parameters.as_object().unwrap().clone(),
+        )
+    }
+
+    fn validation_options() -> ValidationOptions {
+        ValidationOptions::request_object(
+            "s6BhdRkqt3".to_string(),
+            "https://server.example.com".to_string(),
+        )
+    }
+
+    #[test]
+    fn request_object_round_trip() {
+        let secret =
+            Secret::Bytes("client secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(request_object().sign(SignatureAlgorithm::HS256, &secret));
+
+        let decoded = not_err!(
+            ParametersJwt::new_encoded(&token).into_decoded(&secret, SignatureAlgorithm::HS256)
+        );
+        not_err!(decoded.validate(validation_options()));
+        let claims = not_err!(decoded.payload());
+        assert_eq!(claims.private["client_id"], "s6BhdRkqt3");
+        assert_eq!(claims.private["state"], "af0ifjsldkj");
+
+        let other_client = ValidationOptions::request_object(
+            "other".to_string(),
+            "https://server.example.com".to_string(),
+        );
+        assert!(decoded.validate(other_client).is_err());
+
+        let mut with_request_uri = request_object();
+        let _ = with_request_uri.parameters.insert(
+            "request_uri".to_string(),
+            json!("https://client.example.org/r"),
+        );
+        assert!(with_request_uri
+            .sign(SignatureAlgorithm::HS256, &secret)
+            .is_err());
+    }
+
+    #[test]
+    fn request_object_rejects_other_token_types() {
+        let secret =
+            Secret::Bytes("client secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let token = not_err!(
+            ClientAssertion::new("s6BhdRkqt3", "https://server.example.com")
+                .sign(SignatureAlgorithm::HS256, &secret)
+        );
+
+        let decoded = not_err!(
+            ParametersJwt::new_encoded(&token).into_decoded(&secret, SignatureAlgorithm::HS256)
+        );
+        match decoded.validate(validation_options()) {
+            Err(Error::ValidationError(ValidationError::InvalidTokenType(Some(typ)))) => {
+                assert_eq!(typ, "JWT")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "jwe")]
+    fn encrypted_request_object_round_trip() {
+        let secret =
+            Secret::Bytes("client secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let key = JWK::<Empty>::new_octet_key(&[7; 32], Default::default());
+        let options = EncryptionOptions::AES_GCM {
+            nonce: not_err!(random_aes_gcm_nonce()),
+        };
+
+        let token = not_err!(request_object().sign_and_encrypt(
+            SignatureAlgorithm::HS256,
+            &secret,
+            jwe::RegisteredHeader {
+                cek_algorithm: KeyManagementAlgorithm::A256GCMKW,
+                enc_algorithm: ContentEncryptionAlgorithm::A256GCM,
+                ..Default::default()
+            },
+            &key,
+            &options,
+        ));
+        assert_eq!(token.split('.').count(), 5);
+
+        let signed = not_err!(decrypt_nested(
+            &token,
+            &key,
+            KeyManagementAlgorithm::A256GCMKW,
+            ContentEncryptionAlgorithm::A256GCM
+        ));
+        let decoded = not_err!(signed.into_decoded(&secret, SignatureAlgorithm::HS256));
+        not_err!(decoded.validate(validation_options()));
+    }
+
+    fn authorization_response(private: Value, expiry: Timestamp) -> String {
+        ParametersJwt::new_decoded(
+            From::from(RegisteredHeader {
+                algorithm: SignatureAlgorithm::HS256,
+                ..Default::default()
+            }),
+            ClaimsSet {
+                registered: RegisteredClaims {
+                    issuer: Some("https://accounts.example.com".to_string()),
+                    audience: Some(SingleOrMultiple::Single("s6BhdRkqt3".to_string())),
+                    expiry: Some(expiry),
+                    ..Default::default()
+                },
+                private: private.as_object().unwrap().clone(),
+            },
+        )
+        .into_encoded(&Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys())
+        .unwrap()
+        .unwrap_encoded()
+        .to_string()
+    }
+
+    #[test]
+    fn authorization_response_parameters() {
+        let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+        let options = || {
+            ValidationOptions::authorization_response(
+                "https://accounts.example.com".to_string(),
+                "s6BhdRkqt3".to_string(),
+            )
+        };
+        let decode = |token: &str| {
+            ParametersJwt::new_encoded(token)
+                .into_decoded(&secret, SignatureAlgorithm::HS256)
+                .unwrap()
+        };
+
+        let token = authorization_response(
+            json!({ "error": "access_denied", "state": "S8NJ7uqk5fY4EjNvP_G_FtyJu6pUsvH9jsYni9dMAJw" }),
+            (Utc::now() + Duration::minutes(10)).into(),
+        );
+        let response = not_err!(AuthorizationResponse::from_jwt(&decode(&token), options()));
+        assert!(response.is_error());
+        assert_eq!(response.error.as_deref(), Some("access_denied"));
+        assert!(response.additional.is_empty());
+
+        let expired = authorization_response(
+            json!({ "code": "PyyFaux2o7Q0YfXBU32jhw.5FXSQpvr8akv9CeRDSd0QA" }),
+            (Utc::now() - Duration::minutes(10)).into(),
+        );
+        match AuthorizationResponse::from_jwt(&decode(&expired), options()) {
+            Err(Error::ValidationError(ValidationError::Expired(_))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let other_client = ValidationOptions::authorization_response(
+            "https://accounts.example.com".to_string(),
+            "other".to_string(),
+        );
+        assert!(AuthorizationResponse::from_jwt(&decode(&token), other_client).is_err());
+    }
+
+    #[test]
+    fn bearer_token_from_authorization_header() {
+        assert_eq!(bearer_token("Bearer abc.def-ghi"), Some("abc.def-ghi"));
+        assert_eq!(bearer_token("bearer  abc+/=="), Some("abc+/=="));
+        for invalid in &[
+            "",
+            "Bearer",
+            "Bearer ",
+            "Bearer ===",
+            "Bearer abc def",
+            "Bearer a=b",
+            "Basic abc",
+            "Bearerabc",
+        ] {
+            assert_eq!(bearer_token(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn validate_or_introspect_falls_back_for_opaque_tokens() {
+        struct StaticIntrospector(IntrospectionResponse);
+
+        impl Introspector for StaticIntrospector {
+            fn introspect(&self, token: &str) -> Result<IntrospectionResponse, Error> {
+                assert_eq!(token, "2YotnFZFEjr1zCsicMWpAA");
+                Ok(self.0.clone())
+            }
+        }
+
+        let jwks: JWKSet<Empty> = not_err!(serde_json::from_value(json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "key0",
+                "alg": "HS256",
+                "k": "-clnNQnBupZt23N8McUcZytLhan9OmjlJXmqS7daoeY",
+            }]
+        })));
+        let response: IntrospectionResponse = not_err!(serde_json::from_value(json!({
+            "active": true,
+            "scope": "read write",
+            "client_id": "s6BhdRkqt3",
+            "exp": 1419356238,
+            "extension_field": "twenty-seven",
+        })));
+        assert_eq!(response.scopes(), Scopes::new(vec!["read", "write"]));
+        assert_eq!(
+            response.additional.get("extension_field"),
+            Some(&json!("twenty-seven"))
+        );
+
+        let introspector = StaticIntrospector(response.clone());
+        match validate_or_introspect::<Empty, _>(
+            "2YotnFZFEjr1zCsicMWpAA",
+            &jwks,
+            ValidationOptions::default(),
+            &introspector,
+        ) {
+            Ok(ValidatedToken::Introspected(introspected)) => assert_eq!(introspected, response),
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let inactive = StaticIntrospector(IntrospectionResponse::default());
+        match validate_or_introspect::<Empty, _>(
+            "2YotnFZFEjr1zCsicMWpAA",
+            &jwks,
+            ValidationOptions::default(),
+            &inactive,
+        ) {
+            Err(Error::ValidationError(ValidationError::InactiveToken)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        // JWTs are validated locally, and never introspected
+        let jwt = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6ImtleTAifQ.\
+                   eyJjb21wYW55IjoiQUNNRSIsImRlcGFydG1lbnQiOiJUb2lsZXQgQ2xlYW5pbmcifQ.\
+                   nz0a8aSweo6W0K2P7keByUPWl0HLVG45pTDznij5uKw";
+        match validate_or_introspect::<Map<String, Value>, _>(
+            jwt,
+            &jwks,
+            ValidationOptions::default(),
+            &inactive,
+        ) {
+            Ok(ValidatedToken::Jwt(claims)) => {
+                assert_eq!(claims.private.get("company"), Some(&json!("ACME")))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let tampered = format!("{}A", jwt);
+        match validate_or_introspect::<Map<String, Value>, _>(
+            &tampered,
+            &jwks,
+            ValidationOptions::default(),
+            &introspector,
+        ) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,973 @@
+//! Validation of [OpenID Connect](https://openid.net/specs/openid-connect-core-1_0.html) ID
+//! tokens
+//!
+//! The validators in this module check claims that are specific to OpenID Connect. Register them
+//! in [`ValidationOptions`](crate::ValidationOptions), alongside the usual validation of the
+//! registered claims.
+//!
+//! [Back-Channel Logout](https://openid.net/specs/openid-connect-backchannel-1_0.html) tokens can
+//! be validated with [`LogoutToken`], or with the
+//! [`ValidationOptions::logout_token`](crate::ValidationOptions::logout_token) preset.
+//!
+//! The metadata that an OpenID Provider publishes for
+//! [discovery](https://openid.net/specs/openid-connect-discovery-1_0.html) is parsed by
+//! [`ProviderMetadata`], which also builds the options for validating its ID tokens.
+//! [`validate_id_token`] then applies every rule for an ID token in one call, and
+//! [`validate_userinfo`] validates the signed responses of the UserInfo endpoint.
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::Duration;
+use data_encoding::BASE64URL_NOPAD;
+use ring::constant_time::verify_slices_are_equal;
+use ring::digest;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::errors::{Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+#[cfg(feature = "jwe")]
+use crate::jwa::{ContentEncryptionAlgorithm, KeyManagementAlgorithm};
+use crate::jwk::JWKSet;
+#[cfg(feature = "jwe")]
+use crate::jwk::JWK;
+use crate::jws::Compact;
+use crate::secevent::SecurityEventClaims;
+use crate::{ClaimsSet, ClaimsValidator, Empty, Validation, ValidationOptions};
+
+/// The event type URI identifying a
+/// [Back-Channel Logout](https://openid.net/specs/openid-connect-backchannel-1_0.html#LogoutToken)
+/// token, in its `events` claim
+pub const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+/// The media type for the `typ` header of a Back-Channel Logout token. Its use is recommended,
+/// but not required.
+pub const LOGOUT_TOKEN_MEDIA_TYPE: &str = "logout+jwt";
+
+/// Validates the `azp` (authorized party) claim of an ID token, as required by
+/// [OpenID Connect Core §3.1.3.7](https://openid.net/specs/openid-connect-core-1_0.html#IDTokenValidation)
+///
+/// If the token has multiple audiences, the `azp` claim must be present. If the `azp` claim is
+/// present, it must be the client ID of the relying party.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{ClaimsSet, RegisteredClaims, SingleOrMultiple, ValidationOptions};
+/// use biscuit::oidc::AuthorizedParty;
+/// use serde_json::json;
+///
+/// let options = ValidationOptions {
+///     validators: vec![Arc::new(AuthorizedParty::new("my-client"))],
+///     ..Default::default()
+/// };
+///
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims {
+///         audience: Some(SingleOrMultiple::Multiple(vec![
+///             "my-client".to_string(),
+///             "other-client".to_string(),
+///         ])),
+///         ..Default::default()
+///     },
+///     private: json!({ "azp": "my-client" }),
+/// };
+/// assert!(claims.validate(options).is_ok());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuthorizedParty {
+    client_id: String,
+}
+
+impl AuthorizedParty {
+    /// Validate that `client_id` is the authorized party
+    pub fn new<S: Into<String>>(client_id: S) -> Self {
+        AuthorizedParty {
+            client_id: client_id.into(),
+        }
+    }
+}
+
+impl ClaimsValidator for AuthorizedParty {
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        match claims.get("azp") {
+            None | Some(Value::Null) => {
+                let multiple_audiences = claims
+                    .get("aud")
+                    .and_then(Value::as_array)
+                    .map(|audiences| audiences.len() > 1)
+                    .unwrap_or(false);
+                if multiple_audiences {
+                    Err(ValidationError::MissingRequiredClaims(vec![
+                        "azp".to_string()
+                    ]))?
+                }
+                Ok(())
+            }
+            Some(Value::String(azp)) if *azp == self.client_id => Ok(()),
+            Some(Value::String(azp)) => Err(ValidationError::InvalidAuthorizedParty(azp.clone())),
+            Some(_) => Err(ValidationError::MalformedClaim("azp".to_string())),
+        }
+    }
+}
+
+/// Validates the `nonce` claim of an ID token against the value that the relying party sent in
+/// the authentication request, as required by
+/// [OpenID Connect Core §3.1.3.7](https://openid.net/specs/openid-connect-core-1_0.html#IDTokenValidation)
+///
+/// The `nonce` claim must be present, and is compared in constant time.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use biscuit::{ClaimsSet, RegisteredClaims, ValidationOptions};
+/// use biscuit::oidc::Nonce;
+/// use serde_json::json;
+///
+/// let options = ValidationOptions {
+///     validators: vec![Arc::new(Nonce::new("n-0S6_WzA2Mj"))],
+///     ..Default::default()
+/// };
+///
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims::default(),
+///     private: json!({ "nonce": "n-0S6_WzA2Mj" }),
+/// };
+/// assert!(claims.validate(options).is_ok());
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct Nonce {
+    expected: String,
+}
+
+impl Nonce {
+    /// Validate that the `nonce` claim is `expected`
+    pub fn new<S: Into<String>>(expected: S) -> Self {
+        Nonce {
+            expected: expected.into(),
+        }
+    }
+}
+
+/// The expected nonce is not printed, since it is a secret of the relying party
+impl fmt::Debug for Nonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Nonce").finish()
+    }
+}
+
+impl ClaimsValidator for Nonce {
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        match claims.get("nonce") {
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![
+                "nonce".to_string()
+            ])),
+            Some(Value::String(nonce)) => {
+                verify_slices_are_equal(nonce.as_bytes(), self.expected.as_bytes())
+                    .map_err(|_| ValidationError::InvalidNonce)
+            }
+            Some(_) => Err(ValidationError::MalformedClaim("nonce".to_string())),
+        }
+    }
+}
+
+/// Computes the hash used for the `at_hash` and `c_hash` claims of an ID token
+///
+/// This is the base64url encoding of the left-most half of the hash of `value`, using the hash
+/// algorithm of the `algorithm` that the ID token is signed with. Returns an error for
+/// [`SignatureAlgorithm::None`], which has no hash algorithm.
+///
+/// # Examples
+/// ```
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::oidc::left_half_hash;
+///
+/// let at_hash = left_half_hash(
+///     SignatureAlgorithm::RS256,
+///     "jHkWEdUXMU1BwAsC4vtUsZwnNvTIxEl0z9K3vx5KF0Y",
+/// ).unwrap();
+/// assert_eq!(at_hash, "77QmUPtjPfzWtF2AnpK9RQ");
+/// ```
+pub fn left_half_hash(algorithm: SignatureAlgorithm, value: &str) -> Result<String, Error> {
+    use crate::jwa::SignatureAlgorithm::*;
+
+    let hash_algorithm = match algorithm {
+        HS256 | RS256 | ES256 | PS256 => &digest::SHA256,
+        HS384 | RS384 | ES384 | PS384 => &digest::SHA384,
+        HS512 | RS512 | ES512 | PS512 => &digest::SHA512,
+        None => Err(Error::UnsupportedOperation)?,
+    };
+    let hash = digest::digest(hash_algorithm, value.as_bytes());
+    let hash = hash.as_ref();
+    Ok(BASE64URL_NOPAD.encode(&hash[..hash.len() / 2]))
+}
+
+/// Validates the `at_hash` or `c_hash` claim of an ID token, which binds the ID token to the
+/// access token or authorization code that was issued with it
+///
+/// The claim must be present, and is compared in constant time.
+///
+/// # Examples
+/// ```
+/// use biscuit::ClaimsValidator;
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::oidc::TokenHash;
+/// use serde_json::json;
+///
+/// let validator = TokenHash::access_token(
+///     "jHkWEdUXMU1BwAsC4vtUsZwnNvTIxEl0z9K3vx5KF0Y",
+///     SignatureAlgorithm::RS256,
+/// ).unwrap();
+///
+/// let claims = json!({ "at_hash": "77QmUPtjPfzWtF2AnpK9RQ" });
+/// assert!(validator.validate(claims.as_object().unwrap()).is_ok());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenHash {
+    claim: &'static str,
+    expected: String,
+}
+
+impl TokenHash {
+    /// Validate the `at_hash` claim against `access_token`, for an ID token signed with
+    /// `algorithm`
+    pub fn access_token(access_token: &str, algorithm: SignatureAlgorithm) -> Result<Self, Error> {
+        Ok(TokenHash {
+            claim: "at_hash",
+            expected: left_half_hash(algorithm, access_token)?,
+        })
+    }
+
+    /// Validate the `c_hash` claim against the authorization `code`, for an ID token signed
+    /// with `algorithm`
+    pub fn code(code: &str, algorithm: SignatureAlgorithm) -> Result<Self, Error> {
+        Ok(TokenHash {
+            claim: "c_hash",
+            expected: left_half_hash(algorithm, code)?,
+        })
+    }
+}
+
+impl ClaimsValidator for TokenHash {
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        match claims.get(self.claim) {
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![self
+                .claim
+                .to_string()])),
+            Some(Value::String(hash)) => {
+                verify_slices_are_equal(hash.as_bytes(), self.expected.as_bytes())
+                    .map_err(|_| ValidationError::InvalidTokenHash(self.claim.to_string()))
+            }
+            Some(_) => Err(ValidationError::MalformedClaim(self.claim.to_string())),
+        }
+    }
+}
+
+/// Validates the claims specific to a logout token, as required by
+/// [OpenID Connect Back-Channel Logout §2.6](https://openid.net/specs/openid-connect-backchannel-1_0.html#Validation)
+///
+/// - The `events` claim must contain the [`BACKCHANNEL_LOGOUT_EVENT`] with an object payload.
+/// - At least one of the `sub` and `sid` claims must be present. If neither is, both are reported
+///   as missing.
+/// - The `nonce` claim must not be present, so that an ID token cannot be used as a logout token.
+///
+/// The registered claims, including the freshness of `iat`, are validated by the
+/// [`ValidationOptions::logout_token`](crate::ValidationOptions::logout_token) preset, which
+/// registers this validator.
+///
+/// # Examples
+/// ```
+/// use biscuit::ClaimsValidator;
+/// use biscuit::oidc::LogoutToken;
+/// use serde_json::json;
+///
+/// let claims = json!({
+///     "sid": "08a5019c-17e1-4977-8f42-65a12843ea02",
+///     "events": { "http://schemas.openid.net/event/backchannel-logout": {} },
+/// });
+/// assert!(LogoutToken.validate(claims.as_object().unwrap()).is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct LogoutToken;
+
+impl ClaimsValidator for LogoutToken {
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        if SecurityEventClaims::from_claims(claims)?
+            .event(BACKCHANNEL_LOGOUT_EVENT)
+            .is_none()
+        {
+            Err(ValidationError::MalformedClaim("events".to_string()))?
+        }
+
+        let mut identified = false;
+        for claim in &["sub", "sid"] {
+            match claims.get(*claim) {
+                None | Some(Value::Null) => {}
+                Some(Value::String(_)) => identified = true,
+                Some(_) => Err(ValidationError::MalformedClaim(claim.to_string()))?,
+            }
+        }
+        if !identified {
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "sub".to_string(),
+                "sid".to_string(),
+            ]))?
+        }
+
+        match claims.get("nonce") {
+            None | Some(Value::Null) => Ok(()),
+            Some(_) => Err(ValidationError::ForbiddenClaims(vec!["nonce".to_string()])),
+        }
+    }
+}
+
+/// The path of the discovery document of an OpenID Provider, relative to its issuer
+pub const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+/// The metadata of an OpenID Provider, as defined by
+/// [OpenID Connect Discovery §3](https://openid.net/specs/openid-connect-discovery-1_0.html#ProviderMetadata)
+///
+/// Fetch the document at [`ProviderMetadata::discovery_url`] with an HTTP client, and parse it
+/// with [`ProviderMetadata::from_json`]. The keys of the provider are then fetched from
+/// `jwks_uri` as a [`JWKSet`](crate::jwk::JWKSet).
+///
+/// # Examples
+/// ```
+/// use biscuit::jwa::SignatureAlgorithm;
+/// use biscuit::oidc::ProviderMetadata;
+/// use biscuit::Validation;
+///
+/// let issuer = "https://server.example.com";
+/// assert_eq!(
+///     ProviderMetadata::discovery_url(issuer),
+///     "https://server.example.com/.well-known/openid-configuration"
+/// );
+///
+/// let document = br#"{
+///     "issuer": "https://server.example.com",
+///     "jwks_uri": "https://server.example.com/jwks.json",
+///     "id_token_signing_alg_values_supported": ["RS256", "ES256"]
+/// }"#;
+/// let metadata = ProviderMetadata::from_json(document, issuer).unwrap();
+///
+/// let options = metadata.id_token_validation_options("s6BhdRkqt3".to_string());
+/// assert_eq!(
+///     options.algorithms,
+///     Validation::Validate(vec![SignatureAlgorithm::RS256, SignatureAlgorithm::ES256])
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProviderMetadata {
+    /// The issuer identifier, which is the `iss` of the ID tokens of the provider
+    pub issuer: String,
+
+    /// The URL of the authorization endpoint
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub authorization_endpoint: Option<String>,
+
+    /// The URL of the token endpoint
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub token_endpoint: Option<String>,
+
+    /// The URL of the UserInfo endpoint
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub userinfo_endpoint: Option<String>,
+
+    /// The URL of the JWK Set of the provider
+    pub jwks_uri: String,
+
+    /// The `response_type` values that are supported
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub response_types_supported: Vec<String>,
+
+    /// The subject identifier types that are supported
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub subject_types_supported: Vec<String>,
+
+    /// The names of the algorithms that ID tokens are signed with. Names are kept as is, since
+    /// providers may list algorithms that are not supported by this crate.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+
+    /// The names of the algorithms that UserInfo responses are signed with
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub userinfo_signing_alg_values_supported: Vec<String>,
+
+    /// Other metadata of the provider
+    #[serde(flatten)]
+    pub additional: Map<String, Value>,
+}
+
+impl ProviderMetadata {
+    /// The URL of the discovery document of `issuer`
+    pub fn discovery_url(issuer: &str) -> String {
+        format!("{}{}", issuer.trim_end_matches('/'), DISCOVERY_PATH)
+    }
+
+    /// Parse a discovery document, which must be for `issuer`
+    ///
+    /// Returns [`ValidationError::InvalidIssuer`] with the issuer of the document if it does not
+    /// match exactly, as required by
+    /// [OpenID Connect Discovery §4.3](https://openid.net/specs/openid-connect-discovery-1_0.html#ProviderConfigurationValidation).
+    pub fn from_json(json: &[u8], issuer: &str) -> Result<Self, Error> {
+        let metadata: Self = serde_json::from_slice(json)?;
+        if metadata.issuer != issuer {
+            return Err(ValidationError::InvalidIssuer(metadata.issuer).into());
+        }
+        Ok(metadata)
+    }
+
+    /// The supported algorithms for ID tokens, without the ones that are unknown and `none`
+    pub fn id_token_signing_algorithms(&self) -> Vec<SignatureAlgorithm> {
+        signing_algorithms(&self.id_token_signing_alg_values_supported)
+    }
+
+    /// The supported algorithms for UserInfo responses, without the ones that are unknown and
+    /// `none`
+    pub fn userinfo_signing_algorithms(&self) -> Vec<SignatureAlgorithm> {
+        signing_algorithms(&self.userinfo_signing_alg_values_supported)
+    }
+
+    /// Options for validating the ID tokens that the provider issues to `client_id`, like
+    /// [`ValidationOptions::oidc_id_token`], accepting only the algorithms that the provider
+    /// supports
+    pub fn id_token_validation_options(&self, client_id: String) -> ValidationOptions {
+        let mut options = ValidationOptions::oidc_id_token(client_id, self.issuer.clone());
+        let algorithms = self.id_token_signing_algorithms();
+        if !algorithms.is_empty() {
+            options.algorithms = Validation::Validate(algorithms);
+        }
+        options
+    }
+
+    /// Options for validating the signed UserInfo responses that the provider returns to
+    /// `client_id`, as described by
+    /// [OpenID Connect Core §5.3.2](https://openid.net/specs/openid-connect-core-1_0.html#UserInfoResponse)
+    ///
+    /// The `iss`, `aud` and `sub` claims are required. `iss` must be the issuer of the provider,
+    /// `aud` must be `client_id`, and `sub` must be `subject`, the subject of the ID token of
+    /// the user. Only the algorithms that the provider supports for UserInfo responses are
+    /// accepted, or every signed algorithm if it does not list them.
+    pub fn userinfo_validation_options(
+        &self,
+        client_id: String,
+        subject: String,
+    ) -> ValidationOptions {
+        use crate::Presence::Required;
+
+        let algorithms = self.userinfo_signing_algorithms();
+        ValidationOptions {
+            claim_presence_options: crate::ClaimPresenceOptions {
+                issuer: Required,
+                audience: Required,
+                subject: Required,
+                ..Default::default()
+            },
+            issuer: Validation::Validate(self.issuer.clone()),
+            audience: Validation::Validate(client_id),
+            validators: vec![Arc::new(crate::ClaimAssertion::equals(
+                "/sub",
+                Value::from(subject),
+            ))],
+            algorithms: Validation::Validate(if algorithms.is_empty() {
+                crate::signed_algorithms()
+            } else {
+                algorithms
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+fn signing_algorithms(names: &[String]) -> Vec<SignatureAlgorithm> {
+    names
+        .iter()
+        .filter_map(|name| name.parse().ok())
+        .filter(|algorithm| *algorithm != SignatureAlgorithm::None)
+        .collect()
+}
+
+/// The configuration of a relying party for validating the ID tokens of a provider with
+/// [`validate_id_token`]
+#[derive(Clone)]
+pub struct ClientConfig {
+    /// The client ID of the relying party, which must be the audience of the ID tokens
+    pub client_id: String,
+    /// The metadata of the provider, from its discovery document
+    pub provider: ProviderMetadata,
+    /// The keys of the provider, fetched from the `jwks_uri` of its metadata
+    pub jwks: JWKSet<Empty>,
+    /// The nonce sent in the authentication request, if any
+    pub nonce: Option<String>,
+    /// The maximum age of the ID tokens, from their `iat` claim. Only tokens that were not issued
+    /// in the future are accepted if this is `None`.
+    pub max_age: Option<Duration>,
+    /// The access token issued with the ID token, if any, which is checked against the `at_hash`
+    /// claim
+    pub access_token: Option<String>,
+}
+
+impl ClientConfig {
+    /// The configuration of `client_id` for the provider with `provider` metadata and `jwks`
+    /// keys, without nonce, maximum age or access token
+    pub fn new<S: Into<String>>(
+        client_id: S,
+        provider: ProviderMetadata,
+        jwks: JWKSet<Empty>,
+    ) -> Self {
+        ClientConfig {
+            client_id: client_id.into(),
+            provider,
+            jwks,
+            nonce: None,
+            max_age: None,
+            access_token: None,
+        }
+    }
+}
+
+/// The nonce and the access token are not printed, since they are secrets of the relying party
+impl fmt::Debug for ClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("client_id", &self.client_id)
+            .field("provider", &self.provider)
+            .field("jwks", &self.jwks)
+            .field("max_age", &self.max_age)
+            .finish()
+    }
+}
+
+/// Verify and validate an ID token, following
+/// [OpenID Connect Core §3.1.3.7](https://openid.net/specs/openid-connect-core-1_0.html#IDTokenValidation)
+///
+/// The signature is verified with the key of `config.jwks` that the `kid` header refers to, and
+/// the algorithm must be one that the provider supports. The claims are then validated like
+/// [`ProviderMetadata::id_token_validation_options`], which checks the issuer, the audience and
+/// the authorized party, along with the `nonce`, the age of the token and the `at_hash` claim
+/// if they are configured.
+///
+/// Returns the claims set of the token if it is valid.
+pub fn validate_id_token<T>(token: &str, config: &ClientConfig) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let mut options = config
+        .provider
+        .id_token_validation_options(config.client_id.clone());
+    if let Some(ref nonce) = config.nonce {
+        options
+            .validators
+            .push(Arc::new(Nonce::new(nonce.as_str())));
+    }
+    if let Some(ref access_token) = config.access_token {
+        // The hash depends on the algorithm, which is checked to be accepted before the
+        // signature is verified
+        let algorithm = Compact::<ClaimsSet<T>, Empty>::new_encoded(token)
+            .unverified_header()?
+            .registered
+            .algorithm;
+        let token_hash = TokenHash::access_token(access_token, algorithm)?;
+        options.validators.push(Arc::new(token_hash));
+    }
+    if let Some(max_age) = config.max_age {
+        options.issued_at = Validation::Validate(max_age);
+    }
+    crate::providers::verify(token, &config.jwks, options)
+}
+
+/// The content type of signed or encrypted UserInfo responses
+pub const USERINFO_CONTENT_TYPE: &str = "application/jwt";
+
+/// Verify and validate a signed UserInfo response, returned with the
+/// [`USERINFO_CONTENT_TYPE`], for the user with the `subject` of their ID token
+///
+/// The signature is verified with the key of `config.jwks` that the `kid` header refers to,
+/// and the claims are validated like [`ProviderMetadata::userinfo_validation_options`].
+/// Returns the claims of the user if the response is valid.
+pub fn validate_userinfo<T>(
+    response: &str,
+    config: &ClientConfig,
+    subject: &str,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let options = config
+        .provider
+        .userinfo_validation_options(config.client_id.clone(), subject.to_string());
+    crate::providers::verify(response, &config.jwks, options)
+}
+
+/// Decrypt a UserInfo response that is signed and then encrypted with `key` and the expected
+/// algorithms, and validate it like [`validate_userinfo`]
+#[cfg(feature = "jwe")]
+pub fn validate_encrypted_userinfo<T, K>(
+    response: &str,
+    config: &ClientConfig,
+    subject: &str,
+    key: &JWK<K>,
+    cek_algorithm: KeyManagementAlgorithm,
+    enc_algorithm: ContentEncryptionAlgorithm,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+    K: Serialize + DeserializeOwned,
+{
+    let signed = crate::oauth::decrypt_nested(response, key, cek_algorithm, enc_algorithm)?;
+    validate_userinfo(&signed.encoded()?.to_string(), config, subject)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn claims(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn authorized_party_validation() {
+        let validator = AuthorizedParty::new("client");
+
+        not_err!(validator.validate(&claims(json!({ "aud": "client" }))));
+        not_err!(validator.validate(&claims(json!({ "aud": ["client"] }))));
+        not_err!(validator.validate(&claims(
+            json!({ "aud": ["client", "other"], "azp": "client" })
+        )));
+
+        assert_eq!(
+            validator.validate(&claims(json!({ "aud": ["client", "other"] }))),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "azp".to_string()
+            ]))
+        );
+        assert_eq!(
+            validator.validate(&claims(json!({ "aud": "client", "azp": "other" }))),
+            Err(ValidationError::InvalidAuthorizedParty("other".to_string()))
+        );
+        assert_eq!(
+            validator.validate(&claims(json!({ "azp": ["client"] }))),
+            Err(ValidationError::MalformedClaim("azp".to_string()))
+        );
+    }
+
+    #[test]
+    fn nonce_validation() {
+        let validator = Nonce::new("expected");
+
+        not_err!(validator.validate(&claims(json!({ "nonce": "expected" }))));
+        assert_eq!(
+            validator.validate(&claims(json!({ "nonce": "unexpected" }))),
+            Err(ValidationError::InvalidNonce)
+        );
+        assert_eq!(
+            validator.validate(&claims(json!({}))),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "nonce".to_string()
+            ]))
+        );
+        assert_eq!(
+            validator.validate(&claims(json!({ "nonce": 1 }))),
+            Err(ValidationError::MalformedClaim("nonce".to_string()))
+        );
+        assert!(!format!("{:?}", validator).contains("expected"));
+    }
+
+    #[test]
+    fn left_half_hash_uses_signing_algorithm() {
+        // Example from OpenID Connect Core, Appendix A.4
+        let code = "Qcb0Orv1zh30vL1MPRsbm-diHiMwcLyZvn1arpZv-Jxf_11jnpEX3Tgfvk";
+        assert_eq!(
+            not_err!(left_half_hash(SignatureAlgorithm::RS256, code)),
+            "LDktKdoQak3Pk0cnXxCltA"
+        );
+        assert_eq!(
+            not_err!(left_half_hash(SignatureAlgorithm::ES384, code)).len(),
+            32
+        );
+        assert_eq!(
+            not_err!(left_half_hash(SignatureAlgorithm::PS512, code)).len(),
+            43
+        );
+        assert!(left_half_hash(SignatureAlgorithm::None, code).is_err());
+    }
+
+    #[test]
+    fn token_hash_validation() {
+        let validator = not_err!(TokenHash::code(
+            "Qcb0Orv1zh30vL1MPRsbm-diHiMwcLyZvn1arpZv-Jxf_11jnpEX3Tgfvk",
+            SignatureAlgorithm::RS256
+        ));
+
+        not_err!(validator.validate(&claims(json!({ "c_hash": "LDktKdoQak3Pk0cnXxCltA" }))));
+        assert_eq!(
+            validator.validate(&claims(json!({ "c_hash": "77QmUPtjPfzWtF2AnpK9RQ" }))),
+            Err(ValidationError::InvalidTokenHash("c_hash".to_string()))
+        );
+        assert_eq!(
+            validator.validate(&claims(json!({ "at_hash": "LDktKdoQak3Pk0cnXxCltA" }))),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "c_hash".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn logout_token_validation() {
+        let events = json!({ BACKCHANNEL_LOGOUT_EVENT: {} });
+
+        not_err!(LogoutToken.validate(&claims(json!({ "sub": "248289761001", "events": events }))));
+        not_err!(LogoutToken.validate(&claims(json!({ "sid": "08a5019c", "events": events }))));
+
+        assert_eq!(
+            LogoutToken.validate(&claims(json!({ "sub": "248289761001" }))),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "events".to_string()
+            ]))
+        );
+        assert_eq!(
+            LogoutToken.validate(&claims(json!({
+                "sub": "248289761001",
+                "events": { "urn:example:event": {} },
+            }))),
+            Err(ValidationError::MalformedClaim("events".to_string()))
+        );
+        assert_eq!(
+            LogoutToken.validate(&claims(json!({ "events": events }))),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "sub".to_string(),
+                "sid".to_string()
+            ]))
+        );
+        assert_eq!(
+            LogoutToken.validate(&claims(json!({
+                "sub": "248289761001",
+                "nonce": "n-0S6_WzA2Mj",
+                "events": events,
+            }))),
+            Err(ValidationError::ForbiddenClaims(vec!["nonce".to_string()]))
+        );
+    }
+
+    #[test]
+    fn provider_metadata_from_discovery_document() {
+        let document = json!({
+            "issuer": "https://server.example.com/",
+            "jwks_uri": "https://server.example.com/jwks.json",
+            "id_token_signing_alg_values_supported": ["none", "RS256", "EdDSA"],
+            "claims_supported": ["sub", "email"],
+        })
+        .to_string();
+
+        let metadata = not_err!(ProviderMetadata::from_json(
+            document.as_bytes(),
+            "https://server.example.com/"
+        ));
+        assert_eq!(
+            ProviderMetadata::discovery_url(&metadata.issuer),
+            "https://server.example.com/.well-known/openid-configuration"
+        );
+        assert_eq!(
+            metadata.id_token_signing_algorithms(),
+            vec![SignatureAlgorithm::RS256]
+        );
+        assert_eq!(
+            metadata.additional.get("claims_supported"),
+            Some(&json!(["sub", "email"]))
+        );
+
+        match ProviderMetadata::from_json(document.as_bytes(), "https://server.example.com") {
+            Err(Error::ValidationError(ValidationError::InvalidIssuer(issuer))) => {
+                assert_eq!(issuer, "https://server.example.com/")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_id_token_applies_every_rule() {
+        use crate::jws::{RegisteredHeader, Secret};
+        use chrono::Utc;
+
+        let jwks: JWKSet<Empty> = not_err!(serde_json::from_value(json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "key0",
+                "k": "-clnNQnBupZt23N8McUcZytLhan9OmjlJXmqS7daoeY",
+            }]
+        })));
+        let secret = Secret::Bytes(not_err!(
+            BASE64URL_NOPAD.decode(b"-clnNQnBupZt23N8McUcZytLhan9OmjlJXmqS7daoeY")
+        ))
+        // To sign with HS384 too
+        .danger_allow_weak_keys();
+        let provider = not_err!(ProviderMetadata::from_json(
+            json!({
+                "issuer": "https://server.example.com",
+                "jwks_uri": "https://server.example.com/jwks.json",
+                "id_token_signing_alg_values_supported": ["HS256"],
+            })
+            .to_string()
+            .as_bytes(),
+            "https://server.example.com"
+        ));
+        let mut config = ClientConfig::new("s6BhdRkqt3", provider, jwks);
+        config.nonce = Some("n-0S6_WzA2Mj".to_string());
+        config.access_token = Some("jHkWEdUXMU1BwAsC4vtUsZwnNvTIxEl0z9K3vx5KF0Y".to_string());
+        config.max_age = Some(Duration::minutes(5));
+
+        let now = Utc::now().timestamp();
+        let sign = |algorithm: SignatureAlgorithm, claims: Value| {
+            let header = RegisteredHeader {
+                algorithm,
+                key_id: Some("key0".to_string()),
+                ..Default::default()
+            };
+            let claims: ClaimsSet<Map<String, Value>> = not_err!(serde_json::from_value(claims));
+            not_err!(Compact::new_decoded(From::from(header), claims).into_encoded(&secret))
+                .unwrap_encoded()
+                .to_string()
+        };
+        let id_token = |nonce: &str, issued_at: i64| {
+            json!({
+                "iss": "https://server.example.com",
+                "sub": "24400320",
+                "aud": "s6BhdRkqt3",
+                "exp": now + 600,
+                "iat": issued_at,
+                "nonce": nonce,
+                "at_hash": "77QmUPtjPfzWtF2AnpK9RQ",
+            })
+        };
+
+        let token = sign(SignatureAlgorithm::HS256, id_token("n-0S6_WzA2Mj", now));
+        let claims: ClaimsSet<Map<String, Value>> = not_err!(validate_id_token(&token, &config));
+        assert_eq!(claims.registered.subject.as_deref(), Some("24400320"));
+
+        let token = sign(SignatureAlgorithm::HS256, id_token("other", now));
+        match validate_id_token::<Map<String, Value>>(&token, &config) {
+            Err(Error::ValidationError(ValidationError::InvalidNonce)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let token = sign(
+            SignatureAlgorithm::HS256,
+            id_token("n-0S6_WzA2Mj", now - 600),
+        );
+        assert!(validate_id_token::<Map<String, Value>>(&token, &config).is_err());
+
+        // The provider does not sign ID tokens with HS384
+        let token = sign(SignatureAlgorithm::HS384, id_token("n-0S6_WzA2Mj", now));
+        match validate_id_token::<Map<String, Value>>(&token, &config) {
+            Err(Error::ValidationError(ValidationError::WrongAlgorithmHeader)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        config.access_token = Some("another access token".to_string());
+        let token = sign(SignatureAlgorithm::HS256, id_token("n-0S6_WzA2Mj", now));
+        match validate_id_token::<Map<String, Value>>(&token, &config) {
+            Err(Error::ValidationError(ValidationError::InvalidTokenHash(claim))) => {
+                assert_eq!(claim, "at_hash")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(!format!("{:?}", config).contains("another access token"));
+    }
+
+    #[test]
+    fn validate_signed_and_encrypted_userinfo() {
+        use crate::jws::{RegisteredHeader, Secret};
+
+        let jwks: JWKSet<Empty> = not_err!(serde_json::from_value(json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "key0",
+                "k": "-clnNQnBupZt23N8McUcZytLhan9OmjlJXmqS7daoeY",
+            }]
+        })));
+        let secret = Secret::Bytes(not_err!(
+            BASE64URL_NOPAD.decode(b"-clnNQnBupZt23N8McUcZytLhan9OmjlJXmqS7daoeY")
+        ));
+        let provider = not_err!(ProviderMetadata::from_json(
+            json!({
+                "issuer": "https://server.example.com",
+                "jwks_uri": "https://server.example.com/jwks.json",
+                "userinfo_signing_alg_values_supported": ["HS256"],
+            })
+            .to_string()
+            .as_bytes(),
+            "https://server.example.com"
+        ));
+        let config = ClientConfig::new("s6BhdRkqt3", provider, jwks);
+
+        let claims: ClaimsSet<Map<String, Value>> = not_err!(serde_json::from_value(json!({
+            "iss": "https://server.example.com",
+            "aud": "s6BhdRkqt3",
+            "sub": "248289761001",
+            "email": "janedoe@example.com",
+        })));
+        let header = RegisteredHeader {
+            algorithm: SignatureAlgorithm::HS256,
+            key_id: Some("key0".to_string()),
+            ..Default::default()
+        };
+        let signed =
+            not_err!(Compact::new_decoded(From::from(header), claims).into_encoded(&secret));
+        let response = not_err!(signed.encoded()).to_string();
+
+        let userinfo: ClaimsSet<Map<String, Value>> =
+            not_err!(validate_userinfo(&response, &config, "248289761001"));
+        assert_eq!(
+            userinfo.private.get("email"),
+            Some(&json!("janedoe@example.com"))
+        );
+        match validate_userinfo::<Map<String, Value>>(&response, &config, "other") {
+            Err(Error::ValidationError(ValidationError::FailedClaimAssertion(pointer))) => {
+                assert_eq!(pointer, "/sub")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        #[cfg(feature = "jwe")]
+        {
+            use crate::jwa::EncryptionOptions;
+            use crate::jwe;
+
+            let key = JWK::<Empty>::new_octet_key(&[7; 32], Default::default());
+            let encrypted = not_err!(jwe::Compact::new_decrypted(
+                From::from(jwe::RegisteredHeader {
+                    cek_algorithm: KeyManagementAlgorithm::A256GCMKW,
+                    enc_algorithm: ContentEncryptionAlgorithm::A256GCM,
+                    content_type: Some("JWT".to_string()),
+                    ..Default::default()
+                }),
+                signed,
+            )
+            .into_encrypted(
+                &key,
+                &EncryptionOptions::AES_GCM {
+                    nonce: not_err!(crate::jwa::random_aes_gcm_nonce()),
+                },
+            ))
+            .unwrap_encrypted()
+            .to_string();
+
+            let userinfo: ClaimsSet<Map<String, Value>> = not_err!(validate_encrypted_userinfo(
+                &encrypted,
+                &config,
+                "248289761001",
+                &key,
+                KeyManagementAlgorithm::A256GCMKW,
+                ContentEncryptionAlgorithm::A256GCM,
+            ));
+            assert_eq!(userinfo.registered.subject.as_deref(), Some("248289761001"));
+        }
+    }
+}
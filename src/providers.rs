@@ -0,0 +1,310 @@
+//! Validation presets for the tokens of well known identity providers
+//!
+//! Each module knows the issuers, the key endpoints and the quirks of a provider, and builds the
+//! [`ValidationOptions`] for its tokens. The keys are fetched from the endpoints with an HTTP
+//! client, and tokens are then verified and validated with [`verify`].
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::errors::{Error, ValidationError};
+use crate::jwa::{Algorithm, SignatureAlgorithm};
+use crate::jwk::{AlgorithmParameters, JWKSet};
+use crate::jws::{Compact, Secret};
+use crate::{ClaimsSet, Empty, Validation, ValidationOptions};
+
+pub mod azure;
+pub mod cognito;
+pub mod google;
+pub mod kubernetes;
+
+/// Verify the signature of `token` with the key of `jwks` that its `kid` header refers to, and
+/// validate it with `options`
+///
+/// The signature is only verified with an algorithm that is accepted by `options.algorithms`,
+/// so that keys without an `alg` parameter cannot be used with another algorithm. Returns the
+/// claims set of the token if it is valid.
+pub fn verify<T>(
+    token: &str,
+    jwks: &JWKSet<Empty>,
+    options: ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let metrics = options.metrics.clone();
+    let clock = options.temporal_options.clock.clone();
+    crate::record_verification(metrics.as_deref(), &*clock, || {
+        let token = Compact::<ClaimsSet<T>, Empty>::new_encoded(token);
+        let algorithm = token.unverified_header()?.registered.algorithm;
+        if let Validation::Validate(ref algorithms) = options.algorithms {
+            if !algorithms.contains(&algorithm) {
+                Err(ValidationError::WrongAlgorithmHeader)?
+            }
+        }
+
+        let token = token.decode_with_jwks(jwks, Some(algorithm))?;
+        token.validate(options)?;
+        let (_, claims) = token.unwrap_decoded();
+        Ok((algorithm, claims))
+    })
+}
+
+/// Verify `token` like [`verify`], except that a token without a `kid` header is verified by
+/// trying every key of `jwks` that can be used with its algorithm, until one of them verifies
+/// its signature
+///
+/// This is meant for issuers that leave out the `kid` header while several of their keys are
+/// live, such as during a key rotation. Use [`verify_any_key_with_workers`] to try the keys
+/// concurrently.
+pub fn verify_any_key<T>(
+    token: &str,
+    jwks: &JWKSet<Empty>,
+    options: ValidationOptions,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    verify_trying_keys(token, jwks, options, |algorithm, keys| {
+        first_verified(
+            keys.iter()
+                .map(|key| Compact::decode_str(token, key, algorithm)),
+        )
+    })
+}
+
+/// Verify `token` like [`verify_any_key`], trying the keys on up to `workers` threads and
+/// returning as soon as one of them verifies the signature
+///
+/// This bounds the latency of verifying a token without a `kid` header when there are many
+/// candidate keys. The keys are tried on the current thread if `workers` is 0 or 1, or if there
+/// is only one candidate key, and on WebAssembly.
+pub fn verify_any_key_with_workers<T>(
+    token: &str,
+    jwks: &JWKSet<Empty>,
+    options: ValidationOptions,
+    workers: usize,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+
+    verify_trying_keys(token, jwks, options, |algorithm, keys| {
+        let workers = workers.min(keys.len());
+        if workers <= 1 || cfg!(target_arch = "wasm32") {
+            return first_verified(
+                keys.iter()
+                    .map(|key| Compact::decode_str(token, key, algorithm)),
+            );
+        }
+
+        let token = Arc::new(token.to_string());
+        let verified = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let chunk_size = (keys.len() - 1) / workers + 1;
+        for chunk in keys.chunks(chunk_size) {
+            let chunk = chunk.to_vec();
+            let token = Arc::clone(&token);
+            let verified = Arc::clone(&verified);
+            let sender = sender.clone();
+            let _ = std::thread::spawn(move || {
+                for key in &chunk {
+                    if verified.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let result = Compact::decode_str(&token, key, algorithm);
+                    let success = result.is_ok();
+                    if success {
+                        verified.store(true, Ordering::Relaxed);
+                    }
+                    if sender.send(result).is_err() || success {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        first_verified(receiver.into_iter())
+    })
+}
+
+/// Verify `token` with the key that its `kid` header refers to like [`verify`], or with
+/// `try_keys` and the candidate keys of `jwks` if it has no `kid` header, and validate it
+fn verify_trying_keys<T, F>(
+    token: &str,
+    jwks: &JWKSet<Empty>,
+    options: ValidationOptions,
+    try_keys: F,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(SignatureAlgorithm, Vec<Secret>) -> Result<Compact<ClaimsSet<T>, Empty>, Error>,
+{
+    let metrics = options.metrics.clone();
+    let clock = options.temporal_options.clock.clone();
+    crate::record_verification(metrics.as_deref(), &*clock, || {
+        let encoded = Compact::<ClaimsSet<T>, Empty>::new_encoded(token);
+        let header = encoded.unverified_header()?;
+        let algorithm = header.registered.algorithm;
+        if let Validation::Validate(ref algorithms) = options.algorithms {
+            if !algorithms.contains(&algorithm) {
+                Err(ValidationError::WrongAlgorithmHeader)?
+            }
+        }
+
+        let token = match header.registered.key_id {
+            Some(_) => encoded.decode_with_jwks(jwks, Some(algorithm))?,
+            None => {
+                let keys = candidate_keys(jwks, algorithm);
+                if keys.is_empty() {
+                    Err(ValidationError::KeyNotFound)?
+                }
+                try_keys(algorithm, keys)?
+            }
+        };
+        token.validate(options)?;
+        let (_, claims) = token.unwrap_decoded();
+        Ok((algorithm, claims))
+    })
+}
+
+/// The keys of `jwks` that can verify signatures made with `algorithm`. RSA keys that are too
+/// short to be used are left out.
+fn candidate_keys(jwks: &JWKSet<Empty>, algorithm: SignatureAlgorithm) -> Vec<Secret> {
+    use crate::jwa::SignatureAlgorithm::{
+        HS256, HS384, HS512, PS256, PS384, PS512, RS256, RS384, RS512,
+    };
+
+    let hmac = [HS256, HS384, HS512].contains(&algorithm);
+    let rsa = [RS256, RS384, RS512, PS256, PS384, PS512].contains(&algorithm);
+    jwks.keys
+        .iter()
+        .filter(|jwk| match jwk.common.algorithm {
+            Some(Algorithm::Signature(key_algorithm)) => key_algorithm == algorithm,
+            Some(_) => false,
+            None => true,
+        })
+        .filter_map(|jwk| match jwk.algorithm {
+            AlgorithmParameters::RSA(ref parameters) if rsa => {
+                parameters.jws_public_key_secret().ok()
+            }
+            AlgorithmParameters::OctetKey(ref parameters) if hmac => {
+                Some(Secret::Bytes(parameters.value.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The first token of `results` whose signature was verified
+///
+/// If there is none, returns the first error other than an invalid signature, such as a
+/// malformed token, or [`ValidationError::InvalidSignature`].
+fn first_verified<T, I>(results: I) -> Result<T, Error>
+where
+    I: Iterator<Item = Result<T, Error>>,
+{
+    let mut error = None;
+    for result in results {
+        match result {
+            Ok(verified) => return Ok(verified),
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            Err(other) => {
+                if error.is_none() {
+                    error = Some(other);
+                }
+            }
+        }
+    }
+    Err(error.unwrap_or_else(|| ValidationError::InvalidSignature.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwk::JWK;
+    use crate::Token;
+
+    fn jwks() -> JWKSet<Empty> {
+        let keys = (1..=3)
+            .map(|i| JWK::<Empty>::new_octet_key(&[i; 32], Empty {}))
+            .collect();
+        JWKSet { keys }
+    }
+
+    fn options() -> ValidationOptions {
+        ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+            ..Default::default()
+        }
+    }
+
+    fn token(key: u8) -> String {
+        not_err!(Token::builder()
+            .subject("John Doe")
+            .sign(&Secret::Bytes(vec![key; 32]), SignatureAlgorithm::HS256))
+    }
+
+    #[test]
+    fn verify_any_key_tries_every_key() {
+        let claims: ClaimsSet<Empty> = not_err!(verify_any_key(&token(3), &jwks(), options()));
+        assert_eq!(claims.registered.subject.as_deref(), Some("John Doe"));
+
+        match verify_any_key::<Empty>(&token(4), &jwks(), options()) {
+            Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match verify::<Empty>(&token(3), &jwks(), options()) {
+            Err(Error::ValidationError(ValidationError::KidMissing)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn verify_any_key_with_workers_tries_keys_concurrently() {
+        for workers in 0..=4 {
+            let claims: ClaimsSet<Empty> = not_err!(verify_any_key_with_workers(
+                &token(2),
+                &jwks(),
+                options(),
+                workers
+            ));
+            assert_eq!(claims.registered.subject.as_deref(), Some("John Doe"));
+
+            match verify_any_key_with_workers::<Empty>(&token(4), &jwks(), options(), workers) {
+                Err(Error::ValidationError(ValidationError::InvalidSignature)) => {}
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn verify_rejects_unsecured_tokens() {
+        let mut jwks = jwks();
+        jwks.keys[0].common.key_id = Some("key".to_string());
+        let options = ValidationOptions {
+            algorithms: Validation::Validate(vec![SignatureAlgorithm::None]),
+            allow_unsecured_tokens: true,
+            ..Default::default()
+        };
+
+        for key_id in &[None, Some("key")] {
+            let header = crate::jws::RegisteredHeader {
+                key_id: key_id.map(str::to_string),
+                ..Default::default()
+            };
+            let token = not_err!(crate::jws::UnsecuredJwt::<Empty, Empty>::danger_new(
+                From::from(header),
+                Default::default()
+            )
+            .encode());
+
+            match verify_any_key::<Empty>(&token, &jwks, options.clone()) {
+                Err(Error::ValidationError(ValidationError::KeyNotFound))
+                | Err(Error::ValidationError(ValidationError::UnsecuredToken)) => {}
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+    }
+}
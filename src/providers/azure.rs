@@ -0,0 +1,286 @@
+//! [Microsoft Entra ID](https://learn.microsoft.com/en-us/entra/identity-platform/access-tokens)
+//! (Azure AD) ID and access tokens
+//!
+//! Entra ID issues tokens in two versions, with different issuers and key endpoints, which are
+//! chosen with [`TokenVersion`]. The version of a token is in its `ver` claim.
+//!
+//! The discovery documents of the multi-tenant authorities, like `common` and `organizations`,
+//! have an issuer with a `{tenantid}` placeholder, since every tenant has its own issuer. Tokens
+//! of any tenant are validated with [`multi_tenant_options`], which substitutes the `tid` claim
+//! of the token in the issuer.
+//!
+//! The keys at [`jwks_url`] have no `alg` parameter, and are always used with `RS256`.
+//!
+//! # Examples
+//! ```no_run
+//! # fn main() -> Result<(), biscuit::errors::Error> {
+//! use biscuit::jwk::JWKSet;
+//! use biscuit::providers::{self, azure};
+//! use biscuit::{ClaimsSet, Empty};
+//! use serde_json::Value;
+//! # let jwks: JWKSet<Empty> = JWKSet { keys: vec![] };
+//! # let token = "";
+//!
+//! // `jwks` is the document at `azure::jwks_url("contoso.onmicrosoft.com", azure::TokenVersion::V2)`
+//! let options = azure::options(
+//!     "72f988bf-86f1-41af-91ab-2d7cd011db47",
+//!     "6e74172b-be56-4843-9ff4-e66a39bb12e3".to_string(),
+//!     azure::TokenVersion::V2,
+//! );
+//! let claims: ClaimsSet<Value> = providers::verify(token, &jwks, options)?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+
+use serde_json::{Map, Value};
+
+use crate::errors::ValidationError;
+use crate::jwa::SignatureAlgorithm;
+use crate::{
+    ClaimAssertion, ClaimPresenceOptions, ClaimsValidator, Presence, Validation, ValidationOptions,
+};
+
+/// The placeholder for the tenant ID in the issuer of the multi-tenant authorities
+pub const TENANT_ID_PLACEHOLDER: &str = "{tenantid}";
+
+/// The version of the tokens of Entra ID
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenVersion {
+    /// Version 1.0 tokens, issued by `https://sts.windows.net/`. Their audience is usually the
+    /// application ID URI of the API, like `api://my-api`.
+    V1,
+    /// Version 2.0 tokens, issued by `https://login.microsoftonline.com/`. Their audience is the
+    /// client ID of the application.
+    V2,
+}
+
+impl TokenVersion {
+    /// The value of the `ver` claim of the tokens
+    pub fn claim(self) -> &'static str {
+        match self {
+            TokenVersion::V1 => "1.0",
+            TokenVersion::V2 => "2.0",
+        }
+    }
+
+    /// The issuer template of the tokens, with the [`TENANT_ID_PLACEHOLDER`]
+    pub fn issuer_template(self) -> &'static str {
+        match self {
+            TokenVersion::V1 => "https://sts.windows.net/{tenantid}/",
+            TokenVersion::V2 => "https://login.microsoftonline.com/{tenantid}/v2.0",
+        }
+    }
+}
+
+/// The issuer of the tokens of `tenant_id`
+pub fn issuer(tenant_id: &str, version: TokenVersion) -> String {
+    substitute_tenant_id(version.issuer_template(), tenant_id)
+}
+
+/// Replace the [`TENANT_ID_PLACEHOLDER`] in an issuer `template`, like the issuer of the
+/// discovery document of a multi-tenant authority, with `tenant_id`
+pub fn substitute_tenant_id(template: &str, tenant_id: &str) -> String {
+    template.replace(TENANT_ID_PLACEHOLDER, tenant_id)
+}
+
+/// The URL of the JWK Set of `tenant`, which is a tenant ID, a domain name or a multi-tenant
+/// authority like `common`
+///
+/// Applications with their own signing keys must add their client ID to the URL, as an
+/// `appid` query parameter.
+pub fn jwks_url(tenant: &str, version: TokenVersion) -> String {
+    match version {
+        TokenVersion::V1 => format!(
+            "https://login.microsoftonline.com/{}/discovery/keys",
+            tenant
+        ),
+        TokenVersion::V2 => format!(
+            "https://login.microsoftonline.com/{}/discovery/v2.0/keys",
+            tenant
+        ),
+    }
+}
+
+/// Options for validating the tokens of `version` that `tenant_id` issues to `audience`
+///
+/// The `iss`, `aud`, `exp` and `ver` claims are required, `iss` and `aud` must match, and only
+/// `RS256` is accepted.
+pub fn options(tenant_id: &str, audience: String, version: TokenVersion) -> ValidationOptions {
+    let mut options = base_options(audience, version);
+    options.issuer = Validation::Validate(issuer(tenant_id, version));
+    options
+}
+
+/// Options for validating the tokens of `version` that any tenant issues to `audience`, like
+/// [`options`]
+///
+/// The issuer must be the one of the tenant in the `tid` claim. If `tenant_ids` is not empty,
+/// only tokens of those tenants are accepted.
+pub fn multi_tenant_options(
+    audience: String,
+    version: TokenVersion,
+    tenant_ids: Vec<String>,
+) -> ValidationOptions {
+    let mut options = base_options(audience, version);
+    options.validators.push(Arc::new(TenantIssuer {
+        version,
+        tenant_ids,
+    }));
+    options
+}
+
+fn base_options(audience: String, version: TokenVersion) -> ValidationOptions {
+    ValidationOptions {
+        claim_presence_options: ClaimPresenceOptions {
+            issuer: Presence::Required,
+            audience: Presence::Required,
+            expiry: Presence::Required,
+            ..Default::default()
+        },
+        audience: Validation::Validate(audience),
+        validators: vec![Arc::new(ClaimAssertion::equals(
+            "/ver",
+            Value::from(version.claim()),
+        ))],
+        algorithms: Validation::Validate(vec![SignatureAlgorithm::RS256]),
+        ..Default::default()
+    }
+}
+
+/// Validates that the `iss` claim is the issuer of the tenant in the `tid` claim
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TenantIssuer {
+    version: TokenVersion,
+    /// The tenants that are accepted, or every tenant if empty
+    tenant_ids: Vec<String>,
+}
+
+impl ClaimsValidator for TenantIssuer {
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        let tenant_id = match claims.get("tid") {
+            Some(Value::String(tenant_id)) => tenant_id,
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![
+                "tid".to_string()
+            ]))?,
+            Some(_) => Err(ValidationError::MalformedClaim("tid".to_string()))?,
+        };
+        let iss = match claims.get("iss") {
+            Some(Value::String(iss)) => iss,
+            _ => Err(ValidationError::MalformedClaim("iss".to_string()))?,
+        };
+
+        let accepted = self.tenant_ids.is_empty() || self.tenant_ids.contains(tenant_id);
+        if !accepted || *iss != issuer(tenant_id, self.version) {
+            Err(ValidationError::InvalidIssuer(iss.clone()))?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::errors::Error;
+    use crate::jwk::JWKSet;
+    use crate::{ClaimsSet, Empty, RegisteredClaims, SingleOrMultiple};
+
+    const TENANT_ID: &str = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+
+    fn claims(issuer: &str, version: &str, tenant_id: &str) -> ClaimsSet<Value> {
+        ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some(issuer.to_string()),
+                audience: Some(SingleOrMultiple::Single("api://my-api".to_string())),
+                expiry: Some((chrono::Utc::now().timestamp() + 60).into()),
+                ..Default::default()
+            },
+            private: json!({ "ver": version, "tid": tenant_id }),
+        }
+    }
+
+    #[test]
+    fn tenant_issuers() {
+        assert_eq!(
+            issuer(TENANT_ID, TokenVersion::V1),
+            "https://sts.windows.net/72f988bf-86f1-41af-91ab-2d7cd011db47/"
+        );
+        assert_eq!(
+            substitute_tenant_id(
+                "https://login.microsoftonline.com/{tenantid}/v2.0",
+                TENANT_ID
+            ),
+            issuer(TENANT_ID, TokenVersion::V2)
+        );
+
+        let v1 = options(TENANT_ID, "api://my-api".to_string(), TokenVersion::V1);
+        not_err!(
+            claims(&issuer(TENANT_ID, TokenVersion::V1), "1.0", TENANT_ID).validate(v1.clone())
+        );
+        match claims(&issuer(TENANT_ID, TokenVersion::V2), "1.0", TENANT_ID).validate(v1.clone()) {
+            Err(Error::ValidationError(ValidationError::InvalidIssuer(_))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match claims(&issuer(TENANT_ID, TokenVersion::V1), "2.0", TENANT_ID).validate(v1) {
+            Err(Error::ValidationError(ValidationError::FailedClaimAssertion(pointer))) => {
+                assert_eq!(pointer, "/ver")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn multi_tenant_issuers() {
+        let other_tenant = "9188040d-6c67-4c5b-b112-36a304b66dad";
+        let any_tenant = multi_tenant_options("api://my-api".to_string(), TokenVersion::V2, vec![]);
+        not_err!(
+            claims(&issuer(other_tenant, TokenVersion::V2), "2.0", other_tenant)
+                .validate(any_tenant.clone())
+        );
+        // The issuer of another tenant than the one in `tid`
+        match claims(&issuer(other_tenant, TokenVersion::V2), "2.0", TENANT_ID).validate(any_tenant)
+        {
+            Err(Error::ValidationError(ValidationError::InvalidIssuer(_))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let one_tenant = multi_tenant_options(
+            "api://my-api".to_string(),
+            TokenVersion::V2,
+            vec![TENANT_ID.to_string()],
+        );
+        not_err!(
+            claims(&issuer(TENANT_ID, TokenVersion::V2), "2.0", TENANT_ID)
+                .validate(one_tenant.clone())
+        );
+        assert!(
+            claims(&issuer(other_tenant, TokenVersion::V2), "2.0", other_tenant)
+                .validate(one_tenant)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn keys_without_algorithm() {
+        // A key of the Entra ID JWK Set, which has no `alg` but an `issuer` template
+        let jwks: JWKSet<Empty> = not_err!(serde_json::from_value(json!({
+            "keys": [{
+                "kty": "RSA",
+                "use": "sig",
+                "kid": "nOo3ZDrODXEK1jKWhXslHR_KXEg",
+                "x5t": "nOo3ZDrODXEK1jKWhXslHR_KXEg",
+                "n": "oaLLT9hkcSj2tGfZsjbu7Xz1Krs0qEicXPmEsJKOBQHauZ_kRM1HdEkgOJbUznUspE6xOuOSXjlzErqBxXAu4SCvcvVOCYG2v9G3-uIrLF5dstD0sYHBo1VomtKxzF90Vslrkn6rNQgUGIWgvuQTxm1uRklYFPEcTIRw0LnYknzJ06GC9ljKR617wABVrZNkBuDgQKj37qcyxoaxIGdxEcmVFZXJyrxDgdXh9owRmZn6LIJlGjZ9m59emfuwnBnsIQG7DirJwe9SXrLXnexRQWqyzCdkYaOqkpKrsjuxUj2-MHX31FqsdpJJsOAvYXGOYBKJRjhGrGdONVrZdUdTBQ",
+                "e": "AQAB",
+                "x5c": [],
+                "issuer": "https://login.microsoftonline.com/{tenantid}/v2.0",
+            }]
+        })));
+        assert_eq!(jwks.keys[0].common.algorithm, None);
+        assert_eq!(
+            jwks_url("common", TokenVersion::V2),
+            "https://login.microsoftonline.com/common/discovery/v2.0/keys"
+        );
+    }
+}
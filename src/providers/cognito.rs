@@ -0,0 +1,184 @@
+//! [Amazon Cognito](https://docs.aws.amazon.com/cognito/latest/developerguide/amazon-cognito-user-pools-using-tokens-verifying-a-jwt.html)
+//! user pool ID and access tokens
+//!
+//! A user pool issues both kinds of tokens, which are told apart by their `token_use` claim.
+//! ID tokens have the client ID of the app in their `aud` claim, while access tokens have no
+//! audience and carry it in a `client_id` claim instead. [`options`] checks the right claim for
+//! the expected [`TokenUse`].
+//!
+//! # Examples
+//! ```no_run
+//! # fn main() -> Result<(), biscuit::errors::Error> {
+//! use biscuit::jwk::JWKSet;
+//! use biscuit::providers::{self, cognito};
+//! use biscuit::{ClaimsSet, Empty};
+//! use serde_json::Value;
+//! # let jwks: JWKSet<Empty> = JWKSet { keys: vec![] };
+//! # let token = "";
+//!
+//! // `jwks` is the document at `cognito::jwks_url("us-east-1", "us-east-1_example")`
+//! let options = cognito::options(
+//!     "us-east-1",
+//!     "us-east-1_example",
+//!     "3n4b5urk1ft4fl3mg5e62d9ado".to_string(),
+//!     cognito::TokenUse::Access,
+//! );
+//! let claims: ClaimsSet<Value> = providers::verify(token, &jwks, options)?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::jwa::SignatureAlgorithm;
+use crate::{ClaimAssertion, ClaimPresenceOptions, Presence, Validation, ValidationOptions};
+
+/// The kind of Cognito token, in its `token_use` claim
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenUse {
+    /// An ID token, whose audience is the client ID of the app
+    Id,
+    /// An access token, which has the client ID of the app in its `client_id` claim
+    Access,
+}
+
+impl TokenUse {
+    /// The value of the `token_use` claim
+    pub fn claim(self) -> &'static str {
+        match self {
+            TokenUse::Id => "id",
+            TokenUse::Access => "access",
+        }
+    }
+}
+
+/// The issuer of the tokens of the user pool `user_pool_id` in `region`
+pub fn issuer(region: &str, user_pool_id: &str) -> String {
+    format!(
+        "https://cognito-idp.{}.amazonaws.com/{}",
+        region, user_pool_id
+    )
+}
+
+/// The URL of the JWK Set of the user pool `user_pool_id` in `region`
+pub fn jwks_url(region: &str, user_pool_id: &str) -> String {
+    format!("{}/.well-known/jwks.json", issuer(region, user_pool_id))
+}
+
+/// Options for validating the tokens of `token_use` that the user pool `user_pool_id` in
+/// `region` issues to the app `client_id`
+///
+/// The `iss`, `exp`, `iat` and `token_use` claims are required, and only `RS256` is accepted.
+/// ID tokens must have `client_id` as their audience, and access tokens as their `client_id`
+/// claim.
+pub fn options(
+    region: &str,
+    user_pool_id: &str,
+    client_id: String,
+    token_use: TokenUse,
+) -> ValidationOptions {
+    let token_use_assertion = Arc::new(ClaimAssertion::equals(
+        "/token_use",
+        Value::from(token_use.claim()),
+    ));
+    let mut options = ValidationOptions {
+        claim_presence_options: ClaimPresenceOptions {
+            issuer: Presence::Required,
+            expiry: Presence::Required,
+            issued_at: Presence::Required,
+            ..Default::default()
+        },
+        issuer: Validation::Validate(issuer(region, user_pool_id)),
+        validators: vec![token_use_assertion],
+        algorithms: Validation::Validate(vec![SignatureAlgorithm::RS256]),
+        ..Default::default()
+    };
+
+    match token_use {
+        TokenUse::Id => {
+            options.claim_presence_options.audience = Presence::Required;
+            options.audience = Validation::Validate(client_id);
+        }
+        TokenUse::Access => {
+            options.validators.push(Arc::new(ClaimAssertion::equals(
+                "/client_id",
+                Value::from(client_id),
+            )));
+        }
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::errors::{Error, ValidationError};
+    use crate::{ClaimsSet, RegisteredClaims, SingleOrMultiple};
+
+    fn claims(audience: Option<&str>, private: Value) -> ClaimsSet<Value> {
+        let now = chrono::Utc::now().timestamp();
+        ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some(issuer("eu-west-1", "eu-west-1_pool")),
+                audience: audience.map(|audience| SingleOrMultiple::Single(audience.to_string())),
+                issued_at: Some(now.into()),
+                expiry: Some((now + 60).into()),
+                ..Default::default()
+            },
+            private,
+        }
+    }
+
+    #[test]
+    fn cognito_id_tokens() {
+        assert_eq!(
+            jwks_url("eu-west-1", "eu-west-1_pool"),
+            "https://cognito-idp.eu-west-1.amazonaws.com/eu-west-1_pool/.well-known/jwks.json"
+        );
+
+        let options = options(
+            "eu-west-1",
+            "eu-west-1_pool",
+            "app".to_string(),
+            TokenUse::Id,
+        );
+        not_err!(claims(Some("app"), json!({ "token_use": "id" })).validate(options.clone()));
+        match claims(Some("app"), json!({ "token_use": "access" })).validate(options.clone()) {
+            Err(Error::ValidationError(ValidationError::FailedClaimAssertion(pointer))) => {
+                assert_eq!(pointer, "/token_use")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(claims(None, json!({ "token_use": "id" }))
+            .validate(options)
+            .is_err());
+    }
+
+    #[test]
+    fn cognito_access_tokens() {
+        let options = options(
+            "eu-west-1",
+            "eu-west-1_pool",
+            "app".to_string(),
+            TokenUse::Access,
+        );
+        not_err!(
+            claims(None, json!({ "token_use": "access", "client_id": "app" }))
+                .validate(options.clone())
+        );
+        match claims(None, json!({ "token_use": "access", "client_id": "other" }))
+            .validate(options.clone())
+        {
+            Err(Error::ValidationError(ValidationError::FailedClaimAssertion(pointer))) => {
+                assert_eq!(pointer, "/client_id")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(claims(Some("app"), json!({ "token_use": "access" }))
+            .validate(options)
+            .is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Google [Sign-In](https://developers.google.com/identity/openid-connect/openid-connect) and
+//! [Firebase Authentication](https://firebase.google.com/docs/auth/admin/verify-id-tokens) ID
+//! tokens
+//!
+//! Google publishes its keys as a JWK Set at [`JWKS_URL`], and also as a map of key IDs to X.509
+//! certificates at [`X509_CERTS_URL`]. Firebase only publishes certificates, at
+//! [`FIREBASE_X509_CERTS_URL`], which are read with [`jwks_from_x509_certs`].
+//!
+//! # Examples
+//! ```no_run
+//! # fn main() -> Result<(), biscuit::errors::Error> {
+//! use biscuit::providers::{self, google};
+//! use biscuit::ClaimsSet;
+//! use serde_json::Value;
+//! # let certificates = b"{}";
+//! # let token = "";
+//!
+//! // `certificates` is the document at `google::FIREBASE_X509_CERTS_URL`
+//! let jwks = google::jwks_from_x509_certs(certificates)?;
+//! let claims: ClaimsSet<Value> =
+//!     providers::verify(token, &jwks, google::firebase_options("my-project".to_string()))?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+
+use data_encoding::BASE64_MIME;
+use num_bigint::BigUint;
+use serde_json::{Map, Value};
+
+use crate::errors::Error;
+use crate::jwa::{Algorithm, SignatureAlgorithm};
+use crate::jwk::{
+    AlgorithmParameters, CommonParameters, JWKSet, PublicKeyUse, RSAKeyParameters, JWK,
+};
+use crate::{ClaimAssertion, Empty, Validation, ValidationOptions};
+
+/// The values of the `iss` claim of Google ID tokens. Google issues tokens with either.
+pub const ISSUERS: [&str; 2] = ["https://accounts.google.com", "accounts.google.com"];
+
+/// The URL of the JWK Set of Google
+pub const JWKS_URL: &str = "https://www.googleapis.com/oauth2/v3/certs";
+
+/// The URL of the X.509 certificates of Google, as a map of key IDs to certificates
+pub const X509_CERTS_URL: &str = "https://www.googleapis.com/oauth2/v1/certs";
+
+/// The URL of the X.509 certificates of Firebase Authentication, as a map of key IDs to
+/// certificates
+pub const FIREBASE_X509_CERTS_URL: &str =
+    "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com";
+
+/// The issuer of the Firebase ID tokens of `project_id`
+pub fn firebase_issuer(project_id: &str) -> String {
+    format!("https://securetoken.google.com/{}", project_id)
+}
+
+/// Options for validating the Google ID tokens issued to `client_id`, like
+/// [`ValidationOptions::oidc_id_token`], accepting either of the [`ISSUERS`] and only `RS256`
+pub fn id_token_options(client_id: String) -> ValidationOptions {
+    let mut options = ValidationOptions::oidc_id_token(client_id, ISSUERS[0].to_string());
+    options.issuer = Validation::Ignored;
+    options.validators.push(Arc::new(ClaimAssertion::one_of(
+        "/iss",
+        ISSUERS.iter().map(|issuer| Value::from(*issuer)).collect(),
+    )));
+    options.algorithms = Validation::Validate(vec![SignatureAlgorithm::RS256]);
+    options
+}
+
+/// Options for validating the Firebase ID tokens of `project_id`, like
+/// [`ValidationOptions::oidc_id_token`], accepting only `RS256`
+///
+/// The audience of the tokens is the project ID, and their issuer is [`firebase_issuer`].
+pub fn firebase_options(project_id: String) -> ValidationOptions {
+    let issuer = firebase_issuer(&project_id);
+    let mut options = ValidationOptions::oidc_id_token(project_id, issuer);
+    options.algorithms = Validation::Validate(vec![SignatureAlgorithm::RS256]);
+    options
+}
+
+/// Read a map of key IDs to PEM encoded X.509 certificates, like the document at
+/// [`FIREBASE_X509_CERTS_URL`], into a JWK Set of `RS256` keys
+///
+/// Only the public keys are read from the certificates, which are not verified. They are
+/// trusted because they were fetched from Google.
+///
+/// Returns [`Error::InvalidCertificate`] if a certificate does not hold an RSA public key.
+pub fn jwks_from_x509_certs(json: &[u8]) -> Result<JWKSet<Empty>, Error> {
+    let certificates: Map<String, Value> = serde_json::from_slice(json)?;
+    let keys = certificates
+        .into_iter()
+        .map(|(key_id, certificate)| {
+            let (n, e) = certificate
+                .as_str()
+                .and_then(rsa_public_key_from_pem)
+                .ok_or_else(|| Error::InvalidCertificate(key_id.clone()))?;
+            Ok(JWK {
+                common: CommonParameters {
+                    public_key_use: Some(PublicKeyUse::Signature),
+                    algorithm: Some(Algorithm::Signature(SignatureAlgorithm::RS256)),
+                    key_id: Some(key_id),
+                    ..Default::default()
+                },
+                algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                    n,
+                    e,
+                    ..Default::default()
+                }),
+                additional: Empty {},
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+    Ok(JWKSet { keys })
+}
+
+const SEQUENCE: u8 = 0x30;
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+/// The explicit `[0]` tag of the version of a certificate
+const VERSION: u8 = 0xa0;
+/// The DER encoding of the `rsaEncryption` OID, 1.2.840.113549.1.1.1
+const RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+/// The modulus and the exponent of the RSA public key in a PEM encoded X.509 certificate
+fn rsa_public_key_from_pem(pem: &str) -> Option<(BigUint, BigUint)> {
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let der = BASE64_MIME.decode(body.as_bytes()).ok()?;
+    rsa_public_key_from_der(&der)
+}
+
+/// Walks the DER encoded certificate to its `subjectPublicKeyInfo`, as described by
+/// [RFC 5280 §4.1](https://tools.ietf.org/html/rfc5280#section-4.1)
+fn rsa_public_key_from_der(der: &[u8]) -> Option<(BigUint, BigUint)> {
+    let (certificate, _) = der_value(der, SEQUENCE)?;
+    let (tbs_certificate, _) = der_value(certificate, SEQUENCE)?;
+    let fields = match der_value(tbs_certificate, VERSION) {
+        Some((_, rest)) => rest,
+        None => tbs_certificate,
+    };
+    let (_serial_number, fields) = der_value(fields, INTEGER)?;
+    let (_signature, fields) = der_value(fields, SEQUENCE)?;
+    let (_issuer, fields) = der_value(fields, SEQUENCE)?;
+    let (_validity, fields) = der_value(fields, SEQUENCE)?;
+    let (_subject, fields) = der_value(fields, SEQUENCE)?;
+    let (public_key_info, _) = der_value(fields, SEQUENCE)?;
+
+    let (algorithm, public_key_info) = der_value(public_key_info, SEQUENCE)?;
+    let (algorithm, _) = der_value(algorithm, OBJECT_IDENTIFIER)?;
+    if algorithm != RSA_ENCRYPTION {
+        return None;
+    }
+    let (public_key, _) = der_value(public_key_info, BIT_STRING)?;
+    // The number of unused bits, which is 0 for a DER encoded key
+    let public_key = match public_key.split_first() {
+        Some((0, public_key)) => public_key,
+        _ => return None,
+    };
+
+    // `RSAPublicKey` from RFC 8017
+    let (public_key, _) = der_value(public_key, SEQUENCE)?;
+    let (modulus, public_key) = der_value(public_key, INTEGER)?;
+    let (exponent, _) = der_value(public_key, INTEGER)?;
+    Some((
+        BigUint::from_bytes_be(modulus),
+        BigUint::from_bytes_be(exponent),
+    ))
+}
+
+/// Splits the contents of the DER encoded value with `tag` at the start of `input` from the
+/// values after it
+fn der_value(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual_tag, input) = input.split_first()?;
+    if actual_tag != tag {
+        return None;
+    }
+
+    let (&length, input) = input.split_first()?;
+    let (length, input) = if length < 0x80 {
+        (usize::from(length), input)
+    } else {
+        let octets = usize::from(length & 0x7f);
+        if octets == 0 || octets > 4 || input.len() < octets {
+            return None;
+        }
+        let length = input[..octets]
+            .iter()
+            .fold(0, |length, octet| length << 8 | usize::from(*octet));
+        (length, &input[octets..])
+    };
+
+    if input.len() < length {
+        return None;
+    }
+    Some(input.split_at(length))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    #[cfg(feature = "rsa")]
+    use crate::jws::{Compact, RegisteredHeader, Secret};
+    use crate::{ClaimsSet, RegisteredClaims, SingleOrMultiple};
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn jwks_from_firebase_certificates() {
+        let certificates = json!({
+            "key0": include_str!("../../test/fixtures/rsa_certificate.pem"),
+        })
+        .to_string();
+        let jwks = not_err!(jwks_from_x509_certs(certificates.as_bytes()));
+        assert_eq!(jwks.keys.len(), 1);
+
+        let now = chrono::Utc::now();
+        let claims = ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some(firebase_issuer("my-project")),
+                subject: Some("user".to_string()),
+                audience: Some(SingleOrMultiple::Single("my-project".to_string())),
+                issued_at: Some(now.into()),
+                expiry: Some((now + chrono::Duration::hours(1)).into()),
+                ..Default::default()
+            },
+            private: Map::new(),
+        };
+        let secret = not_err!(Secret::rsa_keypair_from_file(
+            "test/fixtures/rsa_private_key.der"
+        ));
+        let header = RegisteredHeader {
+            algorithm: SignatureAlgorithm::RS256,
+            key_id: Some("key0".to_string()),
+            ..Default::default()
+        };
+        let token =
+            not_err!(Compact::new_decoded(From::from(header), claims).into_encoded(&secret))
+                .unwrap_encoded()
+                .to_string();
+
+        let verified: ClaimsSet<Value> = not_err!(crate::providers::verify(
+            &token,
+            &jwks,
+            firebase_options("my-project".to_string())
+        ));
+        assert_eq!(verified.registered.subject.as_deref(), Some("user"));
+        assert!(crate::providers::verify::<Value>(
+            &token,
+            &jwks,
+            firebase_options("other-project".to_string())
+        )
+        .is_err());
+
+        match jwks_from_x509_certs(br#"{"key1": "not a certificate"}"#) {
+            Err(Error::InvalidCertificate(key_id)) => assert_eq!(key_id, "key1"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn google_id_token_accepts_either_issuer() {
+        let options = id_token_options("client".to_string());
+        for issuer in ISSUERS.iter() {
+            let claims = ClaimsSet {
+                registered: RegisteredClaims {
+                    issuer: Some(issuer.to_string()),
+                    subject: Some("user".to_string()),
+                    audience: Some(SingleOrMultiple::Single("client".to_string())),
+                    issued_at: Some(1.into()),
+                    expiry: Some((chrono::Utc::now().timestamp() + 60).into()),
+                    ..Default::default()
+                },
+                private: json!({}),
+            };
+            not_err!(claims.validate(options.clone()));
+        }
+
+        let claims = ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some("https://accounts.example.com".to_string()),
+                ..Default::default()
+            },
+            private: json!({}),
+        };
+        assert!(claims.validate(options).is_err());
+    }
+}
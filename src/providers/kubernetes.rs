@@ -0,0 +1,153 @@
+//! Kubernetes [projected service account tokens](https://kubernetes.io/docs/tasks/configure-pod-container/configure-service-account/#serviceaccount-token-volume-projection)
+//!
+//! The keys of a cluster are published through OpenID Connect discovery: the discovery document
+//! of the issuer of the cluster is at
+//! [`ProviderMetadata::discovery_url`](crate::oidc::ProviderMetadata::discovery_url), and the
+//! JWK Set at its `jwks_uri`. The claims that Kubernetes adds about the service account and the
+//! objects that the token is bound to are deserialized into [`ServiceAccountClaims`].
+//!
+//! # Examples
+//! ```no_run
+//! # fn main() -> Result<(), biscuit::errors::Error> {
+//! use biscuit::jwk::JWKSet;
+//! use biscuit::providers::{self, kubernetes};
+//! use biscuit::{ClaimsSet, Empty};
+//! # let jwks: JWKSet<Empty> = JWKSet { keys: vec![] };
+//! # let token = "";
+//!
+//! let options = kubernetes::options(
+//!     "https://kubernetes.default.svc.cluster.local".to_string(),
+//!     "vault".to_string(),
+//! );
+//! let claims: ClaimsSet<kubernetes::ServiceAccountClaims> =
+//!     providers::verify(token, &jwks, options)?;
+//! println!("Token of {}", claims.private.kubernetes.subject());
+//! # Ok(())
+//! # }
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::jwa::SignatureAlgorithm;
+use crate::{ClaimPresenceOptions, Presence, Timestamp, Validation, ValidationOptions};
+
+/// The name of the claim with the Kubernetes specific claims
+pub const CLAIM: &str = "kubernetes.io";
+
+/// The private claims of a service account token
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ServiceAccountClaims {
+    /// The Kubernetes specific claims, in the `kubernetes.io` claim
+    #[serde(rename = "kubernetes.io")]
+    pub kubernetes: KubernetesClaims,
+}
+
+/// The claims that Kubernetes adds about the service account of a token, and the objects that
+/// the token is bound to
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KubernetesClaims {
+    /// The namespace of the service account
+    pub namespace: String,
+
+    /// The service account
+    #[serde(rename = "serviceaccount")]
+    pub service_account: ObjectReference,
+
+    /// The pod that the token is bound to
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pod: Option<ObjectReference>,
+
+    /// The secret that the token is bound to
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub secret: Option<ObjectReference>,
+
+    /// The node that the pod of the token is running on
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub node: Option<ObjectReference>,
+
+    /// The time after which the API server warns that the token is stale, for tokens that are
+    /// issued with a longer validity than requested
+    #[serde(rename = "warnafter", skip_serializing_if = "Option::is_none", default)]
+    pub warn_after: Option<Timestamp>,
+}
+
+impl KubernetesClaims {
+    /// The subject of the token: `system:serviceaccount:<namespace>:<name>`
+    pub fn subject(&self) -> String {
+        format!(
+            "system:serviceaccount:{}:{}",
+            self.namespace, self.service_account.name
+        )
+    }
+}
+
+/// A reference to a Kubernetes object
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ObjectReference {
+    /// The name of the object
+    pub name: String,
+    /// The UID of the object
+    pub uid: String,
+}
+
+/// Options for validating the service account tokens that the cluster with `issuer` binds to
+/// `audience`
+///
+/// The `iss`, `sub`, `aud`, `exp`, `iat` and `kubernetes.io` claims are required, and `iss` and
+/// `aud` must match. Only `RS256` is accepted, which the API server signs with RSA keys.
+pub fn options(issuer: String, audience: String) -> ValidationOptions {
+    ValidationOptions {
+        claim_presence_options: ClaimPresenceOptions {
+            issuer: Presence::Required,
+            subject: Presence::Required,
+            audience: Presence::Required,
+            expiry: Presence::Required,
+            issued_at: Presence::Required,
+            ..Default::default()
+        },
+        issuer: Validation::Validate(issuer),
+        audience: Validation::Validate(audience),
+        required_claims: vec![CLAIM.to_string()],
+        algorithms: Validation::Validate(vec![SignatureAlgorithm::RS256]),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::ClaimsSet;
+
+    #[test]
+    fn service_account_claims() {
+        let now = chrono::Utc::now().timestamp();
+        let claims: ClaimsSet<ServiceAccountClaims> = not_err!(serde_json::from_value(json!({
+            "aud": ["vault"],
+            "exp": now + 3600,
+            "iat": now,
+            "iss": "https://kubernetes.default.svc.cluster.local",
+            "sub": "system:serviceaccount:default:build-robot",
+            "kubernetes.io": {
+                "namespace": "default",
+                "pod": { "name": "build-robot-7d5b", "uid": "1d1bf4ab-6e4b-4f4f-a2c0-3c1bb5d76b1f" },
+                "serviceaccount": { "name": "build-robot", "uid": "e6e3ec04-7d15-4fd3-8a42-2e1f0aaf1c5d" },
+                "warnafter": now + 600,
+            },
+        })));
+        let kubernetes = &claims.private.kubernetes;
+        assert_eq!(
+            kubernetes.subject(),
+            "system:serviceaccount:default:build-robot"
+        );
+        assert_eq!(
+            kubernetes.pod.as_ref().map(|pod| pod.name.as_str()),
+            Some("build-robot-7d5b")
+        );
+        assert_eq!(kubernetes.node, None);
+
+        let issuer = "https://kubernetes.default.svc.cluster.local".to_string();
+        not_err!(claims.validate(options(issuer.clone(), "vault".to_string())));
+        assert!(claims.validate(options(issuer, "api".to_string())).is_err());
+    }
+}
@@ -0,0 +1,756 @@
+//! [Selective Disclosure for JWTs](https://datatracker.ietf.org/doc/draft-ietf-oauth-selective-disclosure-jwt/)
+//! (SD-JWT)
+//!
+//! An SD-JWT is a JWS whose claims set contains digests of some claims instead of the claims
+//! themselves. The claims are carried next to the JWS as [`Disclosure`]s, and the holder of the
+//! SD-JWT chooses which disclosures to present to a verifier. The holder can also prove
+//! possession of a key with a key binding JWT appended to the presentation.
+//!
+//! Only the `sha-256` hash algorithm is supported.
+//!
+//! # Examples
+//! ```
+//! use biscuit::{ClaimsSet, RegisteredClaims};
+//! use biscuit::jwa::SignatureAlgorithm;
+//! use biscuit::jws::{RegisteredHeader, Secret};
+//! use biscuit::sd_jwt::{self, SdJwt};
+//! use serde_json::json;
+//!
+//! let secret = Secret::Bytes("secret that is at least 32 bytes long".to_string().into_bytes());
+//!
+//! // Issuer: conceal the claims that can be selectively disclosed
+//! let mut claims = json!({ "given_name": "Erika", "family_name": "Mustermann" })
+//!     .as_object()
+//!     .unwrap()
+//!     .clone();
+//! let disclosures = vec![
+//!     sd_jwt::conceal(&mut claims, "given_name").unwrap(),
+//!     sd_jwt::conceal(&mut claims, "family_name").unwrap(),
+//! ];
+//! let claims_set = ClaimsSet {
+//!     registered: RegisteredClaims {
+//!         issuer: Some("https://issuer.example.com".to_string()),
+//!         ..Default::default()
+//!     },
+//!     private: claims,
+//! };
+//! let header = From::from(RegisteredHeader {
+//!     algorithm: SignatureAlgorithm::HS256,
+//!     ..Default::default()
+//! });
+//! let issued = SdJwt::issue(header, claims_set, disclosures, &secret).unwrap();
+//!
+//! // Holder: only disclose the given name
+//! let presentation = issued.present(|disclosure| disclosure.name() == Some("given_name"));
+//! let transported = presentation.to_string();
+//!
+//! // Verifier: reconstruct the disclosed claims
+//! let received: SdJwt = transported.parse().unwrap();
+//! let claims = received.verify(&secret, SignatureAlgorithm::HS256).unwrap();
+//! assert_eq!(claims.private["given_name"], "Erika");
+//! assert!(claims.private.get("family_name").is_none());
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use data_encoding::BASE64URL_NOPAD;
+use ring::digest;
+use ring::rand::SecureRandom;
+use serde_json::{Map, Value};
+
+use crate::errors::{DecodeError, Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+use crate::jws::{self, Header, RegisteredHeader, Secret};
+use crate::oidc::Nonce;
+use crate::{ClaimsSet, ClaimsValidator, Empty, Presence, Validation, ValidationOptions};
+
+/// The media type for the `typ` header of a key binding JWT
+pub const KEY_BINDING_MEDIA_TYPE: &str = "kb+jwt";
+
+/// The name of the hash algorithm used for digests, in the `_sd_alg` claim
+pub const HASH_ALGORITHM: &str = "sha-256";
+
+const DIGESTS: &str = "_sd";
+const DIGEST_ALGORITHM: &str = "_sd_alg";
+const ARRAY_ELEMENT: &str = "...";
+
+/// The claims set of an SD-JWT, with private claims in their JSON form
+pub type SdClaimsSet = ClaimsSet<Map<String, Value>>;
+
+/// A disclosure of a claim of an SD-JWT
+///
+/// A disclosure is the base64url encoding of a JSON array of a random salt, the name of the claim
+/// for object properties, and the value of the claim. The SD-JWT itself only contains the
+/// digest of the encoded disclosure.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Disclosure {
+    salt: String,
+    name: Option<String>,
+    value: Value,
+    encoded: String,
+}
+
+impl Disclosure {
+    /// Create a disclosure of the object property `name` with `value`, using a random salt
+    pub fn object_property<S: Into<String>>(name: S, value: Value) -> Result<Self, Error> {
+        Self::with_salt(random_salt()?, Some(name.into()), value)
+    }
+
+    /// Create a disclosure of an array element with `value`, using a random salt
+    pub fn array_element(value: Value) -> Result<Self, Error> {
+        Self::with_salt(random_salt()?, None, value)
+    }
+
+    /// Parse an encoded disclosure
+    ///
+    /// Returns [`ValidationError::InvalidDisclosure`] if it is not a valid disclosure.
+    pub fn parse(encoded: &str) -> Result<Self, Error> {
+        let invalid = || ValidationError::InvalidDisclosure(encoded.to_string());
+        let bytes = BASE64URL_NOPAD
+            .decode(encoded.as_bytes())
+            .map_err(|_| invalid())?;
+        let mut array = match serde_json::from_slice(&bytes) {
+            Ok(Value::Array(array)) => array,
+            _ => Err(invalid())?,
+        };
+
+        let value = array.pop().ok_or_else(invalid)?;
+        let (salt, name) = match array.as_slice() {
+            [Value::String(salt), Value::String(name)] => (salt.clone(), Some(name.clone())),
+            [Value::String(salt)] => (salt.clone(), None),
+            _ => Err(invalid())?,
+        };
+        if name
+            .as_ref()
+            .map(|name| name == DIGESTS || name == ARRAY_ELEMENT)
+            .unwrap_or(false)
+        {
+            Err(invalid())?
+        }
+
+        Ok(Disclosure {
+            salt,
+            name,
+            value,
+            encoded: encoded.to_string(),
+        })
+    }
+
+    fn with_salt(salt: String, name: Option<String>, value: Value) -> Result<Self, Error> {
+        let array = match name {
+            Some(ref name) => vec![
+                Value::String(salt.clone()),
+                Value::String(name.clone()),
+                value.clone(),
+            ],
+            None => vec![Value::String(salt.clone()), value.clone()],
+        };
+        let encoded = BASE64URL_NOPAD.encode(&serde_json::to_vec(&array)?);
+        Ok(Disclosure {
+            salt,
+            name,
+            value,
+            encoded,
+        })
+    }
+
+    /// The salt of the disclosure
+    pub fn salt(&self) -> &str {
+        &self.salt
+    }
+
+    /// The name of the claim disclosed, or `None` for an array element
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The value of the claim disclosed
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The encoded disclosure, as it is transported
+    pub fn as_str(&self) -> &str {
+        &self.encoded
+    }
+
+    /// The digest of the disclosure that is embedded in the SD-JWT
+    pub fn digest(&self) -> String {
+        hash(&self.encoded)
+    }
+}
+
+impl fmt::Display for Disclosure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encoded)
+    }
+}
+
+/// Conceal the claim `name` of `object`, replacing it with the digest of its disclosure in the
+/// `_sd` claim of `object`
+///
+/// `object` can be the claims set or any object nested in it. Returns an error if the claim is
+/// not present.
+pub fn conceal(object: &mut Map<String, Value>, name: &str) -> Result<Disclosure, Error> {
+    let value = object
+        .remove(name)
+        .ok_or_else(|| Error::ClaimNotFound(name.to_string()))?;
+    let disclosure = Disclosure::object_property(name, value)?;
+
+    match object
+        .entry(DIGESTS)
+        .or_insert_with(|| Value::Array(vec![]))
+    {
+        Value::Array(digests) => digests.push(Value::String(disclosure.digest())),
+        _ => Err(ValidationError::MalformedClaim(DIGESTS.to_string()))?,
+    }
+    Ok(disclosure)
+}
+
+/// Conceal the element at `index` of `array`, replacing it with the digest of its disclosure
+///
+/// Returns an error if `index` is out of bounds.
+pub fn conceal_element(array: &mut [Value], index: usize) -> Result<Disclosure, Error> {
+    let element = array.get_mut(index).ok_or(Error::ElementNotFound(index))?;
+    let disclosure = Disclosure::array_element(element.take())?;
+
+    let mut placeholder = Map::new();
+    let _ = placeholder.insert(
+        ARRAY_ELEMENT.to_string(),
+        Value::String(disclosure.digest()),
+    );
+    *element = Value::Object(placeholder);
+    Ok(disclosure)
+}
+
+/// An SD-JWT, or a presentation of one, in its compact `<JWT>~<Disclosure>~...~<KB-JWT>` form
+///
+/// The key binding JWT is only present in presentations by a holder that proves possession of a
+/// key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SdJwt {
+    issuer_signed: String,
+    disclosures: Vec<Disclosure>,
+    key_binding: Option<String>,
+}
+
+impl SdJwt {
+    /// Sign `claims_set` as an SD-JWT with the algorithm of `header`, adding the `_sd_alg` claim
+    ///
+    /// The claims that can be disclosed should already have been concealed, with [`conceal`] or
+    /// [`conceal_element`], and their `disclosures` are appended to the SD-JWT.
+    pub fn issue(
+        header: Header<Empty>,
+        mut claims_set: SdClaimsSet,
+        disclosures: Vec<Disclosure>,
+        secret: &Secret,
+    ) -> Result<Self, Error> {
+        let _ = claims_set.private.insert(
+            DIGEST_ALGORITHM.to_string(),
+            Value::String(HASH_ALGORITHM.to_string()),
+        );
+        let issuer_signed = jws::Compact::new_decoded(header, claims_set)
+            .into_encoded(secret)?
+            .unwrap_encoded()
+            .to_string();
+
+        Ok(SdJwt {
+            issuer_signed,
+            disclosures,
+            key_binding: None,
+        })
+    }
+
+    /// The JWS signed by the issuer
+    pub fn issuer_signed_jwt(&self) -> &str {
+        &self.issuer_signed
+    }
+
+    /// The disclosures that are presented
+    pub fn disclosures(&self) -> &[Disclosure] {
+        &self.disclosures
+    }
+
+    /// The key binding JWT, if any
+    pub fn key_binding_jwt(&self) -> Option<&str> {
+        self.key_binding.as_deref()
+    }
+
+    /// Create a presentation with only the disclosures for which `disclose` returns `true`
+    ///
+    /// Any key binding JWT is removed, since it is bound to the disclosures presented.
+    pub fn present<F>(&self, mut disclose: F) -> Self
+    where
+        F: FnMut(&Disclosure) -> bool,
+    {
+        SdJwt {
+            issuer_signed: self.issuer_signed.clone(),
+            disclosures: self
+                .disclosures
+                .iter()
+                .filter(|disclosure| disclose(disclosure))
+                .cloned()
+                .collect(),
+            key_binding: None,
+        }
+    }
+
+    /// The `sd_hash` of the presentation, which is the digest of the presentation without the key
+    /// binding JWT
+    pub fn sd_hash(&self) -> String {
+        let mut presentation = format!("{}~", self.issuer_signed);
+        for disclosure in &self.disclosures {
+            presentation.push_str(disclosure.as_str());
+            presentation.push('~');
+        }
+        hash(&presentation)
+    }
+
+    /// Append a key binding JWT to the presentation, signed with the holder's `secret` and
+    /// `algorithm`, for the verifier identified by `audience` and the `nonce` it provided
+    ///
+    /// The `iat` claim of the key binding JWT is set to the current time.
+    pub fn with_key_binding(
+        mut self,
+        algorithm: SignatureAlgorithm,
+        secret: &Secret,
+        audience: &str,
+        nonce: &str,
+    ) -> Result<Self, Error> {
+        let header = From::from(RegisteredHeader {
+            algorithm,
+            media_type: Some(KEY_BINDING_MEDIA_TYPE.to_string()),
+            ..Default::default()
+        });
+        let mut claims = Map::new();
+        let _ = claims.insert("nonce".to_string(), Value::String(nonce.to_string()));
+        let _ = claims.insert("sd_hash".to_string(), Value::String(self.sd_hash()));
+        let claims_set = ClaimsSet {
+            registered: crate::RegisteredClaims {
+                audience: Some(crate::SingleOrMultiple::Single(audience.to_string())),
+                issued_at: Some(chrono::Utc::now().into()),
+                ..Default::default()
+            },
+            private: claims,
+        };
+
+        let key_binding = jws::Compact::new_decoded(header, claims_set)
+            .into_encoded(secret)?
+            .unwrap_encoded()
+            .to_string();
+        self.key_binding = Some(key_binding);
+        Ok(self)
+    }
+
+    /// Verify the signature of the issuer, and reconstruct the claims set from the disclosures
+    /// presented
+    ///
+    /// Returns [`ValidationError::InvalidDisclosure`] if any disclosure is not referenced by the
+    /// SD-JWT exactly once, or would replace a claim that is already present. The claims set
+    /// that is returned should then be validated as usual, with [`ClaimsSet::validate`].
+    pub fn verify(
+        &self,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<SdClaimsSet, Error> {
+        let token = jws::Compact::<SdClaimsSet, Empty>::new_encoded(&self.issuer_signed)
+            .into_decoded(secret, algorithm)?;
+        let mut claims = match serde_json::to_value(token.payload()?)? {
+            Value::Object(claims) => claims,
+            _ => unreachable!("Claims sets are serialized to objects"),
+        };
+
+        match claims.remove(DIGEST_ALGORITHM) {
+            None => {}
+            Some(Value::String(ref alg)) if alg == HASH_ALGORITHM => {}
+            Some(_) => Err(Error::UnsupportedOperation)?,
+        }
+
+        let mut disclosures = HashMap::new();
+        for disclosure in &self.disclosures {
+            if disclosures
+                .insert(disclosure.digest(), disclosure)
+                .is_some()
+            {
+                Err(ValidationError::InvalidDisclosure(
+                    disclosure.as_str().to_string(),
+                ))?
+            }
+        }
+        reconstruct_object(&mut claims, &mut disclosures)?;
+        if let Some(disclosure) = disclosures.values().next() {
+            Err(ValidationError::InvalidDisclosure(
+                disclosure.as_str().to_string(),
+            ))?
+        }
+
+        Ok(serde_json::from_value(Value::Object(claims))?)
+    }
+
+    /// Verify the key binding JWT of the presentation, signed with the holder's `secret` and
+    /// `algorithm`
+    ///
+    /// The `typ` header must be `kb+jwt`, the `nonce` claim must be `nonce`, and the `sd_hash`
+    /// claim must match the presentation. The `iat` claim is required, and is validated along
+    /// with the `aud` claim according to `options`.
+    ///
+    /// Returns [`ValidationError::MissingKeyBinding`] if there is no key binding JWT.
+    pub fn verify_key_binding(
+        &self,
+        secret: &Secret,
+        algorithm: SignatureAlgorithm,
+        nonce: &str,
+        options: ValidationOptions,
+    ) -> Result<(), Error> {
+        let key_binding = self
+            .key_binding
+            .as_ref()
+            .ok_or(ValidationError::MissingKeyBinding)?;
+        let token = jws::Compact::<SdClaimsSet, Empty>::new_encoded(key_binding)
+            .into_decoded(secret, algorithm)?;
+
+        let mut options = options;
+        options.claim_presence_options.issued_at = Presence::Required;
+        options.token_type = Validation::Validate(KEY_BINDING_MEDIA_TYPE.to_string());
+        token.header()?.registered.validate(&options)?;
+
+        let claims_set = token.payload()?;
+        let claims = match serde_json::to_value(claims_set)? {
+            Value::Object(claims) => claims,
+            _ => unreachable!("Claims sets are serialized to objects"),
+        };
+        Nonce::new(nonce).validate(&claims)?;
+        match claims.get("sd_hash") {
+            Some(Value::String(sd_hash)) if *sd_hash == self.sd_hash() => {}
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![
+                "sd_hash".to_string(),
+            ]))?,
+            Some(_) => Err(ValidationError::InvalidTokenHash("sd_hash".to_string()))?,
+        }
+
+        claims_set.validate(options)
+    }
+}
+
+impl fmt::Display for SdJwt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}~", self.issuer_signed)?;
+        for disclosure in &self.disclosures {
+            write!(f, "{}~", disclosure)?;
+        }
+        if let Some(ref key_binding) = self.key_binding {
+            f.write_str(key_binding)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SdJwt {
+    type Err = Error;
+
+    /// Parses the compact form of an SD-JWT. Returns [`DecodeError::PartsLengthError`] if there
+    /// is no `~` separator.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('~').collect();
+        if parts.len() < 2 {
+            Err(DecodeError::PartsLengthError {
+                expected: 2,
+                actual: parts.len(),
+            })?
+        }
+
+        let (key_binding, parts) = parts.split_last().expect("There are at least two parts");
+        let (issuer_signed, disclosures) =
+            parts.split_first().expect("There are at least two parts");
+        Ok(SdJwt {
+            issuer_signed: issuer_signed.to_string(),
+            disclosures: disclosures
+                .iter()
+                .map(|disclosure| Disclosure::parse(disclosure))
+                .collect::<Result<_, _>>()?,
+            key_binding: if key_binding.is_empty() {
+                None
+            } else {
+                Some(key_binding.to_string())
+            },
+        })
+    }
+}
+
+/// Replace the digests in `object` and its descendants with the claims from `disclosures`,
+/// removing the disclosures used
+fn reconstruct_object(
+    object: &mut Map<String, Value>,
+    disclosures: &mut HashMap<String, &Disclosure>,
+) -> Result<(), Error> {
+    let digests = match object.remove(DIGESTS) {
+        None => vec![],
+        Some(Value::Array(digests)) => digests,
+        Some(_) => Err(ValidationError::MalformedClaim(DIGESTS.to_string()))?,
+    };
+
+    for value in object.values_mut() {
+        reconstruct(value, disclosures)?;
+    }
+
+    for digest in digests {
+        let digest = match digest {
+            Value::String(digest) => digest,
+            _ => Err(ValidationError::MalformedClaim(DIGESTS.to_string()))?,
+        };
+        // Digests without a disclosure are either undisclosed or decoys
+        if let Some(disclosure) = disclosures.remove(&digest) {
+            let invalid = || ValidationError::InvalidDisclosure(disclosure.as_str().to_string());
+            let name = disclosure.name.clone().ok_or_else(invalid)?;
+            if object.contains_key(&name) {
+                Err(invalid())?
+            }
+            let mut value = disclosure.value.clone();
+            reconstruct(&mut value, disclosures)?;
+            let _ = object.insert(name, value);
+        }
+    }
+    Ok(())
+}
+
+fn reconstruct(
+    value: &mut Value,
+    disclosures: &mut HashMap<String, &Disclosure>,
+) -> Result<(), Error> {
+    match value {
+        Value::Object(object) => reconstruct_object(object, disclosures),
+        Value::Array(array) => {
+            let mut reconstructed = Vec::with_capacity(array.len());
+            for mut element in array.drain(..) {
+                let digest = match element {
+                    Value::Object(ref placeholder) if placeholder.len() == 1 => {
+                        placeholder.get(ARRAY_ELEMENT).cloned()
+                    }
+                    _ => None,
+                };
+                match digest {
+                    None => {
+                        reconstruct(&mut element, disclosures)?;
+                        reconstructed.push(element);
+                    }
+                    Some(Value::String(digest)) => {
+                        if let Some(disclosure) = disclosures.remove(&digest) {
+                            if disclosure.name.is_some() {
+                                Err(ValidationError::InvalidDisclosure(
+                                    disclosure.as_str().to_string(),
+                                ))?
+                            }
+                            let mut element = disclosure.value.clone();
+                            reconstruct(&mut element, disclosures)?;
+                            reconstructed.push(element);
+                        }
+                    }
+                    Some(_) => Err(ValidationError::MalformedClaim(ARRAY_ELEMENT.to_string()))?,
+                }
+            }
+            *array = reconstructed;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn random_salt() -> Result<String, Error> {
+    let mut salt = [0u8; 16];
+    crate::jwa::rng().fill(&mut salt)?;
+    Ok(BASE64URL_NOPAD.encode(&salt))
+}
+
+fn hash(value: &str) -> String {
+    BASE64URL_NOPAD.encode(digest::digest(&digest::SHA256, value.as_bytes()).as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::RegisteredClaims;
+
+    fn secret() -> Secret {
+        Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys()
+    }
+
+    fn header() -> Header<Empty> {
+        From::from(RegisteredHeader {
+            algorithm: SignatureAlgorithm::HS256,
+            ..Default::default()
+        })
+    }
+
+    fn issue(claims: Value, conceal_claims: &[&str]) -> SdJwt {
+        let mut claims = match claims {
+            Value::Object(claims) => claims,
+            _ => unreachable!(),
+        };
+        let disclosures = conceal_claims
+            .iter()
+            .map(|name| conceal(&mut claims, name).unwrap())
+            .collect();
+        let claims_set = ClaimsSet {
+            registered: RegisteredClaims {
+                issuer: Some("https://issuer.example.com".to_string()),
+                ..Default::default()
+            },
+            private: claims,
+        };
+        not_err!(SdJwt::issue(header(), claims_set, disclosures, &secret()))
+    }
+
+    #[test]
+    fn disclosure_digest() {
+        // Example from the SD-JWT specification
+        let disclosure = not_err!(Disclosure::parse(
+            "WyI2cU1RdlJMNWhhaiIsICJmYW1pbHlfbmFtZSIsICJNw7ZiaXVzIl0"
+        ));
+        assert_eq!(disclosure.salt(), "6qMQvRL5haj");
+        assert_eq!(disclosure.name(), Some("family_name"));
+        assert_eq!(disclosure.value(), &json!("Möbius"));
+        assert_eq!(
+            disclosure.digest(),
+            "uutlBuYeMDyjLLTpf6Jxi7yNkEF35jdyWMn9U7b_RYY"
+        );
+
+        let element = not_err!(Disclosure::array_element(json!("DE")));
+        assert_eq!(not_err!(Disclosure::parse(element.as_str())), element);
+
+        for invalid in &["not base64!", "e30", "WyJzYWx0IiwgIl9zZCIsIDFd"] {
+            assert!(Disclosure::parse(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn sd_jwt_selective_disclosure() {
+        let mut address = json!({ "locality": "Berlin", "country": "DE" });
+        let address_disclosure = conceal(address.as_object_mut().unwrap(), "locality").unwrap();
+        let mut nationalities = vec![json!("US"), json!("DE")];
+        let nationality_disclosure = conceal_element(&mut nationalities, 1).unwrap();
+
+        let mut issued = issue(
+            json!({
+                "given_name": "Erika",
+                "family_name": "Mustermann",
+                "address": address,
+                "nationalities": nationalities,
+            }),
+            &["given_name", "family_name", "address"],
+        );
+        issued.disclosures.push(address_disclosure);
+        issued.disclosures.push(nationality_disclosure);
+
+        let parsed: SdJwt = not_err!(issued.to_string().parse());
+        assert_eq!(parsed, issued);
+
+        let claims = not_err!(parsed.verify(&secret(), SignatureAlgorithm::HS256));
+        assert_eq!(
+            claims.registered.issuer.as_deref(),
+            Some("https://issuer.example.com")
+        );
+        assert_eq!(
+            Value::Object(claims.private),
+            json!({
+                "given_name": "Erika",
+                "family_name": "Mustermann",
+                "address": { "locality": "Berlin", "country": "DE" },
+                "nationalities": ["US", "DE"],
+            })
+        );
+
+        let presentation = issued.present(|disclosure| {
+            disclosure.name() == Some("family_name") || disclosure.name() == Some("address")
+        });
+        let claims = not_err!(presentation.verify(&secret(), SignatureAlgorithm::HS256));
+        assert_eq!(
+            Value::Object(claims.private),
+            json!({
+                "family_name": "Mustermann",
+                "address": { "country": "DE" },
+                "nationalities": ["US"],
+            })
+        );
+    }
+
+    #[test]
+    fn sd_jwt_rejects_invalid_disclosures() {
+        let issued = issue(json!({ "given_name": "Erika" }), &["given_name"]);
+
+        let mut unreferenced = issued.clone();
+        unreferenced
+            .disclosures
+            .push(not_err!(Disclosure::object_property("admin", json!(true))));
+        match unreferenced.verify(&secret(), SignatureAlgorithm::HS256) {
+            Err(Error::ValidationError(ValidationError::InvalidDisclosure(_))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let mut duplicated = issued.clone();
+        duplicated.disclosures.push(issued.disclosures[0].clone());
+        assert!(duplicated
+            .verify(&secret(), SignatureAlgorithm::HS256)
+            .is_err());
+
+        assert!("no separator".parse::<SdJwt>().is_err());
+    }
+
+    #[test]
+    fn sd_jwt_key_binding() {
+        let holder_secret =
+            Secret::Bytes("holder".to_string().into_bytes()).danger_allow_weak_keys();
+        let options = || ValidationOptions {
+            audience: Validation::Validate("https://verifier.example.com".to_string()),
+            ..Default::default()
+        };
+
+        let issued = issue(json!({ "given_name": "Erika" }), &["given_name"]);
+        match issued.verify_key_binding(&holder_secret, SignatureAlgorithm::HS256, "n", options()) {
+            Err(Error::ValidationError(ValidationError::MissingKeyBinding)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let presentation = not_err!(issued.present(|_| true).with_key_binding(
+            SignatureAlgorithm::HS256,
+            &holder_secret,
+            "https://verifier.example.com",
+            "1234567890",
+        ));
+        let presentation: SdJwt = not_err!(presentation.to_string().parse());
+        not_err!(presentation.verify_key_binding(
+            &holder_secret,
+            SignatureAlgorithm::HS256,
+            "1234567890",
+            options()
+        ));
+        assert!(presentation
+            .verify_key_binding(
+                &holder_secret,
+                SignatureAlgorithm::HS256,
+                "0987654321",
+                options()
+            )
+            .is_err());
+
+        // The key binding JWT is bound to the disclosures presented
+        let mut tampered = presentation.clone();
+        tampered.disclosures.clear();
+        match tampered.verify_key_binding(
+            &holder_secret,
+            SignatureAlgorithm::HS256,
+            "1234567890",
+            options(),
+        ) {
+            Err(Error::ValidationError(ValidationError::InvalidTokenHash(claim))) => {
+                assert_eq!(claim, "sd_hash")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let claims = not_err!(presentation.verify(&secret(), SignatureAlgorithm::HS256));
+        assert_eq!(claims.private["given_name"], "Erika");
+    }
+}
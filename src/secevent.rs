@@ -0,0 +1,173 @@
+//! [Security Event Tokens](https://tools.ietf.org/html/rfc8417)
+//!
+//! A Security Event Token (SET) is a JWT describing one or more security events, like a session
+//! being revoked or an account being disabled. The events are carried in the `events` claim,
+//! with a payload for each event keyed by the URI identifying the type of event.
+//!
+//! Use [`SecurityEventClaims`] as the private claims of a [`ClaimsSet`](crate::ClaimsSet), set
+//! the `typ` header to [`MEDIA_TYPE`], and validate received tokens with
+//! [`ValidationOptions::security_event_token`](crate::ValidationOptions::security_event_token).
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::errors::ValidationError;
+use crate::{CompactJson, Timestamp};
+
+/// The media type for the `typ` header of a Security Event Token
+pub const MEDIA_TYPE: &str = "secevent+jwt";
+
+/// The claims specific to a Security Event Token, defined in
+/// [RFC8417#2.2](https://tools.ietf.org/html/rfc8417#section-2.2)
+///
+/// # Examples
+/// ```
+/// use biscuit::{ClaimsSet, RegisteredClaims};
+/// use biscuit::secevent::SecurityEventClaims;
+/// use serde_json::json;
+///
+/// let claims = ClaimsSet {
+///     registered: RegisteredClaims {
+///         issuer: Some("https://idp.example.com/".to_string()),
+///         id: Some("3d0c3cf797584bd193bd0fb1bd4e7d30".to_string()),
+///         issued_at: Some(1458496025.into()),
+///         ..Default::default()
+///     },
+///     private: SecurityEventClaims::default().with_event(
+///         "urn:ietf:params:scim:event:create",
+///         json!({ "ref": "https://scim.example.com/Users/44f6142df96bd6ab61e7521d9" })
+///             .as_object()
+///             .unwrap()
+///             .clone(),
+///     ),
+/// };
+///
+/// assert!(claims.private.event("urn:ietf:params:scim:event:create").is_some());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub struct SecurityEventClaims {
+    /// The security events, keyed by event type URI, with the payload of each event.
+    /// Serialized to `events`.
+    pub events: BTreeMap<String, Map<String, Value>>,
+
+    /// The time at which the event occurred. Serialized to `toe`.
+    #[serde(rename = "toe", skip_serializing_if = "Option::is_none", default)]
+    pub time_of_event: Option<Timestamp>,
+
+    /// The transaction identifier, used to correlate SETs issued for the same transaction.
+    /// Serialized to `txn`.
+    #[serde(rename = "txn", skip_serializing_if = "Option::is_none", default)]
+    pub transaction_id: Option<String>,
+}
+
+impl CompactJson for SecurityEventClaims {}
+
+impl SecurityEventClaims {
+    /// Add an event of type `event_type` with `payload`, replacing any event of the same type
+    pub fn with_event<S: Into<String>>(
+        mut self,
+        event_type: S,
+        payload: Map<String, Value>,
+    ) -> Self {
+        let _ = self.events.insert(event_type.into(), payload);
+        self
+    }
+
+    /// Returns the payload of the event of type `event_type`, if present
+    pub fn event(&self, event_type: &str) -> Option<&Map<String, Value>> {
+        self.events.get(event_type)
+    }
+
+    /// Read the claims of a Security Event Token from a claims set
+    ///
+    /// Returns [`ValidationError::MissingRequiredClaims`] if the `events` claim is missing, and
+    /// [`ValidationError::MalformedClaim`] if any claim does not have the type required by the
+    /// specification.
+    pub fn from_claims(claims: &Map<String, Value>) -> Result<Self, ValidationError> {
+        let events = match claims.get("events") {
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![
+                "events".to_string(),
+            ]))?,
+            Some(events) => Deserialize::deserialize(events)
+                .map_err(|_| ValidationError::MalformedClaim("events".to_string()))?,
+        };
+        let time_of_event = match claims.get("toe") {
+            None | Some(Value::Null) => None,
+            Some(toe) => Some(
+                Timestamp::deserialize(toe)
+                    .map_err(|_| ValidationError::MalformedClaim("toe".to_string()))?,
+            ),
+        };
+        let transaction_id = match claims.get("txn") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(txn)) => Some(txn.clone()),
+            Some(_) => Err(ValidationError::MalformedClaim("txn".to_string()))?,
+        };
+
+        Ok(SecurityEventClaims {
+            events,
+            time_of_event,
+            transaction_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn security_event_claims_round_trip() {
+        let claims = json!({
+            "iss": "https://idp.example.com/",
+            "jti": "756E69717565206964656E746966696572",
+            "iat": 1520364019,
+            "toe": 1520364000,
+            "txn": "8675309",
+            "events": {
+                "https://schemas.openid.net/secevent/risc/event-type/account-disabled": {
+                    "subject": { "subject_type": "iss-sub", "sub": "7375626A656374" },
+                    "reason": "hijacking"
+                }
+            }
+        });
+        let claims = claims.as_object().unwrap();
+
+        let parsed = not_err!(SecurityEventClaims::from_claims(claims));
+        assert_eq!(parsed.transaction_id.as_deref(), Some("8675309"));
+        assert_eq!(parsed.time_of_event, Some(1520364000.into()));
+        let event = parsed
+            .event("https://schemas.openid.net/secevent/risc/event-type/account-disabled")
+            .unwrap();
+        assert_eq!(event["reason"], "hijacking");
+
+        let serialized = not_err!(serde_json::to_value(&parsed));
+        assert_eq!(serialized["events"], claims["events"]);
+        assert_eq!(serialized["txn"], claims["txn"]);
+    }
+
+    #[test]
+    fn security_event_claims_must_be_well_formed() {
+        assert_eq!(
+            SecurityEventClaims::from_claims(&Map::new()),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "events".to_string()
+            ]))
+        );
+
+        let claims = json!({ "events": { "urn:example:event": "not an object" } });
+        assert_eq!(
+            SecurityEventClaims::from_claims(claims.as_object().unwrap()),
+            Err(ValidationError::MalformedClaim("events".to_string()))
+        );
+
+        let claims = json!({ "events": {}, "txn": 1 });
+        assert_eq!(
+            SecurityEventClaims::from_claims(claims.as_object().unwrap()),
+            Err(ValidationError::MalformedClaim("txn".to_string()))
+        );
+    }
+}
@@ -4,5 +4,7 @@
 //! in your applications, should you wish to make extensions to the implementations provided.
 pub mod base64_url_uint;
 pub mod byte_sequence;
+pub mod numeric_date;
 pub mod option_base64_url_uint;
 pub mod option_byte_sequence;
+pub mod option_numeric_date;
@@ -0,0 +1,25 @@
+//! Serialize or deserialize a `DateTime<Utc>` as a
+//! [NumericDate](https://tools.ietf.org/html/rfc7519#section-2), the number of seconds since the
+//! Unix Epoch
+//!
+//! Use this with `#[serde(with = "biscuit::numeric_date")]` on custom claims.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Timestamp;
+
+/// Serialize a `DateTime<Utc>` as a NumericDate
+pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    Timestamp::from(*value).serialize(serializer)
+}
+
+/// Deserialize a `DateTime<Utc>` from a NumericDate
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Timestamp::deserialize(deserializer).map(Into::into)
+}
@@ -0,0 +1,24 @@
+//! Serialize or deserialize an `Option<DateTime<Utc>>` as a
+//! [NumericDate](https://tools.ietf.org/html/rfc7519#section-2)
+//!
+//! Use this with `#[serde(with = "biscuit::option_numeric_date", default)]` on custom claims.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Timestamp;
+
+/// Serialize an `Option<DateTime<Utc>>` as a NumericDate
+pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    value.map(Timestamp::from).serialize(serializer)
+}
+
+/// Deserialize an `Option<DateTime<Utc>>` from a NumericDate
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Timestamp>::deserialize(deserializer).map(|value| value.map(Into::into))
+}
@@ -0,0 +1,362 @@
+//! [JWT-SVIDs](https://github.com/spiffe/spiffe/blob/main/standards/JWT-SVID.md), the JWT
+//! identity documents of [SPIFFE](https://spiffe.io/)
+//!
+//! The subject of a JWT-SVID is the [`SpiffeId`] of a workload. JWT-SVIDs are issued with
+//! [`builder`], and validated with [`verify`] against the keys of the trust bundle of its trust
+//! domain, or with the [`ValidationOptions::jwt_svid`] preset once they are decoded.
+//!
+//! # Examples
+//! ```
+//! # #[cfg(feature = "rsa")]
+//! # {
+//! use biscuit::jwa::SignatureAlgorithm;
+//! use biscuit::jws::Secret;
+//! use biscuit::spiffe::{self, SpiffeId};
+//! use chrono::Duration;
+//!
+//! let id: SpiffeId = "spiffe://example.org/ns/prod/sa/billing".parse().unwrap();
+//! assert_eq!(id.trust_domain(), "example.org");
+//! assert_eq!(id.path(), "/ns/prod/sa/billing");
+//!
+//! let secret = Secret::rsa_keypair_from_file("test/fixtures/rsa_private_key.der").unwrap();
+//! let token = spiffe::builder(&id, "spiffe://example.org/payments", Duration::minutes(5))
+//!     .key_id("bundle-key")
+//!     .sign(&secret, SignatureAlgorithm::RS256)
+//!     .unwrap();
+//! # }
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Duration;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::errors::{Error, ValidationError};
+use crate::jwa::SignatureAlgorithm;
+use crate::jwk::{JWKSet, PublicKeyUse};
+use crate::{ClaimsSet, ClaimsValidator, Empty, Token, TokenBuilder, ValidationOptions};
+
+/// The `use` of the keys of a trust bundle that JWT-SVIDs are signed with
+pub const JWT_SVID_KEY_USE: &str = "jwt-svid";
+
+/// The maximum length of a SPIFFE ID, in bytes
+pub const MAX_ID_LENGTH: usize = 2048;
+
+/// The algorithms that JWT-SVIDs may be signed with. Only asymmetric algorithms are allowed.
+pub const ALGORITHMS: [SignatureAlgorithm; 9] = [
+    SignatureAlgorithm::RS256,
+    SignatureAlgorithm::RS384,
+    SignatureAlgorithm::RS512,
+    SignatureAlgorithm::ES256,
+    SignatureAlgorithm::ES384,
+    SignatureAlgorithm::ES512,
+    SignatureAlgorithm::PS256,
+    SignatureAlgorithm::PS384,
+    SignatureAlgorithm::PS512,
+];
+
+const SCHEME: &str = "spiffe://";
+
+/// A [SPIFFE ID](https://github.com/spiffe/spiffe/blob/main/standards/SPIFFE-ID.md), like
+/// `spiffe://example.org/ns/prod/sa/billing`
+///
+/// The scheme must be `spiffe`, the trust domain may only have lowercase letters, digits,
+/// dots, dashes and underscores, and the segments of the path may only have letters, digits,
+/// dots, dashes and underscores. Segments may not be empty, `.` or `..`, and there is no
+/// trailing `/`, query or fragment.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SpiffeId {
+    id: String,
+    /// The index of the end of the trust domain in `id`
+    trust_domain_end: usize,
+}
+
+impl SpiffeId {
+    /// Parse a SPIFFE ID
+    ///
+    /// Returns [`Error::InvalidUri`] with the value if it is not a valid SPIFFE ID.
+    pub fn new<S: Into<String>>(id: S) -> Result<Self, Error> {
+        let id = id.into();
+        match Self::trust_domain_end(&id) {
+            Some(trust_domain_end) => Ok(SpiffeId {
+                id,
+                trust_domain_end,
+            }),
+            None => Err(Error::InvalidUri(id)),
+        }
+    }
+
+    fn trust_domain_end(id: &str) -> Option<usize> {
+        if id.len() > MAX_ID_LENGTH || !id.starts_with(SCHEME) {
+            return None;
+        }
+        let rest = &id[SCHEME.len()..];
+        let trust_domain_length = rest.find('/').unwrap_or(rest.len());
+        let (trust_domain, path) = rest.split_at(trust_domain_length);
+
+        let valid_trust_domain = !trust_domain.is_empty()
+            && trust_domain.bytes().all(|byte| {
+                byte.is_ascii_lowercase()
+                    || byte.is_ascii_digit()
+                    || byte == b'.'
+                    || byte == b'-'
+                    || byte == b'_'
+            });
+        let valid_path = path.is_empty()
+            || path[1..].split('/').all(|segment| {
+                !segment.is_empty()
+                    && segment != "."
+                    && segment != ".."
+                    && segment.bytes().all(|byte| {
+                        byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'-' || byte == b'_'
+                    })
+            });
+        if valid_trust_domain && valid_path {
+            Some(SCHEME.len() + trust_domain_length)
+        } else {
+            None
+        }
+    }
+
+    /// The trust domain of the ID, like `example.org`
+    pub fn trust_domain(&self) -> &str {
+        &self.id[SCHEME.len()..self.trust_domain_end]
+    }
+
+    /// The path of the ID, like `/ns/prod/sa/billing`, which is empty for the ID of a trust
+    /// domain
+    pub fn path(&self) -> &str {
+        &self.id[self.trust_domain_end..]
+    }
+
+    /// The ID as a string
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+}
+
+impl FromStr for SpiffeId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Self::new(id)
+    }
+}
+
+impl fmt::Display for SpiffeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+/// Validates that the `sub` claim of a JWT-SVID is a SPIFFE ID in the expected trust domain
+///
+/// Returns [`ValidationError::MalformedClaim`] if the `sub` claim is not a valid SPIFFE ID, and
+/// [`ValidationError::InvalidTrustDomain`] if it is in another trust domain.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpiffeSubject {
+    trust_domain: String,
+}
+
+impl SpiffeSubject {
+    /// Validate that the subject is in `trust_domain`
+    pub fn new<S: Into<String>>(trust_domain: S) -> Self {
+        SpiffeSubject {
+            trust_domain: trust_domain.into(),
+        }
+    }
+}
+
+impl ClaimsValidator for SpiffeSubject {
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), ValidationError> {
+        let id = match claims.get("sub") {
+            Some(Value::String(subject)) => SpiffeId::new(subject.as_str())
+                .map_err(|_| ValidationError::MalformedClaim("sub".to_string()))?,
+            None | Some(Value::Null) => Err(ValidationError::MissingRequiredClaims(vec![
+                "sub".to_string()
+            ]))?,
+            Some(_) => Err(ValidationError::MalformedClaim("sub".to_string()))?,
+        };
+        if id.trust_domain() != self.trust_domain {
+            Err(ValidationError::InvalidTrustDomain(
+                id.trust_domain().to_string(),
+            ))?
+        }
+        Ok(())
+    }
+}
+
+/// A builder for a JWT-SVID of `id` for `audience`, which expires in `expires_in`
+///
+/// Sign the token with one of the [`ALGORITHMS`], and set the `kid` header to the ID of the key
+/// in the trust bundle.
+pub fn builder<S: Into<String>>(id: &SpiffeId, audience: S, expires_in: Duration) -> TokenBuilder {
+    Token::builder()
+        .subject(id.as_str())
+        .audience(audience)
+        .expires_in(expires_in)
+        .issued_now()
+}
+
+/// Verify a JWT-SVID with the keys of the trust bundle of `trust_domain`, and validate it with
+/// [`ValidationOptions::jwt_svid`] for `audience`
+///
+/// Only the keys of the bundle whose `use` is `jwt-svid` or that have no `use` are used. Returns
+/// the claims set of the token if it is valid.
+pub fn verify<T>(
+    token: &str,
+    bundle: &JWKSet<Empty>,
+    audience: String,
+    trust_domain: String,
+) -> Result<ClaimsSet<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let keys = bundle
+        .keys
+        .iter()
+        .filter(|key| match key.common.public_key_use {
+            None => true,
+            Some(PublicKeyUse::Other(ref key_use)) => key_use == JWT_SVID_KEY_USE,
+            Some(_) => false,
+        })
+        .cloned()
+        .collect();
+    let options = ValidationOptions::jwt_svid(audience, trust_domain);
+    crate::providers::verify(token, &JWKSet { keys }, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn spiffe_id_parsing() {
+        let id = not_err!(SpiffeId::new("spiffe://example.org"));
+        assert_eq!(id.trust_domain(), "example.org");
+        assert_eq!(id.path(), "");
+
+        let id = not_err!("spiffe://my-domain_1.org/a/B.c-d_e".parse::<SpiffeId>());
+        assert_eq!(id.trust_domain(), "my-domain_1.org");
+        assert_eq!(id.path(), "/a/B.c-d_e");
+        assert_eq!(id.to_string(), "spiffe://my-domain_1.org/a/B.c-d_e");
+
+        for invalid in &[
+            "",
+            "https://example.org/workload",
+            "SPIFFE://example.org/workload",
+            "spiffe://",
+            "spiffe:///workload",
+            "spiffe://Example.org/workload",
+            "spiffe://example.org:8080/workload",
+            "spiffe://user@example.org/workload",
+            "spiffe://example.org/",
+            "spiffe://example.org//workload",
+            "spiffe://example.org/./workload",
+            "spiffe://example.org/../workload",
+            "spiffe://example.org/workload?query",
+            "spiffe://example.org/workload#fragment",
+        ] {
+            match SpiffeId::new(*invalid) {
+                Err(Error::InvalidUri(value)) => assert_eq!(value, *invalid),
+                other => panic!("Unexpected result for {:?}: {:?}", invalid, other),
+            }
+        }
+        let long = format!("spiffe://example.org/{}", "a".repeat(MAX_ID_LENGTH));
+        assert!(SpiffeId::new(long).is_err());
+    }
+
+    #[test]
+    fn spiffe_subject_validation() {
+        let validator = SpiffeSubject::new("example.org");
+        let claims = |value: Value| match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        };
+
+        not_err!(validator.validate(&claims(json!({ "sub": "spiffe://example.org/workload" }))));
+        assert_eq!(
+            validator.validate(&claims(json!({ "sub": "spiffe://other.org/workload" }))),
+            Err(ValidationError::InvalidTrustDomain("other.org".to_string()))
+        );
+        assert_eq!(
+            validator.validate(&claims(json!({ "sub": "workload" }))),
+            Err(ValidationError::MalformedClaim("sub".to_string()))
+        );
+        assert_eq!(
+            validator.validate(&claims(json!({}))),
+            Err(ValidationError::MissingRequiredClaims(vec![
+                "sub".to_string()
+            ]))
+        );
+    }
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn jwt_svid_verification_with_trust_bundle() {
+        use crate::jws::Secret;
+
+        let certificates = json!({
+            "bundle-key": include_str!("../test/fixtures/rsa_certificate.pem"),
+        })
+        .to_string();
+        let mut bundle = not_err!(crate::providers::google::jwks_from_x509_certs(
+            certificates.as_bytes()
+        ));
+        bundle.keys[0].common.public_key_use =
+            Some(PublicKeyUse::Other(JWT_SVID_KEY_USE.to_string()));
+
+        let id = not_err!(SpiffeId::new("spiffe://example.org/workload"));
+        let secret = not_err!(Secret::rsa_keypair_from_file(
+            "test/fixtures/rsa_private_key.der"
+        ));
+        let token = not_err!(
+            builder(&id, "spiffe://example.org/api", Duration::minutes(5))
+                .key_id("bundle-key")
+                .sign(&secret, SignatureAlgorithm::RS256)
+        );
+
+        let claims: ClaimsSet<Value> = not_err!(verify(
+            &token,
+            &bundle,
+            "spiffe://example.org/api".to_string(),
+            "example.org".to_string()
+        ));
+        assert_eq!(claims.registered.subject.as_deref(), Some(id.as_str()));
+
+        assert!(verify::<Value>(
+            &token,
+            &bundle,
+            "spiffe://example.org/other".to_string(),
+            "example.org".to_string()
+        )
+        .is_err());
+        match verify::<Value>(
+            &token,
+            &bundle,
+            "spiffe://example.org/api".to_string(),
+            "other.org".to_string(),
+        ) {
+            Err(Error::ValidationError(ValidationError::InvalidTrustDomain(trust_domain))) => {
+                assert_eq!(trust_domain, "example.org")
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        // Keys for X.509-SVIDs are not used
+        bundle.keys[0].common.public_key_use = Some(PublicKeyUse::Other("x509-svid".to_string()));
+        match verify::<Value>(
+            &token,
+            &bundle,
+            "spiffe://example.org/api".to_string(),
+            "example.org".to_string(),
+        ) {
+            Err(Error::ValidationError(ValidationError::KeyNotFound)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
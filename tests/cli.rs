@@ -0,0 +1,101 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+fn jwt(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_jwt"))
+        .args(args)
+        .output()
+        .expect("the jwt binary runs")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn encode_decode_and_verify_with_rsa() {
+    let encoded = jwt(&[
+        "encode",
+        "--alg",
+        "RS256",
+        "--key",
+        "test/fixtures/rsa_private_key.pem",
+        "--iss",
+        "https://www.acme.com",
+        "--claim",
+        "tenant=ACME",
+        "--claim",
+        "level=3",
+        "--expires-in",
+        "300",
+    ]);
+    assert!(encoded.status.success(), "{:?}", encoded);
+    let token = stdout(&encoded).trim().to_string();
+
+    let decoded = jwt(&["decode", &token]);
+    assert!(decoded.status.success(), "{:?}", decoded);
+    assert!(stdout(&decoded).contains("alg: \"RS256\""));
+    assert!(stdout(&decoded).contains("tenant: \"ACME\""));
+
+    let verified = jwt(&[
+        "verify",
+        &token,
+        "--alg",
+        "RS256",
+        "--key",
+        "test/fixtures/rsa_public_key.pem",
+        "--iss",
+        "https://www.acme.com",
+    ]);
+    assert!(verified.status.success(), "{:?}", verified);
+    let claims: serde_json::Value = serde_json::from_str(&stdout(&verified)).unwrap();
+    assert_eq!(claims["level"], 3);
+
+    let wrong_issuer = jwt(&[
+        "verify",
+        &token,
+        "--alg",
+        "RS256",
+        "--key",
+        "test/fixtures/rsa_public_key.der",
+        "--iss",
+        "https://www.example.com",
+    ]);
+    assert_eq!(wrong_issuer.status.code(), Some(1));
+}
+
+#[test]
+fn verify_with_hmac_secret() {
+    let secret = "secret that is at least 32 bytes long";
+    let encoded = jwt(&[
+        "encode", "--alg", "HS256", "--secret", secret, "--sub", "me",
+    ]);
+    let token = stdout(&encoded).trim().to_string();
+
+    let verified = jwt(&["verify", &token, "--alg", "HS256", "--secret", secret]);
+    assert!(verified.status.success(), "{:?}", verified);
+
+    let forged = jwt(&[
+        "verify",
+        &token,
+        "--alg",
+        "HS256",
+        "--secret",
+        "other secret that is at least 32 bytes long",
+    ]);
+    assert_eq!(forged.status.code(), Some(1));
+
+    // Secrets shorter than the hash output are rejected
+    let weak = jwt(&["encode", "--alg", "HS256", "--secret", "secret"]);
+    assert_eq!(weak.status.code(), Some(1));
+}
+
+#[test]
+fn usage_errors() {
+    let output = jwt(&["encode", "--alg", "XYZ"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected one of"));
+
+    assert_eq!(jwt(&["frobnicate"]).status.code(), Some(2));
+}
@@ -0,0 +1,183 @@
+#![cfg(feature = "derive")]
+
+use biscuit::jwa::SignatureAlgorithm;
+use biscuit::jws::{self, Secret};
+use biscuit::{
+    decode_claims, Claims, Empty, RegisteredClaims, Token, Validation, ValidationOptions,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Claims)]
+struct TenantClaims {
+    #[serde(flatten)]
+    registered: RegisteredClaims,
+    tenant: String,
+    #[serde(with = "biscuit::numeric_date")]
+    onboarded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Claims)]
+struct MarkedClaims<T> {
+    #[serde(flatten)]
+    #[claims(registered)]
+    registered: RegisteredClaims,
+    #[serde(skip)]
+    previous: RegisteredClaims,
+    extra: T,
+}
+
+mod milliseconds {
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(value.timestamp_millis())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        i64::deserialize(deserializer).map(|millis| Utc.timestamp_millis(millis))
+    }
+}
+
+#[biscuit::claims]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SessionClaims {
+    registered: RegisteredClaims,
+    session: String,
+    authenticated_at: DateTime<Utc>,
+    refreshed_at: Option<DateTime<Utc>>,
+    #[serde(with = "milliseconds")]
+    last_seen: DateTime<Utc>,
+}
+
+fn tenant_claims() -> TenantClaims {
+    TenantClaims {
+        registered: RegisteredClaims {
+            issuer: Some("https://www.acme.com".to_string()),
+            subject: Some("John Doe".to_string()),
+            ..Default::default()
+        },
+        tenant: "ACME".to_string(),
+        onboarded_at: Utc.timestamp(1234, 0),
+    }
+}
+
+#[test]
+fn derived_claims_accessors() {
+    let claims = tenant_claims();
+    assert_eq!(claims.issuer(), Some("https://www.acme.com"));
+    assert_eq!(claims.subject(), Some("John Doe"));
+    assert_eq!(claims.expiry(), None);
+
+    let marked = MarkedClaims {
+        registered: RegisteredClaims {
+            id: Some("id".to_string()),
+            ..Default::default()
+        },
+        previous: RegisteredClaims::default(),
+        extra: 1,
+    };
+    assert_eq!(marked.id(), Some("id"));
+}
+
+#[test]
+fn derived_claims_serialize_numeric_dates() {
+    let serialized = serde_json::to_value(tenant_claims()).unwrap();
+    assert_eq!(serialized["onboarded_at"], 1234);
+    assert_eq!(serialized["iss"], "https://www.acme.com");
+}
+
+#[test]
+fn derived_claims_validate_and_sign() {
+    let claims = tenant_claims();
+    let options = ValidationOptions {
+        issuer: Validation::Validate("https://www.acme.com".to_string()),
+        required_claims: vec!["tenant".to_string()],
+        ..Default::default()
+    };
+    claims.validate(options.clone()).unwrap();
+    assert!(claims
+        .validate(ValidationOptions {
+            required_claims: vec!["department".to_string()],
+            ..options
+        })
+        .is_err());
+
+    let secret = Secret::Bytes("secret".to_string().into_bytes()).danger_allow_weak_keys();
+    let token = jws::Compact::<TenantClaims, Empty>::new_decoded(
+        From::from(jws::RegisteredHeader {
+            algorithm: SignatureAlgorithm::HS256,
+            ..Default::default()
+        }),
+        claims,
+    )
+    .into_encoded(&secret)
+    .unwrap();
+    let decoded = token
+        .into_decoded(&secret, SignatureAlgorithm::HS256)
+        .unwrap();
+    assert_eq!(*decoded.payload().unwrap(), tenant_claims());
+}
+
+#[test]
+fn claims_attribute_adds_serde_attributes() {
+    let claims = SessionClaims {
+        registered: RegisteredClaims {
+            subject: Some("John Doe".to_string()),
+            ..Default::default()
+        },
+        session: "abc".to_string(),
+        authenticated_at: Utc.timestamp(1234, 0),
+        refreshed_at: Some(Utc.timestamp(5678, 0)),
+        last_seen: Utc.timestamp(1234, 0),
+    };
+    assert_eq!(claims.subject(), Some("John Doe"));
+
+    let serialized = serde_json::to_value(&claims).unwrap();
+    assert_eq!(serialized["sub"], "John Doe");
+    assert_eq!(serialized["authenticated_at"], 1234);
+    assert_eq!(serialized["refreshed_at"], 5678);
+    assert_eq!(serialized["last_seen"], 1_234_000);
+    assert_eq!(
+        serde_json::from_value::<SessionClaims>(serialized).unwrap(),
+        claims
+    );
+
+    let without_refresh: SessionClaims = serde_json::from_str(
+        r#"{"sub":"John Doe","session":"abc","authenticated_at":1234,"last_seen":1234000}"#,
+    )
+    .unwrap();
+    assert_eq!(without_refresh.refreshed_at, None);
+}
+
+#[test]
+fn decode_derived_claims() {
+    let secret = Secret::bytes_from_str("secret that is at least 32 bytes long");
+    let token = Token::builder()
+        .subject("John Doe")
+        .claim("session", "abc")
+        .claim("authenticated_at", 1234)
+        .claim("last_seen", 1_234_000)
+        .sign(&secret, SignatureAlgorithm::HS256)
+        .unwrap();
+    let options = ValidationOptions {
+        algorithms: Validation::Validate(vec![SignatureAlgorithm::HS256]),
+        ..Default::default()
+    };
+
+    let claims: SessionClaims = decode_claims(&token, &secret, &options).unwrap();
+    assert_eq!(claims.subject(), Some("John Doe"));
+    assert_eq!(claims.authenticated_at, Utc.timestamp(1234, 0));
+
+    let missing_claim = ValidationOptions {
+        required_claims: vec!["tenant".to_string()],
+        ..options
+    };
+    assert!(decode_claims::<SessionClaims>(&token, &secret, &missing_claim).is_err());
+}